@@ -1,19 +1,31 @@
 //! Main analysis engine
 
 use crate::{
+    cache::{content_hash, AnalysisCache, CacheKey, ANALYZER_VERSION},
     config::AnalyzerConfig,
     error::{AnalyzerError, Result},
-    factors::lines_of_code::{calculate_workspace_tsc, analyze_file_tsc, TscMetrics},
+    factors::{lines_of_code::analyze_ast_tsc, FactorContext, FactorRegistry, FactorReport},
     metrics::{AggregatedMetrics, FileMetrics, RepoMetrics, RiskSummary},
-    output::AnalysisReport,
+    output::{AnalysisReport, ConfigSummary, PerformanceStats},
+    parallel,
+    patterns::PatternDetector,
     visitor::FunctionVisitor,
+    workspace::{parse_source, Diagnostic, ParsedWorkspace},
 };
+use std::collections::{BTreeSet, HashMap};
 use std::path::{Path, PathBuf};
-use syn::File as SynFile;
+use std::sync::Arc;
+use std::time::Instant;
 use walkdir::WalkDir;
 
+/// Version of the per-file metrics (`FunctionVisitor` and TSC counting);
+/// bump it when their output changes so cached `FileMetrics` are recomputed
+pub const FILE_METRICS_VERSION: &str = "5";
+
 pub struct AnalyzerEngine {
     config: AnalyzerConfig,
+    cache: Option<AnalysisCache>,
+    patterns: Arc<PatternDetector>,
 }
 
 impl AnalyzerEngine {
@@ -25,7 +37,13 @@ impl AnalyzerEngine {
             });
         }
 
-        Ok(Self { config })
+        let cache = config.cache_dir.clone().map(AnalysisCache::new);
+        let patterns = Arc::new(PatternDetector::from_config(&config.analysis)?);
+        Ok(Self {
+            config,
+            cache,
+            patterns,
+        })
     }
 
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
@@ -41,37 +59,84 @@ impl AnalyzerEngine {
             ..Default::default()
         };
 
-        Ok(Self { config })
+        let patterns = Arc::new(PatternDetector::from_config(&config.analysis)?);
+        Ok(Self {
+            config,
+            cache: None,
+            patterns,
+        })
     }
 
     pub fn analyze(&self) -> Result<AnalysisReport> {
         log::info!("Starting repository analysis: {:?}", self.config.root_path);
+        let started = Instant::now();
 
         let rust_files = self.discover_rust_files()?;
         log::info!("Found {} Rust files to analyze", rust_files.len());
+        let discovered = Instant::now();
+
+        // Files are independent; results come back in discovery order
+        let results = parallel::map(&rust_files, self.config.workers, |file_path| {
+            log::debug!("Analyzing file: {:?}", file_path);
+            let file_started = Instant::now();
+            (self.analyze_file(file_path), file_started.elapsed())
+        });
 
         let mut file_metrics = Vec::new();
+        let mut file_timings = Vec::new();
+        let mut diagnostics = Vec::new();
         let mut total_lines = 0;
         let mut total_functions = 0;
 
-        for file_path in rust_files {
-            log::debug!("Analyzing file: {:?}", file_path);
-
-            match self.analyze_file(&file_path) {
+        for (file_path, (result, elapsed)) in rust_files.iter().zip(results) {
+            match result {
                 Ok(metrics) => {
+                    diagnostics.extend(metrics.diagnostic.clone());
                     total_lines += metrics.lines_of_code;
                     total_functions += metrics.function_count;
+                    file_timings.push((file_path.clone(), elapsed));
                     file_metrics.push(metrics);
                 }
                 Err(e) => {
                     log::warn!("Failed to analyze file {:?}: {}", file_path, e);
-                    // Continue with other files
+                    // Continue with other files, reporting this one
+                    let path = self.relative_path(file_path);
+                    diagnostics.push(match &e {
+                        AnalyzerError::ParseError { source, .. } => {
+                            Diagnostic::from_error(&path, source)
+                        }
+                        other => Diagnostic {
+                            path,
+                            message: other.to_string(),
+                            location: None,
+                            recovered_items: 0,
+                        },
+                    });
                 }
             }
         }
 
-        let aggregated = self.calculate_aggregated_metrics(&file_metrics);
-        let risk_summary = self.assess_risk(&aggregated, &file_metrics);
+        let analysis = &self.config.analysis;
+        let mut aggregated = self.calculate_aggregated_metrics(&file_metrics);
+        if !analysis.complexity_scoring {
+            aggregated.complexity_score = 0.0;
+        }
+        let risk_summary = if analysis.risk_assessment {
+            self.assess_risk(&aggregated, &file_metrics)
+        } else {
+            RiskSummary {
+                risk_level: "not_assessed".to_string(),
+                ..Default::default()
+            }
+        };
+
+        // Number of functions carrying each semantic tag
+        let mut semantic_patterns = HashMap::new();
+        for func in file_metrics.iter().flat_map(|file| &file.functions) {
+            for tag in &func.semantic_tags {
+                *semantic_patterns.entry(tag.clone()).or_insert(0) += 1;
+            }
+        }
 
         let repo_metrics = RepoMetrics {
             root_path: self.config.root_path.clone(),
@@ -80,11 +145,112 @@ impl AnalyzerEngine {
             total_function_count: total_functions,
             files: file_metrics,
             aggregated,
-            semantic_patterns: std::collections::HashMap::new(), // TODO: implement
+            semantic_patterns,
             risk_summary,
         };
 
-        Ok(AnalysisReport::new(repo_metrics))
+        let mut report = AnalysisReport::with_patterns(repo_metrics, &self.patterns);
+        report.diagnostics = diagnostics;
+        report.metadata.config_summary = ConfigSummary::from(&self.config);
+        report.metadata.duration_ms = started.elapsed().as_millis() as u64;
+        if analysis.performance_analysis {
+            report.metadata.performance = Some(PerformanceStats::new(
+                discovered - started,
+                discovered.elapsed(),
+                file_timings,
+            ));
+        }
+
+        // Per-function details feed the summary, so they are dropped last
+        if !self.config.output.include_function_details {
+            for file in &mut report.repository.files {
+                file.functions.clear();
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Run every factor in `registry` over the discovered files
+    pub fn analyze_factors(&self, registry: &FactorRegistry) -> Result<FactorReport> {
+        let selected_files = self.relative_rust_files()?;
+        self.analyze_selected_factors(registry, &selected_files, None)
+    }
+
+    /// The files `analyze` would cover, relative to the root
+    pub fn relative_rust_files(&self) -> Result<Vec<String>> {
+        let root = &self.config.root_path;
+        Ok(self
+            .discover_rust_files()?
+            .iter()
+            .filter_map(|path| path.strip_prefix(root).ok())
+            .map(|path| path.to_string_lossy().to_string())
+            .collect())
+    }
+
+    /// `path` relative to the root, for reporting; the root itself (a single
+    /// analyzed file) stays as given
+    fn relative_path(&self, path: &Path) -> String {
+        path.strip_prefix(&self.config.root_path)
+            .ok()
+            .filter(|relative| !relative.as_os_str().is_empty())
+            .unwrap_or(path)
+            .to_string_lossy()
+            .to_string()
+    }
+
+    /// Run every factor in `registry` over `selected_files` (relative to the root)
+    ///
+    /// This is the `/augment` computation; `rpc_url` enables the on-chain lookups.
+    pub fn analyze_selected_factors(
+        &self,
+        registry: &FactorRegistry,
+        selected_files: &[String],
+        rpc_url: Option<&str>,
+    ) -> Result<FactorReport> {
+        log::info!(
+            "Running {} factors over {} files",
+            registry.len(),
+            selected_files.len()
+        );
+
+        let mut workspace = ParsedWorkspace::load(&self.config.root_path, selected_files);
+        let expansion_error = if self.config.expand_macros {
+            self.expand_macros(&mut workspace).err()
+        } else {
+            None
+        };
+        let ctx = FactorContext::new(&workspace, selected_files)
+            .with_rpc_url(rpc_url)
+            .with_cache(self.cache.as_ref())
+            .with_workers(self.config.factor_workers);
+
+        let mut report =
+            registry
+                .compute_all(&ctx)
+                .map_err(|failure| AnalyzerError::AnalysisError {
+                    message: failure.message,
+                })?;
+        if let Some(error) = expansion_error {
+            log::warn!("{}; analyzing the unexpanded sources", error);
+            report
+                .notes
+                .push(format!("{}; analyzed the unexpanded sources", error));
+        }
+        Ok(report)
+    }
+
+    /// Add the code that macros generate to `workspace`
+    #[cfg(feature = "macro-expansion")]
+    fn expand_macros(&self, workspace: &mut ParsedWorkspace) -> Result<()> {
+        crate::expansion::expand_workspace(workspace).map(|_| ())
+    }
+
+    #[cfg(not(feature = "macro-expansion"))]
+    fn expand_macros(&self, _workspace: &mut ParsedWorkspace) -> Result<()> {
+        Err(AnalyzerError::MacroExpansionError {
+            message: "this build does not include the `macro-expansion` feature".to_string(),
+        })
     }
 
     pub fn analyze_single_file(&self) -> Result<FileMetrics> {
@@ -138,6 +304,9 @@ impl AnalyzerEngine {
             rust_files.push(path.to_path_buf());
         }
 
+        // Directory walk order is filesystem-dependent; sort so reports diff cleanly
+        rust_files.sort();
+
         Ok(rust_files)
     }
 
@@ -181,24 +350,93 @@ impl AnalyzerEngine {
     fn analyze_file(&self, path: &Path) -> Result<FileMetrics> {
         let content = std::fs::read_to_string(path)?;
 
-        // Parse the file
-        let syntax_tree: SynFile =
-            syn::parse_file(&content).map_err(|e| AnalyzerError::ParseError {
-                path: path.to_path_buf(),
-                source: e,
+        // Unchanged content and settings reuse the metrics of an earlier run
+        let cached = self.cache.as_ref().map(|cache| {
+            let key = CacheKey::new("files")
+                .with(ANALYZER_VERSION)
+                .with(FILE_METRICS_VERSION)
+                .with(format!("{:?}", self.config.analysis))
+                .with(self.patterns.fingerprint())
+                .with(content_hash(content.as_bytes()));
+            (cache, key)
+        });
+        let mut metrics = match cached
+            .as_ref()
+            .and_then(|(cache, key)| cache.load::<FileMetrics>(key))
+        {
+            Some(mut metrics) => {
+                log::debug!("Using cached metrics for {:?}", path);
+                metrics.path = path.to_path_buf();
+                metrics
+            }
+            None => {
+                let metrics = self.compute_file_metrics(path, &content)?;
+                if let Some((cache, key)) = &cached {
+                    cache.store(key, &metrics);
+                }
+                metrics
+            }
+        };
+        // Cached diagnostics may come from the same content at another path
+        if let Some(diagnostic) = &mut metrics.diagnostic {
+            diagnostic.path = self.relative_path(path);
+            if let Some(location) = &mut diagnostic.location {
+                location.file = diagnostic.path.clone();
+            }
+        }
+
+        if self.config.output.include_snippets {
+            let lines: Vec<&str> = content.lines().collect();
+            for func in &mut metrics.functions {
+                let (start, end) = func.line_range;
+                func.snippet = lines
+                    .get(start.saturating_sub(1)..end.min(lines.len()))
+                    .map(|snippet| snippet.join("\n"));
+            }
+        }
+        Ok(metrics)
+    }
+
+    fn compute_file_metrics(&self, path: &Path, content: &str) -> Result<FileMetrics> {
+        // Parse the file, keeping the items before a syntax error
+        let (syntax_tree, diagnostic) =
+            parse_source(&self.relative_path(path), content).map_err(|e| {
+                AnalyzerError::ParseError {
+                    path: path.to_path_buf(),
+                    source: e,
+                }
             })?;
 
         // Create visitor and analyze
-        let mut visitor = FunctionVisitor::new(&content);
+        let analysis = &self.config.analysis;
+        let mut visitor = FunctionVisitor::with_weights(analysis.pattern_weights.clone())
+            .with_patterns(Arc::clone(&self.patterns));
         syn::visit::visit_file(&mut visitor, &syntax_tree);
+        for func in &mut visitor.functions {
+            if !analysis.semantic_patterns {
+                func.semantic_tags.clear();
+            }
+            if !analysis.complexity_scoring {
+                func.complexity_score = 0.0;
+            }
+        }
 
         // Calculate lines of code (excluding comments and empty lines)
         // Use TSC (Total Statement Count) instead of lines of code
-        let tsc_metrics = analyze_file_tsc(&content).unwrap_or_default();
+        let tsc_metrics = analyze_ast_tsc(&syntax_tree);
         let lines_of_code = tsc_metrics.total_statements as u32;
 
+        let semantic_tags: BTreeSet<String> = visitor
+            .functions
+            .iter()
+            .flat_map(|func| func.semantic_tags.iter().cloned())
+            .collect();
+
         // Calculate aggregated metrics for this file
-        let aggregated = self.calculate_file_aggregated_metrics(&visitor.functions);
+        let mut aggregated = self.calculate_file_aggregated_metrics(&visitor.functions);
+        if !analysis.complexity_scoring {
+            aggregated.complexity_score = 0.0;
+        }
 
         Ok(FileMetrics {
             path: path.to_path_buf(),
@@ -206,7 +444,8 @@ impl AnalyzerEngine {
             function_count: visitor.functions.len() as u32,
             functions: visitor.functions,
             aggregated,
-            semantic_tags: Vec::new(), // TODO: collect from functions
+            semantic_tags: semantic_tags.into_iter().collect(),
+            diagnostic,
         })
     }
 
@@ -358,3 +597,120 @@ impl AnalyzerEngine {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn analyze(dir: &Path, edit: impl FnOnce(&mut AnalyzerConfig)) -> AnalysisReport {
+        let mut config = AnalyzerConfig {
+            root_path: dir.to_path_buf(),
+            ..Default::default()
+        };
+        edit(&mut config);
+        AnalyzerEngine::new(config).unwrap().analyze().unwrap()
+    }
+
+    #[test]
+    fn test_config_effects() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("lib.rs"),
+            "pub fn swap(a: u64, b: u64) -> u64 {\n    a.checked_ceil_div(b).unwrap()\n}\n",
+        )
+        .unwrap();
+
+        let report = analyze(dir.path(), |_| {});
+        let func = &report.repository.files[0].functions[0];
+        assert!(func.semantic_tags.contains(&"token_swap".to_string()));
+        assert!(func.semantic_tags.contains(&"ceiling_division".to_string()));
+        assert_eq!(report.repository.files[0].semantic_tags, func.semantic_tags);
+        assert_eq!(report.repository.semantic_patterns["token_swap"], 1);
+        let top = &report.summary.top_semantic_patterns;
+        assert_eq!(top.len(), report.repository.semantic_patterns.len());
+        assert!(top.iter().all(|pattern| pattern.count == 1));
+        assert!(func.snippet.is_none());
+        assert!(report.metadata.performance.is_none());
+        let default_score = func.complexity_score;
+
+        let report = analyze(dir.path(), |config| {
+            config.analysis.pattern_weights.fixed_point += 1.0;
+            config.output.include_snippets = true;
+            config.analysis.performance_analysis = true;
+        });
+        let func = &report.repository.files[0].functions[0];
+        assert_eq!(func.complexity_score, default_score + 1.0);
+        assert_eq!(func.snippet.as_deref().unwrap().lines().count(), 3);
+        assert_eq!(
+            report
+                .metadata
+                .performance
+                .as_ref()
+                .unwrap()
+                .slowest_files
+                .len(),
+            1
+        );
+
+        let report = analyze(dir.path(), |config| {
+            config.analysis.semantic_patterns = false;
+            config.analysis.complexity_scoring = false;
+            config.analysis.risk_assessment = false;
+            config.output.include_function_details = false;
+        });
+        let repo = &report.repository;
+        assert!(repo.files[0].functions.is_empty());
+        assert!(repo.semantic_patterns.is_empty());
+        assert!(report.summary.top_semantic_patterns.is_empty());
+        assert_eq!(repo.aggregated.complexity_score, 0.0);
+        assert_eq!(repo.risk_summary.risk_level, "not_assessed");
+        assert_eq!(
+            report.summary.highest_complexity_functions[0].complexity_score,
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_parse_diagnostics() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("ok.rs"),
+            "pub fn ok() {}
+",
+        )
+        .unwrap();
+        // Edition-2024 `gen` block after two good functions
+        std::fs::write(
+            dir.path().join("partial.rs"),
+            "pub fn a() {}\npub fn b() {}\npub fn c() {\n    let _ = gen { yield 1 };\n}\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("broken.rs"), "pub fn broken( {\n").unwrap();
+
+        let report = analyze(dir.path(), |_| {});
+        assert_eq!(report.repository.file_count, 2);
+        assert_eq!(report.repository.total_function_count, 3);
+        let mut diagnostics = report.diagnostics.clone();
+        diagnostics.sort_by(|a, b| a.path.cmp(&b.path));
+        assert_eq!(diagnostics[0].path, "broken.rs");
+        assert_eq!(diagnostics[0].recovered_items, 0);
+        assert_eq!(diagnostics[1].path, "partial.rs");
+        assert_eq!(diagnostics[1].recovered_items, 2);
+        assert_eq!(diagnostics[1].location.as_ref().unwrap().line, 4);
+
+        let engine = AnalyzerEngine::new(AnalyzerConfig {
+            root_path: dir.path().to_path_buf(),
+            ..Default::default()
+        })
+        .unwrap();
+        let factors = engine.analyze_factors(&FactorRegistry::default()).unwrap();
+        assert_eq!(factors.diagnostics.len(), 2);
+        assert!(factors.incomplete.contains(&"complexity".to_string()));
+        assert!(!factors.incomplete.contains(&"dependencies".to_string()));
+        assert_eq!(factors.details["functionCount"]["totalFunctions"], 3);
+        assert!(factors
+            .notes
+            .iter()
+            .any(|note| note.starts_with("2 selected file(s) could not be fully parsed")));
+    }
+}
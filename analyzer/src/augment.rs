@@ -0,0 +1,129 @@
+//! The augment response shared by the `/augment` endpoint and `cli augment`
+//!
+//! Both front ends run the full [`FactorRegistry`](crate::FactorRegistry)
+//! through [`AnalyzerEngine::analyze_selected_factors`](crate::AnalyzerEngine::analyze_selected_factors)
+//! and wrap the result here, so a local run yields the same JSON the platform
+//! computes.
+
+use crate::error::{AnalyzerError, Result};
+use crate::factors::FactorReport;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// API version reported when the caller does not ask for one
+pub const DEFAULT_API_VERSION: &str = "v1";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AugmentResponseMeta {
+    pub api_version: String,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AugmentResponse {
+    pub success: bool,
+    pub workspace_id: String,
+    pub overridden: Vec<String>,
+    pub factors: serde_json::Value,
+    pub raw: serde_json::Value,
+    pub meta: AugmentResponseMeta,
+}
+
+impl AugmentResponse {
+    /// Wrap a factor run over `workspace_path`
+    ///
+    /// A failed run (a required factor errored) becomes `success: false`
+    /// with the error in `raw`.
+    pub fn new(
+        workspace_id: String,
+        workspace_path: &Path,
+        selected_files: Option<&[String]>,
+        api_version: Option<String>,
+        result: Result<FactorReport>,
+    ) -> Self {
+        let meta = AugmentResponseMeta {
+            api_version: api_version.unwrap_or_else(|| DEFAULT_API_VERSION.to_string()),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        };
+
+        match result {
+            Ok(report) => Self {
+                success: true,
+                workspace_id,
+                raw: serde_json::json!({
+                    "selectedFiles": selected_files,
+                    "computed": report.computed,
+                    "cached": report.cached,
+                    "compositeScore": report.composite_score,
+                    "normalization": report.normalization,
+                    "notes": report.notes,
+                    "diagnostics": report.diagnostics,
+                    "incompleteFactors": report.incomplete,
+                    "workspacePath": workspace_path.to_string_lossy(),
+                    "timestamp": chrono::Utc::now().to_rfc3339()
+                }),
+                overridden: report.computed,
+                factors: serde_json::Value::Object(report.factors),
+                meta,
+            },
+            Err(error) => {
+                let message = match error {
+                    AnalyzerError::AnalysisError { message } => message,
+                    other => other.to_string(),
+                };
+                Self {
+                    success: false,
+                    workspace_id,
+                    overridden: Vec::new(),
+                    factors: serde_json::Value::Object(serde_json::Map::new()),
+                    raw: serde_json::json!({
+                        "error": message,
+                        "timestamp": chrono::Utc::now().to_rfc3339()
+                    }),
+                    meta,
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AnalyzerConfig;
+    use crate::{AnalyzerEngine, FactorRegistry};
+
+    #[test]
+    fn test_response() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("lib.rs"), "pub fn f(a: u64) -> u64 { a }\n").unwrap();
+        let engine = AnalyzerEngine::new(AnalyzerConfig {
+            root_path: dir.path().to_path_buf(),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let files = vec!["lib.rs".to_string()];
+        let result = engine.analyze_selected_factors(&FactorRegistry::default(), &files, None);
+        let response =
+            AugmentResponse::new("local".to_string(), dir.path(), Some(&files), None, result);
+        assert!(response.success);
+        assert_eq!(response.meta.api_version, "v1");
+        assert!(response.overridden.contains(&"locFactor".to_string()));
+        assert!(response.factors["locFactor"].is_number());
+        assert_eq!(response.raw["selectedFiles"], serde_json::json!(["lib.rs"]));
+
+        let failed = AugmentResponse::new(
+            "local".to_string(),
+            dir.path(),
+            None,
+            Some("v2".to_string()),
+            Err(AnalyzerError::AnalysisError {
+                message: "factor failed".to_string(),
+            }),
+        );
+        assert!(!failed.success);
+        assert_eq!(failed.raw["error"], "factor failed");
+        assert_eq!(failed.meta.api_version, "v2");
+    }
+}
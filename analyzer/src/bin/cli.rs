@@ -1,9 +1,15 @@
 //! Command-line interface for the AMM analyzer
 
-use amm_analyzer::{analyze_repository, AnalyzerConfig};
-use anyhow::Result;
+use amm_analyzer::augment::AugmentResponse;
+use amm_analyzer::check::{self, Baseline};
+use amm_analyzer::config::{ProjectConfig, PROJECT_CONFIG_FILE};
+use amm_analyzer::html::HtmlOutput;
+use amm_analyzer::markdown::MarkdownOutput;
+use amm_analyzer::output::SarifOutput;
+use amm_analyzer::{analyze_repository, AnalyzerEngine, FactorRegistry, OutputFormat, ReportDiff};
+use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Parser)]
 #[command(name = "amm-analyzer")]
@@ -18,17 +24,26 @@ struct Cli {
 enum Commands {
     /// Analyze a repository or directory
     Analyze {
-        /// Path to the repository or directory to analyze
-        #[arg(short, long, default_value = ".")]
-        path: PathBuf,
+        /// Path to the repository or directory to analyze (default: `.`)
+        #[arg(short, long)]
+        path: Option<PathBuf>,
 
-        /// Output format (json, yaml, toml)
-        #[arg(short, long, default_value = "json")]
-        format: String,
+        /// Project configuration (default: `bulwark.toml` at the repository root, if any)
+        #[arg(short, long)]
+        config: Option<PathBuf>,
 
-        /// Pretty print output
-        #[arg(long, default_value_t = true)]
-        pretty: bool,
+        /// Output format (json, yaml, toml, sarif, html, markdown) [default: json]
+        #[arg(short, long)]
+        format: Option<String>,
+
+        /// Pretty print output (`--pretty false` for compact output) [default: true]
+        #[arg(
+            long,
+            num_args = 0..=1,
+            default_missing_value = "true",
+            action = clap::ArgAction::Set,
+        )]
+        pretty: Option<bool>,
 
         /// Include test files in analysis
         #[arg(long)]
@@ -46,14 +61,22 @@ enum Commands {
         #[arg(long)]
         expand_macros: bool,
 
-        /// Maximum file size to analyze in bytes
-        #[arg(long, default_value = "1048576")] // 1MB
-        max_file_size: usize,
+        /// Maximum file size to analyze in bytes [default: 1048576]
+        #[arg(long)]
+        max_file_size: Option<usize>,
 
         /// Output file (if not specified, outputs to stdout)
         #[arg(short, long)]
         output: Option<PathBuf>,
 
+        /// Cache per-file results in this directory and reuse them for unchanged files
+        #[arg(long)]
+        cache_dir: Option<PathBuf>,
+
+        /// Files to analyze in parallel (0 = one per CPU core) [default: 0]
+        #[arg(long)]
+        workers: Option<usize>,
+
         /// Enable verbose logging
         #[arg(short, long)]
         verbose: bool,
@@ -68,8 +91,14 @@ enum Commands {
         #[arg(short, long, default_value = "json")]
         format: String,
 
-        /// Pretty print output
-        #[arg(long, default_value_t = true)]
+        /// Pretty print output (`--pretty false` for compact output)
+        #[arg(
+            long,
+            default_value_t = true,
+            num_args = 0..=1,
+            default_missing_value = "true",
+            action = clap::ArgAction::Set,
+        )]
         pretty: bool,
 
         /// Enable verbose logging
@@ -77,11 +106,116 @@ enum Commands {
         verbose: bool,
     },
 
+    /// Compute the augment factor set (the `/augment` response) offline
+    Augment {
+        /// Path to the workspace (default: `.`)
+        path: Option<PathBuf>,
+
+        /// Project configuration (default: `bulwark.toml` at the workspace root, if any)
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+
+        /// Files to analyze, relative to the workspace (default: every Rust file)
+        #[arg(long, num_args = 1..)]
+        files: Option<Vec<String>>,
+
+        /// Solana RPC URL for on-chain analysis
+        #[arg(long)]
+        rpc_url: Option<String>,
+
+        /// Expand macros with a local toolchain so factors see generated code
+        /// (requires the `macro-expansion` feature)
+        #[arg(long)]
+        expand_macros: bool,
+
+        /// Workspace ID to report (default: the directory name)
+        #[arg(long)]
+        workspace_id: Option<String>,
+
+        /// API version to report
+        #[arg(long)]
+        api_version: Option<String>,
+
+        /// Pretty print output (`--pretty false` for compact output) [default: true]
+        #[arg(
+            long,
+            num_args = 0..=1,
+            default_missing_value = "true",
+            action = clap::ArgAction::Set,
+        )]
+        pretty: Option<bool>,
+
+        /// Output file (if not specified, outputs to stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Cache per-factor results in this directory and reuse them for unchanged files
+        #[arg(long)]
+        cache_dir: Option<PathBuf>,
+
+        /// Files to analyze in parallel (0 = one per CPU core) [default: 0]
+        #[arg(long)]
+        workers: Option<usize>,
+
+        /// Enable verbose logging
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Compare two analysis reports or augment outputs
+    Diff {
+        /// Earlier report (JSON)
+        old: PathBuf,
+
+        /// Later report (JSON)
+        new: PathBuf,
+
+        /// Output format (text, json, yaml, toml, markdown)
+        #[arg(short, long, default_value = "text")]
+        format: String,
+
+        /// Pretty print output (`--pretty false` for compact output)
+        #[arg(
+            long,
+            default_value_t = true,
+            num_args = 0..=1,
+            default_missing_value = "true",
+            action = clap::ArgAction::Set,
+        )]
+        pretty: bool,
+
+        /// Output file (if not specified, outputs to stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Fail (exit code 1) when the analysis trips a rule in the project config
+    Check {
+        /// Path to the repository or directory to check (default: `.`)
+        #[arg(short, long)]
+        path: Option<PathBuf>,
+
+        /// Project configuration (default: `bulwark.toml` at the repository root)
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+
+        /// Store this run as the configured baseline instead of checking
+        #[arg(long)]
+        write_baseline: bool,
+
+        /// Enable verbose logging
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
     /// Validate configuration file
     Config {
         /// Path to configuration file
         config: PathBuf,
     },
+
+    /// List the registered analysis factors
+    Factors,
 }
 
 fn main() -> Result<()> {
@@ -90,6 +224,7 @@ fn main() -> Result<()> {
     match cli.command {
         Commands::Analyze {
             path,
+            config,
             format,
             pretty,
             include_tests,
@@ -98,6 +233,8 @@ fn main() -> Result<()> {
             expand_macros,
             max_file_size,
             output,
+            cache_dir,
+            workers,
             verbose,
         } => {
             if verbose {
@@ -110,30 +247,48 @@ fn main() -> Result<()> {
                     .init();
             }
 
-            let config = AnalyzerConfig {
-                root_path: path,
-                include_tests,
-                include_benches,
-                include_examples,
-                expand_macros,
-                max_file_size,
-                output: amm_analyzer::config::OutputConfig {
-                    format,
-                    pretty,
-                    include_snippets: false,
-                    include_function_details: true,
-                },
-                ..Default::default()
-            };
+            let project = load_project_config(path, config)?;
+            let registry = project.factor_registry()?;
 
-            log::info!("Starting analysis of repository: {:?}", config.root_path);
+            // Flags given on the command line win over the configuration file
+            let mut config = project.analyzer;
+            config.include_tests |= include_tests;
+            config.include_benches |= include_benches;
+            config.include_examples |= include_examples;
+            config.expand_macros |= expand_macros;
+            config.max_file_size = max_file_size.unwrap_or(config.max_file_size);
+            config.cache_dir = cache_dir.or(config.cache_dir);
+            config.workers = workers.unwrap_or(config.workers);
+            config.output.format = format.unwrap_or(config.output.format);
+            config.output.pretty = pretty.unwrap_or(config.output.pretty);
 
-            let report = analyze_repository(config)?;
+            // Reject an unknown format before spending time on the analysis
+            let output_format: OutputFormat = config.output.format.parse()?;
 
-            let output_content = if pretty {
-                serde_json::to_string_pretty(&report)?
-            } else {
-                serde_json::to_string(&report)?
+            log::info!("Starting analysis of repository: {:?}", config.root_path);
+
+            let output_config = config.output.clone();
+            let output_content = match output_format {
+                OutputFormat::Sarif => {
+                    // SARIF reports the factors' located findings
+                    let report = AnalyzerEngine::new(config)?.analyze_factors(&registry)?;
+                    let log = SarifOutput::log(registry.rules(), &report.findings);
+                    amm_analyzer::output::render(&log, &output_config)?
+                }
+                OutputFormat::Html | OutputFormat::Markdown => {
+                    let engine = AnalyzerEngine::new(config)?;
+                    let report = engine.analyze()?;
+                    let factors = engine.analyze_factors(&registry)?;
+                    if output_format == OutputFormat::Html {
+                        HtmlOutput::render(&report, &factors, &registry)
+                    } else {
+                        MarkdownOutput::render(&report, &factors, &registry)
+                    }
+                }
+                _ => {
+                    let report = analyze_repository(config)?;
+                    amm_analyzer::output::render(&report, &output_config)?
+                }
             };
 
             match output {
@@ -163,32 +318,217 @@ fn main() -> Result<()> {
                     .init();
             }
 
+            let format: OutputFormat = format.parse()?;
+            if matches!(
+                format,
+                OutputFormat::Sarif | OutputFormat::Html | OutputFormat::Markdown
+            ) {
+                anyhow::bail!("{} output is only available for `analyze`", format.name());
+            }
+
             log::info!("Analyzing single file: {:?}", path);
 
             let metrics = amm_analyzer::analyze_file(&path)?;
 
-            let output_content = if pretty {
-                serde_json::to_string_pretty(&metrics)?
+            let output_content = format.render(&metrics, pretty)?;
+
+            println!("{}", output_content);
+        }
+
+        Commands::Augment {
+            path,
+            config,
+            files,
+            rpc_url,
+            expand_macros,
+            workspace_id,
+            api_version,
+            pretty,
+            output,
+            cache_dir,
+            workers,
+            verbose,
+        } => {
+            if verbose {
+                env_logger::Builder::from_env(
+                    env_logger::Env::default().default_filter_or("debug"),
+                )
+                .init();
             } else {
-                serde_json::to_string(&metrics)?
+                env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn"))
+                    .init();
+            }
+
+            let project = load_project_config(path, config)?;
+            let registry = project.factor_registry()?;
+            let mut config = project.analyzer;
+            config.cache_dir = cache_dir.or(config.cache_dir);
+            config.workers = workers.unwrap_or(config.workers);
+            config.expand_macros |= expand_macros;
+            let pretty = pretty.unwrap_or(config.output.pretty);
+            let path = config.root_path.clone();
+
+            let workspace_id = workspace_id.unwrap_or_else(|| {
+                path.canonicalize()
+                    .ok()
+                    .and_then(|path| {
+                        path.file_name()
+                            .map(|name| name.to_string_lossy().to_string())
+                    })
+                    .unwrap_or_else(|| path.to_string_lossy().to_string())
+            });
+            let engine = AnalyzerEngine::new(config)?;
+            let files = match files {
+                Some(files) => files,
+                None => engine.relative_rust_files()?,
             };
+            let result = engine.analyze_selected_factors(&registry, &files, rpc_url.as_deref());
+            let response =
+                AugmentResponse::new(workspace_id, &path, Some(&files), api_version, result);
 
-            println!("{}", output_content);
+            let output_content = OutputFormat::Json.render(&response, pretty)?;
+            match output {
+                Some(output_path) => std::fs::write(output_path, output_content)?,
+                None => println!("{}", output_content),
+            }
+            if !response.success {
+                std::process::exit(1);
+            }
+        }
+
+        Commands::Diff {
+            old,
+            new,
+            format,
+            pretty,
+            output,
+        } => {
+            let read = |path: &PathBuf| -> Result<serde_json::Value> {
+                let content = std::fs::read_to_string(path)?;
+                Ok(serde_json::from_str(&content)?)
+            };
+            let diff = ReportDiff::from_json(&read(&old)?, &read(&new)?)?;
+
+            let output_content = if format == "text" {
+                diff.to_text()
+            } else {
+                match format.parse::<OutputFormat>()? {
+                    OutputFormat::Markdown => diff.to_markdown(),
+                    OutputFormat::Sarif | OutputFormat::Html => {
+                        anyhow::bail!("{} output is not available for `diff`", format)
+                    }
+                    serialized => serialized.render(&diff, pretty)?,
+                }
+            };
+
+            match output {
+                Some(output_path) => std::fs::write(output_path, output_content)?,
+                None => print!("{}", output_content),
+            }
+        }
+
+        Commands::Check {
+            path,
+            config,
+            write_baseline,
+            verbose,
+        } => {
+            if verbose {
+                env_logger::Builder::from_env(
+                    env_logger::Env::default().default_filter_or("debug"),
+                )
+                .init();
+            } else {
+                env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn"))
+                    .init();
+            }
+
+            // Checking needs rules, so the configuration file must exist
+            let config_path = config.unwrap_or_else(|| {
+                path.as_deref()
+                    .unwrap_or(Path::new("."))
+                    .join(PROJECT_CONFIG_FILE)
+            });
+            let project = load_project_config(path, Some(config_path.clone()))?;
+            let baseline_path = project.check.baseline.clone();
+
+            let registry = project.factor_registry()?;
+            let engine = AnalyzerEngine::new(project.analyzer)?;
+            let factors = engine.analyze_factors(&registry)?;
+
+            if write_baseline {
+                let Some(baseline_path) = baseline_path else {
+                    anyhow::bail!("{} sets no `check.baseline` path", config_path.display());
+                };
+                Baseline::from_report(&factors).save(&baseline_path)?;
+                println!("Baseline written to {}", baseline_path.display());
+                return Ok(());
+            }
+
+            let report = engine.analyze()?;
+            let baseline = baseline_path
+                .map(|path| Baseline::load(&path))
+                .transpose()
+                .context("create the baseline with `check --write-baseline`")?;
+            let violations = check::evaluate(
+                &project.check,
+                &registry,
+                &report,
+                &factors,
+                baseline.as_ref(),
+            )?;
+
+            if violations.is_empty() {
+                println!("Check passed");
+            } else {
+                for violation in &violations {
+                    println!("FAIL {}", violation);
+                }
+                println!("Check failed: {} rule(s) tripped", violations.len());
+                std::process::exit(1);
+            }
         }
 
         Commands::Config { config } => {
             log::info!("Validating configuration file: {:?}", config);
 
-            let content = std::fs::read_to_string(&config)?;
-            let _config: AnalyzerConfig = toml::from_str(&content)?;
+            let project = ProjectConfig::from_file(&config)?;
+            project.factor_registry()?;
 
             println!("Configuration file is valid!");
         }
+
+        Commands::Factors => {
+            for factor in FactorRegistry::default().factors() {
+                println!(
+                    "{:<26} {:<8} {}{}",
+                    factor.id(),
+                    factor.version(),
+                    factor.name(),
+                    if factor.required() { " (required)" } else { "" }
+                );
+            }
+        }
     }
 
     Ok(())
 }
 
+/// Project configuration from `--config`, else `bulwark.toml` at the root, else defaults
+///
+/// An explicit `--path` replaces the configured `root_path`.
+fn load_project_config(path: Option<PathBuf>, config: Option<PathBuf>) -> Result<ProjectConfig> {
+    let root = path.clone().unwrap_or_else(|| PathBuf::from("."));
+    let mut project = match &config {
+        Some(config) => ProjectConfig::from_file(config)?,
+        None => ProjectConfig::load(&root)?,
+    };
+    if let (Some(path), Some(_)) = (path, config) {
+        project.analyzer.root_path = path;
+    }
+    Ok(project)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2,30 +2,10 @@
 //!
 //! Provides REST API endpoints for semantic analysis of Rust smart contracts
 
-use amm_analyzer::factors::{
-    calculate_workspace_access_control,
-    calculate_workspace_arithmetic,
-    calculate_workspace_asset_types,
-    calculate_workspace_composability,
-    calculate_workspace_constraint_density,
-    calculate_workspace_cpi_calls,
-    calculate_workspace_cyclomatic_complexity,
-    calculate_workspace_dependencies,
-    calculate_workspace_dos_resource_limits,
-    calculate_workspace_error_handling,
-    calculate_workspace_external_integration,
-    calculate_workspace_input_constraints,
-    calculate_workspace_modularity,
-    calculate_workspace_operational_security,
-    // calculate_workspace_oracle_price_feed, // TODO: Implement oracle_price_feed module
-    calculate_workspace_pda_seeds,
-    calculate_workspace_privileged_roles, // calculate_workspace_statefulness, // TODO: Implement statefulness module
-    calculate_workspace_unsafe_lowlevel,
-    calculate_workspace_upgradeability,
-    count_total_functions,
-    lines_of_code::{analyze_file_tsc, calculate_workspace_tsc},
-};
-use amm_analyzer::{analyze_repository, AnalyzerConfig};
+use amm_analyzer::augment::AugmentResponse;
+use amm_analyzer::factors::FactorReport;
+use amm_analyzer::workspace::release_thread_spans;
+use amm_analyzer::{analyze_repository, AnalyzerConfig, AnalyzerEngine, FactorRegistry};
 use axum::{
     extract::Json,
     http::StatusCode,
@@ -34,7 +14,11 @@ use axum::{
     Router,
 };
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, fs, net::SocketAddr, path::PathBuf};
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+};
 use tower_http::cors::CorsLayer;
 
 #[derive(Debug, Deserialize)]
@@ -43,12 +27,14 @@ struct AnalysisRequest {
     workspace_id: String,
 
     /// Selected files to analyze (relative paths)
+    #[allow(dead_code)] // Accepted for API compatibility; /analyze walks the whole workspace
     selected_files: Option<Vec<String>>,
 
     /// Analysis options
     options: Option<AnalysisOptions>,
 
     /// Solana RPC URL for on-chain analysis (optional)
+    #[allow(dead_code)] // Only used by /augment
     rpc_url: Option<String>,
 }
 
@@ -117,7 +103,7 @@ struct DirectTestResponse {
     mode: String,
 }
 
-// Augmentation request/response (initial stub)
+// Augmentation request; the response is shared with `cli augment`
 #[derive(Debug, Deserialize)]
 struct AugmentRequest {
     workspace_id: String,
@@ -127,20 +113,25 @@ struct AugmentRequest {
     rpc_url: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
-struct AugmentResponseMeta {
-    api_version: String,
-    timestamp: String,
+/// Result cache directory, if `ANALYZER_CACHE_DIR` is set
+fn cache_dir() -> Option<PathBuf> {
+    std::env::var("ANALYZER_CACHE_DIR").ok().map(PathBuf::from)
 }
 
-#[derive(Debug, Serialize)]
-struct AugmentResponse {
-    success: bool,
-    workspace_id: String,
-    overridden: Vec<String>,
-    factors: serde_json::Value,
-    raw: serde_json::Value,
-    meta: AugmentResponseMeta,
+/// Whether `ANALYZER_ALLOW_MACRO_EXPANSION` lets requests expand macros
+///
+/// Expansion builds the uploaded workspace, running its build scripts and
+/// proc macros on the server, so it stays off unless the operator opts in.
+fn macro_expansion_allowed() -> bool {
+    std::env::var("ANALYZER_ALLOW_MACRO_EXPANSION")
+        .is_ok_and(|allowed| matches!(allowed.as_str(), "1" | "true"))
+}
+
+/// Worker threads per request from `ANALYZER_WORKERS`, if set; 0 means one per core
+fn workers() -> Option<usize> {
+    std::env::var("ANALYZER_WORKERS")
+        .ok()
+        .and_then(|workers| workers.parse().ok())
 }
 
 /// Health check endpoint
@@ -234,53 +225,6 @@ async fn test_direct(Json(request): Json<DirectTestRequest>) -> ResponseJson<Dir
     })
 }
 
-/// Helper function to calculate function metrics for workspace files
-fn calculate_workspace_functions(
-    workspace_path: &PathBuf,
-    selected_files: &[String],
-) -> Result<amm_analyzer::factors::function_count::FunctionCountMetrics, Box<dyn std::error::Error>>
-{
-    use amm_analyzer::factors::function_count::FunctionCountMetrics;
-
-    let mut aggregated_metrics = FunctionCountMetrics::default();
-
-    for file_path in selected_files {
-        let full_file_path = workspace_path.join(file_path);
-
-        // Check if the file exists and is a Rust file
-        if full_file_path.exists() && full_file_path.is_file() {
-            if let Some(extension) = full_file_path.extension() {
-                if extension == "rs" {
-                    match fs::read_to_string(&full_file_path) {
-                        Ok(content) => {
-                            if let Ok(metrics) =
-                                amm_analyzer::factors::function_count::count_functions(&content)
-                            {
-                                aggregated_metrics.total_functions += metrics.total_functions;
-                                aggregated_metrics.public_functions += metrics.public_functions;
-                                aggregated_metrics.private_functions += metrics.private_functions;
-                                aggregated_metrics.associated_functions +=
-                                    metrics.associated_functions;
-                                aggregated_metrics.free_functions += metrics.free_functions;
-                            }
-                        }
-                        Err(_) => {
-                            // Skip files that can't be read
-                            continue;
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    // Calculate function factor for the aggregated metrics
-    aggregated_metrics.function_factor =
-        FunctionCountMetrics::calculate_function_factor(aggregated_metrics.total_functions);
-
-    Ok(aggregated_metrics)
-}
-
 /// Augmentation endpoint: calculates actual factor values from workspace files
 async fn augment(Json(request): Json<AugmentRequest>) -> ResponseJson<AugmentResponse> {
     let start_time = std::time::Instant::now();
@@ -296,979 +240,51 @@ async fn augment(Json(request): Json<AugmentRequest>) -> ResponseJson<AugmentRes
         .unwrap_or_else(|_| "/tmp/shared/workspaces".to_string());
     let full_path = PathBuf::from(workspace_path).join(&request.workspace_id);
 
-    let mut factors_map = serde_json::Map::new();
-    let mut computed_factors = Vec::new();
-    let mut notes = Vec::new();
-
-    // Calculate actual lines of code from workspace files using TSC metrics
     let selected_files = request.selected_files.as_deref().unwrap_or(&[]);
-    match calculate_workspace_tsc(&full_path, selected_files) {
-        Ok(tsc_metrics) => {
-            // Add totalLinesOfCode (total statements)
-            factors_map.insert(
-                "totalLinesOfCode".to_string(),
-                serde_json::json!(tsc_metrics.total_statements),
-            );
-            computed_factors.push("totalLinesOfCode".to_string());
-
-            // Add locFactor (normalized 0-100 score)
-            factors_map.insert(
-                "locFactor".to_string(),
-                serde_json::json!(tsc_metrics.loc_factor),
-            );
-            computed_factors.push("locFactor".to_string());
-
-            // Add all TSC metrics for reference
-            factors_map.insert("tscMetrics".to_string(), tsc_metrics.to_json());
-
-            notes.push(format!(
-                "Calculated {} statements (LOC) from workspace files, LOC factor: {:.2}",
-                tsc_metrics.total_statements, tsc_metrics.loc_factor
-            ));
-            log::info!(
-                "Calculated {} statements (LOC) for workspace {}, LOC factor: {:.2}",
-                tsc_metrics.total_statements,
-                request.workspace_id,
-                tsc_metrics.loc_factor
-            );
-        }
-        Err(e) => {
-            log::error!(
-                "Failed to calculate lines of code for workspace {}: {}",
-                request.workspace_id,
-                e
-            );
-            // Fallback to reasonable defaults
-            factors_map.insert("totalLinesOfCode".to_string(), serde_json::json!(100));
-            factors_map.insert("locFactor".to_string(), serde_json::json!(0.0));
-            computed_factors.push("totalLinesOfCode".to_string());
-            computed_factors.push("locFactor".to_string());
-            notes.push(format!("Fallback values used due to error: {}", e));
-        }
-    }
-
-    // Calculate actual function count from workspace files
-    match calculate_workspace_functions(&full_path, selected_files) {
-        Ok(function_metrics) => {
-            // Add numFunctions (total function count)
-            factors_map.insert(
-                "numFunctions".to_string(),
-                serde_json::json!(function_metrics.total_functions),
-            );
-            computed_factors.push("numFunctions".to_string());
-
-            // Add functionFactor (normalized 0-100 score)
-            factors_map.insert(
-                "functionFactor".to_string(),
-                serde_json::json!(function_metrics.function_factor),
-            );
-            computed_factors.push("functionFactor".to_string());
-
-            // Add all function count metrics for reference
-            factors_map.insert(
-                "functionCountMetrics".to_string(),
-                function_metrics.to_json(),
-            );
-
-            notes.push(format!(
-                "Calculated {} functions from workspace files, function factor: {:.2}",
-                function_metrics.total_functions, function_metrics.function_factor
-            ));
-            log::info!(
-                "Calculated {} functions for workspace {}, function factor: {:.2}",
-                function_metrics.total_functions,
-                request.workspace_id,
-                function_metrics.function_factor
-            );
-        }
-        Err(e) => {
-            log::error!(
-                "Failed to calculate functions for workspace {}: {}",
-                request.workspace_id,
-                e
-            );
-            // Fallback to reasonable defaults
-            factors_map.insert("numFunctions".to_string(), serde_json::json!(10));
-            factors_map.insert("functionFactor".to_string(), serde_json::json!(0.0));
-            computed_factors.push("numFunctions".to_string());
-            computed_factors.push("functionFactor".to_string());
-            notes.push(format!("Function count fallback used due to error: {}", e));
-        }
-    }
-
-    // Calculate cyclomatic complexity from workspace files
-    match calculate_workspace_cyclomatic_complexity(&full_path, selected_files) {
-        Ok(complexity_metrics) => {
-            // Add full complexity metrics object
-            factors_map.insert("complexity".to_string(), complexity_metrics.to_json());
-            computed_factors.push("complexity".to_string());
-
-            // Add complexityFactor as a top-level property for easy access
-            factors_map.insert(
-                "complexityFactor".to_string(),
-                serde_json::json!(complexity_metrics.complexity_factor),
-            );
-            computed_factors.push("complexityFactor".to_string());
-
-            notes.push(format!(
-                "Calculated cyclomatic complexity: avg={:.2}, max={}, functions={}, complexity factor={:.2}",
-                complexity_metrics.avg_complexity,
-                complexity_metrics.max_complexity,
-                complexity_metrics.total_functions,
-                complexity_metrics.complexity_factor
-            ));
-            log::info!(
-                "Calculated cyclomatic complexity for workspace {}: avg={:.2}, max={}, functions={}, complexity factor={:.2}",
-                request.workspace_id,
-                complexity_metrics.avg_complexity,
-                complexity_metrics.max_complexity,
-                complexity_metrics.total_functions,
-                complexity_metrics.complexity_factor
-            );
-        }
-        Err(e) => {
-            log::error!(
-                "Failed to calculate cyclomatic complexity for workspace {}: {}",
-                request.workspace_id,
-                e
-            );
-            // Return error as requested - no fallback for complexity
-            return ResponseJson(AugmentResponse {
-                success: false,
-                workspace_id: request.workspace_id,
-                overridden: Vec::new(),
-                factors: serde_json::Value::Object(serde_json::Map::new()),
-                raw: serde_json::json!({
-                    "error": format!("Cyclomatic complexity calculation failed: {}", e),
-                    "timestamp": chrono::Utc::now().to_rfc3339()
-                }),
-                meta: AugmentResponseMeta {
-                    api_version: request.api_version.unwrap_or_else(|| "v1".to_string()),
-                    timestamp: chrono::Utc::now().to_rfc3339(),
-                },
-            });
-        }
-    }
-
-    // Calculate modularity metrics from workspace files
-    match calculate_workspace_modularity(&full_path, selected_files) {
-        Ok(modularity_metrics) => {
-            factors_map.insert("modularity".to_string(), modularity_metrics.to_json());
-            computed_factors.push("modularity".to_string());
-            notes.push(format!(
-                "Calculated modularity metrics: {} files, {} modules, score={:.1}, avg lines/file={:.1}",
-                modularity_metrics.total_files,
-                modularity_metrics.total_modules,
-                modularity_metrics.modularity_score,
-                modularity_metrics.avg_lines_per_file
-            ));
-            log::info!(
-                "Calculated modularity for workspace {}: {} files, {} modules, score={:.1}",
-                request.workspace_id,
-                modularity_metrics.total_files,
-                modularity_metrics.total_modules,
-                modularity_metrics.modularity_score
-            );
-        }
-        Err(e) => {
-            log::error!(
-                "Failed to calculate modularity for workspace {}: {}",
-                request.workspace_id,
-                e
-            );
-            // Return error as requested - no fallback for modularity
-            return ResponseJson(AugmentResponse {
-                success: false,
-                workspace_id: request.workspace_id,
-                overridden: Vec::new(),
-                factors: serde_json::Value::Object(serde_json::Map::new()),
-                raw: serde_json::json!({
-                    "error": format!("Modularity calculation failed: {}", e),
-                    "timestamp": chrono::Utc::now().to_rfc3339()
-                }),
-                meta: AugmentResponseMeta {
-                    api_version: request.api_version.unwrap_or_else(|| "v1".to_string()),
-                    timestamp: chrono::Utc::now().to_rfc3339(),
-                },
-            });
-        }
-    }
-
-    // Calculate access control metrics from workspace files
-    log::info!("📊 PROGRESS: Starting access control analysis...");
-    match calculate_workspace_access_control(&full_path, selected_files) {
-        Ok(access_control_metrics) => {
-            factors_map.insert(
-                "accessControl".to_string(),
-                access_control_metrics.to_json(),
-            );
-            computed_factors.push("accessControl".to_string());
-            notes.push(format!(
-                "Analyzed access control: {} total handlers, {} decorators, {} constraints, {} explicit checks, {} distinct patterns",
-            access_control_metrics.gated_handler_count,
-            access_control_metrics.manual_check_count,
-            access_control_metrics.account_close_count,
-            access_control_metrics.unique_role_count,
-            access_control_metrics.access_control_factor
-            ));
-            log::info!(
-                "Calculated access control for workspace {}: {} gated handlers, {} manual checks, {} account closes, {} unique roles, AC Factor: {:.2}",
-                request.workspace_id,
-                access_control_metrics.gated_handler_count,
-                access_control_metrics.manual_check_count,
-                access_control_metrics.account_close_count,
-                access_control_metrics.unique_role_count,
-                access_control_metrics.access_control_factor
-            );
-        }
-        Err(e) => {
-            log::error!(
-                "Failed to calculate access control for workspace {}: {}",
-                request.workspace_id,
-                e
-            );
-            // Return error as requested - no fallback for access control
-            return ResponseJson(AugmentResponse {
-                success: false,
-                workspace_id: request.workspace_id,
-                overridden: Vec::new(),
-                factors: serde_json::Value::Object(serde_json::Map::new()),
-                raw: serde_json::json!({
-                    "error": format!("Access control calculation failed: {}", e),
-                    "timestamp": chrono::Utc::now().to_rfc3339()
-                }),
-                meta: AugmentResponseMeta {
-                    api_version: request.api_version.unwrap_or_else(|| "v1".to_string()),
-                    timestamp: chrono::Utc::now().to_rfc3339(),
-                },
-            });
-        }
-    }
-
-    // Calculate PDA seed metrics from workspace files
-    log::info!(
-        "🔍 SERVER DEBUG: About to analyze PDA seeds for workspace: {:?}",
-        full_path
-    );
-    match calculate_workspace_pda_seeds(&full_path, selected_files) {
-        Ok(pda_metrics) => {
-            factors_map.insert("pdaSeeds".to_string(), pda_metrics.to_json());
-            computed_factors.push("pdaSeeds".to_string());
-            notes.push(format!(
-                "Analyzed PDA seeds: {} total accounts, {} complexity score, PDA Factor: {:.2}, {} distinct patterns",
-                pda_metrics.total_pda_accounts,
-                pda_metrics.total_seed_complexity_score,
-                pda_metrics.pda_complexity_factor,
-                pda_metrics.distinct_seed_patterns
-            ));
-            log::info!(
-                "Calculated PDA seeds for workspace {}: {} total accounts, {} complexity score, PDA Factor: {:.2}, {} distinct patterns",
-                request.workspace_id,
-                pda_metrics.total_pda_accounts,
-                pda_metrics.total_seed_complexity_score,
-                pda_metrics.pda_complexity_factor,
-                pda_metrics.distinct_seed_patterns
-            );
-        }
-        Err(e) => {
-            log::error!(
-                "Failed to calculate PDA seeds for workspace {}: {}",
-                request.workspace_id,
-                e
-            );
-            // Return error as requested - no fallback for PDA seeds
-            return ResponseJson(AugmentResponse {
-                success: false,
-                workspace_id: request.workspace_id,
-                overridden: Vec::new(),
-                factors: serde_json::Value::Object(serde_json::Map::new()),
-                raw: serde_json::json!({
-                    "error": format!("PDA seeds calculation failed: {}", e),
-                    "timestamp": chrono::Utc::now().to_rfc3339()
-                }),
-                meta: AugmentResponseMeta {
-                    api_version: request.api_version.unwrap_or_else(|| "v1".to_string()),
-                    timestamp: chrono::Utc::now().to_rfc3339(),
-                },
-            });
-        }
-    }
-
-    // Calculate CPI call metrics from workspace files
-    log::info!(
-        "🔍 SERVER DEBUG: About to analyze CPI calls for workspace: {:?}",
-        full_path
-    );
-    match calculate_workspace_cpi_calls(&full_path, selected_files) {
-        Ok(cpi_metrics) => {
-            factors_map.insert("cpiCalls".to_string(), cpi_metrics.to_json());
-            computed_factors.push("cpiCalls".to_string());
-            notes.push(format!(
-                "Analyzed CPI calls: {} total ({} signed, {} unsigned), {} unique programs, complexity={:.1}",
-                cpi_metrics.total_cpi_calls,
-                cpi_metrics.signed_cpi_calls,
-                cpi_metrics.unsigned_cpi_calls,
-                cpi_metrics.unique_programs,
-                cpi_metrics.cpi_factor
-            ));
-            log::info!(
-                "Calculated CPI calls for workspace {}: {} total, {} unique programs, complexity={:.1}",
-                request.workspace_id,
-                cpi_metrics.total_cpi_calls,
-                cpi_metrics.unique_programs,
-                cpi_metrics.cpi_factor
-            );
-        }
-        Err(e) => {
-            log::error!(
-                "Failed to calculate CPI calls for workspace {}: {}",
-                request.workspace_id,
-                e
-            );
-            // Return error as requested - no fallback for CPI calls
-            return ResponseJson(AugmentResponse {
-                success: false,
-                workspace_id: request.workspace_id,
-                overridden: Vec::new(),
-                factors: serde_json::Value::Object(serde_json::Map::new()),
-                raw: serde_json::json!({
-                    "error": format!("CPI calls calculation failed: {}", e),
-                    "timestamp": chrono::Utc::now().to_rfc3339()
-                }),
-                meta: AugmentResponseMeta {
-                    api_version: request.api_version.unwrap_or_else(|| "v1".to_string()),
-                    timestamp: chrono::Utc::now().to_rfc3339(),
-                },
-            });
-        }
-    }
-
-    // Calculate input/constraint surface metrics
-    log::info!(
-        "🔍 SERVER DEBUG: About to analyze input constraints for workspace: {:?}",
-        full_path
-    );
-    match calculate_workspace_input_constraints(&full_path, selected_files) {
-        Ok(input_constraint_metrics) => {
-            factors_map.insert(
-                "inputConstraints".to_string(),
-                input_constraint_metrics.to_json(),
-            );
-            computed_factors.push("inputConstraints".to_string());
-            notes.push(format!(
-                "Analyzed input constraints: {} handlers, avg {:.1} accounts, {} amount handlers, {} constraints",
-                input_constraint_metrics.total_handlers_found,
-                input_constraint_metrics.avg_accounts_per_handler,
-                input_constraint_metrics.total_amount_handlers,
-                input_constraint_metrics.total_constraints
-            ));
-            log::info!(
-                "Calculated input constraints for workspace {}: {} handlers, {} constraints",
-                request.workspace_id,
-                input_constraint_metrics.total_handlers_found,
-                input_constraint_metrics.total_constraints
-            );
-        }
-        Err(e) => {
-            log::error!(
-                "Failed to calculate input constraints for workspace {}: {}",
-                request.workspace_id,
-                e
-            );
-            // Return error as requested - no fallback for input constraints
-            return ResponseJson(AugmentResponse {
-                success: false,
-                workspace_id: request.workspace_id,
-                overridden: Vec::new(),
-                factors: serde_json::Value::Object(serde_json::Map::new()),
-                raw: serde_json::json!({
-                    "error": format!("Input constraints calculation failed: {}", e),
-                    "timestamp": chrono::Utc::now().to_rfc3339()
-                }),
-                meta: AugmentResponseMeta {
-                    api_version: request.api_version.unwrap_or_else(|| "v1".to_string()),
-                    timestamp: chrono::Utc::now().to_rfc3339(),
-                },
-            });
-        }
-    }
-
-    // Calculate arithmetic operation metrics
-    log::info!(
-        "🔍 SERVER DEBUG: About to analyze arithmetic operations for workspace: {:?}",
-        full_path
-    );
-    match calculate_workspace_arithmetic(&full_path, selected_files) {
-        Ok(arithmetic_metrics) => {
-            factors_map.insert(
-                "arithmeticOperations".to_string(),
-                arithmetic_metrics.to_json(),
-            );
-            computed_factors.push("arithmeticOperations".to_string());
-            notes.push(format!(
-                "Analyzed arithmetic operations: {} math handlers, {} high-risk ops, {} medium-risk ops, factor {:.1}",
-                arithmetic_metrics.total_math_handlers,
-                arithmetic_metrics.high_risk_ops_count,
-                arithmetic_metrics.medium_risk_ops_count,
-                arithmetic_metrics.arithmetic_factor
-            ));
-            log::info!(
-                "Calculated arithmetic operations for workspace {}: {} math handlers, factor {:.1}",
-                request.workspace_id,
-                arithmetic_metrics.total_math_handlers,
-                arithmetic_metrics.arithmetic_factor
-            );
-        }
-        Err(e) => {
-            log::error!(
-                "Failed to calculate arithmetic operations for workspace {}: {}",
-                request.workspace_id,
-                e
-            );
-            notes.push(format!("Arithmetic operations analysis failed: {}", e));
-        }
-    }
-
-    // Calculate asset types metrics
-    log::info!("📊 PROGRESS: Starting asset types analysis...");
-    match calculate_workspace_asset_types(&full_path, selected_files) {
-        Ok(asset_metrics) => {
-            factors_map.insert("assetTypes".to_string(), asset_metrics.to_json());
-            computed_factors.push("assetTypes".to_string());
-            notes.push(format!(
-                "Analyzed asset types: {} distinct standards (SPL-Token: {}, SPL-Token-2022: {}, Metaplex: {}, Custom: {}), factor {:.1}",
-                asset_metrics.distinct_asset_standards,
-                asset_metrics.uses_spl_token,
-                asset_metrics.uses_spl_token_2022,
-                asset_metrics.uses_metaplex_nft,
-                asset_metrics.custom_asset_definitions,
-                asset_metrics.asset_types_factor
-            ));
-            log::info!(
-                "Calculated asset types for workspace {}: {} distinct standards, factor {:.1}",
-                request.workspace_id,
-                asset_metrics.distinct_asset_standards,
-                asset_metrics.asset_types_factor
-            );
-        }
-        Err(e) => {
-            log::error!(
-                "Failed to calculate asset types for workspace {}: {}",
-                request.workspace_id,
-                e
-            );
-            notes.push(format!("Asset types analysis failed: {}", e));
-        }
-    }
-
-    // Calculate invariants and risk parameters metrics
-    log::info!("📊 PROGRESS: Starting invariants and risk parameters analysis...");
-    match calculate_workspace_constraint_density(&full_path, selected_files) {
-        Ok(invariants_metrics) => {
-            factors_map.insert(
-                "invariantsAndRiskParams".to_string(),
-                invariants_metrics.to_json(),
-            );
-            computed_factors.push("invariantsAndRiskParams".to_string());
-            notes.push(format!(
-                "Analyzed constraint density: {} total assertions, {} complexity score, factor {:.1}",
-                invariants_metrics.total_assertions,
-                invariants_metrics.total_assertion_complexity_score,
-                invariants_metrics.constraint_density_factor
-            ));
-            log::info!(
-                "Calculated constraint density for workspace {}: {} total assertions, {} complexity score, factor {:.1}",
-                request.workspace_id,
-                invariants_metrics.total_assertions,
-                invariants_metrics.total_assertion_complexity_score,
-                invariants_metrics.constraint_density_factor
-            );
-        }
-        Err(e) => {
-            log::error!(
-                "Failed to calculate invariants for workspace {}: {}",
-                request.workspace_id,
-                e
-            );
-            notes.push(format!("Invariants analysis failed: {}", e));
-        }
-    }
-
-    // Calculate oracle and price feed usage metrics
-    // TODO: Implement oracle_price_feed module
-    /*
-    log::info!("📊 PROGRESS: Starting oracle and price feed usage analysis...");
-    match calculate_workspace_oracle_price_feed(&full_path, selected_files) {
-        Ok(oracle_metrics) => {
-            factors_map.insert("oracleAndPriceFeedUsage".to_string(), oracle_metrics.to_json());
-            computed_factors.push("oracleAndPriceFeedUsage".to_string());
-            notes.push(format!(
-                "Analyzed oracle usage: {} oracle programs, {} price feed calls, {} staleness checks, total score {:.1}",
-                oracle_metrics.oracle_programs_detected,
-                oracle_metrics.price_feed_calls,
-                oracle_metrics.staleness_checks,
-                oracle_metrics.total_oracle_score
-            ));
-            log::info!(
-                "Calculated oracle usage for workspace {}: {} oracle programs, {} price feed calls, {} staleness checks, total score {:.1}",
-                request.workspace_id,
-                oracle_metrics.oracle_programs_detected,
-                oracle_metrics.price_feed_calls,
-                oracle_metrics.staleness_checks,
-                oracle_metrics.total_oracle_score
-            );
-        }
-        Err(e) => {
-            log::error!(
-                "Failed to calculate oracle usage for workspace {}: {}",
-                request.workspace_id,
-                e
-            );
-            notes.push(format!("Oracle usage analysis failed: {}", e));
-        }
-    }
-    */
-
-    // Calculate privileged roles and admin action metrics
-    log::info!(
-        "🔍 SERVER DEBUG: About to analyze privileged roles for workspace: {:?}",
-        full_path
-    );
-    match calculate_workspace_privileged_roles(&full_path, selected_files) {
-        Ok(privileged_roles_metrics) => {
-            factors_map.insert(
-                "privilegedRoles".to_string(),
-                privileged_roles_metrics.to_json(),
-            );
-            computed_factors.push("privilegedRoles".to_string());
-            notes.push(format!(
-                "Analyzed access control: {} gated handlers, {} account closes, {} manual checks, AC factor {:.1}",
-                privileged_roles_metrics.total_gated_handlers,
-                privileged_roles_metrics.total_account_closes,
-                privileged_roles_metrics.total_manual_checks,
-                privileged_roles_metrics.ac_factor
-            ));
-            log::info!(
-                "Calculated access control for workspace {}: {} handlers, AC factor {:.1}",
-                request.workspace_id,
-                privileged_roles_metrics.total_handlers_found,
-                privileged_roles_metrics.ac_factor
-            );
-        }
-        Err(e) => {
-            log::error!(
-                "Failed to calculate privileged roles for workspace {}: {}",
-                request.workspace_id,
-                e
-            );
-            notes.push(format!("Privileged roles analysis failed: {}", e));
-        }
-    }
-
-    // Calculate unsafe and low-level usage metrics
-    log::info!(
-        "🔍 SERVER DEBUG: About to analyze unsafe/low-level usage for workspace: {:?}",
-        full_path
-    );
-    match calculate_workspace_unsafe_lowlevel(&full_path, selected_files) {
-        Ok(unsafe_metrics) => {
-            factors_map.insert("unsafeLowLevel".to_string(), unsafe_metrics.to_json());
-            computed_factors.push("unsafeLowLevel".to_string());
-            notes.push(format!(
-                "Analyzed unsafe/low-level usage: {} unsafe blocks, {} unsafe functions, {} transmute usage, {} bytemuck usage, {} ptr operations, complexity score {:.1}",
-                unsafe_metrics.total_unsafe_blocks,
-                unsafe_metrics.total_unsafe_functions,
-                unsafe_metrics.transmute_usage,
-                unsafe_metrics.bytemuck_usage,
-                unsafe_metrics.ptr_operations,
-                unsafe_metrics.unsafe_complexity_score
-            ));
-            log::info!(
-                "Calculated unsafe/low-level usage for workspace {}: {} unsafe blocks, {} unsafe functions, {} transmute, {} bytemuck, complexity {:.1}",
-                request.workspace_id,
-                unsafe_metrics.total_unsafe_blocks,
-                unsafe_metrics.total_unsafe_functions,
-                unsafe_metrics.transmute_usage,
-                unsafe_metrics.bytemuck_usage,
-                unsafe_metrics.unsafe_complexity_score
-            );
-        }
-        Err(e) => {
-            log::error!(
-                "Failed to calculate unsafe/low-level usage for workspace {}: {}",
-                request.workspace_id,
-                e
-            );
-            notes.push(format!("Unsafe/low-level usage analysis failed: {}", e));
-        }
-    }
-
-    // Calculate error handling metrics
-    log::info!(
-        "🔍 SERVER DEBUG: About to analyze error handling for workspace: {:?}",
-        full_path
-    );
-    match calculate_workspace_error_handling(&full_path, selected_files) {
-        Ok(error_metrics) => {
-            factors_map.insert("errorHandling".to_string(), error_metrics.to_json());
-            computed_factors.push("errorHandling".to_string());
-            notes.push(format!(
-                "Analyzed error handling: {} require macros, {} require_eq macros, {} total invariants, factor {:.1}",
-                error_metrics.total_require_macros,
-                error_metrics.total_require_eq_macros,
-                error_metrics.total_invariants,
-                error_metrics.error_handling_factor
-            ));
-            log::info!(
-                "Calculated error handling for workspace {}: {} require, {} require_eq, {} total invariants, factor {:.1}",
-                request.workspace_id,
-                error_metrics.total_require_macros,
-                error_metrics.total_require_eq_macros,
-                error_metrics.total_invariants,
-                error_metrics.error_handling_factor
-            );
-        }
-        Err(e) => {
-            log::error!(
-                "Failed to calculate error handling for workspace {}: {}",
-                request.workspace_id,
-                e
-            );
-            notes.push(format!("Error handling analysis failed: {}", e));
-        }
-    }
-
-    // Calculate upgradeability and governance control metrics (2-phase hybrid analysis)
-    log::info!(
-        "🔍 SERVER DEBUG: About to analyze upgradeability for workspace: {:?}",
-        full_path
-    );
-    match calculate_workspace_upgradeability(&full_path, selected_files, request.rpc_url.as_deref())
-    {
-        Ok(upgradeability_metrics) => {
-            factors_map.insert(
-                "upgradeability".to_string(),
-                upgradeability_metrics.to_json(),
-            );
-            // Add governance factor as top-level property
-            factors_map.insert(
-                "governanceFactor".to_string(),
-                serde_json::Value::Number(
-                    serde_json::Number::from_f64(upgradeability_metrics.governance_factor).unwrap(),
-                ),
-            );
-            computed_factors.push("upgradeability".to_string());
-            computed_factors.push("governanceFactor".to_string());
-
-            let status_msg = if upgradeability_metrics.on_chain_analysis_performed {
-                format!(
-                    "Governance analysis complete: Program ID {}, Status: {}, Factor: {:.1}",
-                    upgradeability_metrics
-                        .program_id
-                        .as_deref()
-                        .unwrap_or("N/A"),
-                    upgradeability_metrics.governance_status,
-                    upgradeability_metrics.governance_factor
-                )
-            } else {
-                format!("Governance analysis skipped: Program ID {}, Status: {}, Factor: {:.1} (RPC: {})", 
-                    upgradeability_metrics.program_id.as_deref().unwrap_or("N/A"),
-                    upgradeability_metrics.governance_status,
-                    upgradeability_metrics.governance_factor,
-                    if request.rpc_url.is_some() { "provided but failed" } else { "not provided" })
-            };
-
-            notes.push(status_msg);
-            log::info!(
-                "Calculated upgradeability for workspace {}: Program ID: {:?}, Status: {}, Factor: {:.1}, On-chain: {}",
-                request.workspace_id,
-                upgradeability_metrics.program_id,
-                upgradeability_metrics.governance_status,
-                upgradeability_metrics.governance_factor,
-                upgradeability_metrics.on_chain_analysis_performed
-            );
-        }
-        Err(e) => {
-            log::error!(
-                "Failed to calculate upgradeability for workspace {}: {}",
-                request.workspace_id,
-                e
-            );
-            notes.push(format!("Upgradeability analysis failed: {}", e));
-        }
-    }
+    let result = augment_factors(&full_path, selected_files, request.rpc_url.as_deref());
+    // The request's syntax trees are gone; free their sources on this thread
+    release_thread_spans();
 
-    // Calculate external integration and oracle metrics
-    log::info!(
-        "🔍 SERVER DEBUG: About to analyze external integration for workspace: {:?}",
-        full_path
-    );
-    match calculate_workspace_external_integration(&full_path, selected_files) {
-        Ok(external_integration_metrics) => {
-            factors_map.insert(
-                "externalIntegration".to_string(),
-                external_integration_metrics.to_json(),
-            );
-            computed_factors.push("externalIntegration".to_string());
-            notes.push(format!(
-                    "Analyzed external integration: {} oracle integrations, {} bridge integrations, {} defi integrations, {} external cpi calls, risk score {:.1}",
-                    external_integration_metrics.total_oracle_integrations,
-                    external_integration_metrics.total_bridge_integrations,
-                    external_integration_metrics.total_defi_integrations,
-                    external_integration_metrics.external_cpi_calls,
-                    external_integration_metrics.integration_risk_score
-                ));
-            log::info!(
-                    "Calculated external integration for workspace {}: {} oracle, {} bridge, {} defi, {} cpi, risk {:.1}",
-                    request.workspace_id,
-                    external_integration_metrics.total_oracle_integrations,
-                    external_integration_metrics.total_bridge_integrations,
-                    external_integration_metrics.total_defi_integrations,
-                    external_integration_metrics.external_cpi_calls,
-                    external_integration_metrics.integration_risk_score
-                );
-        }
-        Err(e) => {
-            log::error!(
-                "Failed to calculate external integration for workspace {}: {}",
-                request.workspace_id,
-                e
-            );
-            notes.push(format!("External integration analysis failed: {}", e));
-        }
-    }
-
-    // Calculate composability and inter-program complexity metrics
-    log::info!("📊 PROGRESS: Starting composability analysis...");
-    match calculate_workspace_composability(&full_path, selected_files) {
-        Ok(composability_metrics) => {
-            factors_map.insert("composability".to_string(), composability_metrics.to_json());
-            computed_factors.push("composability".to_string());
-            notes.push(format!(
-                    "Analyzed composability: {} handlers found, {} multi-CPI handlers, composability factor {:.1}",
-                    composability_metrics.total_handlers_found,
-                    composability_metrics.multi_cpi_handlers_count,
-                    composability_metrics.composability_factor
-                ));
-            log::info!(
-                    "Calculated composability for workspace {}: {} handlers, {} multi-CPI handlers, factor {:.1}",
-                    request.workspace_id,
-                    composability_metrics.total_handlers_found,
-                    composability_metrics.multi_cpi_handlers_count,
-                    composability_metrics.composability_factor
-                );
-        }
-        Err(e) => {
-            log::error!(
-                "Failed to calculate composability for workspace {}: {}",
-                request.workspace_id,
-                e
-            );
-            notes.push(format!("Composability analysis failed: {}", e));
-        }
-    }
-
-    // Calculate statefulness and sequence of operations metrics
-    // TODO: Implement statefulness module
-    /*
-    log::info!(
-        "🔍 SERVER DEBUG: About to analyze statefulness for workspace: {:?}",
-        full_path
-    );
-    match calculate_workspace_statefulness(&full_path, selected_files) {
-        Ok(statefulness_metrics) => {
-            factors_map.insert("statefulness".to_string(), statefulness_metrics.to_json());
-            computed_factors.push("statefulness".to_string());
-            notes.push(format!(
-                "Analyzed statefulness: {} mutable accounts, {} sequence dependencies, {} multi-step workflows, {} race condition risks, complexity score {:.1}",
-                statefulness_metrics.mutable_account_patterns,
-                statefulness_metrics.sequence_dependency_patterns,
-                statefulness_metrics.multi_step_workflow_patterns,
-                statefulness_metrics.race_condition_risks,
-                statefulness_metrics.statefulness_complexity_score
-            ));
-            log::info!(
-                "Calculated statefulness for workspace {}: {} mutable accounts, {} sequences, {} workflows, {} race conditions, complexity {:.1}",
-                request.workspace_id,
-                statefulness_metrics.mutable_account_patterns,
-                statefulness_metrics.sequence_dependency_patterns,
-                statefulness_metrics.multi_step_workflow_patterns,
-                statefulness_metrics.race_condition_risks,
-                statefulness_metrics.statefulness_complexity_score
-            );
-        }
-        Err(e) => {
-            log::error!(
-                "Failed to calculate statefulness for workspace {}: {}",
-                request.workspace_id,
-                e
-            );
-            notes.push(format!("Statefulness analysis failed: {}", e));
-        }
-    }
-    */
-
-    // Calculate DOS and resource limits metrics
-    log::info!(
-        "🔍 SERVER DEBUG: About to analyze DOS resource limits for workspace: {:?}",
-        full_path
-    );
-    match calculate_workspace_dos_resource_limits(&full_path, selected_files) {
-        Ok(dos_metrics) => {
-            factors_map.insert("dosResourceLimits".to_string(), dos_metrics.to_json());
-            computed_factors.push("dosResourceLimits".to_string());
-            notes.push(format!(
-                "Analyzed DOS resource limits: {} handlers found, {} with vec params, {} with loops, {} dynamic space accounts, resource factor {:.1}",
-                dos_metrics.total_handlers_found,
-                dos_metrics.handlers_with_vec_params,
-                dos_metrics.handlers_with_loops,
-                dos_metrics.dynamic_space_accounts,
-                dos_metrics.resource_factor
-            ));
-            log::info!(
-                "Calculated DOS resource limits for workspace {}: {} handlers, {} vec params, {} loops, {} dynamic space, factor {:.1}",
-                request.workspace_id,
-                dos_metrics.total_handlers_found,
-                dos_metrics.handlers_with_vec_params,
-                dos_metrics.handlers_with_loops,
-                dos_metrics.dynamic_space_accounts,
-                dos_metrics.resource_factor
-            );
-        }
-        Err(e) => {
-            log::error!(
-                "Failed to calculate DOS resource limits for workspace {}: {}",
-                request.workspace_id,
-                e
-            );
-            notes.push(format!("DOS resource limits analysis failed: {}", e));
-        }
-    }
-
-    // Calculate operational security metrics
-    log::info!(
-        "🔍 SERVER DEBUG: About to analyze operational security for workspace: {:?}",
-        full_path
-    );
-    match calculate_workspace_operational_security(&full_path, selected_files) {
-        Ok(operational_metrics) => {
-            factors_map.insert(
-                "operationalSecurity".to_string(),
-                operational_metrics.to_json(),
-            );
-            computed_factors.push("operationalSecurity".to_string());
-            notes.push(format!(
-                "Analyzed operational security: {} control handlers, {} pause checks, {} sysvar dependencies, opsec factor {:.1}",
-                operational_metrics.control_handlers,
-                operational_metrics.pause_checks,
-                operational_metrics.sysvar_dependencies,
-                operational_metrics.opsec_factor
-            ));
-            log::info!(
-                "Calculated operational security for workspace {}: {} control handlers, {} pause checks, {} sysvar dependencies, opsec factor {:.1}",
-                request.workspace_id,
-                operational_metrics.control_handlers,
-                operational_metrics.pause_checks,
-                operational_metrics.sysvar_dependencies,
-                operational_metrics.opsec_factor
-            );
-        }
-        Err(e) => {
-            log::error!(
-                "Failed to calculate operational security for workspace {}: {}",
-                request.workspace_id,
-                e
-            );
-            notes.push(format!("Operational security analysis failed: {}", e));
-        }
-    }
-
-    // Calculate external dependencies from workspace Cargo.toml
-    log::info!(
-        "🔍 SERVER DEBUG: About to analyze dependencies for workspace: {:?}",
-        full_path
-    );
-    log::info!("🔍 SERVER DEBUG: Workspace exists: {}", full_path.exists());
-    log::info!(
-        "🔍 SERVER DEBUG: Workspace is directory: {}",
-        full_path.is_dir()
-    );
-    match calculate_workspace_dependencies(&full_path, selected_files) {
-        Ok(dependency_metrics) => {
-            factors_map.insert("dependencies".to_string(), dependency_metrics.to_json());
-            computed_factors.push("dependencies".to_string());
-            notes.push(format!(
-                "Analyzed dependencies: {} total (T1:{}, T2:{}, T3:{}, T4:{}), security score={:.1}",
-                dependency_metrics.total_dependencies,
-                dependency_metrics.tier_1_count,
-                dependency_metrics.tier_2_count,
-                dependency_metrics.tier_3_count,
-                dependency_metrics.tier_4_count,
-                dependency_metrics.dependency_factor
-            ));
-            log::info!(
-                "Calculated dependencies for workspace {}: {} total, security score={:.1}",
-                request.workspace_id,
-                dependency_metrics.total_dependencies,
-                dependency_metrics.dependency_factor
-            );
-        }
-        Err(e) => {
-            log::error!(
-                "Failed to calculate dependencies for workspace {}: {}",
-                request.workspace_id,
-                e
-            );
-            // Return error as requested - no fallback for dependencies
-            return ResponseJson(AugmentResponse {
-                success: false,
-                workspace_id: request.workspace_id,
-                overridden: Vec::new(),
-                factors: serde_json::Value::Object(serde_json::Map::new()),
-                raw: serde_json::json!({
-                    "error": format!("Dependencies calculation failed: {}", e),
-                    "timestamp": chrono::Utc::now().to_rfc3339()
-                }),
-                meta: AugmentResponseMeta {
-                    api_version: request.api_version.unwrap_or_else(|| "v1".to_string()),
-                    timestamp: chrono::Utc::now().to_rfc3339(),
-                },
-            });
-        }
-    }
-
-    // Build raw diagnostic information
-    let raw = serde_json::json!({
-        "selectedFiles": request.selected_files,
-        "computed": computed_factors,
-        "notes": notes,
-        "workspacePath": full_path.to_string_lossy(),
-        "timestamp": chrono::Utc::now().to_rfc3339()
-    });
-
-    let elapsed = start_time.elapsed();
-    log::info!(
-        "✅ AUGMENT COMPLETE: Processed workspace {} in {:.2}s with {} factors: {}",
+    let response = AugmentResponse::new(
         request.workspace_id,
-        elapsed.as_secs_f64(),
-        computed_factors.len(),
-        computed_factors.join(", ")
+        &full_path,
+        request.selected_files.as_deref(),
+        request.api_version,
+        result,
     );
 
-    let response = AugmentResponse {
-        success: true,
-        workspace_id: request.workspace_id,
-        overridden: computed_factors.clone(),
-        factors: serde_json::Value::Object(factors_map),
-        raw,
-        meta: AugmentResponseMeta {
-            api_version: request.api_version.unwrap_or_else(|| "v1".to_string()),
-            timestamp: chrono::Utc::now().to_rfc3339(),
-        },
-    };
+    let elapsed = start_time.elapsed();
+    if response.success {
+        log::info!(
+            "✅ AUGMENT COMPLETE: Processed workspace {} in {:.2}s with {} factors: {}",
+            response.workspace_id,
+            elapsed.as_secs_f64(),
+            response.overridden.len(),
+            response.overridden.join(", ")
+        );
+    }
     ResponseJson(response)
 }
 
+/// Factors of an uploaded workspace, with the default configuration
+///
+/// The workspace's own `bulwark.toml` is never read: it comes from the
+/// submitter, and would let them point pattern packs at files on the server
+/// or reweigh the platform's scores.
+fn augment_factors(
+    workspace: &Path,
+    selected_files: &[String],
+    rpc_url: Option<&str>,
+) -> amm_analyzer::Result<FactorReport> {
+    AnalyzerEngine::new(AnalyzerConfig {
+        root_path: workspace.to_path_buf(),
+        cache_dir: cache_dir(),
+        workers: workers().unwrap_or(0),
+        ..Default::default()
+    })?
+    .analyze_selected_factors(&FactorRegistry::default(), selected_files, rpc_url)
+}
+
 /// Simple GET variant for diagnostics to confirm route exists
 async fn augment_get() -> ResponseJson<serde_json::Value> {
     ResponseJson(serde_json::json!({
@@ -1333,13 +349,17 @@ async fn analyze_workspace(
         include_tests: options.include_tests.unwrap_or(false),
         include_benches: options.include_benches.unwrap_or(false),
         include_examples: options.include_examples.unwrap_or(false),
-        expand_macros: options.expand_macros.unwrap_or(false),
+        expand_macros: options.expand_macros.unwrap_or(false) && macro_expansion_allowed(),
         max_file_size: options.max_file_size.unwrap_or(1024 * 1024), // 1MB default
+        cache_dir: cache_dir(),
+        workers: workers().unwrap_or(0),
         ..Default::default()
     };
 
     // Perform analysis
-    match analyze_repository(config) {
+    let result = analyze_repository(config);
+    release_thread_spans();
+    match result {
         Ok(report) => {
             log::info!(
                 "Analysis completed successfully for workspace: {}",
@@ -1474,6 +494,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         std::env::var("SHARED_WORKSPACE_PATH")
             .unwrap_or_else(|_| "/tmp/shared/workspaces".to_string())
     );
+    match cache_dir() {
+        Some(dir) => log::info!("Result cache: {}", dir.display()),
+        None => log::info!("Result cache disabled (set ANALYZER_CACHE_DIR to enable)"),
+    }
 
     // Start server
     let listener = tokio::net::TcpListener::bind(addr).await?;
@@ -1481,3 +505,40 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_augment_ignores_workspace_config() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("secret.toml"), "SECRET-CONTENT = [").unwrap();
+        let workspace = dir.path().join("workspace");
+        std::fs::create_dir_all(workspace.join("src")).unwrap();
+        std::fs::write(
+            workspace.join("src/lib.rs"),
+            "pub fn swap(amount: u64) -> u64 { if amount > 0 { amount } else { 1 } }",
+        )
+        .unwrap();
+        std::fs::write(
+            workspace.join("bulwark.toml"),
+            r#"
+            [analysis]
+            pattern_packs = ["../secret.toml", "/etc/passwd"]
+
+            [factors.complexity]
+            weight = 100.0
+            "#,
+        )
+        .unwrap();
+
+        let selected = vec!["src/lib.rs".to_string()];
+        let result = augment_factors(&workspace, &selected, None);
+        let response = AugmentResponse::new("test".to_string(), &workspace, None, None, result);
+        assert!(response.success);
+        let json = serde_json::to_string(&response).unwrap();
+        assert!(!json.contains("SECRET-CONTENT"));
+        assert!(!json.contains("root:"));
+    }
+}
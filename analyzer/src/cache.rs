@@ -0,0 +1,149 @@
+//! On-disk cache for incremental analysis
+//!
+//! Results are stored as JSON under `<dir>/<namespace>/<key>.json`. A key is
+//! the SHA-256 of everything the result depends on (file contents, the
+//! analyzer version and the version of the factor that produced it), so any
+//! change simply misses and recomputes; stale entries are never invalidated
+//! in place.
+
+use serde::{de::DeserializeOwned, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// Analyzer version folded into every cache key
+pub const ANALYZER_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Hex SHA-256 of `bytes`
+pub fn content_hash(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// Identifies one cached result
+#[derive(Debug, Clone)]
+pub struct CacheKey {
+    namespace: &'static str,
+    hasher: Sha256,
+}
+
+impl CacheKey {
+    /// Start a key; `namespace` separates unrelated kinds of results
+    pub fn new(namespace: &'static str) -> Self {
+        Self {
+            namespace,
+            hasher: Sha256::new(),
+        }
+    }
+
+    /// Fold one input into the key
+    pub fn with(mut self, part: impl AsRef<[u8]>) -> Self {
+        let part = part.as_ref();
+        // Length-prefix parts so ("ab", "c") and ("a", "bc") differ
+        self.hasher.update((part.len() as u64).to_le_bytes());
+        self.hasher.update(part);
+        self
+    }
+
+    pub fn namespace(&self) -> &'static str {
+        self.namespace
+    }
+
+    /// Hex digest naming the entry on disk
+    pub fn digest(&self) -> String {
+        format!("{:x}", self.hasher.clone().finalize())
+    }
+}
+
+/// Directory of cached analysis results
+#[derive(Debug, Clone)]
+pub struct AnalysisCache {
+    dir: PathBuf,
+}
+
+impl AnalysisCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn entry_path(&self, key: &CacheKey) -> PathBuf {
+        self.dir
+            .join(key.namespace())
+            .join(format!("{}.json", key.digest()))
+    }
+
+    /// Cached value for `key`; unreadable or outdated entries are misses
+    pub fn load<T: DeserializeOwned>(&self, key: &CacheKey) -> Option<T> {
+        let path = self.entry_path(key);
+        let content = std::fs::read(&path).ok()?;
+        match serde_json::from_slice(&content) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                log::warn!("Ignoring corrupt cache entry {:?}: {}", path, e);
+                None
+            }
+        }
+    }
+
+    /// Store `value` under `key`
+    ///
+    /// Failures are logged and otherwise ignored: the cache only ever saves
+    /// work, it never fails an analysis.
+    pub fn store<T: Serialize>(&self, key: &CacheKey, value: &T) {
+        if let Err(e) = self.try_store(key, value) {
+            log::warn!("Failed to write cache entry in {:?}: {}", self.dir, e);
+        }
+    }
+
+    fn try_store<T: Serialize>(&self, key: &CacheKey, value: &T) -> std::io::Result<()> {
+        let path = self.entry_path(key);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        // Write then rename so concurrent readers never see a partial entry
+        let tmp = path.with_extension(format!("{}.tmp", uuid::Uuid::new_v4()));
+        std::fs::write(&tmp, serde_json::to_vec(value)?)?;
+        std::fs::rename(&tmp, &path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_depends_on_every_part() {
+        let key = |parts: &[&str]| {
+            parts
+                .iter()
+                .fold(CacheKey::new("files"), |key, part| key.with(part))
+                .digest()
+        };
+
+        assert_eq!(key(&["a", "bc"]), key(&["a", "bc"]));
+        assert_ne!(key(&["a", "bc"]), key(&["ab", "c"]));
+        assert_ne!(key(&["a", "bc"]), key(&["a", "bd"]));
+        assert_eq!(content_hash(b"abc").len(), 64);
+    }
+
+    #[test]
+    fn test_store_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = AnalysisCache::new(dir.path().join("cache"));
+        let key = CacheKey::new("files").with(content_hash(b"fn main() {}"));
+
+        assert_eq!(cache.load::<Vec<u32>>(&key), None);
+        cache.store(&key, &vec![1u32, 2, 3]);
+        assert_eq!(cache.load::<Vec<u32>>(&key), Some(vec![1, 2, 3]));
+
+        let other = CacheKey::new("files").with(content_hash(b"fn main() { }"));
+        assert_eq!(cache.load::<Vec<u32>>(&other), None);
+
+        // A corrupt entry is a miss, not an error
+        std::fs::write(cache.entry_path(&key), "{").unwrap();
+        assert_eq!(cache.load::<Vec<u32>>(&key), None);
+    }
+}
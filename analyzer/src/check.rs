@@ -0,0 +1,354 @@
+//! Threshold and baseline gating for CI (`cli check`)
+//!
+//! A check run evaluates the rules of a [`CheckConfig`] against the current
+//! analysis and, optionally, a stored [`Baseline`]. Every rule that trips
+//! becomes a [`Violation`]; any violation fails the check.
+
+use crate::config::CheckConfig;
+use crate::diff::{ReportDiff, ReportSnapshot};
+use crate::error::{AnalyzerError, Result};
+use crate::factors::{FactorRegistry, FactorReport, Finding};
+use crate::output::AnalysisReport;
+use crate::workspace::SourceLocation;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+/// Risk levels in increasing order, as reported by `RiskSummary`
+const RISK_LEVELS: &[&str] = &["low", "medium", "high", "critical"];
+
+/// A factor run stored as the comparison point for later checks
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Baseline {
+    /// The augment `factors` map
+    pub factors: Map<String, Value>,
+    pub findings: Vec<Finding>,
+}
+
+impl Baseline {
+    pub fn from_report(report: &FactorReport) -> Self {
+        Self {
+            factors: report.factors.clone(),
+            findings: report.findings.clone(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| AnalyzerError::ConfigError {
+            message: format!("Cannot read baseline {}: {}", path.display(), e),
+        })?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// A check rule that tripped
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Violation {
+    /// Which rule tripped, e.g. `threshold:complexityFactor`
+    pub rule: String,
+    pub message: String,
+    pub location: Option<SourceLocation>,
+}
+
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] ", self.rule)?;
+        if let Some(location) = &self.location {
+            write!(f, "{}: ", location.display())?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Evaluate every rule in `config`; an empty result means the check passed
+///
+/// `registry` holds the enabled factors, whose finding rules
+/// `deny_new_findings` may name.
+pub fn evaluate(
+    config: &CheckConfig,
+    registry: &FactorRegistry,
+    report: &AnalysisReport,
+    factors: &FactorReport,
+    baseline: Option<&Baseline>,
+) -> Result<Vec<Violation>> {
+    let mut violations = Vec::new();
+    let current = ReportSnapshot::from_augment_factors(&factors.factors);
+
+    for (key, &limit) in &config.thresholds {
+        match current.scores.get(key) {
+            Some(&score) if score > limit => violations.push(Violation {
+                rule: format!("threshold:{}", key),
+                message: format!(
+                    "{} is {:.1}, above the threshold of {:.1}",
+                    key, score, limit
+                ),
+                location: None,
+            }),
+            Some(_) => {}
+            None => {
+                return Err(AnalyzerError::ConfigError {
+                    message: format!("Threshold for unknown score key '{}'", key),
+                })
+            }
+        }
+    }
+
+    if let Some(level) = &config.fail_on_risk_level {
+        let limit = risk_rank(level).ok_or_else(|| AnalyzerError::ConfigError {
+            message: format!(
+                "Unknown risk level '{}' (expected one of {})",
+                level,
+                RISK_LEVELS.join(", ")
+            ),
+        })?;
+        let actual = &report.repository.risk_summary.risk_level;
+        if risk_rank(actual).is_some_and(|rank| rank >= limit) {
+            violations.push(Violation {
+                rule: "riskLevel".to_string(),
+                message: format!("Risk level is {}, failing at {} and above", actual, level),
+                location: None,
+            });
+        }
+    }
+
+    let empty = Baseline::default();
+    let baseline = baseline.unwrap_or(&empty);
+
+    if config.deny_new_tier4 {
+        let before = ReportSnapshot::from_augment_factors(&baseline.factors);
+        let before = ReportSnapshot {
+            // No baseline entry means nothing was known before
+            tier4_crates: Some(before.tier4_crates.unwrap_or_default()),
+            ..before
+        };
+        for name in ReportDiff::between(&before, &current).new_tier4_crates {
+            violations.push(Violation {
+                rule: "newTier4Dependency".to_string(),
+                message: format!("New tier-4 (unknown or custom) dependency `{}`", name),
+                location: None,
+            });
+        }
+    }
+
+    for rule in &config.deny_new_findings {
+        if !registry.rules().any(|known| known.id == rule) {
+            return Err(AnalyzerError::ConfigError {
+                message: format!("Unknown or disabled finding rule '{}'", rule),
+            });
+        }
+        for finding in new_findings(&baseline.findings, &factors.findings, rule) {
+            violations.push(Violation {
+                rule: format!("newFinding:{}", rule),
+                message: finding.message.clone(),
+                location: Some(finding.location.clone()),
+            });
+        }
+    }
+
+    Ok(violations)
+}
+
+fn risk_rank(level: &str) -> Option<usize> {
+    RISK_LEVELS
+        .iter()
+        .position(|known| known.eq_ignore_ascii_case(level))
+}
+
+/// Findings of `rule` in `current` that the baseline does not account for
+///
+/// Line numbers shift whenever code above a finding changes, so findings are
+/// counted per file: a file only has new findings when it has more than the
+/// baseline did, and those not at a baseline line are reported first.
+fn new_findings<'a>(baseline: &[Finding], current: &'a [Finding], rule: &str) -> Vec<&'a Finding> {
+    let mut known: BTreeMap<&str, HashSet<usize>> = BTreeMap::new();
+    let mut known_count: BTreeMap<&str, usize> = BTreeMap::new();
+    for finding in baseline.iter().filter(|f| f.rule_id == rule) {
+        known
+            .entry(&finding.location.file)
+            .or_default()
+            .insert(finding.location.line);
+        *known_count.entry(&finding.location.file).or_default() += 1;
+    }
+
+    let mut by_file: BTreeMap<&str, Vec<&Finding>> = BTreeMap::new();
+    for finding in current.iter().filter(|f| f.rule_id == rule) {
+        by_file
+            .entry(&finding.location.file)
+            .or_default()
+            .push(finding);
+    }
+
+    let mut new = Vec::new();
+    for (file, mut findings) in by_file {
+        let excess = findings
+            .len()
+            .saturating_sub(known_count.get(file).copied().unwrap_or(0));
+        if excess == 0 {
+            continue;
+        }
+        let lines = known.get(file);
+        // Stable sort: unknown lines first, otherwise source order
+        findings.sort_by_key(|f| lines.is_some_and(|lines| lines.contains(&f.location.line)));
+        findings.truncate(excess);
+        findings.sort_by_key(|f| (f.location.line, f.location.column));
+        new.extend(findings);
+    }
+    new
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::FactorSettings;
+    use crate::factors::unsafe_lowlevel::UNSAFE_BLOCK;
+
+    fn unsafe_block(file: &str, line: usize) -> Finding {
+        let location = SourceLocation {
+            file: file.to_string(),
+            line,
+            column: 5,
+            end_line: line,
+            end_column: 11,
+        };
+        UNSAFE_BLOCK.at(location, "`unsafe` block")
+    }
+
+    fn factor_report(complexity: f64, tier4: &[&str], findings: Vec<Finding>) -> FactorReport {
+        let mut report = FactorReport::default();
+        report.factors.insert(
+            "complexity".to_string(),
+            serde_json::json!({ "complexityFactor": complexity }),
+        );
+        report.factors.insert(
+            "dependencies".to_string(),
+            serde_json::json!({ "dependencyFactor": 10.0, "tier4Crates": tier4 }),
+        );
+        report.findings = findings;
+        report
+    }
+
+    fn analysis_report(risk_level: &str) -> AnalysisReport {
+        let dir = tempfile::tempdir().unwrap();
+        let mut report = crate::analyze_repository(crate::AnalyzerConfig {
+            root_path: dir.path().to_path_buf(),
+            ..Default::default()
+        })
+        .unwrap();
+        report.repository.risk_summary.risk_level = risk_level.to_string();
+        report
+    }
+
+    #[test]
+    fn test_thresholds_and_risk_level() {
+        let config: CheckConfig = toml::from_str(
+            r#"
+            fail_on_risk_level = "high"
+
+            [thresholds]
+            complexityFactor = 60
+            dependencyFactor = 50
+            "#,
+        )
+        .unwrap();
+
+        let factors = factor_report(72.5, &[], Vec::new());
+        let registry = FactorRegistry::default();
+        let violations = evaluate(
+            &config,
+            &registry,
+            &analysis_report("critical"),
+            &factors,
+            None,
+        )
+        .unwrap();
+        let rules: Vec<_> = violations.iter().map(|v| v.rule.as_str()).collect();
+        assert_eq!(rules, vec!["threshold:complexityFactor", "riskLevel"]);
+
+        let factors = factor_report(40.0, &[], Vec::new());
+        assert!(evaluate(
+            &config,
+            &registry,
+            &analysis_report("medium"),
+            &factors,
+            None
+        )
+        .unwrap()
+        .is_empty());
+
+        let mut unknown = config.clone();
+        unknown.thresholds.insert("noSuchFactor".to_string(), 1.0);
+        assert!(evaluate(&unknown, &registry, &analysis_report("low"), &factors, None).is_err());
+    }
+
+    #[test]
+    fn test_unknown_finding_rules() {
+        let factors = factor_report(10.0, &[], Vec::new());
+        let report = analysis_report("low");
+
+        let typo = CheckConfig {
+            deny_new_findings: vec!["unsafeLowlevel/unsafeBlock".to_string()],
+            ..Default::default()
+        };
+        let registry = FactorRegistry::default();
+        assert!(evaluate(&typo, &registry, &report, &factors, None).is_err());
+
+        // A rule of a disabled factor can never trip
+        let config = CheckConfig {
+            deny_new_findings: vec![UNSAFE_BLOCK.id.to_string()],
+            ..Default::default()
+        };
+        assert!(evaluate(&config, &registry, &report, &factors, None)
+            .unwrap()
+            .is_empty());
+        let settings: BTreeMap<String, FactorSettings> =
+            toml::from_str("[unsafeLowLevel]\nenabled = false").unwrap();
+        let registry = FactorRegistry::default().configure(&settings).unwrap();
+        assert!(evaluate(&config, &registry, &report, &factors, None).is_err());
+    }
+
+    #[test]
+    fn test_baseline_rules() {
+        let config = CheckConfig {
+            deny_new_tier4: true,
+            deny_new_findings: vec![UNSAFE_BLOCK.id.to_string()],
+            ..Default::default()
+        };
+        let report = analysis_report("low");
+
+        let baseline = Baseline::from_report(&factor_report(
+            10.0,
+            &["legacy-math"],
+            vec![unsafe_block("lib.rs", 10)],
+        ));
+        // The known unsafe block moved down two lines; a second one appeared
+        let current = factor_report(
+            10.0,
+            &["legacy-math", "shady-math"],
+            vec![unsafe_block("lib.rs", 12), unsafe_block("lib.rs", 30)],
+        );
+
+        let registry = FactorRegistry::default();
+        let violations = evaluate(&config, &registry, &report, &current, Some(&baseline)).unwrap();
+        assert_eq!(violations.len(), 2);
+        assert_eq!(violations[0].rule, "newTier4Dependency");
+        assert!(violations[0].message.contains("shady-math"));
+        assert_eq!(violations[1].rule, "newFinding:unsafeLowLevel/unsafeBlock");
+        assert_eq!(violations[1].location.as_ref().unwrap().line, 12);
+        assert_eq!(
+            violations[1].to_string(),
+            "[newFinding:unsafeLowLevel/unsafeBlock] lib.rs:12:5: `unsafe` block"
+        );
+
+        // Without a baseline everything is new
+        let violations = evaluate(&config, &registry, &report, &current, None).unwrap();
+        assert_eq!(violations.len(), 4);
+    }
+}
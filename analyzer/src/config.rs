@@ -1,9 +1,14 @@
 //! Configuration for the AMM analyzer
 
+use crate::error::{AnalyzerError, Result};
+use crate::factors::FactorRegistry;
+use crate::patterns::BUILTIN_PACKS;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AnalyzerConfig {
     /// Root directory to analyze
     pub root_path: PathBuf,
@@ -26,6 +31,18 @@ pub struct AnalyzerConfig {
     /// Maximum file size to analyze (in bytes)
     pub max_file_size: usize,
 
+    /// Directory for cached per-file results; `None` disables caching
+    pub cache_dir: Option<PathBuf>,
+
+    /// Files analyzed concurrently; 0 means one per available core
+    pub workers: usize,
+
+    /// Factors computed concurrently; 0 means one per available core
+    ///
+    /// Syntax trees cannot cross threads, so every extra worker parses its
+    /// own copy of the workspace. The default of 1 parses it once.
+    pub factor_workers: usize,
+
     /// Output format configuration
     pub output: OutputConfig,
 
@@ -34,6 +51,7 @@ pub struct AnalyzerConfig {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct OutputConfig {
     /// Output format: "json", "yaml", "toml"
     pub format: String,
@@ -49,41 +67,159 @@ pub struct OutputConfig {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AnalysisConfig {
-    /// Enable semantic pattern recognition
+    /// Enable semantic pattern recognition (per-function semantic tags)
     pub semantic_patterns: bool,
 
-    /// Enable complexity scoring
+    /// Enable complexity scoring (function, file and repository complexity scores)
     pub complexity_scoring: bool,
 
-    /// Enable risk assessment
+    /// Enable risk assessment (the report's `risk_summary`)
     pub risk_assessment: bool,
 
-    /// Enable performance analysis
+    /// Enable performance analysis (per-phase and per-file timings in the report metadata)
     pub performance_analysis: bool,
 
-    /// Custom pattern weights
+    /// Custom pattern weights, applied to function complexity scores
     pub pattern_weights: PatternWeights,
+
+    /// Semantic pattern packs on top of the AMM patterns: built-in pack names
+    /// ("lending", "perps", "staking", "governance") or `.toml`/`.yaml` files
+    pub pattern_packs: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct PatternWeights {
-    /// Weight for checked arithmetic operations
+    /// Weight for arithmetic operations other than fixed-point helpers
     pub checked_arithmetic: f64,
 
     /// Weight for mathematical functions
     pub math_functions: f64,
 
-    /// Weight for fixed-point operations
+    /// Weight for fixed-point operations (`checked_ceil_div`, `integer_sqrt`)
     pub fixed_point: f64,
 
-    /// Weight for unsafe operations
+    /// Weight for potentially panicking operations (`unwrap`, `panic!`)
     pub unsafe_operations: f64,
 
-    /// Weight for loop operations
+    /// Weight for control flow (cyclomatic complexity)
     pub loop_operations: f64,
 }
 
+/// Project configuration file looked up at the repository root
+pub const PROJECT_CONFIG_FILE: &str = "bulwark.toml";
+
+/// Project configuration file (`bulwark.toml`)
+///
+/// The `AnalyzerConfig` fields sit at the top level, next to the `[factors]`
+/// and `[check]` tables. Relative paths in the file (`root_path`,
+/// `cache_dir`, `check.baseline`, pattern pack files) are resolved against
+/// its directory.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectConfig {
+    #[serde(flatten)]
+    pub analyzer: AnalyzerConfig,
+
+    /// Per-factor settings keyed by factor id, e.g. `[factors.complexity]`
+    pub factors: BTreeMap<String, FactorSettings>,
+
+    /// CI gating rules for `cli check`
+    pub check: CheckConfig,
+}
+
+/// Settings for one factor
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FactorSettings {
+    /// Whether the factor runs; required factors cannot be disabled
+    pub enabled: bool,
+
+    /// Weight of the factor's score in the composite score
+    pub weight: f64,
+
+    /// Overrides for the factor's score normalization (terms, curves, rounding)
+    pub normalization: Option<serde_json::Value>,
+}
+
+/// Rules that fail `cli check`
+///
+/// Without a baseline every tier-4 crate and every finding of a denied rule
+/// counts as new.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CheckConfig {
+    /// Highest allowed value per score key, e.g. `complexityFactor = 60`
+    pub thresholds: BTreeMap<String, f64>,
+
+    /// Fail when the risk level is at or above this ("medium", "high", "critical")
+    pub fail_on_risk_level: Option<String>,
+
+    /// Earlier run to compare against (`cli check --write-baseline` creates it)
+    pub baseline: Option<PathBuf>,
+
+    /// Fail on tier-4 (unknown or custom) crates not in the baseline
+    pub deny_new_tier4: bool,
+
+    /// Finding rules whose new occurrences fail, e.g. `unsafeLowLevel/unsafeBlock`
+    pub deny_new_findings: Vec<String>,
+}
+
+impl ProjectConfig {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| AnalyzerError::ConfigError {
+            message: format!("Cannot read {}: {}", path.display(), e),
+        })?;
+        let mut config: Self =
+            toml::from_str(&content).map_err(|e| AnalyzerError::ConfigError {
+                message: format!("Invalid {}: {}", path.display(), e),
+            })?;
+
+        let dir = path.parent().unwrap_or_else(|| Path::new("."));
+        config.analyzer.root_path = dir.join(&config.analyzer.root_path);
+        if let Some(cache_dir) = &mut config.analyzer.cache_dir {
+            *cache_dir = dir.join(&*cache_dir);
+        }
+        if let Some(baseline) = &mut config.check.baseline {
+            *baseline = dir.join(&*baseline);
+        }
+        for pack in &mut config.analyzer.analysis.pattern_packs {
+            if BUILTIN_PACKS.iter().all(|(builtin, _)| builtin != pack) {
+                *pack = dir.join(&*pack).display().to_string();
+            }
+        }
+        Ok(config)
+    }
+
+    /// Load `bulwark.toml` from the repository root, if there is one
+    pub fn discover(root: &Path) -> Result<Option<Self>> {
+        let path = root.join(PROJECT_CONFIG_FILE);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        log::info!("Using project configuration {}", path.display());
+        Self::from_file(&path).map(Some)
+    }
+
+    /// The repository's `bulwark.toml`, or the defaults for `root` without one
+    pub fn load(root: &Path) -> Result<Self> {
+        Ok(Self::discover(root)?.unwrap_or_else(|| Self {
+            analyzer: AnalyzerConfig {
+                root_path: root.to_path_buf(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }))
+    }
+
+    /// The default factors with this project's `[factors]` settings applied
+    pub fn factor_registry(&self) -> Result<FactorRegistry> {
+        FactorRegistry::default().configure(&self.factors)
+    }
+}
+
 impl Default for AnalyzerConfig {
     fn default() -> Self {
         Self {
@@ -98,6 +234,9 @@ impl Default for AnalyzerConfig {
             ],
             expand_macros: false,
             max_file_size: 1024 * 1024, // 1MB
+            cache_dir: None,
+            workers: 0,
+            factor_workers: 1,
             output: OutputConfig::default(),
             analysis: AnalysisConfig::default(),
         }
@@ -123,6 +262,19 @@ impl Default for AnalysisConfig {
             risk_assessment: true,
             performance_analysis: false,
             pattern_weights: PatternWeights::default(),
+            pattern_packs: ["lending", "perps", "staking", "governance"]
+                .map(String::from)
+                .to_vec(),
+        }
+    }
+}
+
+impl Default for FactorSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            weight: 1.0,
+            normalization: None,
         }
     }
 }
@@ -132,9 +284,96 @@ impl Default for PatternWeights {
         Self {
             checked_arithmetic: 1.0,
             math_functions: 1.2,
-            fixed_point: 1.5,
+            // Fixed-point helpers counted as plain arithmetic before the
+            // weights were applied; keep default scores unchanged
+            fixed_point: 1.0,
             unsafe_operations: 2.0,
             loop_operations: 0.8,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project_config_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let defaults = ProjectConfig::load(dir.path()).unwrap();
+        assert_eq!(defaults.analyzer.root_path, dir.path());
+        assert_eq!(
+            defaults.factor_registry().unwrap().len(),
+            FactorRegistry::default().len()
+        );
+
+        std::fs::write(
+            dir.path().join(PROJECT_CONFIG_FILE),
+            "[factors.oraclePriceFeed]\nenabled = false\n",
+        )
+        .unwrap();
+        let project = ProjectConfig::load(dir.path()).unwrap();
+        let registry = project.factor_registry().unwrap();
+        assert!(registry.get("oraclePriceFeed").is_none());
+        assert_eq!(registry.len(), FactorRegistry::default().len() - 1);
+    }
+
+    #[test]
+    fn test_project_config_from_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(PROJECT_CONFIG_FILE);
+        std::fs::write(
+            &path,
+            r#"
+            root_path = "programs"
+            include_tests = true
+
+            [output]
+            include_snippets = true
+
+            [analysis]
+            pattern_packs = ["staking", "packs/nft.toml"]
+
+            [analysis.pattern_weights]
+            fixed_point = 3.0
+
+            [factors.oraclePriceFeed]
+            enabled = false
+
+            [factors.complexity]
+            weight = 2
+
+            [check]
+            baseline = "ci/baseline.json"
+            "#,
+        )
+        .unwrap();
+
+        let config = ProjectConfig::from_file(&path).unwrap();
+        assert_eq!(config.analyzer.root_path, dir.path().join("programs"));
+        assert!(config.analyzer.include_tests);
+        assert_eq!(config.analyzer.max_file_size, 1024 * 1024);
+        assert!(config.analyzer.output.include_snippets);
+        assert!(config.analyzer.output.pretty);
+        assert_eq!(config.analyzer.analysis.pattern_weights.fixed_point, 3.0);
+        assert_eq!(config.analyzer.analysis.pattern_weights.math_functions, 1.2);
+        assert_eq!(
+            config.analyzer.analysis.pattern_packs,
+            [
+                "staking".to_string(),
+                dir.path().join("packs/nft.toml").display().to_string()
+            ]
+        );
+        assert!(!config.factors["oraclePriceFeed"].enabled);
+        assert_eq!(config.factors["complexity"].weight, 2.0);
+        assert_eq!(
+            config.check.baseline,
+            Some(dir.path().join("ci/baseline.json"))
+        );
+
+        assert!(ProjectConfig::discover(dir.path()).unwrap().is_some());
+        assert!(ProjectConfig::discover(&dir.path().join("programs"))
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,447 @@
+//! Comparing two analysis runs
+//!
+//! Either input may be an [`AnalysisReport`] or an augment response. Both are
+//! first reduced to a [`ReportSnapshot`] holding only what can be compared;
+//! a section of the diff is produced only when both snapshots carry it (an
+//! `AnalysisReport` has per-function complexity but no handler list, an
+//! augment response the reverse).
+
+use crate::error::{AnalyzerError, Result};
+use crate::output::AnalysisReport;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+/// Score changes smaller than this are noise from float formatting
+const SCORE_EPSILON: f64 = 1e-6;
+
+/// The comparable parts of one analysis run
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReportSnapshot {
+    /// Scores by key (`acFactor`, `riskScore`, ...)
+    pub scores: BTreeMap<String, f64>,
+
+    /// Instruction handler names
+    pub handlers: Option<BTreeSet<String>>,
+
+    /// Programs invoked through CPI
+    pub cpi_targets: Option<BTreeSet<String>>,
+
+    /// Unknown or custom (tier 4) crates
+    pub tier4_crates: Option<BTreeSet<String>>,
+
+    /// Cyclomatic complexity by `file::function`
+    pub function_complexity: Option<BTreeMap<String, u32>>,
+}
+
+impl ReportSnapshot {
+    /// Snapshot of an `AnalysisReport` or augment response
+    pub fn from_json(value: &Value) -> Result<Self> {
+        if value.get("repository").is_some() {
+            let report: AnalysisReport = serde_json::from_value(value.clone())?;
+            Ok(Self::from_analysis_report(&report))
+        } else if let Some(Value::Object(factors)) = value.get("factors") {
+            Ok(Self::from_augment_factors(factors))
+        } else {
+            Err(AnalyzerError::AnalysisError {
+                message: "Expected an analysis report or an augment response".to_string(),
+            })
+        }
+    }
+
+    pub fn from_analysis_report(report: &AnalysisReport) -> Self {
+        let repo = &report.repository;
+        let scores = BTreeMap::from([
+            ("riskScore".to_string(), repo.risk_summary.risk_score),
+            (
+                "complexityScore".to_string(),
+                repo.aggregated.complexity_score,
+            ),
+            (
+                "avgCyclomaticComplexity".to_string(),
+                repo.aggregated.avg_cyclomatic_complexity,
+            ),
+            ("safetyRatio".to_string(), repo.aggregated.safety_ratio),
+        ]);
+
+        let function_complexity = repo
+            .files
+            .iter()
+            .flat_map(|file| {
+                // Relative paths, so runs over different checkouts line up
+                let path = file
+                    .path
+                    .strip_prefix(&repo.root_path)
+                    .unwrap_or(&file.path);
+                let path = path.to_string_lossy().to_string();
+                file.functions.iter().map(move |function| {
+                    (
+                        format!("{}::{}", path, function.name),
+                        function.control_flow.cyclomatic_complexity,
+                    )
+                })
+            })
+            .collect();
+
+        Self {
+            scores,
+            function_complexity: Some(function_complexity),
+            ..Default::default()
+        }
+    }
+
+    /// Snapshot of the augment `factors` map
+    pub fn from_augment_factors(factors: &serde_json::Map<String, Value>) -> Self {
+        // Scores are the `…Factor` numbers, published either at the top level
+        // or inside a factor's metrics object
+        let mut scores = BTreeMap::new();
+        for (key, value) in factors {
+            match value {
+                Value::Number(n) if key.ends_with("Factor") => {
+                    scores.insert(key.clone(), n.as_f64().unwrap_or_default());
+                }
+                Value::Object(details) => {
+                    for (key, value) in details {
+                        if let (true, Some(n)) = (key.ends_with("Factor"), value.as_f64()) {
+                            scores.entry(key.clone()).or_insert(n);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let names = |factor: &str, key: &str| -> Option<BTreeSet<String>> {
+            factors.get(factor)?.get(key)?.as_array().map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| item.as_str().map(str::to_string))
+                    .collect()
+            })
+        };
+
+        Self {
+            scores,
+            handlers: names("modularity", "instructionHandlers"),
+            cpi_targets: names("cpiCalls", "programTargets"),
+            tier4_crates: names("dependencies", "tier4Crates"),
+            function_complexity: None,
+        }
+    }
+}
+
+/// Change of one score between two runs
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScoreDelta {
+    pub key: String,
+    pub old: Option<f64>,
+    pub new: Option<f64>,
+    /// `new - old`, when the score is in both runs
+    pub delta: Option<f64>,
+}
+
+/// A function whose cyclomatic complexity grew
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComplexityIncrease {
+    /// `file::function`
+    pub function: String,
+    pub old: u32,
+    pub new: u32,
+}
+
+/// Differences between two analysis runs
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportDiff {
+    /// Scores that changed, appeared or disappeared
+    pub score_deltas: Vec<ScoreDelta>,
+    pub added_handlers: Vec<String>,
+    pub removed_handlers: Vec<String>,
+    pub new_cpi_targets: Vec<String>,
+    pub new_tier4_crates: Vec<String>,
+    pub complexity_increases: Vec<ComplexityIncrease>,
+}
+
+impl ReportDiff {
+    /// Compare two `AnalysisReport` or augment JSON documents
+    pub fn from_json(old: &Value, new: &Value) -> Result<Self> {
+        Ok(Self::between(
+            &ReportSnapshot::from_json(old)?,
+            &ReportSnapshot::from_json(new)?,
+        ))
+    }
+
+    pub fn between(old: &ReportSnapshot, new: &ReportSnapshot) -> Self {
+        let keys: BTreeSet<&String> = old.scores.keys().chain(new.scores.keys()).collect();
+        let score_deltas = keys
+            .into_iter()
+            .filter_map(|key| {
+                let (before, after) = (old.scores.get(key).copied(), new.scores.get(key).copied());
+                let delta = before.zip(after).map(|(before, after)| after - before);
+                if delta.is_some_and(|delta| delta.abs() < SCORE_EPSILON) {
+                    return None;
+                }
+                Some(ScoreDelta {
+                    key: key.clone(),
+                    old: before,
+                    new: after,
+                    delta,
+                })
+            })
+            .collect();
+
+        let added =
+            |old: &Option<BTreeSet<String>>, new: &Option<BTreeSet<String>>| match (old, new) {
+                (Some(old), Some(new)) => new.difference(old).cloned().collect(),
+                _ => Vec::new(),
+            };
+
+        let complexity_increases = match (&old.function_complexity, &new.function_complexity) {
+            (Some(before), Some(after)) => after
+                .iter()
+                .filter_map(|(function, &new)| {
+                    let old = *before.get(function)?;
+                    (new > old).then(|| ComplexityIncrease {
+                        function: function.clone(),
+                        old,
+                        new,
+                    })
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        Self {
+            score_deltas,
+            added_handlers: added(&old.handlers, &new.handlers),
+            removed_handlers: added(&new.handlers, &old.handlers),
+            new_cpi_targets: added(&old.cpi_targets, &new.cpi_targets),
+            new_tier4_crates: added(&old.tier4_crates, &new.tier4_crates),
+            complexity_increases,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Plain-text rendering for terminals
+    pub fn to_text(&self) -> String {
+        if self.is_empty() {
+            return "No differences\n".to_string();
+        }
+
+        let mut text = String::new();
+        if !self.score_deltas.is_empty() {
+            text.push_str("Scores\n");
+            for delta in &self.score_deltas {
+                let _ = writeln!(
+                    text,
+                    "  {:<28} {:>8} -> {:<8} {}",
+                    delta.key,
+                    score(delta.old),
+                    score(delta.new),
+                    delta
+                        .delta
+                        .map(|d| format!("({:+.1})", d))
+                        .unwrap_or_default(),
+                );
+            }
+        }
+
+        let mut list = |title: &str, items: &[String], sign: char| {
+            if !items.is_empty() {
+                let _ = writeln!(text, "{}", title);
+                for item in items {
+                    let _ = writeln!(text, "  {} {}", sign, item);
+                }
+            }
+        };
+        list("Added handlers", &self.added_handlers, '+');
+        list("Removed handlers", &self.removed_handlers, '-');
+        list("New CPI targets", &self.new_cpi_targets, '+');
+        list("New tier-4 crates", &self.new_tier4_crates, '+');
+
+        if !self.complexity_increases.is_empty() {
+            text.push_str("Cyclomatic complexity increases\n");
+            for increase in &self.complexity_increases {
+                let _ = writeln!(
+                    text,
+                    "  {} {} -> {}",
+                    increase.function, increase.old, increase.new
+                );
+            }
+        }
+        text
+    }
+
+    /// Markdown rendering for pull request comments
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::from("## Analysis diff\n\n");
+        if self.is_empty() {
+            md.push_str("No differences.\n");
+            return md;
+        }
+
+        if !self.score_deltas.is_empty() {
+            md.push_str("### Scores\n\n| Key | Old | New | Δ |\n|---|---:|---:|---:|\n");
+            for delta in &self.score_deltas {
+                let _ = writeln!(
+                    md,
+                    "| `{}` | {} | {} | {} |",
+                    delta.key,
+                    score(delta.old),
+                    score(delta.new),
+                    delta
+                        .delta
+                        .map(|d| format!("{:+.1}", d))
+                        .unwrap_or_default(),
+                );
+            }
+            md.push('\n');
+        }
+
+        let mut list = |title: &str, items: &[String]| {
+            if !items.is_empty() {
+                let _ = writeln!(md, "### {}\n", title);
+                for item in items {
+                    let _ = writeln!(md, "- `{}`", item);
+                }
+                md.push('\n');
+            }
+        };
+        list("Added handlers", &self.added_handlers);
+        list("Removed handlers", &self.removed_handlers);
+        list("New CPI targets", &self.new_cpi_targets);
+        list("New tier-4 crates", &self.new_tier4_crates);
+
+        if !self.complexity_increases.is_empty() {
+            md.push_str("### Cyclomatic complexity increases\n\n| Function | Old | New |\n|---|---:|---:|\n");
+            for increase in &self.complexity_increases {
+                let _ = writeln!(
+                    md,
+                    "| `{}` | {} | {} |",
+                    increase.function, increase.old, increase.new
+                );
+            }
+            md.push('\n');
+        }
+        md
+    }
+}
+
+fn score(value: Option<f64>) -> String {
+    value.map_or_else(|| "-".to_string(), |v| format!("{:.1}", v))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn augment(ac: f64, handlers: &[&str], targets: &[&str], tier4: &[&str]) -> Value {
+        json!({
+            "success": true,
+            "factors": {
+                "locFactor": 10.0,
+                "privilegedRoles": { "acFactor": ac, "totalManualChecks": 1 },
+                "modularity": { "instructionHandlers": handlers },
+                "cpiCalls": { "cpiFactor": 5.0, "programTargets": targets },
+                "dependencies": { "dependencyFactor": 20.0, "tier4Crates": tier4 },
+            },
+        })
+    }
+
+    #[test]
+    fn test_augment_diff() {
+        let old = augment(10.0, &["deposit", "swap"], &["token_program"], &[]);
+        let new = augment(
+            25.0,
+            &["swap", "withdraw"],
+            &["token_program", "jupiter"],
+            &["shady-math"],
+        );
+        let diff = ReportDiff::from_json(&old, &new).unwrap();
+
+        assert_eq!(
+            diff.score_deltas,
+            vec![ScoreDelta {
+                key: "acFactor".to_string(),
+                old: Some(10.0),
+                new: Some(25.0),
+                delta: Some(15.0),
+            }]
+        );
+        assert_eq!(diff.added_handlers, vec!["withdraw"]);
+        assert_eq!(diff.removed_handlers, vec!["deposit"]);
+        assert_eq!(diff.new_cpi_targets, vec!["jupiter"]);
+        assert_eq!(diff.new_tier4_crates, vec!["shady-math"]);
+
+        assert!(diff.to_text().contains("acFactor"));
+        assert!(diff
+            .to_markdown()
+            .contains("| `acFactor` | 10.0 | 25.0 | +15.0 |"));
+        assert!(ReportDiff::from_json(&old, &old).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_complexity_increases() {
+        let snapshot = |complexity: &[(&str, u32)]| ReportSnapshot {
+            function_complexity: Some(
+                complexity
+                    .iter()
+                    .map(|(name, cc)| (name.to_string(), *cc))
+                    .collect(),
+            ),
+            ..Default::default()
+        };
+        let old = snapshot(&[("lib.rs::swap", 3), ("lib.rs::quote", 4)]);
+        let new = snapshot(&[
+            ("lib.rs::swap", 5),
+            ("lib.rs::quote", 2),
+            ("lib.rs::new", 9),
+        ]);
+
+        let diff = ReportDiff::between(&old, &new);
+        assert_eq!(
+            diff.complexity_increases,
+            vec![ComplexityIncrease {
+                function: "lib.rs::swap".to_string(),
+                old: 3,
+                new: 5,
+            }]
+        );
+        // Neither snapshot lists handlers, so none are reported
+        assert!(diff.added_handlers.is_empty());
+    }
+
+    #[test]
+    fn test_analysis_report_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("lib.rs"),
+            "pub fn swap(a: u64) -> u64 { if a > 1 { a } else { 0 } }\n",
+        )
+        .unwrap();
+        let report = crate::analyze_repository(crate::AnalyzerConfig {
+            root_path: dir.path().to_path_buf(),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let snapshot = ReportSnapshot::from_json(&serde_json::to_value(&report).unwrap()).unwrap();
+        let functions = snapshot.function_complexity.unwrap();
+        assert_eq!(functions.len(), 1);
+        assert_eq!(functions.keys().collect::<Vec<_>>(), vec!["lib.rs::swap"]);
+        assert!(snapshot.scores.contains_key("riskScore"));
+        assert!(snapshot.handlers.is_none());
+    }
+
+    #[test]
+    fn test_rejects_unknown_documents() {
+        assert!(ReportSnapshot::from_json(&json!({ "foo": 1 })).is_err());
+    }
+}
@@ -20,6 +20,12 @@ pub enum AnalyzerError {
     #[error("JSON serialization error: {0}")]
     JsonError(#[from] serde_json::Error),
 
+    #[error("{format} serialization error: {message}")]
+    SerializationError {
+        format: &'static str,
+        message: String,
+    },
+
     #[error("Configuration error: {message}")]
     ConfigError { message: String },
 
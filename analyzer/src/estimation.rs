@@ -0,0 +1,466 @@
+//! Audit-effort estimation from augment factors
+//!
+//! Turns the augment `factors` map into the numbers `bulwark_storage` persists
+//! in an `AuditRecord`: an effort range in days, an auditor headcount range, a
+//! cost range in USD and a 0-100 score. Factor scores are first combined into
+//! four weighted categories (structural, security, systemic, economic); the
+//! total is then placed on a timeline calibrated against past audits and
+//! priced with a [`RateCard`].
+
+use crate::error::{AnalyzerError, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// A factor score feeding a category
+struct Input {
+    /// Factor id in the augment `factors` map
+    factor: &'static str,
+    /// Score key inside the factor's metrics
+    key: &'static str,
+    /// Weight within the category
+    weight: f64,
+}
+
+struct Category {
+    name: &'static str,
+    /// Weight within the total score
+    weight: f64,
+    inputs: &'static [Input],
+}
+
+const fn input(factor: &'static str, key: &'static str, weight: f64) -> Input {
+    Input {
+        factor,
+        key,
+        weight,
+    }
+}
+
+const CATEGORIES: &[Category] = &[
+    Category {
+        name: "structural",
+        weight: 0.20,
+        inputs: &[
+            input("tscMetrics", "locFactor", 0.25),
+            input("functionCountMetrics", "functionFactor", 0.25),
+            input("complexity", "complexityFactor", 0.20),
+            input("modularity", "anchorModularityScore", 0.15),
+            input("dependencies", "dependencyFactor", 0.15),
+        ],
+    },
+    Category {
+        name: "security",
+        weight: 0.30,
+        inputs: &[
+            input("accessControl", "accessControlFactor", 0.20),
+            input("pdaSeeds", "pdaComplexityFactor", 0.15),
+            input("cpiCalls", "cpiFactor", 0.15),
+            input("inputConstraints", "inputConstraintFactor", 0.15),
+            input("arithmeticOperations", "arithmeticFactor", 0.10),
+            input("privilegedRoles", "acFactor", 0.10),
+            input("unsafeLowLevel", "unsafeFactor", 0.10),
+            input("errorHandling", "errorHandlingFactor", 0.05),
+        ],
+    },
+    Category {
+        name: "systemic",
+        weight: 0.30,
+        inputs: &[
+            input("upgradeability", "governanceFactor", 0.20),
+            input("dependencies", "externalIntegrationFactor", 0.30),
+            input("composability", "composabilityFactor", 0.20),
+            input("dosResourceLimits", "resourceFactor", 0.15),
+            input("operationalSecurity", "opsecFactor", 0.15),
+        ],
+    },
+    Category {
+        name: "economic",
+        weight: 0.20,
+        inputs: &[
+            input("assetTypes", "assetTypesFactor", 0.50),
+            input("invariantsAndRiskParams", "constraintDensityFactor", 0.50),
+        ],
+    },
+];
+
+/// A point of the timeline calibration: audits of this median total score
+/// took `median_days` typically and `skewed_days` at the long end, with the
+/// minimum auditor headcount
+struct Calibration {
+    score: f64,
+    median_days: f64,
+    skewed_days: f64,
+}
+
+/// Sorted by score
+const CALIBRATION: &[Calibration] = &[
+    Calibration {
+        score: 16.98,
+        median_days: 8.0,
+        skewed_days: 12.2,
+    },
+    Calibration {
+        score: 23.895,
+        median_days: 15.0,
+        skewed_days: 24.6,
+    },
+    Calibration {
+        score: 35.01,
+        median_days: 17.0,
+        skewed_days: 28.0,
+    },
+    Calibration {
+        score: 43.81,
+        median_days: 20.0,
+        skewed_days: 28.0,
+    },
+    Calibration {
+        score: 50.62,
+        median_days: 30.0,
+        skewed_days: 36.0,
+    },
+];
+
+/// Extra median days per score point beyond the last calibration point
+const SLOPE_ABOVE_MAX: f64 = 1.468429;
+
+/// Fewer median days per score point below the first calibration point
+const SLOPE_BELOW_MIN: f64 = 0.627746;
+
+const MIN_MEDIAN_DAYS: f64 = 1.0;
+
+/// Prices and staffing the estimate is quoted with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RateCard {
+    /// Weekly rate per auditor at the low end of the range, in USD
+    pub weekly_rate_low_usd: u64,
+
+    /// Weekly rate per auditor at the high end of the range, in USD
+    pub weekly_rate_high_usd: u64,
+
+    pub days_per_week: f64,
+
+    /// Smallest team; the calibration timelines assume this headcount
+    pub min_auditors: u8,
+
+    /// Largest team, which shortens the timeline
+    pub max_auditors: u8,
+
+    /// Share of the work that parallelizes across auditors (0-1)
+    pub parallel_fraction: f64,
+}
+
+impl Default for RateCard {
+    fn default() -> Self {
+        Self {
+            weekly_rate_low_usd: 2500,
+            weekly_rate_high_usd: 6000,
+            days_per_week: 5.0,
+            min_auditors: 2,
+            max_auditors: 3,
+            parallel_fraction: 0.8,
+        }
+    }
+}
+
+impl RateCard {
+    fn validate(&self) -> Result<()> {
+        let invalid = |message: &str| {
+            Err(AnalyzerError::ConfigError {
+                message: format!("Invalid rate card: {}", message),
+            })
+        };
+        if self.min_auditors == 0 || self.max_auditors < self.min_auditors {
+            return invalid("auditors must satisfy 1 <= min_auditors <= max_auditors");
+        }
+        if self.days_per_week.is_nan() || self.days_per_week <= 0.0 {
+            return invalid("days_per_week must be positive");
+        }
+        if !(0.0..=1.0).contains(&self.parallel_fraction) {
+            return invalid("parallel_fraction must be between 0 and 1");
+        }
+        if self.weekly_rate_high_usd < self.weekly_rate_low_usd {
+            return invalid("weekly_rate_high_usd is below weekly_rate_low_usd");
+        }
+        Ok(())
+    }
+
+    /// Days a team of `auditors` needs for work `days` long with `min_auditors`
+    fn scale_days(&self, days: u16, auditors: u8) -> u16 {
+        let share = self.min_auditors as f64 / auditors as f64;
+        let multiplier = (1.0 - self.parallel_fraction) + self.parallel_fraction * share;
+        round_days(days as f64 * multiplier)
+    }
+
+    /// Cost of `auditors` working `days` at `weekly_rate_usd` each
+    fn cost(&self, days: u16, weekly_rate_usd: u64, auditors: u8) -> u64 {
+        let per_auditor = (days as f64 / self.days_per_week * weekly_rate_usd as f64).round();
+        per_auditor as u64 * auditors as u64
+    }
+}
+
+/// How much one factor score moved the total
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Contribution {
+    pub factor: String,
+    pub key: String,
+    pub category: String,
+    pub score: f64,
+    /// Category weight × weight within the category
+    pub weight: f64,
+    /// Points added to the total score
+    pub points: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CategoryScore {
+    pub name: String,
+    pub score: f64,
+    pub weight: f64,
+}
+
+/// Why the estimate came out as it did
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Explanation {
+    /// Unrounded total score
+    pub total_score: f64,
+    pub categories: Vec<CategoryScore>,
+    /// Every factor score, largest contribution first
+    pub contributions: Vec<Contribution>,
+    /// `factor.key` scores absent from the input, counted as 0
+    pub missing: Vec<String>,
+}
+
+impl Explanation {
+    /// The `n` factors that added the most to the total
+    pub fn drivers(&self, n: usize) -> &[Contribution] {
+        &self.contributions[..n.min(self.contributions.len())]
+    }
+}
+
+/// An audit quote in the field types of `bulwark_storage::AuditRecord`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEstimate {
+    pub min_days: u16,
+    pub max_days: u16,
+    pub min_resources: u8,
+    pub max_resources: u8,
+    pub min_cost_usd: u64,
+    pub max_cost_usd: u64,
+    /// Total score, 0-100
+    pub score: u8,
+    pub explanation: Explanation,
+}
+
+impl AuditEstimate {
+    /// Estimate from the augment `factors` map
+    ///
+    /// The low end is the median timeline with the smallest team at the low
+    /// rate, the high end the skewed timeline with the largest team at the
+    /// high rate.
+    pub fn from_factors(factors: &Map<String, Value>, rate_card: &RateCard) -> Result<Self> {
+        rate_card.validate()?;
+        let explanation = explain(factors);
+        let total = explanation.total_score;
+
+        let (median_days, skewed_days) = timeline(total);
+        let max_days = rate_card.scale_days(skewed_days, rate_card.max_auditors);
+
+        Ok(Self {
+            min_days: median_days,
+            max_days,
+            min_resources: rate_card.min_auditors,
+            max_resources: rate_card.max_auditors,
+            min_cost_usd: rate_card.cost(
+                median_days,
+                rate_card.weekly_rate_low_usd,
+                rate_card.min_auditors,
+            ),
+            max_cost_usd: rate_card.cost(
+                max_days,
+                rate_card.weekly_rate_high_usd,
+                rate_card.max_auditors,
+            ),
+            score: total.round().clamp(0.0, 100.0) as u8,
+            explanation,
+        })
+    }
+
+    /// One line per estimate field plus the top factors
+    pub fn summary(&self) -> String {
+        let mut lines = vec![
+            format!("Score: {}/100", self.score),
+            format!("Effort: {}-{} days", self.min_days, self.max_days),
+            format!("Auditors: {}-{}", self.min_resources, self.max_resources),
+            format!("Cost: ${}-${} USD", self.min_cost_usd, self.max_cost_usd),
+        ];
+        for driver in self.explanation.drivers(3) {
+            lines.push(format!(
+                "  {} ({}): {:.1} → +{:.1} points",
+                driver.key, driver.category, driver.score, driver.points
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+fn explain(factors: &Map<String, Value>) -> Explanation {
+    let mut categories = Vec::new();
+    let mut contributions = Vec::new();
+    let mut missing = Vec::new();
+    let mut total_score = 0.0;
+
+    for category in CATEGORIES {
+        let mut category_score = 0.0;
+        for input in category.inputs {
+            let score = factors
+                .get(input.factor)
+                .and_then(|metrics| metrics.get(input.key))
+                .and_then(Value::as_f64);
+            if score.is_none() {
+                missing.push(format!("{}.{}", input.factor, input.key));
+            }
+            let score = score.unwrap_or(0.0);
+            category_score += score * input.weight;
+
+            let weight = category.weight * input.weight;
+            contributions.push(Contribution {
+                factor: input.factor.to_string(),
+                key: input.key.to_string(),
+                category: category.name.to_string(),
+                score,
+                weight,
+                points: score * weight,
+            });
+        }
+        total_score += category_score * category.weight;
+        categories.push(CategoryScore {
+            name: category.name.to_string(),
+            score: category_score,
+            weight: category.weight,
+        });
+    }
+
+    // Stable sort keeps the category order among equal contributions
+    contributions.sort_by(|a, b| b.points.total_cmp(&a.points));
+
+    Explanation {
+        total_score,
+        categories,
+        contributions,
+        missing,
+    }
+}
+
+/// Median and skewed days for `score` with the minimum team
+fn timeline(score: f64) -> (u16, u16) {
+    let (first, last) = (&CALIBRATION[0], &CALIBRATION[CALIBRATION.len() - 1]);
+
+    if score >= last.score {
+        let median = round_days(last.median_days + SLOPE_ABOVE_MAX * (score - last.score));
+        let skewed = round_days(median as f64 + (last.skewed_days - last.median_days));
+        return (median, skewed);
+    }
+    if score <= first.score {
+        let median = round_days(
+            (first.median_days - SLOPE_BELOW_MIN * (first.score - score)).max(MIN_MEDIAN_DAYS),
+        );
+        let skewed = round_days(median as f64 + (first.skewed_days - first.median_days));
+        return (median, skewed);
+    }
+
+    let (lower, upper) = CALIBRATION
+        .windows(2)
+        .map(|pair| (&pair[0], &pair[1]))
+        .find(|(_, upper)| score <= upper.score)
+        .unwrap_or((first, last));
+    let slope = (score - lower.score) / (upper.score - lower.score);
+    (
+        round_days(lower.median_days + slope * (upper.median_days - lower.median_days)),
+        round_days(lower.skewed_days + slope * (upper.skewed_days - lower.skewed_days)),
+    )
+}
+
+fn round_days(days: f64) -> u16 {
+    days.round().clamp(0.0, u16::MAX as f64) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn factors(score: f64) -> Map<String, Value> {
+        let mut factors = Map::new();
+        for category in CATEGORIES {
+            for input in category.inputs {
+                let metrics = factors
+                    .entry(input.factor)
+                    .or_insert_with(|| serde_json::json!({}));
+                metrics[input.key] = serde_json::json!(score);
+            }
+        }
+        factors
+    }
+
+    #[test]
+    fn test_estimate_matches_calibration() {
+        let rate_card = RateCard::default();
+
+        // Every factor at 30 puts the total at 30, between the 2nd and 3rd points
+        let estimate = AuditEstimate::from_factors(&factors(30.0), &rate_card).unwrap();
+        assert_eq!(estimate.score, 30);
+        // Median 16 days; skewed 26 days shortened to 19 by a third auditor
+        assert_eq!((estimate.min_days, estimate.max_days), (16, 19));
+        assert_eq!((estimate.min_resources, estimate.max_resources), (2, 3));
+        // 16 days / 5 × $2500 × 2 and 19 days / 5 × $6000 × 3
+        assert_eq!(estimate.min_cost_usd, 16_000);
+        assert_eq!(estimate.max_cost_usd, 68_400);
+        assert!(estimate.explanation.missing.is_empty());
+
+        // Beyond the calibration the timeline keeps growing
+        let estimate = AuditEstimate::from_factors(&factors(80.0), &rate_card).unwrap();
+        assert_eq!(estimate.score, 80);
+        assert_eq!(estimate.min_days, 73);
+
+        // An empty factor set is the shortest audit
+        let estimate = AuditEstimate::from_factors(&Map::new(), &rate_card).unwrap();
+        assert_eq!(estimate.score, 0);
+        assert_eq!(estimate.min_days, 1);
+        assert_eq!(estimate.explanation.missing.len(), 20);
+
+        let invalid = RateCard {
+            max_auditors: 1,
+            ..Default::default()
+        };
+        assert!(AuditEstimate::from_factors(&factors(30.0), &invalid).is_err());
+    }
+
+    #[test]
+    fn test_explanation_ranks_drivers() {
+        let mut factors = factors(10.0);
+        factors["cpiCalls"]["cpiFactor"] = serde_json::json!(90.0);
+        factors["upgradeability"]["governanceFactor"] = serde_json::json!(100.0);
+
+        let estimate = AuditEstimate::from_factors(&factors, &RateCard::default()).unwrap();
+        let drivers: Vec<_> = estimate
+            .explanation
+            .drivers(2)
+            .iter()
+            .map(|c| c.key.as_str())
+            .collect();
+        assert_eq!(drivers, vec!["governanceFactor", "cpiFactor"]);
+
+        let points: f64 = estimate
+            .explanation
+            .contributions
+            .iter()
+            .map(|c| c.points)
+            .sum();
+        assert!((points - estimate.explanation.total_score).abs() < 1e-9);
+        assert!(estimate.summary().contains("governanceFactor (systemic)"));
+    }
+}
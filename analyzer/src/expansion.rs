@@ -0,0 +1,540 @@
+//! Macro expansion (`macro-expansion` feature)
+//!
+//! Anchor's `#[derive(Accounts)]`, `#[account]` and `#[program]` generate the
+//! account validation (`try_accounts`) and instruction dispatch that factors
+//! never see in the written source. With `expand_macros`, every package with
+//! selected files is expanded by a local toolchain (`cargo expand`, or
+//! `cargo rustc -- -Zunpretty=expanded` without cargo-expand), and the items
+//! that exist only in the expanded crate are added to the workspace's
+//! [`expanded`](ParsedWorkspace::expanded) files as `<file> (expanded)`. Each
+//! of their lines maps back to the item that generated it, so findings in
+//! generated code point at the original source. Only the access control and
+//! input constraint factors read generated code; size and structure factors
+//! measure the written source alone.
+//!
+//! Without a toolchain, or when the package does not build, expansion fails
+//! with [`AnalyzerError::MacroExpansionError`] and the unexpanded sources are
+//! analyzed as before.
+
+use crate::error::{AnalyzerError, Result};
+use crate::workspace::{ExpansionMap, LineIndex, ParsedFile, ParsedWorkspace, SourceLocation};
+use quote::ToTokens;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use syn::spanned::Spanned;
+
+/// Appended to a file's workspace path to name the code generated from it
+pub const EXPANDED_SUFFIX: &str = " (expanded)";
+
+/// Expand every package with selected files and add the generated code
+///
+/// Returns the number of generated items added. A package that fails to
+/// expand is skipped; the first error is returned only when none expanded.
+pub fn expand_workspace(workspace: &mut ParsedWorkspace) -> Result<usize> {
+    let crate_roots = crate_roots(workspace);
+    if crate_roots.is_empty() {
+        return Err(AnalyzerError::MacroExpansionError {
+            message: "no selected library root (src/lib.rs) to expand".to_string(),
+        });
+    }
+
+    let mut added = 0;
+    let mut first_error = None;
+    let mut expanded_any = false;
+    for (package_dir, root_file) in crate_roots {
+        let result = expand_package(&package_dir)
+            .and_then(|expanded| add_expansion(workspace, &root_file, &expanded));
+        match result {
+            Ok(count) => {
+                log::info!(
+                    "Expanded macros of {:?}: {} generated items",
+                    package_dir,
+                    count
+                );
+                added += count;
+                expanded_any = true;
+            }
+            Err(e) => {
+                log::warn!("Skipping macro expansion of {:?}: {}", package_dir, e);
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+
+    match first_error {
+        Some(e) if !expanded_any => Err(e),
+        _ => Ok(added),
+    }
+}
+
+/// Package directory and library root (workspace path) of every package
+/// with selected files, when the root itself is selected
+fn crate_roots(workspace: &ParsedWorkspace) -> BTreeMap<PathBuf, String> {
+    let packages: BTreeSet<&Path> = workspace
+        .files
+        .iter()
+        .filter_map(|file| {
+            file.full_path
+                .ancestors()
+                .skip(1)
+                .take_while(|dir| dir.starts_with(&workspace.root))
+                .find(|dir| is_package(dir))
+        })
+        .collect();
+
+    let mut roots = BTreeMap::new();
+    for package_dir in packages {
+        let lib = package_dir.join("src").join("lib.rs");
+        match workspace.files.iter().find(|file| file.full_path == lib) {
+            Some(root) => {
+                roots.insert(package_dir.to_path_buf(), root.path.clone());
+            }
+            None => log::debug!(
+                "Not expanding {:?}: its library root is not selected",
+                package_dir
+            ),
+        }
+    }
+    roots
+}
+
+fn is_package(dir: &Path) -> bool {
+    std::fs::read_to_string(dir.join("Cargo.toml"))
+        .is_ok_and(|manifest| manifest.contains("[package]"))
+}
+
+/// Expanded source of the library target of the package in `package_dir`
+pub fn expand_package(package_dir: &Path) -> Result<String> {
+    let manifest = package_dir.join("Cargo.toml");
+
+    let mut cargo_expand = Command::new("cargo");
+    cargo_expand
+        .args([
+            "expand",
+            "--lib",
+            "--ugly",
+            "--color",
+            "never",
+            "--manifest-path",
+        ])
+        .arg(&manifest);
+
+    // rustc prints the same expansion; `RUSTC_BOOTSTRAP` lets a stable
+    // toolchain accept the unstable flag
+    let mut rustc = Command::new("cargo");
+    rustc
+        .args([
+            "rustc",
+            "--lib",
+            "--profile=check",
+            "--quiet",
+            "--manifest-path",
+        ])
+        .arg(&manifest)
+        .args(["--", "-Zunpretty=expanded"])
+        .env("RUSTC_BOOTSTRAP", "1");
+
+    let mut errors = Vec::new();
+    for mut command in [cargo_expand, rustc] {
+        match command.output() {
+            Ok(output) if output.status.success() && !output.stdout.is_empty() => {
+                return Ok(String::from_utf8_lossy(&output.stdout).into_owned());
+            }
+            Ok(output) => errors.push(error_line(&String::from_utf8_lossy(&output.stderr))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(AnalyzerError::MacroExpansionError {
+                    message: "no Rust toolchain found (`cargo` is not on PATH)".to_string(),
+                });
+            }
+            Err(e) => errors.push(e.to_string()),
+        }
+    }
+
+    Err(AnalyzerError::MacroExpansionError {
+        message: format!(
+            "could not expand {}: {}",
+            manifest.display(),
+            errors.join("; ")
+        ),
+    })
+}
+
+/// The first error line of a command's stderr, or its last line
+fn error_line(stderr: &str) -> String {
+    let mut lines = stderr
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty());
+    lines
+        .clone()
+        .find(|line| line.starts_with("error"))
+        .or_else(|| lines.next_back())
+        .unwrap_or("exited without output")
+        .to_string()
+}
+
+/// Add the items of `expanded`, the expanded crate rooted at the workspace
+/// file `root_file`, that no selected file contains
+///
+/// Generated items go into one `<file> (expanded)` file per module file;
+/// each maps back to the original item just before it (the struct a derive
+/// sits on, the module a `#[program]` rewrote), or to the top of its file.
+/// Returns the number of generated items added.
+pub fn add_expansion(
+    workspace: &mut ParsedWorkspace,
+    root_file: &str,
+    expanded: &str,
+) -> Result<usize> {
+    let expanded_ast =
+        syn::parse_file(expanded).map_err(|e| AnalyzerError::MacroExpansionError {
+            message: format!("expanded source of {} does not parse: {}", root_file, e),
+        })?;
+    let root = workspace
+        .files
+        .iter()
+        .find(|file| file.path == root_file)
+        .ok_or_else(|| AnalyzerError::MacroExpansionError {
+            message: format!("{} is not a parsed workspace file", root_file),
+        })?;
+
+    let mut generated = Vec::new();
+    collect_generated(
+        workspace,
+        &expanded_ast.items,
+        &Scope::file(root),
+        &mut generated,
+    );
+
+    // Copy each generated item's lines of the expansion into its file's
+    // generated code, recording the origin of every line
+    let index = LineIndex::new(expanded);
+    let mut files: BTreeMap<String, (PathBuf, String, ExpansionMap)> = BTreeMap::new();
+    for item in &generated {
+        let (_, source, map) = files.entry(item.file.clone()).or_insert_with(|| {
+            (
+                item.full_path.clone(),
+                String::new(),
+                ExpansionMap::default(),
+            )
+        });
+        for line in item.lines.clone() {
+            if let Some(range) = index.line_range(line, expanded) {
+                source.push_str(&expanded[range]);
+            }
+            source.push('\n');
+            map.origins.push(item.origin.clone());
+        }
+    }
+
+    for (path, (full_path, source, map)) in files {
+        let path = format!("{}{}", path, EXPANDED_SUFFIX);
+        let mut file =
+            ParsedFile::parse(&path, source).map_err(|e| AnalyzerError::MacroExpansionError {
+                message: format!("generated code of {} does not parse: {}", path, e),
+            })?;
+        file.full_path = full_path;
+        file.expansion = Some(map);
+        workspace.expanded.retain(|existing| existing.path != path);
+        workspace.expanded.push(file);
+    }
+
+    Ok(generated.len())
+}
+
+/// A module of the original source: where its items are and where its
+/// out-of-line children live
+struct Scope<'a> {
+    file: &'a ParsedFile,
+    items: &'a [syn::Item],
+
+    /// Directory of `mod child;` files, relative to the workspace root
+    dir: PathBuf,
+
+    /// Origin of generated items before the module's first original item
+    origin: SourceLocation,
+}
+
+impl<'a> Scope<'a> {
+    fn file(file: &'a ParsedFile) -> Self {
+        let path = Path::new(&file.path);
+        let is_root = path
+            .file_name()
+            .is_some_and(|name| name == "lib.rs" || name == "main.rs" || name == "mod.rs");
+        let parent = path.parent().unwrap_or(Path::new(""));
+        let dir = match path.file_stem() {
+            Some(stem) if !is_root => parent.join(stem),
+            _ => parent.to_path_buf(),
+        };
+        Self {
+            file,
+            items: &file.ast.items,
+            dir,
+            origin: SourceLocation {
+                file: file.path.clone(),
+                line: 1,
+                column: 1,
+                end_line: 1,
+                end_column: 1,
+            },
+        }
+    }
+
+    /// Scope of the child module `item`, when its source is selected
+    fn child(&self, workspace: &'a ParsedWorkspace, item: &'a syn::ItemMod) -> Option<Self> {
+        if let Some((_, items)) = &item.content {
+            return Some(Self {
+                file: self.file,
+                items,
+                dir: self.dir.join(item.ident.to_string()),
+                origin: self.file.location(&item.ident),
+            });
+        }
+
+        let candidates = match path_attribute(item) {
+            Some(path) => {
+                let parent = Path::new(&self.file.path).parent().unwrap_or(Path::new(""));
+                vec![parent.join(path)]
+            }
+            None => {
+                let name = item.ident.to_string();
+                vec![
+                    self.dir.join(format!("{}.rs", name)),
+                    self.dir.join(name).join("mod.rs"),
+                ]
+            }
+        };
+        let file = workspace
+            .files
+            .iter()
+            .find(|file| candidates.iter().any(|path| Path::new(&file.path) == path))?;
+        Some(Self::file(file))
+    }
+}
+
+/// The `#[path = "..."]` of an out-of-line module
+fn path_attribute(item: &syn::ItemMod) -> Option<String> {
+    item.attrs.iter().find_map(|attr| match &attr.meta {
+        syn::Meta::NameValue(meta) if meta.path.is_ident("path") => match &meta.value {
+            syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(path),
+                ..
+            }) => Some(path.value()),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+/// An item of the expansion with no counterpart in the original source
+struct GeneratedItem {
+    /// Workspace path of the module file it belongs to
+    file: String,
+    full_path: PathBuf,
+
+    /// Its 1-based lines in the expanded source
+    lines: std::ops::RangeInclusive<usize>,
+
+    origin: SourceLocation,
+}
+
+/// Walk the expanded `items` of the module `scope` alongside its original
+/// items, collecting the expanded items that have no original
+fn collect_generated<'a>(
+    workspace: &'a ParsedWorkspace,
+    items: &[syn::Item],
+    scope: &Scope<'a>,
+    generated: &mut Vec<GeneratedItem>,
+) {
+    // Each original matches once, so a second `impl Pool` is generated
+    let mut originals: HashMap<String, Vec<&'a syn::Item>> = HashMap::new();
+    for item in scope.items.iter().rev() {
+        if let Some(key) = item_key(item) {
+            originals.entry(key).or_default().push(item);
+        }
+    }
+
+    let mut origin = scope.origin.clone();
+    for item in items {
+        let Some(key) = item_key(item) else {
+            continue;
+        };
+        let Some(original) = originals.get_mut(&key).and_then(Vec::pop) else {
+            let span = item.span();
+            generated.push(GeneratedItem {
+                file: scope.file.path.clone(),
+                full_path: scope.file.full_path.clone(),
+                lines: span.start().line..=span.end().line,
+                origin: origin.clone(),
+            });
+            continue;
+        };
+
+        origin = match item_ident(original) {
+            Some(ident) => scope.file.location(ident),
+            None => scope.file.location(original),
+        };
+        if let (syn::Item::Mod(expanded), syn::Item::Mod(original)) = (item, original) {
+            let child = scope.child(workspace, original);
+            if let (Some((_, items)), Some(child)) = (&expanded.content, child) {
+                collect_generated(workspace, items, &child, generated);
+            }
+        }
+    }
+}
+
+/// Name of a named item
+fn item_ident(item: &syn::Item) -> Option<&syn::Ident> {
+    match item {
+        syn::Item::Const(item) => Some(&item.ident),
+        syn::Item::Enum(item) => Some(&item.ident),
+        syn::Item::Fn(item) => Some(&item.sig.ident),
+        syn::Item::Macro(item) => item.ident.as_ref(),
+        syn::Item::Mod(item) => Some(&item.ident),
+        syn::Item::Static(item) => Some(&item.ident),
+        syn::Item::Struct(item) => Some(&item.ident),
+        syn::Item::Trait(item) => Some(&item.ident),
+        syn::Item::Type(item) => Some(&item.ident),
+        syn::Item::Union(item) => Some(&item.ident),
+        _ => None,
+    }
+}
+
+/// Identity of an item across the original and expanded source; `use`,
+/// `extern crate` and macro invocations have none (expansion rewrites them)
+fn item_key(item: &syn::Item) -> Option<String> {
+    let kind = match item {
+        syn::Item::Const(_) => "const",
+        syn::Item::Enum(_) => "enum",
+        syn::Item::Fn(_) => "fn",
+        syn::Item::Macro(_) => "macro",
+        syn::Item::Mod(_) => "mod",
+        syn::Item::Static(_) => "static",
+        syn::Item::Struct(_) => "struct",
+        syn::Item::Trait(_) => "trait",
+        syn::Item::Type(_) => "type",
+        syn::Item::Union(_) => "union",
+        // Expansion qualifies trait paths, so impls match on the last segments
+        syn::Item::Impl(item) => {
+            let self_ty = type_name(&item.self_ty);
+            return Some(match &item.trait_ {
+                Some((_, path, _)) => format!("impl {} for {}", path_name(path), self_ty),
+                None => format!("impl {}", self_ty),
+            });
+        }
+        _ => return None,
+    };
+    item_ident(item).map(|ident| format!("{} {}", kind, ident))
+}
+
+fn path_name(path: &syn::Path) -> String {
+    path.segments
+        .last()
+        .map(|segment| segment.ident.to_string())
+        .unwrap_or_default()
+}
+
+fn type_name(ty: &syn::Type) -> String {
+    match ty {
+        syn::Type::Path(ty) => path_name(&ty.path),
+        ty => ty.to_token_stream().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORIGINAL: &str = r#"use anchor_lang::prelude::*;
+
+#[program]
+pub mod vault {
+    use super::*;
+    pub fn deposit(ctx: Context<Deposit>) -> Result<()> { Ok(()) }
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    pub owner: Signer<'info>,
+}
+"#;
+
+    const EXPANDED: &str = r#"#![feature(prelude_import)]
+#[prelude_import]
+use std::prelude::rust_2021::*;
+#[macro_use]
+extern crate std;
+use anchor_lang::prelude::*;
+pub mod vault {
+    use super::*;
+    pub fn deposit(ctx: Context<Deposit>) -> Result<()> { Ok(()) }
+    pub mod __private {
+        pub fn __global_deposit() {}
+    }
+}
+pub struct Deposit<'info> {
+    pub owner: Signer<'info>,
+}
+#[automatically_derived]
+impl<'info> anchor_lang::Accounts<'info, DepositBumps> for Deposit<'info> {
+    fn try_accounts(accounts: &mut &'info [AccountInfo<'info>]) -> Result<Self> {
+        let owner: Signer = anchor_lang::Accounts::try_accounts(accounts)
+            .map_err(|e| e.with_account_name("owner"))?;
+        if !owner.is_signer { return Err(ErrorCode::ConstraintSigner.into()); }
+        Ok(Deposit { owner })
+    }
+}
+"#;
+
+    #[test]
+    fn test_add_expansion_maps_generated_items() {
+        let file = ParsedFile::parse("src/lib.rs", ORIGINAL.to_string()).unwrap();
+        let mut workspace = ParsedWorkspace::from_files(Path::new("."), vec![file]);
+
+        let added = add_expansion(&mut workspace, "src/lib.rs", EXPANDED).unwrap();
+        assert_eq!(added, 2);
+        assert!(workspace.is_expanded());
+
+        assert_eq!(workspace.files.len(), 1);
+        let generated = &workspace.expanded[0];
+        assert_eq!(generated.path, "src/lib.rs (expanded)");
+        assert!(generated.source.contains("fn try_accounts"));
+        assert!(!generated.source.contains("fn deposit"));
+
+        // Generated items map to the original item before them: the handler
+        // in the `#[program]` module, the struct of a derive
+        let line_of = |needle: &str| {
+            generated
+                .source
+                .lines()
+                .position(|line| line.contains(needle))
+                .unwrap()
+                + 1
+        };
+        let location = |line| SourceLocation {
+            file: generated.path.clone(),
+            line,
+            ..Default::default()
+        };
+        let private = workspace.original_location(&location(line_of("__private")));
+        assert_eq!(
+            private.map(|l| (l.file, l.line)),
+            Some(("src/lib.rs".into(), 6))
+        );
+        let signer = workspace.original_location(&location(line_of("is_signer")));
+        assert_eq!(signer.map(|l| l.line), Some(10));
+    }
+
+    #[test]
+    fn test_expansion_degrades_without_package() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("lib.rs"), "fn a() {}").unwrap();
+        let mut workspace = ParsedWorkspace::load(dir.path(), &["lib.rs".to_string()]);
+
+        let error = expand_workspace(&mut workspace).unwrap_err();
+        assert!(matches!(error, AnalyzerError::MacroExpansionError { .. }));
+        assert!(expand_package(dir.path()).is_err());
+        assert_eq!(workspace.files.len(), 1);
+        assert!(!workspace.is_expanded());
+    }
+}
@@ -1,21 +1,29 @@
 //! Access-Controlled Handler analysis for Anchor smart contracts
 //!
 //! This module analyzes Anchor-specific access control patterns to count
-//! handlers that are gated by signer/authority checks.
-
+//! handlers that are gated by signer/authority checks. In native program
+//! code, `is_signer` checks and `owner`/`key` comparisons are the manual
+//! checks, and a native instruction handler that checks a signer is a gated
+//! handler.
+
+use crate::factors::handlers::{is_handler_fn, HandlerIndex};
+use crate::factors::native::{self, AccountCheck};
+use crate::factors::normalization::{Curve, Normalization};
+use crate::factors::registry::{Factor, FactorContext, FactorOutput};
+use crate::workspace::{ParsedWorkspace, SourceLocation};
 use std::collections::HashSet;
-use std::path::PathBuf;
-use syn::{visit::Visit, Attribute, Expr, Item};
+use std::path::Path;
+use syn::{spanned::Spanned, visit::Visit, Attribute, Expr, Item};
 
 #[derive(Debug, Clone, Default)]
 pub struct AccessControlMetrics {
-    /// Number of gated handlers (with signer or has_one constraints) - N_AC_Handlers (Weight: ×10)
+    /// Number of gated handlers (with signer or has_one constraints) - N_AC_Handlers (Weight: ×4)
     pub gated_handler_count: usize,
 
-    /// Number of manual authority checks (explicit if or require! checks) - N_Manual (Weight: ×5)
+    /// Number of manual authority checks (explicit if or require! checks) - N_Manual (Weight: ×2)
     pub manual_check_count: usize,
 
-    /// Number of account close operations (close = constraints) - N_Close (Weight: ×5)
+    /// Number of account close operations (close = constraints) - N_Close (Weight: ×2)
     pub account_close_count: usize,
 
     /// Number of unique roles (distinct signer/authority fields) - N_Role
@@ -24,6 +32,9 @@ pub struct AccessControlMetrics {
     /// Set of unique role identifiers found across all handlers (for internal merging)
     pub unique_roles: HashSet<String>,
 
+    /// Where each manual check was found
+    pub manual_check_locations: Vec<SourceLocation>,
+
     /// Calculated AC Factor (0-100, higher = riskier) - The FINAL SCORE
     pub access_control_factor: f64,
 }
@@ -43,95 +54,104 @@ impl AccessControlMetrics {
 
             // Debug/Detailed fields (useful for detailed report but minimized)
             "uniqueRoles": self.unique_roles.iter().collect::<Vec<_>>(),
+            "manualCheckLocations": self.manual_check_locations,
         })
     }
 
-    /// Calculate the AC Factor using the specified formula
-    pub fn calculate_ac_factor(&mut self) {
-        // Calculate role penalty: (N_Role - 1) × 5
-        let role_penalty = if self.unique_role_count > 0 {
-            (self.unique_role_count - 1) * 2
-        } else {
-            0
-        };
+    /// Default AC Factor scoring
+    /// AC Factor = min(100, (gated_handlers × 4) + (manual_checks × 2) + (closes × 2) + (N_Role - 1) × 2)
+    pub fn default_normalization() -> Normalization {
+        Normalization::new(Curve::percent())
+            .term("gatedHandlers", 4.0)
+            .term("manualChecks", 2.0)
+            .term("accountCloses", 2.0)
+            .term("extraRoles", 2.0)
+    }
 
-        // AC Factor = min(100, (gated_handlers × 10) + (manual_checks × 5) + (closes × 5) + role_penalty)
-        let weighted_sum = (self.gated_handler_count * 4)
-            + (self.manual_check_count * 2)
-            + (self.account_close_count * 2)
-            + role_penalty;
+    /// Measurements the AC Factor is computed from
+    pub fn normalization_inputs(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            ("gatedHandlers", self.gated_handler_count as f64),
+            ("manualChecks", self.manual_check_count as f64),
+            ("accountCloses", self.account_close_count as f64),
+            // The first role is free: (N_Role - 1)
+            (
+                "extraRoles",
+                self.unique_role_count.saturating_sub(1) as f64,
+            ),
+        ]
+    }
 
-        self.access_control_factor = (weighted_sum as f64).min(100.0);
+    /// Calculate the AC Factor using the default scoring
+    pub fn calculate_ac_factor(&mut self) {
+        self.access_control_factor =
+            Self::default_normalization().score(&self.normalization_inputs());
     }
 }
 
 /// Calculate access control metrics for workspace files
 pub fn calculate_workspace_access_control(
-    workspace_path: &PathBuf,
+    workspace_path: &Path,
     selected_files: &[String],
+) -> Result<AccessControlMetrics, Box<dyn std::error::Error>> {
+    let workspace = ParsedWorkspace::load(workspace_path, selected_files);
+    analyze_workspace_access_control(&workspace)
+}
+
+/// Calculate access control metrics over an already-parsed workspace
+pub fn analyze_workspace_access_control(
+    workspace: &ParsedWorkspace,
 ) -> Result<AccessControlMetrics, Box<dyn std::error::Error>> {
     log::info!(
         "🔍 ACCESS CONTROL DEBUG: Starting analysis for workspace: {:?}",
-        workspace_path
+        workspace.root
     );
     log::info!(
         "🔍 ACCESS CONTROL DEBUG: Analyzing {} files",
-        selected_files.len()
+        workspace.files.len()
     );
 
     let mut metrics = AccessControlMetrics::default();
     let mut analyzed_files = 0;
-
-    // Analyze each file
-    for file_path in selected_files {
-        let full_file_path = workspace_path.join(file_path);
-
-        if full_file_path.exists() && full_file_path.is_file() {
-            if let Some(extension) = full_file_path.extension() {
-                if extension == "rs" {
-                    match std::fs::read_to_string(&full_file_path) {
-                        Ok(content) => {
-                            match analyze_file_access_control(&content) {
-                                Ok(file_metrics) => {
-                                    // Merge AC Factor metrics from this file
-                                    metrics.gated_handler_count += file_metrics.gated_handler_count;
-                                    metrics.manual_check_count += file_metrics.manual_check_count;
-                                    metrics.account_close_count += file_metrics.account_close_count;
-
-                                    // Merge unique roles
-                                    for role in file_metrics.unique_roles {
-                                        metrics.unique_roles.insert(role);
-                                    }
-
-                                    analyzed_files += 1;
-
-                                    log::debug!(
-                                        "🔍 ACCESS CONTROL DEBUG: File {}: {} gated handlers, {} manual checks",
-                                        file_path,
-                                        file_metrics.gated_handler_count,
-                                        file_metrics.manual_check_count
-                                    );
-                                }
-                                Err(e) => {
-                                    log::warn!(
-                                        "🔍 ACCESS CONTROL DEBUG: Failed to analyze access control for file {}: {}",
-                                        file_path,
-                                        e
-                                    );
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            log::warn!(
-                                "🔍 ACCESS CONTROL DEBUG: Failed to read file {}: {}",
-                                file_path,
-                                e
-                            );
-                        }
-                    }
-                }
-            }
+    let handlers = HandlerIndex::from_workspace(workspace);
+
+    // Parsed files use the AST, including the validation macros generate;
+    // files that failed to parse fall back to string analysis
+    let sources = workspace
+        .files_with_expanded()
+        .map(|file| (file.path.as_str(), Some(&file.ast), file.source.as_str()))
+        .chain(workspace.failures.iter().filter_map(|failure| {
+            failure
+                .source
+                .as_deref()
+                .map(|source| (failure.path.as_str(), None, source))
+        }));
+
+    for (file_path, syntax_tree, content) in sources {
+        let file_metrics =
+            analyze_source_access_control(file_path, syntax_tree, content, &handlers);
+
+        // Merge AC Factor metrics from this file
+        metrics.gated_handler_count += file_metrics.gated_handler_count;
+        metrics.manual_check_count += file_metrics.manual_check_count;
+        metrics.account_close_count += file_metrics.account_close_count;
+        metrics
+            .manual_check_locations
+            .extend(file_metrics.manual_check_locations);
+
+        // Merge unique roles
+        for role in file_metrics.unique_roles {
+            metrics.unique_roles.insert(role);
         }
+
+        analyzed_files += 1;
+
+        log::debug!(
+            "🔍 ACCESS CONTROL DEBUG: File {}: {} gated handlers, {} manual checks",
+            file_path,
+            file_metrics.gated_handler_count,
+            file_metrics.manual_check_count
+        );
     }
 
     // Calculate unique role count
@@ -157,14 +177,34 @@ pub fn calculate_workspace_access_control(
 pub fn analyze_file_access_control(
     content: &str,
 ) -> Result<AccessControlMetrics, Box<dyn std::error::Error>> {
+    let syntax_tree = syn::parse_file(content).ok();
+    let handlers = syntax_tree
+        .as_ref()
+        .map(|syntax_tree| HandlerIndex::from_ast("", syntax_tree))
+        .unwrap_or_default();
+    Ok(analyze_source_access_control(
+        "",
+        syntax_tree.as_ref(),
+        content,
+        &handlers,
+    ))
+}
+
+/// Analyze access control patterns given a file's source and, if it parsed, its AST
+fn analyze_source_access_control(
+    file_path: &str,
+    syntax_tree: Option<&syn::File>,
+    content: &str,
+    handlers: &HandlerIndex,
+) -> AccessControlMetrics {
     // First try AST-based analysis
-    let mut metrics = match syn::parse_file(content) {
-        Ok(syntax_tree) => {
-            let mut visitor = AccessControlVisitor::new();
-            visitor.visit_file(&syntax_tree);
+    let mut metrics = match syntax_tree {
+        Some(syntax_tree) => {
+            let mut visitor = AccessControlVisitor::new(file_path, handlers);
+            visitor.visit_file(syntax_tree);
             visitor.metrics
         }
-        Err(_) => {
+        None => {
             // If AST parsing fails, fall back to string-based analysis
             log::debug!("AST parsing failed, falling back to string-based analysis");
             AccessControlMetrics::default()
@@ -172,7 +212,7 @@ pub fn analyze_file_access_control(
     };
 
     // Add string-based pattern detection for more reliable macro detection
-    let string_metrics = analyze_string_patterns(content);
+    let string_metrics = analyze_string_patterns(file_path, content);
 
     // Merge the results - only add if AST analysis didn't find them
     // (No longer needed since we removed the general metrics)
@@ -187,12 +227,14 @@ pub fn analyze_file_access_control(
         // Only use string analysis if AST analysis found nothing at all
         metrics.gated_handler_count = string_metrics.gated_handler_count;
         metrics.manual_check_count = string_metrics.manual_check_count;
+        metrics.manual_check_locations = string_metrics.manual_check_locations;
         metrics.account_close_count = string_metrics.account_close_count;
     }
 
     for role in string_metrics.unique_roles {
         metrics.unique_roles.insert(role);
     }
+    metrics.unique_role_count = metrics.unique_roles.len();
 
     // Debug: print unique roles found
     log::debug!(
@@ -201,11 +243,11 @@ pub fn analyze_file_access_control(
         metrics.unique_roles
     );
 
-    Ok(metrics)
+    metrics
 }
 
 /// String-based pattern analysis for more reliable detection
-fn analyze_string_patterns(content: &str) -> AccessControlMetrics {
+fn analyze_string_patterns(file_path: &str, content: &str) -> AccessControlMetrics {
     let mut metrics = AccessControlMetrics::default();
     let lines: Vec<&str> = content.lines().collect();
 
@@ -252,6 +294,11 @@ fn analyze_string_patterns(content: &str) -> AccessControlMetrics {
                 || line.contains("require!")
             {
                 metrics.manual_check_count += 1;
+                metrics.manual_check_locations.push(line_location(
+                    file_path,
+                    line_num,
+                    lines[line_num],
+                ));
             }
 
             // Look for authority check function calls - Weight: ×5 (manual checks)
@@ -263,6 +310,11 @@ fn analyze_string_patterns(content: &str) -> AccessControlMetrics {
                 || line.contains("assert_owner")
             {
                 metrics.manual_check_count += 1;
+                metrics.manual_check_locations.push(line_location(
+                    file_path,
+                    line_num,
+                    lines[line_num],
+                ));
             }
 
             // Look for authority key comparisons - Weight: ×5 (manual checks)
@@ -271,6 +323,11 @@ fn analyze_string_patterns(content: &str) -> AccessControlMetrics {
                 || (line.contains("admin") && line.contains("ADMIN"))
             {
                 metrics.manual_check_count += 1;
+                metrics.manual_check_locations.push(line_location(
+                    file_path,
+                    line_num,
+                    lines[line_num],
+                ));
             }
 
             // Extract role identifiers for unique role counting
@@ -323,17 +380,32 @@ fn analyze_string_patterns(content: &str) -> AccessControlMetrics {
     metrics
 }
 
+/// Whole-line location for findings of the string fallback
+fn line_location(file_path: &str, line_index: usize, line: &str) -> SourceLocation {
+    SourceLocation {
+        file: file_path.to_string(),
+        line: line_index + 1,
+        column: 1,
+        end_line: line_index + 1,
+        end_column: line.chars().count() + 1,
+    }
+}
+
 /// Visitor to analyze access control patterns
-struct AccessControlVisitor {
+struct AccessControlVisitor<'a> {
     metrics: AccessControlMetrics,
     current_function: Option<String>,
+    file_path: String,
+    handlers: &'a HandlerIndex<'a>,
 }
 
-impl AccessControlVisitor {
-    fn new() -> Self {
+impl<'a> AccessControlVisitor<'a> {
+    fn new(file_path: &str, handlers: &'a HandlerIndex<'a>) -> Self {
         Self {
             metrics: AccessControlMetrics::default(),
             current_function: None,
+            file_path: file_path.to_string(),
+            handlers,
         }
     }
 
@@ -362,21 +434,19 @@ impl AccessControlVisitor {
     }
 }
 
-impl<'ast> Visit<'ast> for AccessControlVisitor {
+impl<'ast> Visit<'ast> for AccessControlVisitor<'_> {
     fn visit_item(&mut self, item: &'ast Item) {
         match item {
             Item::Fn(item_fn) => {
                 let function_name = item_fn.sig.ident.to_string();
                 self.current_function = Some(function_name.clone());
 
-                let mut has_access_control = false;
                 let mut has_account_constraints = false;
 
                 // Check function attributes for access control patterns
                 for attr in &item_fn.attrs {
                     if self.is_access_control_attr(attr) {
                         // Access control decorator detected - handled by gated_handler_count
-                        has_access_control = true;
                         log::debug!("🔍 ACCESS CONTROL DEBUG: Found access control decorator in function: {}", function_name);
                     }
 
@@ -391,7 +461,10 @@ impl<'ast> Visit<'ast> for AccessControlVisitor {
 
                 // Visit function body to check for explicit authority checks
                 let mut has_explicit_checks = false;
-                let mut body_visitor = AuthorityCheckVisitor::new();
+                let mut body_visitor = AuthorityCheckVisitor::new(
+                    &self.file_path,
+                    native::is_native_sig(&item_fn.sig),
+                );
                 body_visitor.visit_block(&item_fn.block);
 
                 if body_visitor.has_authority_checks {
@@ -402,8 +475,8 @@ impl<'ast> Visit<'ast> for AccessControlVisitor {
                     );
                 }
 
-                // Track AC Factor specific metrics
-                if has_account_constraints {
+                // Track AC Factor specific metrics (gating only counts on instruction handlers)
+                if has_account_constraints && is_handler_fn(item_fn) {
                     // Check if this has gated constraints (signer or has_one)
                     for attr in &item_fn.attrs {
                         let attr_str = format!("{}", quote::quote! { #attr });
@@ -414,8 +487,20 @@ impl<'ast> Visit<'ast> for AccessControlVisitor {
                     }
                 }
 
+                // Native handlers are gated by their own `is_signer` checks
+                let native_handler = self
+                    .handlers
+                    .lookup(item_fn)
+                    .is_some_and(|handler| handler.native);
+                if native_handler && body_visitor.signer_checks > 0 {
+                    self.metrics.gated_handler_count += 1;
+                }
+
                 if has_explicit_checks {
                     self.metrics.manual_check_count += body_visitor.manual_check_count;
+                    self.metrics
+                        .manual_check_locations
+                        .extend(body_visitor.check_locations);
                 }
 
                 // Collect unique roles from this function
@@ -432,27 +517,86 @@ impl<'ast> Visit<'ast> for AccessControlVisitor {
             }
         }
     }
+
+    /// Methods of native processors (`impl Processor { fn process_x(...) }`)
+    fn visit_impl_item_fn(&mut self, method: &'ast syn::ImplItemFn) {
+        if native::is_native_sig(&method.sig) {
+            let mut body_visitor = AuthorityCheckVisitor::new(&self.file_path, true);
+            body_visitor.visit_block(&method.block);
+
+            let native_handler = self
+                .handlers
+                .lookup_method(method)
+                .is_some_and(|handler| handler.native);
+            if native_handler && body_visitor.signer_checks > 0 {
+                self.metrics.gated_handler_count += 1;
+            }
+            self.metrics.manual_check_count += body_visitor.manual_check_count;
+            self.metrics
+                .manual_check_locations
+                .extend(body_visitor.check_locations);
+            self.metrics.unique_roles.extend(body_visitor.unique_roles);
+        }
+
+        syn::visit::visit_impl_item_fn(self, method);
+    }
 }
 
 /// Helper visitor to detect authority checks in function bodies
-struct AuthorityCheckVisitor {
+struct AuthorityCheckVisitor<'a> {
     has_authority_checks: bool,
     manual_check_count: usize,
     unique_roles: HashSet<String>,
+    file_path: &'a str,
+    check_locations: Vec<SourceLocation>,
+
+    /// Whether the body is native program code, checking accounts by hand
+    native: bool,
+
+    /// Native `is_signer` checks
+    signer_checks: usize,
 }
 
-impl AuthorityCheckVisitor {
-    fn new() -> Self {
+impl<'a> AuthorityCheckVisitor<'a> {
+    fn new(file_path: &'a str, native: bool) -> Self {
         Self {
             has_authority_checks: false,
             manual_check_count: 0,
             unique_roles: HashSet::new(),
+            file_path,
+            check_locations: Vec::new(),
+            native,
+            signer_checks: 0,
         }
     }
+
+    /// Count a manual check and record where it is
+    fn record_check<T: Spanned>(&mut self, node: &T) {
+        self.has_authority_checks = true;
+        self.manual_check_count += 1;
+        self.check_locations
+            .push(SourceLocation::of(self.file_path, node));
+    }
 }
 
-impl<'ast> Visit<'ast> for AuthorityCheckVisitor {
+impl<'ast> Visit<'ast> for AuthorityCheckVisitor<'_> {
     fn visit_expr(&mut self, expr: &'ast Expr) {
+        if self.native {
+            match native::account_check(expr) {
+                Some(AccountCheck::Signer) => {
+                    self.record_check(expr);
+                    self.signer_checks += 1;
+                    self.unique_roles.insert("signer".to_string());
+                }
+                Some(AccountCheck::Owner) => {
+                    self.record_check(expr);
+                    self.unique_roles.insert("owner".to_string());
+                }
+                Some(AccountCheck::Key) => self.record_check(expr),
+                Some(AccountCheck::Writable) | None => {}
+            }
+        }
+
         match expr {
             Expr::Call(call_expr) => {
                 if let Expr::Path(path_expr) = &*call_expr.func {
@@ -465,8 +609,7 @@ impl<'ast> Visit<'ast> for AuthorityCheckVisitor {
                             || func_name == "require_owner"
                             || func_name == "require_signer"
                         {
-                            self.has_authority_checks = true;
-                            self.manual_check_count += 1;
+                            self.record_check(call_expr);
                             log::debug!(
                                 "🔍 ACCESS CONTROL DEBUG: Found authority check function: {}",
                                 func_name
@@ -484,8 +627,7 @@ impl<'ast> Visit<'ast> for AuthorityCheckVisitor {
                             || func_name == "require"
                         {
                             // Check for native Solana authority check macros
-                            self.has_authority_checks = true;
-                            self.manual_check_count += 1;
+                            self.record_check(call_expr);
                             log::debug!(
                                 "🔍 ACCESS CONTROL DEBUG: Found authority check macro: {}",
                                 func_name
@@ -505,12 +647,10 @@ impl<'ast> Visit<'ast> for AuthorityCheckVisitor {
                     let right_str = format!("{}", quote::quote! { #right_path });
 
                     if left_str.contains("authority") && right_str.contains("AUTHORITY") {
-                        self.has_authority_checks = true;
-                        self.manual_check_count += 1;
+                        self.record_check(binary_expr);
                         self.unique_roles.insert("authority".to_string());
                     } else if left_str.contains("owner") && right_str.contains("OWNER") {
-                        self.has_authority_checks = true;
-                        self.manual_check_count += 1;
+                        self.record_check(binary_expr);
                         self.unique_roles.insert("owner".to_string());
                     }
                 }
@@ -522,8 +662,7 @@ impl<'ast> Visit<'ast> for AuthorityCheckVisitor {
                     || macro_name.contains("assert_eq")
                     || macro_name.contains("require")
                 {
-                    self.has_authority_checks = true;
-                    self.manual_check_count += 1;
+                    self.record_check(macro_expr);
                 }
             }
             _ => {}
@@ -532,6 +671,63 @@ impl<'ast> Visit<'ast> for AuthorityCheckVisitor {
         // Continue visiting nested expressions
         syn::visit::visit_expr(self, expr);
     }
+
+    fn visit_stmt_macro(&mut self, stmt_macro: &'ast syn::StmtMacro) {
+        // Statement-position macros (`assert_eq!(...);`) never reach visit_expr
+        if let Some(segment) = stmt_macro.mac.path.segments.last() {
+            let macro_name = segment.ident.to_string();
+            if macro_name == "check_assert_eq"
+                || macro_name == "assert_eq"
+                || macro_name.starts_with("require")
+            {
+                self.record_check(stmt_macro);
+            }
+        }
+
+        syn::visit::visit_stmt_macro(self, stmt_macro);
+    }
+}
+
+/// Registry entry for the access control factor
+pub struct AccessControlFactor;
+
+impl Factor for AccessControlFactor {
+    fn id(&self) -> &'static str {
+        "accessControl"
+    }
+
+    fn version(&self) -> &'static str {
+        "1.3.0"
+    }
+
+    fn name(&self) -> &'static str {
+        "Access control"
+    }
+
+    fn required(&self) -> bool {
+        true
+    }
+
+    fn normalization(&self) -> Option<Normalization> {
+        Some(AccessControlMetrics::default_normalization())
+    }
+
+    fn compute(&self, ctx: &FactorContext) -> Result<FactorOutput, Box<dyn std::error::Error>> {
+        let mut metrics = analyze_workspace_access_control(ctx.workspace)?;
+        metrics.access_control_factor = ctx
+            .normalization_or(AccessControlMetrics::default_normalization)
+            .score(&metrics.normalization_inputs());
+        Ok(
+            FactorOutput::keyed(self.id(), metrics.access_control_factor, metrics.to_json()).with_note(format!(
+                "Analyzed access control: {} gated handlers, {} manual checks, {} account closes, {} unique roles, AC factor {:.1}",
+                metrics.gated_handler_count,
+                metrics.manual_check_count,
+                metrics.account_close_count,
+                metrics.unique_role_count,
+                metrics.access_control_factor,
+            )),
+        )
+    }
 }
 
 #[cfg(test)]
@@ -595,50 +791,53 @@ mod tests {
 
     #[test]
     fn test_ac_factor_calculation() {
-        let mut metrics = AccessControlMetrics::default();
-
         // Test case: 2 gated handlers, 3 manual checks, 1 account close, 3 unique roles
-        metrics.gated_handler_count = 2; // ×10 = 20
-        metrics.manual_check_count = 3; // ×5 = 15
-        metrics.account_close_count = 1; // ×5 = 5
-        metrics.unique_role_count = 3; // penalty = (3-1) × 5 = 10
+        let mut metrics = AccessControlMetrics {
+            gated_handler_count: 2, // ×4 = 8
+            manual_check_count: 3,  // ×2 = 6
+            account_close_count: 1, // ×2 = 2
+            unique_role_count: 3,   // penalty = (3-1) × 2 = 4
+            ..Default::default()
+        };
 
         metrics.calculate_ac_factor();
 
-        // Expected: 20 + 15 + 5 + 10 = 50
-        assert_eq!(metrics.access_control_factor, 50.0);
+        // Expected: 8 + 6 + 2 + 4 = 20
+        assert_eq!(metrics.access_control_factor, 20.0);
     }
 
     #[test]
     fn test_ac_factor_capping() {
-        let mut metrics = AccessControlMetrics::default();
-
         // Test case that would exceed 100
-        metrics.gated_handler_count = 10; // ×10 = 100
-        metrics.manual_check_count = 5; // ×5 = 25
-        metrics.account_close_count = 2; // ×5 = 10
-        metrics.unique_role_count = 5; // penalty = (5-1) × 5 = 20
+        let mut metrics = AccessControlMetrics {
+            gated_handler_count: 20, // ×4 = 80
+            manual_check_count: 5,   // ×2 = 10
+            account_close_count: 2,  // ×2 = 4
+            unique_role_count: 5,    // penalty = (5-1) × 2 = 8
+            ..Default::default()
+        };
 
         metrics.calculate_ac_factor();
 
-        // Expected: min(100, 100 + 25 + 10 + 20) = 100
+        // Expected: min(100, 80 + 10 + 4 + 8) = 100
         assert_eq!(metrics.access_control_factor, 100.0);
     }
 
     #[test]
     fn test_ac_factor_zero_roles() {
-        let mut metrics = AccessControlMetrics::default();
-
         // Test case with no unique roles
-        metrics.gated_handler_count = 1; // ×10 = 10
-        metrics.manual_check_count = 2; // ×5 = 10
-        metrics.account_close_count = 0; // ×5 = 0
-        metrics.unique_role_count = 0; // penalty = 0
+        let mut metrics = AccessControlMetrics {
+            gated_handler_count: 1, // ×4 = 4
+            manual_check_count: 2,  // ×2 = 4
+            account_close_count: 0, // ×2 = 0
+            unique_role_count: 0,   // penalty = 0
+            ..Default::default()
+        };
 
         metrics.calculate_ac_factor();
 
-        // Expected: 10 + 10 + 0 + 0 = 20
-        assert_eq!(metrics.access_control_factor, 20.0);
+        // Expected: 4 + 4 + 0 + 0 = 8
+        assert_eq!(metrics.access_control_factor, 8.0);
     }
 
     #[test]
@@ -671,6 +870,123 @@ mod tests {
 
         let result = analyze_file_access_control(code).unwrap();
         assert_eq!(result.manual_check_count, 2); // require_auth + assert_eq
+        let lines: Vec<_> = result
+            .manual_check_locations
+            .iter()
+            .map(|l| l.line)
+            .collect();
+        assert_eq!(lines, vec![3, 4]);
+    }
+
+    #[test]
+    fn test_native_account_checks() {
+        let code = r#"
+            entrypoint!(process_instruction);
+
+            pub fn process_instruction(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+                match VaultInstruction::unpack(data)? {
+                    VaultInstruction::Withdraw => process_withdraw(program_id, accounts),
+                }
+            }
+
+            pub fn process_withdraw(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+                let iter = &mut accounts.iter();
+                let authority = next_account_info(iter)?;
+                let vault = next_account_info(iter)?;
+                if !authority.is_signer {
+                    return Err(ProgramError::MissingRequiredSignature);
+                }
+                if vault.owner != program_id {
+                    return Err(ProgramError::IncorrectProgramId);
+                }
+                Ok(())
+            }
+
+            fn assert_key(account: &AccountInfo, expected: &Pubkey) -> ProgramResult {
+                if account.key != expected {
+                    return Err(ProgramError::InvalidArgument);
+                }
+                Ok(())
+            }
+        "#;
+
+        let result = analyze_file_access_control(code).unwrap();
+        assert_eq!(result.gated_handler_count, 1);
+        assert_eq!(result.manual_check_count, 3); // is_signer + owner + key
+        assert!(result.unique_roles.contains("signer"));
+        assert!(result.unique_roles.contains("owner"));
+    }
+
+    #[test]
+    fn test_native_processor_methods() {
+        let code = r#"
+            entrypoint!(process_instruction);
+
+            pub fn process_instruction(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+                Processor::process(program_id, accounts, data)
+            }
+
+            pub struct Processor;
+
+            impl Processor {
+                pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+                    match VaultInstruction::unpack(data)? {
+                        VaultInstruction::Deposit { amount } => Self::process_deposit(accounts, amount),
+                        VaultInstruction::Withdraw => Self::process_withdraw(program_id, accounts),
+                    }
+                }
+
+                fn process_deposit(accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+                    let iter = &mut accounts.iter();
+                    let depositor = next_account_info(iter)?;
+                    let vault = next_account_info(iter)?;
+                    if !depositor.is_signer {
+                        return Err(ProgramError::MissingRequiredSignature);
+                    }
+                    Ok(())
+                }
+
+                fn process_withdraw(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+                    let iter = &mut accounts.iter();
+                    let authority = next_account_info(iter)?;
+                    let vault = next_account_info(iter)?;
+                    let destination = next_account_info(iter)?;
+                    if !authority.is_signer {
+                        return Err(ProgramError::MissingRequiredSignature);
+                    }
+                    if vault.owner != program_id {
+                        return Err(ProgramError::IncorrectProgramId);
+                    }
+                    Ok(())
+                }
+            }
+        "#;
+
+        let result = analyze_file_access_control(code).unwrap();
+        assert_eq!(result.gated_handler_count, 2);
+        assert_eq!(result.manual_check_count, 3); // 2 is_signer + owner
+    }
+
+    #[test]
+    fn test_native_helpers_are_not_gated_handlers() {
+        let code = r#"
+            entrypoint!(process_instruction);
+
+            pub fn process_instruction(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+                assert_signers(accounts)
+            }
+
+            fn assert_signers(accounts: &[AccountInfo]) -> ProgramResult {
+                if !accounts[0].is_signer {
+                    return Err(ProgramError::MissingRequiredSignature);
+                }
+                Ok(())
+            }
+        "#;
+
+        let result = analyze_file_access_control(code).unwrap();
+        assert_eq!(result.gated_handler_count, 0);
+        assert_eq!(result.manual_check_count, 1);
     }
 
     #[test]
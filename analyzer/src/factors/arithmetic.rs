@@ -4,13 +4,16 @@
 //! It is "handler-centric," meaning it only counts high-risk arithmetic
 //! operations (*, /, %) that occur *inside* a true Anchor instruction handler.
 
+use crate::factors::handlers::{file_module_path, HandlerIndex};
+use crate::factors::normalization::{Curve, Normalization};
+use crate::factors::registry::{Factor, FactorContext, FactorOutput, FindingRule, Severity};
+use crate::workspace::{ParsedWorkspace, SourceLocation};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
-use std::path::PathBuf;
+use std::path::Path;
 use syn::{
-    parse_file,
     visit::{self, Visit},
-    BinOp, Expr, ExprBinary, ExprCall, ExprMethodCall, ItemFn,
+    BinOp, Block, Expr, ExprBinary, ExprCall, ExprMethodCall, ItemFn, ItemMod,
 };
 
 /// Metrics for high-risk arithmetic operations in Anchor handlers
@@ -29,6 +32,8 @@ pub struct ArithmeticMetrics {
     pub math_handlers: Vec<String>,
     /// Breakdown of all ops found
     pub operation_breakdown: HashMap<String, u32>,
+    /// Where each `/`, `%`, `.div()` or `.rem()` reachable from a handler is
+    pub unchecked_division_locations: Vec<SourceLocation>,
 
     /// Final Normalized Factor (0-100)
     pub arithmetic_factor: f64,
@@ -48,26 +53,72 @@ impl ArithmeticMetrics {
             "mediumRiskOpsCount": self.medium_risk_ops_count,
             "mathHandlers": self.math_handlers,
             "operationBreakdown": self.operation_breakdown,
+            "uncheckedDivisionLocations": self.unchecked_division_locations,
         })
     }
+
+    /// Default Arithmetic Factor scoring
+    /// Raw score: math handlers × 10 + high-risk ops × 3 + medium-risk ops × 1
+    /// Normalized so that 50 is 100
+    pub fn default_normalization() -> Normalization {
+        Normalization::new(Curve::linear(0.0, 50.0))
+            .term("mathHandlers", 10.0)
+            .term("highRiskOps", 3.0)
+            .term("mediumRiskOps", 1.0)
+    }
+
+    /// Measurements the Arithmetic Factor is computed from
+    pub fn normalization_inputs(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            ("mathHandlers", self.total_math_handlers as f64),
+            ("highRiskOps", self.high_risk_ops_count as f64),
+            ("mediumRiskOps", self.medium_risk_ops_count as f64),
+        ]
+    }
+
+    /// Fill the raw score and the normalized factor using `normalization`
+    fn score_with(&mut self, normalization: &Normalization) {
+        let inputs = self.normalization_inputs();
+        self.raw_risk_score = normalization.weighted_sum(&inputs);
+        self.arithmetic_factor = normalization.score(&inputs);
+    }
+}
+
+/// A free function of the workspace: a node of the call graph
+#[derive(Debug)]
+struct FnNode<'a> {
+    /// File declaring the function
+    file: &'a str,
+    /// Module path and name, e.g. `["instructions", "swap", "handler"]`
+    path: Vec<String>,
 }
 
 /// Pass 1: Visitor to find all mathy functions and their operations
-#[derive(Debug, Default)]
-struct MathFinderVisitor {
-    current_function_name: Option<String>,
-    math_ops_by_function: HashMap<String, (u32, u32)>, // (high_risk_ops, medium_risk_ops)
+#[derive(Debug)]
+struct MathFinderVisitor<'g> {
+    current_file: String,
+    /// Call graph node of every function, by the identity of its body
+    functions: &'g HashMap<*const Block, usize>,
+    current_function: Option<usize>,
+    math_ops_by_function: HashMap<usize, (u32, u32)>, // (high_risk_ops, medium_risk_ops)
     operation_breakdown: HashMap<String, u32>,
+    /// Unchecked divisions and remainders, by enclosing function
+    unchecked_divisions_by_function: HashMap<usize, Vec<SourceLocation>>,
 }
 
 /// Pass 2: Visitor to build call graph
 #[derive(Debug, Default)]
-struct CallGraphVisitor {
-    current_function_name: Option<String>,
-    call_graph: HashMap<String, HashSet<String>>, // Maps function to functions it calls
+struct CallGraphVisitor<'a> {
+    current_file: &'a str,
+    module_path: Vec<String>,
+    current_function: Option<usize>,
+    functions: Vec<FnNode<'a>>,
+    ids: HashMap<*const Block, usize>,
+    /// Paths of the calls each function makes, resolved once every file is visited
+    calls: HashMap<usize, Vec<Vec<String>>>,
 }
 
-impl MathFinderVisitor {
+impl MathFinderVisitor<'_> {
     /// Checks if a function/method name is a high-risk operation
     fn is_high_risk_math_fn(&self, name: &str) -> bool {
         matches!(
@@ -100,11 +151,8 @@ impl MathFinderVisitor {
 
     /// Records an operation in the current function
     fn record_op(&mut self, op: &str, risk: &str) {
-        if let Some(ref func_name) = self.current_function_name {
-            let entry = self
-                .math_ops_by_function
-                .entry(func_name.clone())
-                .or_insert((0, 0));
+        if let Some(func) = self.current_function {
+            let entry = self.math_ops_by_function.entry(func).or_insert((0, 0));
             *self.operation_breakdown.entry(op.to_string()).or_insert(0) += 1;
 
             if risk == "high" {
@@ -114,24 +162,41 @@ impl MathFinderVisitor {
             }
         }
     }
+
+    /// Records where an unchecked division happens in the current function
+    fn record_unchecked_division<T: syn::spanned::Spanned>(&mut self, node: &T) {
+        if let Some(func) = self.current_function {
+            self.unchecked_divisions_by_function
+                .entry(func)
+                .or_default()
+                .push(SourceLocation::of(&self.current_file, node));
+        }
+    }
 }
 
-impl<'ast> Visit<'ast> for MathFinderVisitor {
+impl<'ast> Visit<'ast> for MathFinderVisitor<'_> {
     fn visit_item_fn(&mut self, node: &'ast ItemFn) {
         // Set context for this function
-        self.current_function_name = Some(node.sig.ident.to_string());
+        let enclosing = self.current_function;
+        self.current_function = self.functions.get(&(&*node.block as *const Block)).copied();
 
         // Visit the function body
         visit::visit_item_fn(self, node);
 
-        // Clear context
-        self.current_function_name = None;
+        // Restore the enclosing function's context
+        self.current_function = enclosing;
     }
 
     fn visit_expr_binary(&mut self, node: &'ast ExprBinary) {
         match &node.op {
-            BinOp::Div(_) => self.record_op("Div (/)", "high"),
-            BinOp::Rem(_) => self.record_op("Rem (%)", "high"),
+            BinOp::Div(_) => {
+                self.record_op("Div (/)", "high");
+                self.record_unchecked_division(node);
+            }
+            BinOp::Rem(_) => {
+                self.record_op("Rem (%)", "high");
+                self.record_unchecked_division(node);
+            }
             BinOp::Mul(_) => self.record_op("Mul (*)", "medium"),
             _ => {
                 // Ignored: Add, Sub, Bitwise, etc.
@@ -144,6 +209,9 @@ impl<'ast> Visit<'ast> for MathFinderVisitor {
         let method_name = node.method.to_string();
         if self.is_high_risk_math_fn(&method_name) {
             self.record_op(&method_name, "high");
+            if matches!(method_name.as_str(), "div" | "rem") {
+                self.record_unchecked_division(node);
+            }
         } else if self.is_medium_risk_math_fn(&method_name) {
             self.record_op(&method_name, "medium");
         }
@@ -165,13 +233,24 @@ impl<'ast> Visit<'ast> for MathFinderVisitor {
     }
 }
 
-impl CallGraphVisitor {
-    /// Extracts function name from a call expression
-    fn extract_function_name(&self, node: &ExprCall) -> Option<String> {
+impl<'a> CallGraphVisitor<'a> {
+    /// Visit a file, naming its functions after the module the file declares
+    fn visit_workspace_file(&mut self, path: &'a str, ast: &'a syn::File) {
+        self.current_file = path;
+        self.module_path = file_module_path(path);
+        self.visit_file(ast);
+    }
+
+    /// Extracts the path of a called function, e.g. `["instructions", "swap", "handler"]`
+    fn extract_function_path(&self, node: &ExprCall) -> Option<Vec<String>> {
         if let Expr::Path(expr_path) = &*node.func {
-            if let Some(segment) = expr_path.path.segments.last() {
-                return Some(segment.ident.to_string());
-            }
+            let path: Vec<String> = expr_path
+                .path
+                .segments
+                .iter()
+                .map(|segment| segment.ident.to_string())
+                .collect();
+            return (!path.is_empty()).then_some(path);
         }
         None
     }
@@ -180,59 +259,155 @@ impl CallGraphVisitor {
     fn extract_method_name(&self, node: &ExprMethodCall) -> Option<String> {
         Some(node.method.to_string())
     }
+
+    fn record_call(&mut self, path: Vec<String>) {
+        if let Some(caller) = self.current_function {
+            self.calls.entry(caller).or_default().push(path);
+        }
+    }
+
+    /// Functions a call made by `caller` may reach
+    ///
+    /// `crate::`, `self::` and `super::` paths resolve exactly; other paths
+    /// resolve relative to the caller's module first, then from the crate
+    /// root, and finally to every function of the crate whose path ends with
+    /// the call (imports are not followed).
+    fn resolve_call(&self, caller: usize, call: &[String]) -> Vec<usize> {
+        let caller_node = &self.functions[caller];
+        let module = &caller_node.path[..caller_node.path.len() - 1];
+
+        let (anchored, rest) = match call.first().map(String::as_str) {
+            Some("crate") => (Some(&[][..]), &call[1..]),
+            Some("self") => (Some(module), &call[1..]),
+            Some("super") => {
+                let supers = call.iter().take_while(|s| *s == "super").count();
+                (
+                    Some(&module[..module.len().saturating_sub(supers)]),
+                    &call[supers..],
+                )
+            }
+            _ => (None, call),
+        };
+        if rest.is_empty() {
+            return Vec::new();
+        }
+
+        let root = crate_root(caller_node.file);
+        let candidates: Vec<usize> = self
+            .functions
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| crate_root(node.file) == root && node.path.ends_with(rest))
+            .map(|(id, _)| id)
+            .collect();
+        let exactly = |prefix: &[String]| -> Vec<usize> {
+            candidates
+                .iter()
+                .copied()
+                .filter(|id| {
+                    let path = &self.functions[*id].path;
+                    path.len() == prefix.len() + rest.len() && path.starts_with(prefix)
+                })
+                .collect()
+        };
+
+        let resolved = match anchored {
+            Some(prefix) => exactly(prefix),
+            None => {
+                let relative = exactly(module);
+                if relative.is_empty() {
+                    exactly(&[])
+                } else {
+                    relative
+                }
+            }
+        };
+        if resolved.is_empty() {
+            candidates
+        } else {
+            resolved
+        }
+    }
+
+    /// Edges from every function to the functions its calls resolve to
+    fn call_graph(&self) -> HashMap<usize, HashSet<usize>> {
+        self.calls
+            .iter()
+            .map(|(&caller, calls)| {
+                let callees = calls
+                    .iter()
+                    .flat_map(|call| self.resolve_call(caller, call))
+                    .collect();
+                (caller, callees)
+            })
+            .collect()
+    }
 }
 
-impl<'ast> Visit<'ast> for CallGraphVisitor {
-    fn visit_item_fn(&mut self, node: &'ast ItemFn) {
+impl<'a> Visit<'a> for CallGraphVisitor<'a> {
+    fn visit_item_mod(&mut self, node: &'a ItemMod) {
+        self.module_path.push(node.ident.to_string());
+        visit::visit_item_mod(self, node);
+        self.module_path.pop();
+    }
+
+    fn visit_item_fn(&mut self, node: &'a ItemFn) {
+        // Register the function as a node of the graph
+        let id = self.functions.len();
+        let mut path = self.module_path.clone();
+        path.push(node.sig.ident.to_string());
+        self.functions.push(FnNode {
+            file: self.current_file,
+            path,
+        });
+        self.ids.insert(&*node.block as *const Block, id);
+
         // Set context for this function
-        self.current_function_name = Some(node.sig.ident.to_string());
+        let enclosing = self.current_function.replace(id);
 
         // Visit the function body
         visit::visit_item_fn(self, node);
 
-        // Clear context
-        self.current_function_name = None;
+        // Restore the enclosing function's context
+        self.current_function = enclosing;
     }
 
-    fn visit_expr_call(&mut self, node: &'ast ExprCall) {
-        if let Some(ref caller_name) = self.current_function_name {
-            if let Some(called_name) = self.extract_function_name(node) {
-                self.call_graph
-                    .entry(caller_name.clone())
-                    .or_default()
-                    .insert(called_name);
-            }
+    fn visit_expr_call(&mut self, node: &'a ExprCall) {
+        if let Some(called_path) = self.extract_function_path(node) {
+            self.record_call(called_path);
         }
         visit::visit_expr_call(self, node);
     }
 
-    fn visit_expr_method_call(&mut self, node: &'ast ExprMethodCall) {
-        if let Some(ref caller_name) = self.current_function_name {
-            if let Some(called_name) = self.extract_method_name(node) {
-                self.call_graph
-                    .entry(caller_name.clone())
-                    .or_default()
-                    .insert(called_name);
-            }
+    fn visit_expr_method_call(&mut self, node: &'a ExprMethodCall) {
+        if let Some(called_name) = self.extract_method_name(node) {
+            self.record_call(vec![called_name]);
         }
         visit::visit_expr_method_call(self, node);
     }
 }
 
+/// The package a source file belongs to: its path up to the last `src/`
+fn crate_root(file: &str) -> &str {
+    file.rfind("src/")
+        .or_else(|| file.rfind("src\\"))
+        .map_or("", |i| &file[..i])
+}
+
 /// Performs DFS traversal to find all functions reachable from a starting function
 fn find_reachable_functions(
-    call_graph: &HashMap<String, HashSet<String>>,
-    start_function: &str,
-) -> HashSet<String> {
+    call_graph: &HashMap<usize, HashSet<usize>>,
+    start_function: usize,
+) -> HashSet<usize> {
     let mut visited = HashSet::new();
-    let mut stack = vec![start_function.to_string()];
+    let mut stack = vec![start_function];
 
     while let Some(current) = stack.pop() {
-        if visited.insert(current.clone()) {
+        if visited.insert(current) {
             if let Some(callees) = call_graph.get(&current) {
                 for callee in callees {
                     if !visited.contains(callee) {
-                        stack.push(callee.clone());
+                        stack.push(*callee);
                     }
                 }
             }
@@ -242,90 +417,74 @@ fn find_reachable_functions(
     visited
 }
 
-/// Checks if a function is an Anchor handler
-fn is_anchor_handler(node: &ItemFn) -> bool {
-    // Robust heuristic for an Anchor handler:
-    // 1. It's public
-    // 2. Its *first* argument is `Context<...>`
-    matches!(node.vis, syn::Visibility::Public(_))
-        && node.sig.inputs.len() > 0
-        && is_context_arg(&node.sig.inputs[0])
-}
-
-/// Checks if an argument is the Anchor Context
-fn is_context_arg(arg: &syn::FnArg) -> bool {
-    if let syn::FnArg::Typed(pat_type) = arg {
-        if let syn::Type::Path(type_path) = &*pat_type.ty {
-            if let Some(segment) = type_path.path.segments.last() {
-                return segment.ident == "Context";
-            }
-        }
-    }
-    false
-}
-
 /// Main driver function to run the two-pass analysis
 pub fn calculate_workspace_arithmetic(
-    workspace_path: &PathBuf,
+    workspace_path: &Path,
     selected_files: &[String],
+) -> Result<ArithmeticMetrics, Box<dyn std::error::Error>> {
+    let workspace = ParsedWorkspace::load(workspace_path, selected_files);
+    analyze_workspace_arithmetic(&workspace)
+}
+
+/// Run the two-pass call graph analysis over an already-parsed workspace
+pub fn analyze_workspace_arithmetic(
+    workspace: &ParsedWorkspace,
 ) -> Result<ArithmeticMetrics, Box<dyn std::error::Error>> {
     log::info!("🔍 ARITHMETIC DEBUG: Starting two-pass call graph analysis...");
 
-    let mut math_finder = MathFinderVisitor::default();
+    // Pass 2 runs first: it names every function after its module, so calls
+    // to same-named functions (`swap::handler`, `deposit::handler`) stay apart
     let mut call_graph_builder = CallGraphVisitor::default();
-    let mut all_handlers = Vec::new();
-
-    // Analyze each selected file
-    for file_path in selected_files {
-        let full_path = workspace_path.join(file_path);
-        if !full_path.exists() || !full_path.is_file() {
-            continue;
-        }
-
-        match std::fs::read_to_string(&full_path) {
-            Ok(content) => match parse_file(&content) {
-                Ok(ast) => {
-                    // Pass 1: Find all mathy functions
-                    math_finder.visit_file(&ast);
-
-                    // Pass 2: Build call graph
-                    call_graph_builder.visit_file(&ast);
-
-                    // Also collect all handlers for final analysis
-                    for item in &ast.items {
-                        if let syn::Item::Fn(func) = item {
-                            if is_anchor_handler(func) {
-                                all_handlers.push(func.sig.ident.to_string());
-                            }
-                        }
-                    }
-                }
-                Err(e) => {
-                    log::warn!("Failed to parse AST for {:?}: {}", full_path, e);
-                }
-            },
-            Err(e) => {
-                log::warn!("Failed to read file {:?}: {}", full_path, e);
-            }
-        }
+    for file in &workspace.files {
+        call_graph_builder.visit_workspace_file(&file.path, &file.ast);
+    }
+    let call_graph = call_graph_builder.call_graph();
+
+    // Pass 1: Find all mathy functions
+    let mut math_finder = MathFinderVisitor {
+        current_file: String::new(),
+        functions: &call_graph_builder.ids,
+        current_function: None,
+        math_ops_by_function: HashMap::new(),
+        operation_breakdown: HashMap::new(),
+        unchecked_divisions_by_function: HashMap::new(),
+    };
+    for file in &workspace.files {
+        math_finder.current_file = file.path.clone();
+        math_finder.visit_file(&file.ast);
     }
 
+    // Handlers are discovered across the whole workspace, including `#[program]` modules
+    let handlers = HandlerIndex::from_workspace(workspace);
+
     // --- Pass 3: Call Graph Analysis ---
-    let mut metrics = ArithmeticMetrics::default();
-    metrics.total_handlers_found = all_handlers.len() as u32;
+    let mut metrics = ArithmeticMetrics {
+        total_handlers_found: handlers.len() as u32,
+        ..Default::default()
+    };
+
+    let mut reachable_from_handlers = HashSet::new();
+    for handler in handlers.handlers() {
+        // Start from the function carrying the handler logic, not its name:
+        // every instruction module may call its implementation `handler`
+        let Some(&start) = call_graph_builder
+            .ids
+            .get(&(handler.body().block as *const Block))
+        else {
+            continue;
+        };
 
-    for handler_name in &all_handlers {
         // Find all functions reachable from this handler
-        let reachable_functions =
-            find_reachable_functions(&call_graph_builder.call_graph, handler_name);
+        let reachable_functions = find_reachable_functions(&call_graph, start);
+        reachable_from_handlers.extend(reachable_functions.iter().copied());
 
         let mut handler_has_math = false;
         let mut handler_high_risk_ops = 0;
         let mut handler_medium_risk_ops = 0;
 
         // Check if any reachable function contains math operations
-        for func_name in &reachable_functions {
-            if let Some((high_ops, medium_ops)) = math_finder.math_ops_by_function.get(func_name) {
+        for func in &reachable_functions {
+            if let Some((high_ops, medium_ops)) = math_finder.math_ops_by_function.get(func) {
                 handler_has_math = true;
                 handler_high_risk_ops += high_ops;
                 handler_medium_risk_ops += medium_ops;
@@ -334,7 +493,7 @@ pub fn calculate_workspace_arithmetic(
 
         if handler_has_math {
             metrics.total_math_handlers += 1;
-            metrics.math_handlers.push(handler_name.clone());
+            metrics.math_handlers.push(handler.name.clone());
             metrics.high_risk_ops_count += handler_high_risk_ops;
             metrics.medium_risk_ops_count += handler_medium_risk_ops;
         }
@@ -343,18 +502,17 @@ pub fn calculate_workspace_arithmetic(
     // Copy operation breakdown from math finder
     metrics.operation_breakdown = math_finder.operation_breakdown;
 
-    // --- Final Calculation and Normalization (0-100) ---
-    // 1. Calculate the Raw Score
-    let raw_risk_score = (metrics.total_math_handlers as f64 * 10.0)
-        + (metrics.high_risk_ops_count as f64 * 3.0)
-        + (metrics.medium_risk_ops_count as f64 * 1.0);
-
-    // 2. Normalize to 0-100
-    let upper_bound = 50.0; // Our 100% risk cap
-    let factor = (raw_risk_score / upper_bound) * 100.0;
+    for (func, locations) in math_finder.unchecked_divisions_by_function {
+        if reachable_from_handlers.contains(&func) {
+            metrics.unchecked_division_locations.extend(locations);
+        }
+    }
+    metrics
+        .unchecked_division_locations
+        .sort_by(|a, b| (&a.file, a.line, a.column).cmp(&(&b.file, b.line, b.column)));
 
-    metrics.arithmetic_factor = factor.min(100.0); // Cap at 100
-    metrics.raw_risk_score = raw_risk_score;
+    // --- Final Calculation and Normalization (0-100) ---
+    metrics.score_with(&ArithmeticMetrics::default_normalization());
 
     log::info!(
         "🔍 ARITHMETIC DEBUG: Two-pass analysis complete. Found {} handlers, {} math handlers. Factor: {:.2}",
@@ -366,6 +524,65 @@ pub fn calculate_workspace_arithmetic(
     Ok(metrics)
 }
 
+/// A handler divides with `/`, `%`, `.div()` or `.rem()`, which panic on zero
+pub const UNCHECKED_DIVISION: FindingRule = FindingRule {
+    id: "arithmeticOperations/uncheckedDivision",
+    name: "UncheckedDivision",
+    description:
+        "Division or remainder reachable from a handler without `checked_div`/`checked_rem`",
+    severity: Severity::Warning,
+};
+
+/// Registry entry for the arithmetic operations factor
+pub struct ArithmeticFactor;
+
+impl Factor for ArithmeticFactor {
+    fn id(&self) -> &'static str {
+        "arithmeticOperations"
+    }
+
+    fn version(&self) -> &'static str {
+        "1.4.0"
+    }
+
+    fn name(&self) -> &'static str {
+        "Arithmetic operations"
+    }
+
+    fn rules(&self) -> &'static [FindingRule] {
+        &[UNCHECKED_DIVISION]
+    }
+
+    fn normalization(&self) -> Option<Normalization> {
+        Some(ArithmeticMetrics::default_normalization())
+    }
+
+    fn compute(&self, ctx: &FactorContext) -> Result<FactorOutput, Box<dyn std::error::Error>> {
+        let mut metrics = analyze_workspace_arithmetic(ctx.workspace)?;
+        metrics.score_with(&ctx.normalization_or(ArithmeticMetrics::default_normalization));
+        let findings = metrics
+            .unchecked_division_locations
+            .iter()
+            .map(|location| {
+                UNCHECKED_DIVISION.at(
+                    location.clone(),
+                    "Unchecked division reachable from an instruction handler; use `checked_div`/`checked_rem`",
+                )
+            })
+            .collect();
+        Ok(
+            FactorOutput::keyed(self.id(), metrics.arithmetic_factor, metrics.to_json()).with_note(format!(
+                "Analyzed arithmetic operations: {} math handlers, {} high-risk ops, {} medium-risk ops, factor {:.1}",
+                metrics.total_math_handlers,
+                metrics.high_risk_ops_count,
+                metrics.medium_risk_ops_count,
+                metrics.arithmetic_factor,
+            ))
+            .with_findings(findings),
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -788,4 +1005,134 @@ mod tests {
         assert!(result.high_risk_ops_count >= 2); // At least 2 division operations
         assert!(result.arithmetic_factor > 0.0);
     }
+
+    #[test]
+    fn test_program_module_handlers() {
+        let lib = r#"
+            use anchor_lang::prelude::*;
+
+            #[program]
+            pub mod amm {
+                use super::*;
+
+                pub fn swap(ctx: Context<'_, '_, '_, 'info, Swap<'info>>, amount: u64) -> Result<()> {
+                    instructions::swap::handler(ctx, amount)
+                }
+
+                pub fn sync(ctx: Context<Sync>) -> Result<()> {
+                    Ok(())
+                }
+            }
+        "#;
+        let swap = r#"
+            use anchor_lang::prelude::*;
+
+            pub fn handler(ctx: Context<Swap>, amount: u64) -> Result<()> {
+                let fee = amount / 100;
+                Ok(())
+            }
+        "#;
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src/instructions")).unwrap();
+        std::fs::write(dir.path().join("src/lib.rs"), lib).unwrap();
+        std::fs::write(dir.path().join("src/instructions/swap.rs"), swap).unwrap();
+
+        let result = calculate_workspace_arithmetic(
+            dir.path(),
+            &[
+                "src/lib.rs".to_string(),
+                "src/instructions/swap.rs".to_string(),
+            ],
+        )
+        .unwrap();
+
+        // The delegated implementation is not counted as a separate handler
+        assert_eq!(result.total_handlers_found, 2);
+        assert_eq!(result.math_handlers, vec!["swap".to_string()]);
+        assert_eq!(result.high_risk_ops_count, 1);
+    }
+
+    #[test]
+    fn test_same_named_implementations() {
+        let lib = r#"
+            #[program]
+            pub mod amm {
+                use super::*;
+
+                pub fn swap(ctx: Context<Swap>, amount: u64) -> Result<()> {
+                    instructions::swap::handler(ctx, amount)
+                }
+
+                pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+                    instructions::deposit::handler(ctx, amount)
+                }
+            }
+        "#;
+        let swap = r#"
+            pub fn handler(ctx: Context<Swap>, amount: u64) -> Result<()> {
+                let fee = amount / 100;
+                let out = amount % 7;
+                Ok(())
+            }
+        "#;
+        let deposit = r#"
+            pub fn handler(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+                ctx.accounts.pool.balance += amount;
+                Ok(())
+            }
+        "#;
+
+        let files = [
+            ("programs/amm/src/lib.rs", lib),
+            ("programs/amm/src/instructions/swap.rs", swap),
+            ("programs/amm/src/instructions/deposit.rs", deposit),
+        ]
+        .into_iter()
+        .map(|(path, code)| crate::workspace::ParsedFile::parse(path, code.to_string()).unwrap())
+        .collect();
+        let workspace = ParsedWorkspace::from_files(Path::new("."), files);
+        let metrics = analyze_workspace_arithmetic(&workspace).unwrap();
+
+        // Each entry point reaches only its own `handler`
+        assert_eq!(metrics.total_handlers_found, 2);
+        assert_eq!(metrics.math_handlers, vec!["swap".to_string()]);
+        assert_eq!(metrics.high_risk_ops_count, 2);
+    }
+
+    #[test]
+    fn test_unchecked_division_locations() {
+        let code = r#"
+            struct Context<T> {
+                _phantom: std::marker::PhantomData<T>,
+            }
+
+            struct Swap;
+
+            type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+            pub fn swap(ctx: Context<Swap>, a: u64, b: u64) -> Result<()> {
+                let checked = a.checked_div(b);
+                let out = quote(a, b);
+                Ok(())
+            }
+
+            fn quote(a: u64, b: u64) -> u64 {
+                a / b
+            }
+
+            fn unused(a: u64) -> u64 {
+                a % 7
+            }
+        "#;
+
+        let file = crate::workspace::ParsedFile::parse("lib.rs", code.to_string()).unwrap();
+        let workspace = ParsedWorkspace::from_files(Path::new("."), vec![file]);
+        let metrics = analyze_workspace_arithmetic(&workspace).unwrap();
+
+        // Only the division reachable from the handler is reported
+        assert_eq!(metrics.unchecked_division_locations.len(), 1);
+        let location = &metrics.unchecked_division_locations[0];
+        assert_eq!((location.file.as_str(), location.line), ("lib.rs", 17));
+    }
 }
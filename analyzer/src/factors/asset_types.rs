@@ -4,6 +4,9 @@
 //! standards (SPL-Token, SPL-Token-2022, Metaplex NFT, Custom) via reliable
 //! AST-based type and import detection.
 
+use crate::factors::normalization::{Curve, Normalization};
+use crate::factors::registry::{Factor, FactorContext, FactorOutput};
+use crate::workspace::ParsedWorkspace;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use syn::{visit::Visit, ExprCall, ItemEnum, ItemStruct, Type, UseTree};
@@ -42,6 +45,27 @@ impl AssetTypesMetrics {
             "filesSkipped": self.files_skipped,
         })
     }
+
+    /// Default Asset Types Factor scoring
+    /// 25% per distinct standard: 0 standards = 0%, 1 = 25%, ..., 4 = 100%
+    pub fn default_normalization() -> Normalization {
+        Normalization::new(Curve::percent())
+            .term("splToken", 25.0)
+            .term("splToken2022", 25.0)
+            .term("metaplexNft", 25.0)
+            .term("customAssets", 25.0)
+    }
+
+    /// Measurements the Asset Types Factor is computed from, 1 per standard in use
+    pub fn normalization_inputs(&self) -> Vec<(&'static str, f64)> {
+        let used = |flag: bool| if flag { 1.0 } else { 0.0 };
+        vec![
+            ("splToken", used(self.uses_spl_token)),
+            ("splToken2022", used(self.uses_spl_token_2022)),
+            ("metaplexNft", used(self.uses_metaplex_nft)),
+            ("customAssets", used(self.custom_asset_definitions > 0)),
+        ]
+    }
 }
 
 /// Visitor for detecting asset standards via AST analysis
@@ -61,7 +85,7 @@ impl AssetTypesVisitor {
         self.metrics
             .detected_standard_indicators
             .entry(standard.to_string())
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(indicator.to_string());
     }
 
@@ -249,54 +273,29 @@ impl<'ast> Visit<'ast> for AssetTypesVisitor {
 
 /// Calculate asset types metrics for workspace
 pub fn calculate_workspace_asset_types(
-    workspace_path: &std::path::PathBuf,
+    workspace_path: &std::path::Path,
     selected_files: &[String],
+) -> Result<AssetTypesMetrics, Box<dyn std::error::Error>> {
+    let workspace = ParsedWorkspace::load(workspace_path, selected_files);
+    analyze_workspace_asset_types(&workspace)
+}
+
+/// Detect asset standards over an already-parsed workspace
+pub fn analyze_workspace_asset_types(
+    workspace: &ParsedWorkspace,
 ) -> Result<AssetTypesMetrics, Box<dyn std::error::Error>> {
     log::info!(
         "🔍 ASSET TYPES DEBUG: Starting analysis for workspace: {:?}",
-        workspace_path
+        workspace.root
     );
 
     let mut visitor = AssetTypesVisitor::new();
-    let mut files_analyzed = 0;
-    let mut files_skipped = 0;
-
-    for file_path in selected_files {
-        let full_path = workspace_path.join(file_path);
+    let files_analyzed = workspace.files.len() as u32;
+    let files_skipped = workspace.skipped_count() as u32;
 
-        if !full_path.exists() {
-            log::warn!("🔍 ASSET TYPES DEBUG: File does not exist: {:?}", full_path);
-            files_skipped += 1;
-            continue;
-        }
-
-        if !full_path.extension().map_or(false, |ext| ext == "rs") {
-            log::info!(
-                "🔍 ASSET TYPES DEBUG: Skipping non-Rust file: {:?}",
-                full_path
-            );
-            files_skipped += 1;
-            continue;
-        }
-
-        log::info!("🔍 ASSET TYPES DEBUG: Analyzing file: {:?}", full_path);
-
-        match std::fs::read_to_string(&full_path) {
-            Ok(content) => match syn::parse_file(&content) {
-                Ok(ast) => {
-                    visitor.visit_file(&ast);
-                    files_analyzed += 1;
-                }
-                Err(e) => {
-                    log::warn!("Failed to parse AST for {:?}: {}", full_path, e);
-                    files_skipped += 1;
-                }
-            },
-            Err(e) => {
-                log::warn!("Failed to read file {:?}: {}", full_path, e);
-                files_skipped += 1;
-            }
-        }
+    for file in &workspace.files {
+        log::info!("🔍 ASSET TYPES DEBUG: Analyzing file: {:?}", file.full_path);
+        visitor.visit_file(&file.ast);
     }
 
     // Calculate final metrics
@@ -315,15 +314,8 @@ pub fn calculate_workspace_asset_types(
         };
 
     // Calculate final factor (0-100) based on variety
-    // 0 standards = 0%, 1 = 25%, 2 = 50%, 3 = 75%, 4 = 100%
-    metrics.asset_types_factor = match metrics.distinct_asset_standards {
-        0 => 0.0,
-        1 => 25.0,
-        2 => 50.0,
-        3 => 75.0,
-        4 => 100.0,
-        _ => 100.0, // Cap at 100%
-    };
+    metrics.asset_types_factor =
+        AssetTypesMetrics::default_normalization().score(&metrics.normalization_inputs());
 
     log::info!(
         "🔍 ASSET TYPES DEBUG: Analysis complete. Files analyzed: {}, Files skipped: {}",
@@ -334,6 +326,45 @@ pub fn calculate_workspace_asset_types(
     Ok(metrics)
 }
 
+/// Registry entry for the asset types factor
+pub struct AssetTypesFactor;
+
+impl Factor for AssetTypesFactor {
+    fn id(&self) -> &'static str {
+        "assetTypes"
+    }
+
+    fn version(&self) -> &'static str {
+        "1.0.0"
+    }
+
+    fn name(&self) -> &'static str {
+        "Asset types"
+    }
+
+    fn normalization(&self) -> Option<Normalization> {
+        Some(AssetTypesMetrics::default_normalization())
+    }
+
+    fn compute(&self, ctx: &FactorContext) -> Result<FactorOutput, Box<dyn std::error::Error>> {
+        let mut metrics = analyze_workspace_asset_types(ctx.workspace)?;
+        metrics.asset_types_factor = ctx
+            .normalization_or(AssetTypesMetrics::default_normalization)
+            .score(&metrics.normalization_inputs());
+        Ok(
+            FactorOutput::keyed(self.id(), metrics.asset_types_factor, metrics.to_json()).with_note(format!(
+                "Analyzed asset types: {} distinct standards (SPL-Token: {}, SPL-Token-2022: {}, Metaplex: {}, Custom: {}), factor {:.1}",
+                metrics.distinct_asset_standards,
+                metrics.uses_spl_token,
+                metrics.uses_spl_token_2022,
+                metrics.uses_metaplex_nft,
+                metrics.custom_asset_definitions,
+                metrics.asset_types_factor,
+            )),
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -4,8 +4,12 @@
 //! for Rust source files using syn AST parsing, with special focus on
 //! Solana/Anchor smart contract patterns.
 
-use std::path::PathBuf;
-use syn::{visit::Visit, Expr, File, ImplItemFn, Item, ItemFn, Meta, MetaList, Stmt, Visibility};
+use crate::factors::handlers::HandlerIndex;
+use crate::factors::normalization::{Curve, Normalization};
+use crate::factors::registry::{Factor, FactorContext, FactorOutput};
+use crate::workspace::ParsedWorkspace;
+use std::path::Path;
+use syn::{visit::Visit, Expr, File, ImplItemFn, Item, ItemFn, Meta, MetaList, Stmt};
 
 #[derive(Debug, Clone)]
 pub struct ComplexityMetrics {
@@ -39,37 +43,58 @@ impl ComplexityMetrics {
         })
     }
 
-    /// Calculate Complexity Factor based on cyclomatic complexity
+    /// Default Complexity Factor scoring
     /// Normalize Max CC to [0, 100] where 50 is mapped to 100 and capped
     /// Normalize Avg CC to [0, 100] where 15 is mapped to 100 and capped
-    /// Weighted sum: 70% Max CC, 30% Avg CC
+    /// Weighted sum: 70% Max CC, 30% Avg CC, rounded to 2 decimal places
+    pub fn default_normalization() -> Normalization {
+        Normalization::new(Curve::percent())
+            .curved_term("maxComplexity", 0.7, Curve::linear(0.0, 50.0))
+            .curved_term("avgComplexity", 0.3, Curve::linear(0.0, 15.0))
+            .rounded(2)
+    }
+
+    /// Measurements the Complexity Factor is computed from
+    pub fn normalization_inputs(&self) -> Vec<(&'static str, f64)> {
+        Self::inputs(self.max_complexity, self.avg_complexity)
+    }
+
+    fn inputs(max_complexity: u32, avg_complexity: f64) -> Vec<(&'static str, f64)> {
+        vec![
+            ("maxComplexity", max_complexity as f64),
+            ("avgComplexity", avg_complexity),
+        ]
+    }
+
+    /// Calculate Complexity Factor based on cyclomatic complexity, with the default scoring
     pub fn calculate_complexity_factor(
         max_cyclomatic_complexity: u32,
         avg_cyclomatic_complexity: f64,
     ) -> f64 {
-        // Normalize Max CC to [0, 100] where 50 is mapped to 100 and capped
-        let max_cc_score = ((max_cyclomatic_complexity as f64 / 50.0) * 100.0).min(100.0);
-
-        // Normalize Avg CC to [0, 100] where 15 is mapped to 100 and capped
-        let avg_cc_score = ((avg_cyclomatic_complexity / 15.0) * 100.0).min(100.0);
-
-        // Weighted sum: 70% Max CC, 30% Avg CC
-        let complexity_factor = 0.7 * max_cc_score + 0.3 * avg_cc_score;
-
-        // Round to two decimal places for clean reporting
-        (complexity_factor * 100.0).round() / 100.0
+        Self::default_normalization().score(&Self::inputs(
+            max_cyclomatic_complexity,
+            avg_cyclomatic_complexity,
+        ))
     }
 }
 
 /// Calculate cyclomatic complexity for workspace files
 pub fn calculate_workspace_cyclomatic_complexity(
-    workspace_path: &PathBuf,
+    workspace_path: &Path,
     selected_files: &[String],
+) -> Result<ComplexityMetrics, Box<dyn std::error::Error>> {
+    let workspace = ParsedWorkspace::load(workspace_path, selected_files);
+    analyze_workspace_complexity(&workspace)
+}
+
+/// Calculate cyclomatic complexity over an already-parsed workspace
+pub fn analyze_workspace_complexity(
+    workspace: &ParsedWorkspace,
 ) -> Result<ComplexityMetrics, Box<dyn std::error::Error>> {
     log::info!(
         "Calculating cyclomatic complexity for {} files in workspace: {:?}",
-        selected_files.len(),
-        workspace_path
+        workspace.files.len(),
+        workspace.root
     );
 
     let mut total_functions = 0;
@@ -81,69 +106,49 @@ pub fn calculate_workspace_cyclomatic_complexity(
     let mut anchor_constraint_complexity_sum = 0;
     let mut max_anchor_constraint_complexity = 0;
     let mut analyzed_files = 0;
-    let mut failed_files = Vec::new();
-
-    for file_path in selected_files {
-        let full_file_path = workspace_path.join(file_path);
-
-        // Check if the file exists and is a Rust file
-        if full_file_path.exists() && full_file_path.is_file() {
-            if let Some(extension) = full_file_path.extension() {
-                if extension == "rs" {
-                    match std::fs::read_to_string(&full_file_path) {
-                        Ok(content) => {
-                            match analyze_file_complexity(&content) {
-                                Ok(file_metrics) => {
-                                    total_functions += file_metrics.total_functions;
-                                    complexity_sum += (file_metrics.avg_complexity
-                                        * file_metrics.total_functions as f64)
-                                        as u32;
-                                    max_complexity =
-                                        max_complexity.max(file_metrics.max_complexity);
-                                    cognitive_complexity_sum += (file_metrics
-                                        .avg_cognitive_complexity
-                                        * file_metrics.total_functions as f64)
-                                        as u32;
-                                    max_cognitive_complexity = max_cognitive_complexity
-                                        .max(file_metrics.max_cognitive_complexity);
-                                    anchor_instruction_handlers +=
-                                        file_metrics.anchor_instruction_handlers;
-                                    anchor_constraint_complexity_sum += (file_metrics
-                                        .avg_anchor_constraint_complexity
-                                        * file_metrics.anchor_instruction_handlers as f64)
-                                        as u32;
-                                    max_anchor_constraint_complexity =
-                                        max_anchor_constraint_complexity
-                                            .max(file_metrics.max_anchor_constraint_complexity);
-                                    analyzed_files += 1;
-
-                                    log::debug!(
-                                        "File {}: {} functions, avg complexity: {:.2}, max complexity: {}",
-                                        file_path,
-                                        file_metrics.total_functions,
-                                        file_metrics.avg_complexity,
-                                        file_metrics.max_complexity
-                                    );
-                                }
-                                Err(e) => {
-                                    log::warn!(
-                                        "Failed to analyze complexity for file {}: {}",
-                                        file_path,
-                                        e
-                                    );
-                                    failed_files.push(format!("{}: {}", file_path, e));
-                                    // Continue with other files instead of failing completely
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            log::warn!("Failed to read file {}: {}", file_path, e);
-                            // Continue with other files
-                        }
-                    }
-                }
-            }
+    let mut failed_files: Vec<String> = workspace
+        .failures
+        .iter()
+        .map(|failure| format!("{}: {}", failure.path, failure.message))
+        .collect();
+    let handlers = HandlerIndex::from_workspace(workspace);
+
+    for file in &workspace.files {
+        // Empty files parse, but carry nothing to measure
+        if file.source.trim().is_empty() {
+            log::warn!(
+                "Failed to analyze complexity for file {}: File content is empty",
+                file.path
+            );
+            failed_files.push(format!("{}: File content is empty", file.path));
+            continue;
         }
+
+        let file_metrics = analyze_ast_complexity(&file.ast, &handlers);
+
+        total_functions += file_metrics.total_functions;
+        complexity_sum +=
+            (file_metrics.avg_complexity * file_metrics.total_functions as f64) as u32;
+        max_complexity = max_complexity.max(file_metrics.max_complexity);
+        cognitive_complexity_sum +=
+            (file_metrics.avg_cognitive_complexity * file_metrics.total_functions as f64) as u32;
+        max_cognitive_complexity =
+            max_cognitive_complexity.max(file_metrics.max_cognitive_complexity);
+        anchor_instruction_handlers += file_metrics.anchor_instruction_handlers;
+        anchor_constraint_complexity_sum += (file_metrics.avg_anchor_constraint_complexity
+            * file_metrics.anchor_instruction_handlers as f64)
+            as u32;
+        max_anchor_constraint_complexity =
+            max_anchor_constraint_complexity.max(file_metrics.max_anchor_constraint_complexity);
+        analyzed_files += 1;
+
+        log::debug!(
+            "File {}: {} functions, avg complexity: {:.2}, max complexity: {}",
+            file.path,
+            file_metrics.total_functions,
+            file_metrics.avg_complexity,
+            file_metrics.max_complexity
+        );
     }
 
     if analyzed_files == 0 {
@@ -213,6 +218,7 @@ pub fn calculate_workspace_cyclomatic_complexity(
 }
 
 /// Analyze cyclomatic complexity for a single file
+#[cfg(test)]
 fn analyze_file_complexity(content: &str) -> Result<ComplexityMetrics, Box<dyn std::error::Error>> {
     // Check if content is empty or too short
     if content.trim().is_empty() {
@@ -243,8 +249,14 @@ fn analyze_file_complexity(content: &str) -> Result<ComplexityMetrics, Box<dyn s
         error_msg
     })?;
 
-    let mut complexity_visitor = ComplexityVisitor::new();
-    complexity_visitor.visit_file(&syntax_tree);
+    let handlers = HandlerIndex::from_ast("lib.rs", &syntax_tree);
+    Ok(analyze_ast_complexity(&syntax_tree, &handlers))
+}
+
+/// Analyze cyclomatic complexity for a parsed file
+fn analyze_ast_complexity(syntax_tree: &File, handlers: &HandlerIndex) -> ComplexityMetrics {
+    let mut complexity_visitor = ComplexityVisitor::new(handlers);
+    complexity_visitor.visit_file(syntax_tree);
 
     let function_count = complexity_visitor.function_complexities.len();
     let max_complexity = complexity_visitor
@@ -299,7 +311,7 @@ fn analyze_file_complexity(content: &str) -> Result<ComplexityMetrics, Box<dyn s
     let complexity_factor =
         ComplexityMetrics::calculate_complexity_factor(max_complexity, avg_complexity);
 
-    Ok(ComplexityMetrics {
+    ComplexityMetrics {
         avg_complexity,
         max_complexity,
         total_functions: function_count,
@@ -309,11 +321,12 @@ fn analyze_file_complexity(content: &str) -> Result<ComplexityMetrics, Box<dyn s
         avg_anchor_constraint_complexity,
         max_anchor_constraint_complexity,
         complexity_factor,
-    })
+    }
 }
 
 /// Enhanced visitor to calculate cyclomatic complexity for functions
-struct ComplexityVisitor {
+struct ComplexityVisitor<'a> {
+    handlers: &'a HandlerIndex<'a>,
     function_complexities: Vec<u32>,
     cognitive_complexities: Vec<u32>,
     anchor_constraint_complexities: Vec<u32>,
@@ -325,9 +338,10 @@ struct ComplexityVisitor {
     current_anchor_constraint_complexity: u32,
 }
 
-impl ComplexityVisitor {
-    fn new() -> Self {
+impl<'a> ComplexityVisitor<'a> {
+    fn new(handlers: &'a HandlerIndex<'a>) -> Self {
         Self {
+            handlers,
             function_complexities: Vec::new(),
             cognitive_complexities: Vec::new(),
             anchor_constraint_complexities: Vec::new(),
@@ -340,46 +354,6 @@ impl ComplexityVisitor {
         }
     }
 
-    /// Check if a function is an Anchor instruction handler
-    fn is_anchor_instruction_handler(&self, item_fn: &ItemFn) -> bool {
-        // Check if function is public (instruction handlers are typically public)
-        if !matches!(item_fn.vis, Visibility::Public(_)) {
-            return false;
-        }
-
-        // Check for Anchor-specific attributes
-        for attr in &item_fn.attrs {
-            if let Ok(meta) = attr.parse_args::<Meta>() {
-                if let Meta::Path(path) = meta {
-                    if path.is_ident("instruction") || path.is_ident("handler") {
-                        return true;
-                    }
-                }
-            }
-        }
-
-        // Check function name patterns common in Anchor programs
-        let name = item_fn.sig.ident.to_string();
-        // Common Anchor instruction patterns
-        if name.ends_with("_handler")
-            || name.starts_with("initialize")
-            || name.starts_with("update")
-            || name.starts_with("transfer")
-            || name.starts_with("swap")
-            || name.starts_with("deposit")
-            || name.starts_with("withdraw")
-        {
-            return true;
-        }
-
-        // Check if function takes a Context parameter (common in Anchor)
-        if self.has_context_parameter(&item_fn.sig) {
-            return true;
-        }
-
-        false
-    }
-
     /// Analyze Anchor account constraints for complexity
     fn analyze_anchor_constraints(&mut self, item_fn: &ItemFn) {
         let mut constraint_count = 0;
@@ -388,12 +362,10 @@ impl ComplexityVisitor {
         // This is a simplified analysis - in practice, you'd need to track
         // the relationship between instruction handlers and their account structs
         for attr in &item_fn.attrs {
-            if let Ok(meta) = attr.parse_args::<Meta>() {
-                if let Meta::List(meta_list) = meta {
-                    if meta_list.path.is_ident("account") {
-                        // Count constraints within #[account(...)] attributes
-                        constraint_count += self.count_constraints_in_meta_list(&meta_list);
-                    }
+            if let Ok(Meta::List(meta_list)) = attr.parse_args::<Meta>() {
+                if meta_list.path.is_ident("account") {
+                    // Count constraints within #[account(...)] attributes
+                    constraint_count += self.count_constraints_in_meta_list(&meta_list);
                 }
             }
         }
@@ -408,87 +380,9 @@ impl ComplexityVisitor {
         // For now, we'll return a basic count based on the presence of account attributes
         1
     }
-
-    /// Check if an impl method is an Anchor instruction handler
-    fn is_anchor_instruction_handler_method(&self, item_fn: &ImplItemFn) -> bool {
-        // Check if method is public (instruction handlers are typically public)
-        if !matches!(item_fn.vis, Visibility::Public(_)) {
-            return false;
-        }
-
-        // Check for Anchor-specific attributes
-        for attr in &item_fn.attrs {
-            if let Ok(meta) = attr.parse_args::<Meta>() {
-                if let Meta::Path(path) = meta {
-                    if path.is_ident("instruction") || path.is_ident("handler") {
-                        return true;
-                    }
-                }
-            }
-        }
-
-        // Check method name patterns common in Anchor programs
-        let name = item_fn.sig.ident.to_string();
-        // Common Anchor instruction patterns for methods
-        if name.ends_with("_handler")
-            || name.starts_with("initialize")
-            || name.starts_with("update")
-            || name.starts_with("transfer")
-            || name.starts_with("swap")
-            || name.starts_with("deposit")
-            || name.starts_with("withdraw")
-            || name == "validate" // Common validation method
-            || name == "execute"
-        // Common execution method
-        {
-            return true;
-        }
-
-        // Check if method takes a Context parameter (common in Anchor)
-        if self.has_context_parameter(&item_fn.sig) {
-            return true;
-        }
-
-        false
-    }
-
-    /// Analyze Anchor account constraints for impl methods
-    fn analyze_anchor_constraints_method(&mut self, item_fn: &ImplItemFn) {
-        let mut constraint_count = 0;
-
-        // Look for #[derive(Accounts)] structs in the method's context
-        for attr in &item_fn.attrs {
-            if let Ok(meta) = attr.parse_args::<Meta>() {
-                if let Meta::List(meta_list) = meta {
-                    if meta_list.path.is_ident("account") {
-                        // Count constraints within #[account(...)] attributes
-                        constraint_count += self.count_constraints_in_meta_list(&meta_list);
-                    }
-                }
-            }
-        }
-
-        self.current_anchor_constraint_complexity = constraint_count;
-    }
-
-    /// Check if a function signature has a Context parameter (Anchor pattern)
-    fn has_context_parameter(&self, sig: &syn::Signature) -> bool {
-        for input in &sig.inputs {
-            if let syn::FnArg::Typed(pat_type) = input {
-                if let syn::Type::Path(type_path) = &*pat_type.ty {
-                    if let Some(segment) = type_path.path.segments.last() {
-                        if segment.ident.to_string().starts_with("Context") {
-                            return true;
-                        }
-                    }
-                }
-            }
-        }
-        false
-    }
 }
 
-impl<'ast> Visit<'ast> for ComplexityVisitor {
+impl<'ast> Visit<'ast> for ComplexityVisitor<'_> {
     fn visit_item(&mut self, item: &'ast Item) {
         match item {
             Item::Fn(item_fn) => {
@@ -500,7 +394,7 @@ impl<'ast> Visit<'ast> for ComplexityVisitor {
                 self.current_anchor_constraint_complexity = 0;
 
                 // Check if this is an Anchor instruction handler
-                let is_anchor_handler = self.is_anchor_instruction_handler(item_fn);
+                let is_anchor_handler = self.handlers.lookup(item_fn).is_some();
                 if is_anchor_handler {
                     self.anchor_instruction_handlers += 1;
                     self.analyze_anchor_constraints(item_fn);
@@ -643,14 +537,6 @@ impl<'ast> Visit<'ast> for ComplexityVisitor {
         self.current_cognitive_complexity = 0;
         self.current_nesting_depth = 0;
         self.max_nesting_depth = 0;
-        self.current_anchor_constraint_complexity = 0;
-
-        // Check if this is an Anchor instruction handler (methods can be handlers too)
-        let is_anchor_handler = self.is_anchor_instruction_handler_method(item_fn);
-        if is_anchor_handler {
-            self.anchor_instruction_handlers += 1;
-            self.analyze_anchor_constraints_method(item_fn);
-        }
 
         // Visit the method body to calculate complexity
         self.visit_block(&item_fn.block);
@@ -658,11 +544,52 @@ impl<'ast> Visit<'ast> for ComplexityVisitor {
         // Store method metrics
         self.function_complexities.push(self.current_complexity);
         self.cognitive_complexities.push(self.max_nesting_depth);
+    }
+}
 
-        if is_anchor_handler {
-            self.anchor_constraint_complexities
-                .push(self.current_anchor_constraint_complexity);
-        }
+/// Registry entry for the cyclomatic complexity factor
+pub struct ComplexityFactor;
+
+impl Factor for ComplexityFactor {
+    fn id(&self) -> &'static str {
+        "complexity"
+    }
+
+    fn version(&self) -> &'static str {
+        "1.2.0"
+    }
+
+    fn name(&self) -> &'static str {
+        "Cyclomatic complexity"
+    }
+
+    fn required(&self) -> bool {
+        true
+    }
+
+    fn normalization(&self) -> Option<Normalization> {
+        Some(ComplexityMetrics::default_normalization())
+    }
+
+    fn compute(&self, ctx: &FactorContext) -> Result<FactorOutput, Box<dyn std::error::Error>> {
+        let mut metrics = analyze_workspace_complexity(ctx.workspace)?;
+        metrics.complexity_factor = ctx
+            .normalization_or(ComplexityMetrics::default_normalization)
+            .score(&metrics.normalization_inputs());
+        Ok(
+            FactorOutput::keyed(self.id(), metrics.complexity_factor, metrics.to_json())
+                .with_field(
+                    "complexityFactor",
+                    serde_json::json!(metrics.complexity_factor),
+                )
+                .with_note(format!(
+                "Calculated cyclomatic complexity: avg={:.2}, max={}, functions={}, complexity factor={:.2}",
+                metrics.avg_complexity,
+                metrics.max_complexity,
+                metrics.total_functions,
+                metrics.complexity_factor,
+            )),
+        )
     }
 }
 
@@ -905,7 +832,7 @@ mod tests {
 
         let result = analyze_file_complexity(code).unwrap();
         assert_eq!(result.total_functions, 3); // validate, execute, check_condition
-        assert_eq!(result.anchor_instruction_handlers, 2); // validate and execute should be detected
+        assert_eq!(result.anchor_instruction_handlers, 0); // Impl methods are not instruction handlers
         assert_eq!(result.max_complexity, 2); // Should have complexity from if/match statements (max is 2)
     }
 
@@ -966,7 +893,7 @@ mod tests {
 
         let result = analyze_file_complexity(code).unwrap();
         assert_eq!(result.total_functions, 2); // anchor_handler + validate
-        assert_eq!(result.anchor_instruction_handlers, 2); // Both should be detected due to Context parameter
+        assert_eq!(result.anchor_instruction_handlers, 1); // Only the free function; impl methods are not handlers
     }
 
     #[test]
@@ -5,11 +5,15 @@
 //! single transaction. It replaces the old name-based checks with a
 //! focused AST analysis of actual CPI calls within handlers.
 
+use crate::factors::handlers::HandlerIndex;
+use crate::factors::normalization::{Curve, Normalization};
+use crate::factors::registry::{Factor, FactorContext, FactorOutput};
+use crate::workspace::{ParsedWorkspace, SourceLocation};
 use quote::quote;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use syn::{
-    parse_file,
+    spanned::Spanned,
     visit::{self, Visit},
     Expr, ItemFn, Path,
 };
@@ -29,6 +33,8 @@ pub struct ComposabilityMetrics {
     /// Helper Metrics (for Audibility)
     /// Maps handler name to the number of CPIs it makes
     pub handler_cpi_counts: HashMap<String, u32>,
+    /// Where each CPI inside a handler was found
+    pub cpi_locations: Vec<SourceLocation>,
     pub files_analyzed: u32,
     pub files_skipped: u32,
 }
@@ -42,31 +48,51 @@ impl ComposabilityMetrics {
             "totalHandlersFound": self.total_handlers_found,
             "multiCpiHandlersCount": self.multi_cpi_handlers_count,
             "handlerCpiCounts": self.handler_cpi_counts,
+            "cpiLocations": self.cpi_locations,
             "filesAnalyzed": self.files_analyzed,
             "filesSkipped": self.files_skipped,
         })
     }
+
+    /// Default Composability Factor scoring
+    /// Raw score: handlers making more than one CPI
+    /// Upper bound: 10 handlers performing multi-CPI actions = 100% risk
+    pub fn default_normalization() -> Normalization {
+        Normalization::new(Curve::linear(0.0, 10.0)).term("multiCpiHandlers", 1.0)
+    }
+
+    /// Measurements the Composability Factor is computed from
+    pub fn normalization_inputs(&self) -> Vec<(&'static str, f64)> {
+        vec![("multiCpiHandlers", self.multi_cpi_handlers_count as f64)]
+    }
+
+    /// Fill the raw score and the normalized factor using `normalization`
+    fn score_with(&mut self, normalization: &Normalization) {
+        let inputs = self.normalization_inputs();
+        self.raw_risk_score = normalization.weighted_sum(&inputs);
+        self.composability_factor = normalization.score(&inputs);
+    }
 }
 
 /// Visitor for detecting handlers with multiple CPIs
-#[derive(Debug, Default)]
-struct ComposabilityVisitor {
+#[derive(Debug)]
+struct ComposabilityVisitor<'a> {
+    handlers: &'a HandlerIndex<'a>,
     metrics: ComposabilityMetrics,
     current_handler_name: Option<String>,
     cpi_calls_in_current_handler: u32,
+    current_handler_file: &'a str,
 }
 
-impl ComposabilityVisitor {
-    /// Checks if an argument is the Anchor Context
-    fn is_context_arg(&self, arg: &syn::FnArg) -> bool {
-        if let syn::FnArg::Typed(pat_type) = arg {
-            if let syn::Type::Path(type_path) = &*pat_type.ty {
-                if let Some(segment) = type_path.path.segments.last() {
-                    return segment.ident == "Context";
-                }
-            }
+impl<'a> ComposabilityVisitor<'a> {
+    fn new(handlers: &'a HandlerIndex<'a>) -> Self {
+        Self {
+            handlers,
+            metrics: ComposabilityMetrics::default(),
+            current_handler_name: None,
+            cpi_calls_in_current_handler: 0,
+            current_handler_file: "",
         }
-        false
     }
 
     /// Get a clean string path from a function call or macro path
@@ -76,6 +102,14 @@ impl ComposabilityVisitor {
         Some(path_str)
     }
 
+    /// Count a CPI in the current handler and record where it is
+    fn record_cpi<T: Spanned>(&mut self, node: &T) {
+        self.cpi_calls_in_current_handler += 1;
+        self.metrics
+            .cpi_locations
+            .push(SourceLocation::of(self.current_handler_file, node));
+    }
+
     /// Analyze a path to determine if it's a known CPI call
     fn is_cpi_path(&self, path_str: &str) -> bool {
         // Native invoke (covers invoke, invoke_signed, etc.)
@@ -104,17 +138,13 @@ impl ComposabilityVisitor {
     }
 }
 
-impl<'ast> Visit<'ast> for ComposabilityVisitor {
+impl<'ast> Visit<'ast> for ComposabilityVisitor<'_> {
     /// --- Find Instruction Handlers ---
     fn visit_item_fn(&mut self, node: &'ast ItemFn) {
-        // Robust heuristic for an Anchor handler
-        let is_anchor_handler = matches!(node.vis, syn::Visibility::Public(_))
-            && node.sig.inputs.len() > 0
-            && self.is_context_arg(&node.sig.inputs[0]);
-
-        if is_anchor_handler {
+        if let Some(handler) = self.handlers.lookup(node) {
             self.metrics.total_handlers_found += 1;
-            let handler_name = node.sig.ident.to_string();
+            let handler_name = handler.name.clone();
+            self.current_handler_file = handler.body().file;
 
             // --- Set Context for this Handler ---
             self.current_handler_name = Some(handler_name.clone());
@@ -153,7 +183,7 @@ impl<'ast> Visit<'ast> for ComposabilityVisitor {
             if let Expr::Path(path_expr) = &*node.func {
                 if let Some(path_str) = self.get_call_path_string(&path_expr.path) {
                     if self.is_cpi_path(&path_str) {
-                        self.cpi_calls_in_current_handler += 1;
+                        self.record_cpi(node);
                     }
                 }
             }
@@ -172,7 +202,7 @@ impl<'ast> Visit<'ast> for ComposabilityVisitor {
                     || path_str == "invoke_signed"
                     || path_str.ends_with("::invoke_signed")
                 {
-                    self.cpi_calls_in_current_handler += 1;
+                    self.record_cpi(node);
                 }
             }
         }
@@ -183,46 +213,29 @@ impl<'ast> Visit<'ast> for ComposabilityVisitor {
 
 /// Calculate composability metrics for workspace
 pub fn calculate_workspace_composability(
-    workspace_path: &std::path::PathBuf,
+    workspace_path: &std::path::Path,
     selected_files: &[String],
+) -> Result<ComposabilityMetrics, Box<dyn std::error::Error>> {
+    let workspace = ParsedWorkspace::load(workspace_path, selected_files);
+    analyze_workspace_composability(&workspace)
+}
+
+/// Measure composability over an already-parsed workspace
+pub fn analyze_workspace_composability(
+    workspace: &ParsedWorkspace,
 ) -> Result<ComposabilityMetrics, Box<dyn std::error::Error>> {
     log::info!(
         "🔍 COMPOSABILITY DEBUG: Starting analysis for workspace: {:?}",
-        workspace_path
+        workspace.root
     );
 
-    let mut visitor = ComposabilityVisitor::default();
-    let mut files_analyzed = 0;
-    let mut files_skipped = 0;
-
-    for file_path in selected_files {
-        let full_path = workspace_path.join(file_path);
-
-        if !full_path.exists() {
-            files_skipped += 1;
-            continue;
-        }
-        if !full_path.extension().map_or(false, |ext| ext == "rs") {
-            files_skipped += 1;
-            continue;
-        }
+    let handlers = HandlerIndex::from_workspace(workspace);
+    let mut visitor = ComposabilityVisitor::new(&handlers);
+    let files_analyzed = workspace.files.len() as u32;
+    let files_skipped = workspace.skipped_count() as u32;
 
-        match std::fs::read_to_string(&full_path) {
-            Ok(content) => match parse_file(&content) {
-                Ok(ast) => {
-                    visitor.visit_file(&ast);
-                    files_analyzed += 1;
-                }
-                Err(e) => {
-                    log::warn!("Failed to parse AST for {:?}: {}", full_path, e);
-                    files_skipped += 1;
-                }
-            },
-            Err(e) => {
-                log::warn!("Failed to read file {:?}: {}", full_path, e);
-                files_skipped += 1;
-            }
-        }
+    for file in &workspace.files {
+        visitor.visit_file(&file.ast);
     }
 
     // --- Final Calculation and Normalization (0-100) ---
@@ -230,15 +243,7 @@ pub fn calculate_workspace_composability(
     metrics.files_analyzed = files_analyzed;
     metrics.files_skipped = files_skipped;
 
-    // 1. Calculate the Raw Score (Based only on handlers with >1 CPI)
-    let raw_risk_score = metrics.multi_cpi_handlers_count as f64;
-    metrics.raw_risk_score = raw_risk_score;
-
-    // 2. Normalize to 0-100
-    // Upper bound: 10 handlers performing multi-CPI actions = 100% risk
-    let upper_bound = 10.0;
-    let factor = (raw_risk_score / upper_bound) * 100.0;
-    metrics.composability_factor = factor.min(100.0); // Cap at 100
+    metrics.score_with(&ComposabilityMetrics::default_normalization());
 
     log::info!(
         "🔍 COMPOSABILITY DEBUG: Analysis complete - {} files analyzed. Found {} handlers with >1 CPI. Factor: {:.2}",
@@ -250,6 +255,40 @@ pub fn calculate_workspace_composability(
     Ok(visitor.metrics)
 }
 
+/// Registry entry for the composability factor
+pub struct ComposabilityFactor;
+
+impl Factor for ComposabilityFactor {
+    fn id(&self) -> &'static str {
+        "composability"
+    }
+
+    fn version(&self) -> &'static str {
+        "1.2.0"
+    }
+
+    fn name(&self) -> &'static str {
+        "Composability"
+    }
+
+    fn normalization(&self) -> Option<Normalization> {
+        Some(ComposabilityMetrics::default_normalization())
+    }
+
+    fn compute(&self, ctx: &FactorContext) -> Result<FactorOutput, Box<dyn std::error::Error>> {
+        let mut metrics = analyze_workspace_composability(ctx.workspace)?;
+        metrics.score_with(&ctx.normalization_or(ComposabilityMetrics::default_normalization));
+        Ok(
+            FactorOutput::keyed(self.id(), metrics.composability_factor, metrics.to_json()).with_note(format!(
+                "Analyzed composability: {} handlers found, {} multi-CPI handlers, composability factor {:.1}",
+                metrics.total_handlers_found,
+                metrics.multi_cpi_handlers_count,
+                metrics.composability_factor,
+            )),
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -266,7 +305,8 @@ mod tests {
         "#;
 
         let ast = syn::parse_file(code).unwrap();
-        let mut visitor = ComposabilityVisitor::default();
+        let handlers = HandlerIndex::from_ast("lib.rs", &ast);
+        let mut visitor = ComposabilityVisitor::new(&handlers);
         visitor.visit_file(&ast);
 
         assert_eq!(visitor.metrics.total_handlers_found, 1);
@@ -290,11 +330,19 @@ mod tests {
         "#;
 
         let ast = syn::parse_file(code).unwrap();
-        let mut visitor = ComposabilityVisitor::default();
+        let handlers = HandlerIndex::from_ast("lib.rs", &ast);
+        let mut visitor = ComposabilityVisitor::new(&handlers);
         visitor.visit_file(&ast);
 
         assert_eq!(visitor.metrics.total_handlers_found, 1);
         assert_eq!(visitor.metrics.multi_cpi_handlers_count, 1); // 2 CPIs > 1
+        let lines: Vec<_> = visitor
+            .metrics
+            .cpi_locations
+            .iter()
+            .map(|l| l.line)
+            .collect();
+        assert_eq!(lines, vec![5, 6]);
         assert_eq!(
             visitor.metrics.handler_cpi_counts.get("multi_cpi_handler"),
             Some(&2)
@@ -311,7 +359,8 @@ mod tests {
         "#;
 
         let ast = syn::parse_file(code).unwrap();
-        let mut visitor = ComposabilityVisitor::default();
+        let handlers = HandlerIndex::from_ast("lib.rs", &ast);
+        let mut visitor = ComposabilityVisitor::new(&handlers);
         visitor.visit_file(&ast);
 
         assert_eq!(visitor.metrics.total_handlers_found, 0);
@@ -320,14 +369,13 @@ mod tests {
 
     #[test]
     fn test_factor_calculation() {
-        let mut metrics = ComposabilityMetrics::default();
-        metrics.multi_cpi_handlers_count = 3;
-
-        let raw_risk_score = metrics.multi_cpi_handlers_count as f64;
-        let upper_bound = 10.0;
-        let factor = (raw_risk_score / upper_bound) * 100.0;
-        let composability_factor = factor.min(100.0);
-
-        assert_eq!(composability_factor, 30.0); // 3/10 * 100 = 30%
+        let mut metrics = ComposabilityMetrics {
+            multi_cpi_handlers_count: 3,
+            ..Default::default()
+        };
+        metrics.score_with(&ComposabilityMetrics::default_normalization());
+
+        assert_eq!(metrics.raw_risk_score, 3.0);
+        assert_eq!(metrics.composability_factor, 30.0); // 3/10 * 100 = 30%
     }
 }
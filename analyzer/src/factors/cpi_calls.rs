@@ -3,9 +3,11 @@
 //! This module analyzes Anchor-specific CPI patterns to count cross-program invocations
 //! and assess the integration surface and complexity of external program dependencies.
 
-use quote::quote;
+use crate::factors::normalization::{Curve, Normalization};
+use crate::factors::registry::{Factor, FactorContext, FactorOutput};
+use crate::workspace::{ParsedWorkspace, SourceLocation};
 use std::collections::HashSet;
-use std::path::PathBuf;
+use std::path::Path;
 use syn::{visit::Visit, Expr};
 
 #[derive(Debug, Clone, Default)]
@@ -37,6 +39,9 @@ pub struct CpiMetrics {
     /// Number of other program CPIs
     pub other_program_cpis: usize,
 
+    /// Where each counted CPI call was found
+    pub cpi_locations: Vec<SourceLocation>,
+
     /// Raw, unbounded complexity score
     pub cpi_complexity_score_raw: f64,
 
@@ -57,106 +62,99 @@ impl CpiMetrics {
             "systemProgramCpis": self.system_program_cpis,
             "associatedTokenProgramCpis": self.associated_token_program_cpis,
             "otherProgramCpis": self.other_program_cpis,
+            "cpiLocations": self.cpi_locations,
             "cpiComplexityScoreRaw": self.cpi_complexity_score_raw,
             "cpiFactor": self.cpi_factor
         })
     }
 
-    /// Calculate CPI complexity score based on program diversity and call types
-    fn calculate_complexity_score(&mut self) {
-        let program_diversity = self.unique_programs as f64;
+    /// Default CPI Factor scoring
+    /// Raw score: program diversity × 2 + signed ratio × 10
+    /// Normalized so that 30 (10 unique programs + 100% signed ratio) is 100
+    pub fn default_normalization() -> Normalization {
+        Normalization::new(Curve::linear(0.0, 30.0))
+            .term("programDiversity", 2.0)
+            .term("signedRatio", 10.0)
+    }
+
+    /// Measurements the CPI Factor is computed from
+    pub fn normalization_inputs(&self) -> Vec<(&'static str, f64)> {
         let signed_ratio = if self.total_cpi_calls > 0 {
             self.signed_cpi_calls as f64 / self.total_cpi_calls as f64
         } else {
             0.0
         };
+        vec![
+            ("programDiversity", self.unique_programs as f64),
+            ("signedRatio", signed_ratio),
+        ]
+    }
 
-        // 1. Calculate the raw, unbounded score
-        self.cpi_complexity_score_raw = program_diversity * 2.0 + signed_ratio * 10.0;
-
-        // 2. Define the upper bound for 100% risk
-        let upper_bound = 30.0; // 10 unique programs + 100% signed ratio
+    /// Calculate CPI complexity score based on program diversity and call types
+    fn calculate_complexity_score(&mut self) {
+        self.score_with(&Self::default_normalization());
+    }
 
-        // 3. Normalize the raw score to the 0-100 factor
-        if self.cpi_complexity_score_raw == 0.0 {
-            self.cpi_factor = 0.0;
-        } else {
-            self.cpi_factor = (self.cpi_complexity_score_raw / upper_bound) * 100.0;
-            self.cpi_factor = self.cpi_factor.min(100.0); // Cap at 100
-        }
+    /// Fill the raw score and the normalized factor using `normalization`
+    fn score_with(&mut self, normalization: &Normalization) {
+        let inputs = self.normalization_inputs();
+        self.cpi_complexity_score_raw = normalization.weighted_sum(&inputs);
+        self.cpi_factor = normalization.score(&inputs);
     }
 }
 
 /// Calculate CPI metrics for workspace files
 pub fn calculate_workspace_cpi_calls(
-    workspace_path: &PathBuf,
+    workspace_path: &Path,
     selected_files: &[String],
+) -> Result<CpiMetrics, Box<dyn std::error::Error>> {
+    let workspace = ParsedWorkspace::load(workspace_path, selected_files);
+    analyze_workspace_cpi_calls(&workspace)
+}
+
+/// Calculate CPI metrics over an already-parsed workspace
+pub fn analyze_workspace_cpi_calls(
+    workspace: &ParsedWorkspace,
 ) -> Result<CpiMetrics, Box<dyn std::error::Error>> {
     let mut metrics = CpiMetrics::default();
-    let mut analyzed_files = 0;
+    let analyzed_files = workspace.files.len();
 
     log::info!(
         "🔍 CPI CALLS DEBUG: Starting CPI analysis for {} files in workspace: {}",
-        selected_files.len(),
-        workspace_path.display()
+        workspace.files.len(),
+        workspace.root.display()
     );
 
-    for file_path in selected_files {
-        let full_path = workspace_path.join(file_path);
-
-        if full_path.exists() && full_path.extension().map_or(false, |ext| ext == "rs") {
-            log::info!(
-                "🔍 CPI CALLS DEBUG: Analyzing file: {}",
-                full_path.display()
-            );
-
-            match std::fs::read_to_string(&full_path) {
-                Ok(content) => {
-                    match analyze_file_cpi_calls(&content) {
-                        Ok(file_metrics) => {
-                            // Merge metrics from this file
-                            metrics.total_cpi_calls += file_metrics.total_cpi_calls;
-                            metrics.signed_cpi_calls += file_metrics.signed_cpi_calls;
-                            metrics.unsigned_cpi_calls += file_metrics.unsigned_cpi_calls;
-                            metrics.token_program_cpis += file_metrics.token_program_cpis;
-                            metrics.system_program_cpis += file_metrics.system_program_cpis;
-                            metrics.associated_token_program_cpis +=
-                                file_metrics.associated_token_program_cpis;
-                            metrics.other_program_cpis += file_metrics.other_program_cpis;
-
-                            // Merge program targets
-                            for target in file_metrics.program_targets {
-                                metrics.program_targets.insert(target);
-                            }
-
-                            analyzed_files += 1;
-
-                            log::info!(
-                                "🔍 CPI CALLS DEBUG: File {} - {} total CPIs, {} signed, {} unsigned",
-                                file_path,
-                                file_metrics.total_cpi_calls,
-                                file_metrics.signed_cpi_calls,
-                                file_metrics.unsigned_cpi_calls
-                            );
-                        }
-                        Err(e) => {
-                            log::warn!(
-                                "🔍 CPI CALLS DEBUG: Failed to analyze file {}: {}",
-                                file_path,
-                                e
-                            );
-                        }
-                    }
-                }
-                Err(e) => {
-                    log::warn!(
-                        "🔍 CPI CALLS DEBUG: Failed to read file {}: {}",
-                        file_path,
-                        e
-                    );
-                }
-            }
+    for file in &workspace.files {
+        log::info!(
+            "🔍 CPI CALLS DEBUG: Analyzing file: {}",
+            file.full_path.display()
+        );
+
+        let file_metrics = analyze_ast_cpi_calls(&file.path, &file.ast);
+
+        // Merge metrics from this file
+        metrics.total_cpi_calls += file_metrics.total_cpi_calls;
+        metrics.signed_cpi_calls += file_metrics.signed_cpi_calls;
+        metrics.unsigned_cpi_calls += file_metrics.unsigned_cpi_calls;
+        metrics.token_program_cpis += file_metrics.token_program_cpis;
+        metrics.system_program_cpis += file_metrics.system_program_cpis;
+        metrics.associated_token_program_cpis += file_metrics.associated_token_program_cpis;
+        metrics.other_program_cpis += file_metrics.other_program_cpis;
+        metrics.cpi_locations.extend(file_metrics.cpi_locations);
+
+        // Merge program targets
+        for target in file_metrics.program_targets {
+            metrics.program_targets.insert(target);
         }
+
+        log::info!(
+            "🔍 CPI CALLS DEBUG: File {} - {} total CPIs, {} signed, {} unsigned",
+            file.path,
+            file_metrics.total_cpi_calls,
+            file_metrics.signed_cpi_calls,
+            file_metrics.unsigned_cpi_calls
+        );
     }
 
     // Calculate final metrics
@@ -179,25 +177,32 @@ pub fn analyze_file_cpi_calls(content: &str) -> Result<CpiMetrics, Box<dyn std::
     let syntax_tree: syn::File =
         syn::parse_file(content).map_err(|e| format!("Failed to parse Rust file: {}", e))?;
 
-    let mut visitor = CpiVisitor::new();
-    visitor.visit_file(&syntax_tree);
+    Ok(analyze_ast_cpi_calls("", &syntax_tree))
+}
+
+/// Analyze CPI calls in a parsed file
+pub fn analyze_ast_cpi_calls(file_path: &str, syntax_tree: &syn::File) -> CpiMetrics {
+    let mut visitor = CpiVisitor::new(file_path);
+    visitor.visit_file(syntax_tree);
 
     // Calculate unique programs for single file analysis
     visitor.metrics.unique_programs = visitor.metrics.program_targets.len();
     visitor.metrics.calculate_complexity_score();
 
-    Ok(visitor.metrics)
+    visitor.metrics
 }
 
 /// Visitor to analyze CPI call patterns
 struct CpiVisitor {
     metrics: CpiMetrics,
+    file_path: String,
 }
 
 impl CpiVisitor {
-    fn new() -> Self {
+    fn new(file_path: &str) -> Self {
         Self {
             metrics: CpiMetrics::default(),
+            file_path: file_path.to_string(),
         }
     }
 
@@ -328,6 +333,9 @@ impl<'ast> Visit<'ast> for CpiVisitor {
             if let Some(path_str) = self.get_call_path_string(&call.func) {
                 // 3. Check if this path is a known CPI
                 if self.analyze_cpi_path(&path_str) {
+                    self.metrics
+                        .cpi_locations
+                        .push(SourceLocation::of(&self.file_path, call));
                     // 4. If it's a CPI, we count it and *stop* descending.
                     // This prevents double-counting if a CPI is
                     // passed as an argument to another CPI.
@@ -341,6 +349,46 @@ impl<'ast> Visit<'ast> for CpiVisitor {
     }
 }
 
+/// Registry entry for the CPI call factor
+pub struct CpiCallsFactor;
+
+impl Factor for CpiCallsFactor {
+    fn id(&self) -> &'static str {
+        "cpiCalls"
+    }
+
+    fn version(&self) -> &'static str {
+        "1.0.0"
+    }
+
+    fn name(&self) -> &'static str {
+        "CPI calls"
+    }
+
+    fn required(&self) -> bool {
+        true
+    }
+
+    fn normalization(&self) -> Option<Normalization> {
+        Some(CpiMetrics::default_normalization())
+    }
+
+    fn compute(&self, ctx: &FactorContext) -> Result<FactorOutput, Box<dyn std::error::Error>> {
+        let mut metrics = analyze_workspace_cpi_calls(ctx.workspace)?;
+        metrics.score_with(&ctx.normalization_or(CpiMetrics::default_normalization));
+        Ok(
+            FactorOutput::keyed(self.id(), metrics.cpi_factor, metrics.to_json()).with_note(format!(
+                "Analyzed CPI calls: {} total ({} signed, {} unsigned), {} unique programs, complexity={:.1}",
+                metrics.total_cpi_calls,
+                metrics.signed_cpi_calls,
+                metrics.unsigned_cpi_calls,
+                metrics.unique_programs,
+                metrics.cpi_factor,
+            )),
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -363,6 +411,9 @@ mod tests {
         assert_eq!(result.signed_cpi_calls, 1);
         assert_eq!(result.other_program_cpis, 2);
         assert!(result.program_targets.contains("native_invoke"));
+
+        let lines: Vec<_> = result.cpi_locations.iter().map(|l| l.line).collect();
+        assert_eq!(lines, vec![6, 7]);
     }
 
     #[test]
@@ -475,11 +526,13 @@ mod tests {
 
     #[test]
     fn test_cpi_complexity_score_calculation() {
-        let mut metrics = CpiMetrics::default();
-        metrics.total_cpi_calls = 5;
-        metrics.signed_cpi_calls = 3;
-        metrics.unsigned_cpi_calls = 2;
-        metrics.unique_programs = 2;
+        let mut metrics = CpiMetrics {
+            total_cpi_calls: 5,
+            signed_cpi_calls: 3,
+            unsigned_cpi_calls: 2,
+            unique_programs: 2,
+            ..Default::default()
+        };
         metrics.program_targets.insert("token_program".to_string());
         metrics.program_targets.insert("system_program".to_string());
 
@@ -3,8 +3,10 @@
 //! This module analyzes Cargo.toml dependencies and classifies them by security tier
 //! to assess the overall dependency risk profile of the codebase.
 
+use crate::factors::normalization::{Curve, Normalization};
+use crate::factors::registry::{Factor, FactorContext, FactorOutput};
 use std::collections::HashSet;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use toml::Value;
 
 #[derive(Debug, Clone)]
@@ -61,6 +63,12 @@ pub struct DependencyClassifier {
     rust_ecosystem: HashSet<String>,
 }
 
+impl Default for DependencyClassifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl DependencyClassifier {
     pub fn new() -> Self {
         let solana_official = [
@@ -296,29 +304,38 @@ impl Default for DependencyMetrics {
 }
 
 impl DependencyMetrics {
+    /// Default Dependency Factor scoring
+    /// The mean risk per dependency by tier: Solana official 0.1, security crates 0.3,
+    /// popular ecosystem 0.5, unknown/custom 1.0, scaled to 0-100 (higher is riskier)
+    pub fn default_normalization() -> Normalization {
+        Normalization::new(Curve::linear(0.0, 1.0))
+            .term("tier1", 0.1) // Solana official (very safe)
+            .term("tier2", 0.3) // Security crates (safe)
+            .term("tier3", 0.5) // Popular ecosystem (moderate)
+            .term("tier4", 1.0) // Unknown/custom (risky)
+    }
+
+    /// Measurements the Dependency Factor is computed from: the dependency
+    /// count of each tier, averaged over `total_dependencies`
+    pub fn normalization_inputs(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            ("tier1", self.tier_1_count as f64),
+            ("tier2", self.tier_2_count as f64),
+            ("tier3", self.tier_3_count as f64),
+            ("tier4", self.tier_4_count as f64),
+        ]
+    }
+
     pub fn calculate_score(&mut self) {
         self.total_dependencies =
             self.tier_1_count + self.tier_2_count + self.tier_3_count + self.tier_4_count;
+        self.score_with(&Self::default_normalization());
+    }
 
-        if self.total_dependencies == 0 {
-            self.dependency_factor = 0.0; // No dependencies = no risk
-            return;
-        }
-
-        // 1. Calculate the Weighted Risk Score (0.0 to 1.0)
-        let total = self.total_dependencies as f64;
-        let weighted_risk_sum = self.tier_1_count as f64 * 0.1 +  // Solana official (very safe)
-            self.tier_2_count as f64 * 0.3 +  // Security crates (safe)
-            self.tier_3_count as f64 * 0.5 +  // Popular ecosystem (moderate)
-            self.tier_4_count as f64 * 1.0; // Unknown/custom (risky)
-
-        let base_risk_score_0_1 = weighted_risk_sum / total;
-
-        // 2. Scale to 0-100 (Higher is riskier)
-        self.dependency_factor = base_risk_score_0_1 * 100.0;
-
-        // 3. Ensure bounds (already handled by the calculation but good practice)
-        self.dependency_factor = self.dependency_factor.max(0.0).min(100.0);
+    /// Fill the factor using `normalization`; no dependencies = no risk
+    fn score_with(&mut self, normalization: &Normalization) {
+        self.dependency_factor =
+            normalization.mean_score(&self.normalization_inputs(), self.total_dependencies);
     }
 
     /// Calculates the "External Integration" factor (0-100)
@@ -504,7 +521,7 @@ fn process_dependency_table(
 
 /// Recursively find Cargo.toml files with depth limit
 fn find_cargo_toml_recursive(
-    dir: &PathBuf,
+    dir: &Path,
     cargo_toml_paths: &mut Vec<PathBuf>,
     current_depth: usize,
     max_depth: usize,
@@ -552,7 +569,7 @@ fn find_cargo_toml_recursive(
 
 /// Calculate external dependencies metrics for workspace
 pub fn calculate_workspace_dependencies(
-    workspace_path: &PathBuf,
+    workspace_path: &Path,
     _selected_files: &[String], // We analyze Cargo.toml instead of individual files
 ) -> Result<DependencyMetrics, Box<dyn std::error::Error>> {
     log::info!(
@@ -882,6 +899,57 @@ pub fn calculate_workspace_dependencies(
     Ok(metrics)
 }
 
+/// Registry entry for the dependency factor
+pub struct DependenciesFactor;
+
+impl Factor for DependenciesFactor {
+    fn id(&self) -> &'static str {
+        "dependencies"
+    }
+
+    fn version(&self) -> &'static str {
+        "1.0.0"
+    }
+
+    fn name(&self) -> &'static str {
+        "Dependencies"
+    }
+
+    fn required(&self) -> bool {
+        true
+    }
+
+    /// Reads every Cargo.toml under the workspace, not just the selection
+    fn cacheable(&self, _ctx: &FactorContext) -> bool {
+        false
+    }
+
+    fn normalization(&self) -> Option<Normalization> {
+        Some(DependencyMetrics::default_normalization())
+    }
+
+    /// Reads manifests only, so unparseable sources do not affect it
+    fn reads_sources(&self) -> bool {
+        false
+    }
+
+    fn compute(&self, ctx: &FactorContext) -> Result<FactorOutput, Box<dyn std::error::Error>> {
+        let mut metrics = calculate_workspace_dependencies(ctx.workspace_path, ctx.selected_files)?;
+        metrics.score_with(&ctx.normalization_or(DependencyMetrics::default_normalization));
+        Ok(
+            FactorOutput::keyed(self.id(), metrics.dependency_factor, metrics.to_json()).with_note(format!(
+                "Analyzed dependencies: {} total (T1:{}, T2:{}, T3:{}, T4:{}), security score={:.1}",
+                metrics.total_dependencies,
+                metrics.tier_1_count,
+                metrics.tier_2_count,
+                metrics.tier_3_count,
+                metrics.tier_4_count,
+                metrics.dependency_factor,
+            )),
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -6,10 +6,15 @@
 //! 2. Handlers containing loops (CU amplification)
 //! 3. Dynamic space calculations in account definitions (memory exhaustion)
 
+use crate::factors::handlers::HandlerIndex;
+use crate::factors::normalization::{Curve, Normalization};
+use crate::factors::registry::{Factor, FactorContext, FactorOutput};
+use crate::workspace::{ParsedWorkspace, SourceLocation};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use syn::{
     parse::{Parse, ParseStream, Result as SynResult},
+    spanned::Spanned,
     visit::{self, Visit},
     Expr, ExprForLoop, ExprLoop, ExprWhile, ItemFn, ItemStruct, Token,
 };
@@ -41,6 +46,8 @@ pub struct DosResourceLimitsMetrics {
     pub handler_vec_param_counts: HashMap<String, u32>,
     /// Maps handler name to loop count
     pub handler_loop_counts: HashMap<String, u32>,
+    /// Where each loop inside a handler was found
+    pub loop_locations: Vec<SourceLocation>,
     /// Maps account name to space calculation type
     pub account_space_types: HashMap<String, String>,
 
@@ -61,11 +68,38 @@ impl DosResourceLimitsMetrics {
             "maxConstantSpace": self.max_constant_space,
             "handlerVecParamCounts": self.handler_vec_param_counts,
             "handlerLoopCounts": self.handler_loop_counts,
+            "loopLocations": self.loop_locations,
             "accountSpaceTypes": self.account_space_types,
             "filesAnalyzed": self.files_analyzed,
             "filesSkipped": self.files_skipped,
         })
     }
+
+    /// Default Resource Factor scoring
+    /// Vec params are highest risk (10x), loops are medium risk (5x), dynamic space is low risk (2x)
+    /// Upper bound: 5 vec handlers + 10 loop handlers + 5 dynamic space = 110 = 100% risk
+    pub fn default_normalization() -> Normalization {
+        Normalization::new(Curve::linear(0.0, 110.0))
+            .term("vecParamHandlers", 10.0)
+            .term("loopHandlers", 5.0)
+            .term("dynamicSpaceAccounts", 2.0)
+    }
+
+    /// Measurements the factor is computed from
+    pub fn normalization_inputs(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            ("vecParamHandlers", self.handlers_with_vec_params as f64),
+            ("loopHandlers", self.handlers_with_loops as f64),
+            ("dynamicSpaceAccounts", self.dynamic_space_accounts as f64),
+        ]
+    }
+
+    /// Fill the raw score and the normalized factor using `normalization`
+    fn score_with(&mut self, normalization: &Normalization) {
+        let inputs = self.normalization_inputs();
+        self.raw_risk_score = normalization.weighted_sum(&inputs);
+        self.resource_factor = normalization.score(&inputs);
+    }
 }
 
 /// Custom parser for space attributes in account definitions
@@ -110,25 +144,34 @@ impl Parse for SpaceParser {
 }
 
 /// Visitor for detecting resource exhaustion patterns
-#[derive(Debug, Default)]
-struct DosResourceLimitsVisitor {
+#[derive(Debug)]
+struct DosResourceLimitsVisitor<'a> {
+    handlers: &'a HandlerIndex<'a>,
     metrics: DosResourceLimitsMetrics,
     current_handler_name: Option<String>,
     vec_params_in_current_handler: u32,
     loops_in_current_handler: u32,
+    current_handler_file: &'a str,
 }
 
-impl DosResourceLimitsVisitor {
-    /// Checks if an argument is the Anchor Context
-    fn is_context_arg(&self, arg: &syn::FnArg) -> bool {
-        if let syn::FnArg::Typed(pat_type) = arg {
-            if let syn::Type::Path(type_path) = &*pat_type.ty {
-                if let Some(segment) = type_path.path.segments.last() {
-                    return segment.ident == "Context";
-                }
-            }
+impl<'a> DosResourceLimitsVisitor<'a> {
+    fn new(handlers: &'a HandlerIndex<'a>) -> Self {
+        Self {
+            handlers,
+            metrics: DosResourceLimitsMetrics::default(),
+            current_handler_name: None,
+            vec_params_in_current_handler: 0,
+            loops_in_current_handler: 0,
+            current_handler_file: "",
         }
-        false
+    }
+
+    /// Count a loop in the current handler and record where it is
+    fn record_loop<T: Spanned>(&mut self, node: &T) {
+        self.loops_in_current_handler += 1;
+        self.metrics
+            .loop_locations
+            .push(SourceLocation::of(self.current_handler_file, node));
     }
 
     /// Checks if a type is Vec<T> or &[T]
@@ -244,17 +287,13 @@ impl DosResourceLimitsVisitor {
     }
 }
 
-impl<'ast> Visit<'ast> for DosResourceLimitsVisitor {
+impl<'ast> Visit<'ast> for DosResourceLimitsVisitor<'_> {
     /// Find instruction handlers and analyze their parameters
     fn visit_item_fn(&mut self, node: &'ast ItemFn) {
-        // Robust heuristic for an Anchor handler
-        let is_anchor_handler = matches!(node.vis, syn::Visibility::Public(_))
-            && node.sig.inputs.len() > 0
-            && self.is_context_arg(&node.sig.inputs[0]);
-
-        if is_anchor_handler {
+        if let Some(handler) = self.handlers.lookup(node) {
             self.metrics.total_handlers_found += 1;
-            let handler_name = node.sig.ident.to_string();
+            let handler_name = handler.name.clone();
+            self.current_handler_file = handler.body().file;
 
             // Set context for this handler
             self.current_handler_name = Some(handler_name.clone());
@@ -303,21 +342,21 @@ impl<'ast> Visit<'ast> for DosResourceLimitsVisitor {
     /// Count loops within handlers
     fn visit_expr_loop(&mut self, node: &'ast ExprLoop) {
         if self.current_handler_name.is_some() {
-            self.loops_in_current_handler += 1;
+            self.record_loop(node);
         }
         visit::visit_expr_loop(self, node);
     }
 
     fn visit_expr_while(&mut self, node: &'ast ExprWhile) {
         if self.current_handler_name.is_some() {
-            self.loops_in_current_handler += 1;
+            self.record_loop(node);
         }
         visit::visit_expr_while(self, node);
     }
 
     fn visit_expr_for_loop(&mut self, node: &'ast ExprForLoop) {
         if self.current_handler_name.is_some() {
-            self.loops_in_current_handler += 1;
+            self.record_loop(node);
         }
         visit::visit_expr_for_loop(self, node);
     }
@@ -355,46 +394,29 @@ impl<'ast> Visit<'ast> for DosResourceLimitsVisitor {
 
 /// Calculate DOS resource limits metrics for workspace
 pub fn calculate_workspace_dos_resource_limits(
-    workspace_path: &std::path::PathBuf,
+    workspace_path: &std::path::Path,
     selected_files: &[String],
+) -> Result<DosResourceLimitsMetrics, Box<dyn std::error::Error>> {
+    let workspace = ParsedWorkspace::load(workspace_path, selected_files);
+    analyze_workspace_dos_resource_limits(&workspace)
+}
+
+/// Measure DOS resource limits over an already-parsed workspace
+pub fn analyze_workspace_dos_resource_limits(
+    workspace: &ParsedWorkspace,
 ) -> Result<DosResourceLimitsMetrics, Box<dyn std::error::Error>> {
     log::info!(
         "🔍 DOS RESOURCE LIMITS DEBUG: Starting analysis for workspace: {:?}",
-        workspace_path
+        workspace.root
     );
 
-    let mut visitor = DosResourceLimitsVisitor::default();
-    let mut files_analyzed = 0;
-    let mut files_skipped = 0;
-
-    for file_path in selected_files {
-        let full_path = workspace_path.join(file_path);
-
-        if !full_path.exists() {
-            files_skipped += 1;
-            continue;
-        }
-        if !full_path.extension().map_or(false, |ext| ext == "rs") {
-            files_skipped += 1;
-            continue;
-        }
+    let handlers = HandlerIndex::from_workspace(workspace);
+    let mut visitor = DosResourceLimitsVisitor::new(&handlers);
+    let files_analyzed = workspace.files.len() as u32;
+    let files_skipped = workspace.skipped_count() as u32;
 
-        match std::fs::read_to_string(&full_path) {
-            Ok(content) => match syn::parse_file(&content) {
-                Ok(ast) => {
-                    visitor.visit_file(&ast);
-                    files_analyzed += 1;
-                }
-                Err(e) => {
-                    log::warn!("Failed to parse AST for {:?}: {}", full_path, e);
-                    files_skipped += 1;
-                }
-            },
-            Err(e) => {
-                log::warn!("Failed to read file {:?}: {}", full_path, e);
-                files_skipped += 1;
-            }
-        }
+    for file in &workspace.files {
+        visitor.visit_file(&file.ast);
     }
 
     // Final calculation and normalization (0-100)
@@ -402,19 +424,7 @@ pub fn calculate_workspace_dos_resource_limits(
     metrics.files_analyzed = files_analyzed;
     metrics.files_skipped = files_skipped;
 
-    // Calculate raw risk score with weighted factors
-    // Vec params are highest risk (10x), loops are medium risk (5x), dynamic space is low risk (2x)
-    let raw_risk_score = (metrics.handlers_with_vec_params as f64 * 10.0)
-        + (metrics.handlers_with_loops as f64 * 5.0)
-        + (metrics.dynamic_space_accounts as f64 * 2.0);
-
-    metrics.raw_risk_score = raw_risk_score;
-
-    // Normalize to 0-100
-    // Upper bound: 5 vec handlers + 10 loop handlers + 5 dynamic space = 100% risk
-    let upper_bound = (5.0 * 10.0) + (10.0 * 5.0) + (5.0 * 2.0); // 110
-    let factor = (raw_risk_score / upper_bound) * 100.0;
-    metrics.resource_factor = factor.min(100.0); // Cap at 100
+    metrics.score_with(&DosResourceLimitsMetrics::default_normalization());
 
     log::info!(
         "🔍 DOS RESOURCE LIMITS DEBUG: Analysis complete - {} files analyzed. Vec handlers: {}, Loop handlers: {}, Dynamic space: {}, Factor: {:.2}",
@@ -428,6 +438,42 @@ pub fn calculate_workspace_dos_resource_limits(
     Ok(visitor.metrics)
 }
 
+/// Registry entry for the DOS resource limits factor
+pub struct DosResourceLimitsFactor;
+
+impl Factor for DosResourceLimitsFactor {
+    fn id(&self) -> &'static str {
+        "dosResourceLimits"
+    }
+
+    fn version(&self) -> &'static str {
+        "1.2.0"
+    }
+
+    fn name(&self) -> &'static str {
+        "DOS resource limits"
+    }
+
+    fn normalization(&self) -> Option<Normalization> {
+        Some(DosResourceLimitsMetrics::default_normalization())
+    }
+
+    fn compute(&self, ctx: &FactorContext) -> Result<FactorOutput, Box<dyn std::error::Error>> {
+        let mut metrics = analyze_workspace_dos_resource_limits(ctx.workspace)?;
+        metrics.score_with(&ctx.normalization_or(DosResourceLimitsMetrics::default_normalization));
+        Ok(
+            FactorOutput::keyed(self.id(), metrics.resource_factor, metrics.to_json()).with_note(format!(
+                "Analyzed DOS resource limits: {} handlers found, {} with vec params, {} with loops, {} dynamic space accounts, resource factor {:.1}",
+                metrics.total_handlers_found,
+                metrics.handlers_with_vec_params,
+                metrics.handlers_with_loops,
+                metrics.dynamic_space_accounts,
+                metrics.resource_factor,
+            )),
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -443,7 +489,8 @@ mod tests {
         "#;
 
         let ast = syn::parse_file(code).unwrap();
-        let mut visitor = DosResourceLimitsVisitor::default();
+        let handlers = HandlerIndex::from_ast("lib.rs", &ast);
+        let mut visitor = DosResourceLimitsVisitor::new(&handlers);
         visitor.visit_file(&ast);
 
         assert_eq!(visitor.metrics.total_handlers_found, 1);
@@ -468,7 +515,8 @@ mod tests {
         "#;
 
         let ast = syn::parse_file(code).unwrap();
-        let mut visitor = DosResourceLimitsVisitor::default();
+        let handlers = HandlerIndex::from_ast("lib.rs", &ast);
+        let mut visitor = DosResourceLimitsVisitor::new(&handlers);
         visitor.visit_file(&ast);
 
         assert_eq!(visitor.metrics.total_handlers_found, 1);
@@ -477,6 +525,9 @@ mod tests {
             visitor.metrics.handler_loop_counts.get("loop_handler"),
             Some(&1)
         );
+        assert_eq!(visitor.metrics.loop_locations.len(), 1);
+        assert_eq!(visitor.metrics.loop_locations[0].file, "lib.rs");
+        assert_eq!(visitor.metrics.loop_locations[0].line, 5);
     }
 
     #[test]
@@ -492,7 +543,8 @@ mod tests {
         "#;
 
         let ast = syn::parse_file(code).unwrap();
-        let mut visitor = DosResourceLimitsVisitor::default();
+        let handlers = HandlerIndex::from_ast("lib.rs", &ast);
+        let mut visitor = DosResourceLimitsVisitor::new(&handlers);
         visitor.visit_file(&ast);
 
         assert_eq!(visitor.metrics.dynamic_space_accounts, 1);
@@ -515,7 +567,8 @@ mod tests {
         "#;
 
         let ast = syn::parse_file(code).unwrap();
-        let mut visitor = DosResourceLimitsVisitor::default();
+        let handlers = HandlerIndex::from_ast("lib.rs", &ast);
+        let mut visitor = DosResourceLimitsVisitor::new(&handlers);
         visitor.visit_file(&ast);
 
         assert_eq!(visitor.metrics.dynamic_space_accounts, 0);
@@ -528,20 +581,16 @@ mod tests {
 
     #[test]
     fn test_factor_calculation() {
-        let mut metrics = DosResourceLimitsMetrics::default();
-        metrics.handlers_with_vec_params = 2;
-        metrics.handlers_with_loops = 3;
-        metrics.dynamic_space_accounts = 1;
-
-        let raw_risk_score = (metrics.handlers_with_vec_params as f64 * 10.0)
-            + (metrics.handlers_with_loops as f64 * 5.0)
-            + (metrics.dynamic_space_accounts as f64 * 2.0);
-
-        let upper_bound = (5.0 * 10.0) + (10.0 * 5.0) + (5.0 * 2.0); // 110
-        let factor = (raw_risk_score / upper_bound) * 100.0;
-        let resource_factor = factor.min(100.0);
+        let mut metrics = DosResourceLimitsMetrics {
+            handlers_with_vec_params: 2,
+            handlers_with_loops: 3,
+            dynamic_space_accounts: 1,
+            ..Default::default()
+        };
+        metrics.score_with(&DosResourceLimitsMetrics::default_normalization());
 
         // (2*10 + 3*5 + 1*2) / 110 * 100 = 37/110 * 100 = 33.6%
-        assert!((resource_factor - 33.6).abs() < 0.1);
+        assert_eq!(metrics.raw_risk_score, 37.0);
+        assert!((metrics.resource_factor - 33.6).abs() < 0.1);
     }
 }
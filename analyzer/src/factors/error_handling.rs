@@ -5,7 +5,16 @@
 //!
 //! This count serves as a direct proxy for the "branch surface" and
 //! the number of "intended invariants" an auditor must review.
+//!
+//! Native programs have no `require!`: their invariants are early
+//! `return Err(...)`s in functions returning `ProgramResult` (or any return
+//! of a `ProgramError`), which are counted alongside.
 
+use crate::factors::native;
+use crate::factors::normalization::{Curve, Normalization};
+use crate::factors::registry::{Factor, FactorContext, FactorOutput};
+use crate::workspace::ParsedWorkspace;
+use quote::ToTokens;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use syn::{visit::Visit, Path};
@@ -15,9 +24,11 @@ pub struct ErrorHandlingMetrics {
     // --- The 2 Sub-Factors (Raw Data) ---
     pub total_require_macros: u32,
     pub total_require_eq_macros: u32,
+    /// Native `return Err(...)` guards
+    pub total_program_error_returns: u32,
 
     // --- Core Metric ---
-    /// The total count of all invariants (require + require_eq)
+    /// The total count of all invariants (require + require_eq + error returns)
     pub total_invariants: u32,
 
     // --- Final Score (0-100) ---
@@ -41,19 +52,43 @@ impl ErrorHandlingMetrics {
             "totalInvariants": self.total_invariants,
             "totalRequireMacros": self.total_require_macros,
             "totalRequireEqMacros": self.total_require_eq_macros,
+            "totalProgramErrorReturns": self.total_program_error_returns,
             "errorPatternBreakdown": self.error_pattern_breakdown,
             "filesAnalyzed": self.files_analyzed,
             "filesSkipped": self.files_skipped
         })
     }
+
+    /// Default Error Handling Factor scoring
+    /// Raw score: total invariants (`require!`, `require_eq!` and native error returns)
+    /// We set a high cap. 100 invariants is a 100% complex contract.
+    pub fn default_normalization() -> Normalization {
+        Normalization::new(Curve::linear(0.0, 100.0)).term("invariants", 1.0)
+    }
+
+    /// Measurements the factor is computed from
+    pub fn normalization_inputs(&self) -> Vec<(&'static str, f64)> {
+        vec![("invariants", self.total_invariants as f64)]
+    }
+
+    /// Fill the raw score and the normalized factor using `normalization`
+    fn score_with(&mut self, normalization: &Normalization) {
+        let inputs = self.normalization_inputs();
+        self.raw_risk_score = normalization.weighted_sum(&inputs);
+        self.error_handling_factor = normalization.score(&inputs);
+    }
 }
 
-/// Visitor for detecting ONLY `require!` and `require_eq!`
+/// Visitor for detecting ONLY `require!`, `require_eq!` and native error returns
 #[derive(Debug, Default)]
 struct ErrorHandlingVisitor {
     require_macros: u32,
     require_eq_macros: u32,
+    program_error_returns: u32,
     error_pattern_counts: HashMap<String, u32>,
+
+    /// Whether the enclosing function returns a `ProgramResult`
+    in_native_fn: bool,
 }
 
 impl ErrorHandlingVisitor {
@@ -84,7 +119,42 @@ impl ErrorHandlingVisitor {
     }
 }
 
+impl ErrorHandlingVisitor {
+    /// Visit a function body, tracking whether it is native program code
+    fn visit_fn_body(&mut self, sig: &syn::Signature, visit: impl FnOnce(&mut Self)) {
+        let outer = self.in_native_fn;
+        self.in_native_fn = native::mentions(sig.output.to_token_stream(), "ProgramResult")
+            || native::mentions(sig.output.to_token_stream(), "ProgramError");
+        visit(self);
+        self.in_native_fn = outer;
+    }
+}
+
 impl<'ast> Visit<'ast> for ErrorHandlingVisitor {
+    fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
+        self.visit_fn_body(&node.sig, |v| syn::visit::visit_item_fn(v, node));
+    }
+
+    fn visit_impl_item_fn(&mut self, node: &'ast syn::ImplItemFn) {
+        self.visit_fn_body(&node.sig, |v| syn::visit::visit_impl_item_fn(v, node));
+    }
+
+    // Native invariants: `return Err(ProgramError::...)` or any error return
+    // from a function returning `ProgramResult`
+    fn visit_expr_return(&mut self, node: &'ast syn::ExprReturn) {
+        if let Some(syn::Expr::Call(call)) = node.expr.as_deref() {
+            let is_err = matches!(&*call.func, syn::Expr::Path(func) if func.path.is_ident("Err"));
+            if is_err
+                && (self.in_native_fn || native::mentions(call.to_token_stream(), "ProgramError"))
+            {
+                self.program_error_returns += 1;
+                self.record_error_pattern("program_error_return");
+            }
+        }
+
+        syn::visit::visit_expr_return(self, node);
+    }
+
     // --- THIS IS THE FIX (Part 1) ---
     // Catches statement-level macros: `require!(...);`
     fn visit_stmt_macro(&mut self, node: &'ast syn::StmtMacro) {
@@ -126,41 +196,39 @@ impl<'ast> Visit<'ast> for ErrorHandlingVisitor {
 
 /// Calculate error handling metrics for workspace
 pub fn calculate_workspace_error_handling(
-    workspace_path: &std::path::PathBuf,
+    workspace_path: &std::path::Path,
     selected_files: &[String],
+) -> Result<ErrorHandlingMetrics, Box<dyn std::error::Error>> {
+    let workspace = ParsedWorkspace::load(workspace_path, selected_files);
+    analyze_workspace_error_handling(&workspace)
+}
+
+/// Count `require!` invariants over an already-parsed workspace
+pub fn analyze_workspace_error_handling(
+    workspace: &ParsedWorkspace,
 ) -> Result<ErrorHandlingMetrics, Box<dyn std::error::Error>> {
     log::info!(
         "🔍 ERROR HANDLING DEBUG: Starting 'require' analysis for workspace: {:?}",
-        workspace_path
+        workspace.root
     );
 
     let mut metrics = ErrorHandlingMetrics::default();
     let mut files_analyzed = 0;
-    let mut files_skipped = 0;
-
-    for file_path in selected_files {
-        let full_path = workspace_path.join(file_path);
-
-        if !full_path.exists() {
-            files_skipped += 1;
-            continue;
-        }
-        if !full_path.extension().map_or(false, |ext| ext == "rs") {
-            files_skipped += 1;
-            continue;
-        }
-
-        log::info!("🔍 ERROR HANDLING DEBUG: Analyzing file: {:?}", full_path);
+    let files_skipped = workspace.skipped_count() as u32;
 
-        let content = std::fs::read_to_string(&full_path)?;
-        let syntax_tree = syn::parse_file(&content)?;
+    for file in &workspace.files {
+        log::info!(
+            "🔍 ERROR HANDLING DEBUG: Analyzing file: {:?}",
+            file.full_path
+        );
 
         let mut visitor = ErrorHandlingVisitor::default();
-        visitor.visit_file(&syntax_tree);
+        visitor.visit_file(&file.ast);
 
         // Accumulate metrics from this visitor
         metrics.total_require_macros += visitor.require_macros;
         metrics.total_require_eq_macros += visitor.require_eq_macros;
+        metrics.total_program_error_returns += visitor.program_error_returns;
 
         // Merge pattern breakdown
         for (pattern, count) in visitor.error_pattern_counts {
@@ -173,17 +241,11 @@ pub fn calculate_workspace_error_handling(
     // --- Final Calculation and Normalization (0-100) ---
     metrics.files_analyzed = files_analyzed;
     metrics.files_skipped = files_skipped;
-    metrics.total_invariants = metrics.total_require_macros + metrics.total_require_eq_macros;
-
-    // 1. Calculate the Raw Score (simple count)
-    let raw_risk_score = metrics.total_invariants as f64;
-    metrics.raw_risk_score = raw_risk_score;
+    metrics.total_invariants = metrics.total_require_macros
+        + metrics.total_require_eq_macros
+        + metrics.total_program_error_returns;
 
-    // 2. Normalize to 0-100
-    // We set a high cap. 100 invariants is a 100% complex contract.
-    let upper_bound = 100.0;
-    let factor = (raw_risk_score / upper_bound) * 100.0;
-    metrics.error_handling_factor = factor.min(100.0); // Cap at 100
+    metrics.score_with(&ErrorHandlingMetrics::default_normalization());
 
     log::info!(
         "🔍 ERROR HANDLING DEBUG: Analysis complete - {} files analyzed, {} total invariants. Factor: {:.2}",
@@ -194,3 +256,39 @@ pub fn calculate_workspace_error_handling(
 
     Ok(metrics)
 }
+
+/// Registry entry for the error handling factor
+pub struct ErrorHandlingFactor;
+
+impl Factor for ErrorHandlingFactor {
+    fn id(&self) -> &'static str {
+        "errorHandling"
+    }
+
+    fn version(&self) -> &'static str {
+        "1.1.0"
+    }
+
+    fn name(&self) -> &'static str {
+        "Error handling"
+    }
+
+    fn normalization(&self) -> Option<Normalization> {
+        Some(ErrorHandlingMetrics::default_normalization())
+    }
+
+    fn compute(&self, ctx: &FactorContext) -> Result<FactorOutput, Box<dyn std::error::Error>> {
+        let mut metrics = analyze_workspace_error_handling(ctx.workspace)?;
+        metrics.score_with(&ctx.normalization_or(ErrorHandlingMetrics::default_normalization));
+        Ok(
+            FactorOutput::keyed(self.id(), metrics.error_handling_factor, metrics.to_json()).with_note(format!(
+                "Analyzed error handling: {} require macros, {} require_eq macros, {} error returns, {} total invariants, factor {:.1}",
+                metrics.total_require_macros,
+                metrics.total_require_eq_macros,
+                metrics.total_program_error_returns,
+                metrics.total_invariants,
+                metrics.error_handling_factor,
+            )),
+        )
+    }
+}
@@ -1,3 +1,6 @@
+use crate::factors::normalization::{Curve, Normalization};
+use crate::factors::registry::{Factor, FactorContext, FactorOutput};
+use crate::workspace::ParsedWorkspace;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use syn::{visit::Visit, Expr, ItemFn, Path};
@@ -86,6 +89,110 @@ impl ExternalIntegrationMetrics {
             "filesSkipped": self.files_skipped
         })
     }
+
+    /// Default integration risk scoring (unbounded, higher is more risky)
+    /// Oracles × 3, bridges × 5, DeFi protocols × 2, external CPI calls × 1.5
+    pub fn default_normalization() -> Normalization {
+        Normalization::new(Curve::Raw)
+            .term("oracleIntegrations", 3.0)
+            .term("bridgeIntegrations", 5.0)
+            .term("defiIntegrations", 2.0)
+            .term("externalCpiCalls", 1.5)
+    }
+
+    /// Measurements the integration risk score is computed from
+    pub fn normalization_inputs(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            ("oracleIntegrations", self.total_oracle_integrations as f64),
+            ("bridgeIntegrations", self.total_bridge_integrations as f64),
+            ("defiIntegrations", self.total_defi_integrations as f64),
+            ("externalCpiCalls", self.external_cpi_calls as f64),
+        ]
+    }
+
+    /// Fill the risk scores using `normalization`
+    fn score_with(&mut self, normalization: &Normalization) {
+        let inputs = self.normalization_inputs();
+        let (oracle, external): (Vec<_>, Vec<_>) = inputs
+            .iter()
+            .partition(|(name, _)| *name == "oracleIntegrations");
+        self.oracle_dependency_score = normalization.weighted_sum(&oracle);
+        self.external_dependency_score = normalization.weighted_sum(&external);
+        self.integration_risk_score = normalization.score(&inputs);
+    }
+}
+
+/// Pyth price accessor methods (`price_feed.get_price_no_older_than(..)`)
+pub(crate) const PYTH_PRICE_METHODS: &[&str] = &[
+    "get_price",
+    "get_latest_price",
+    "get_price_unchecked",
+    "get_price_no_older_than",
+];
+
+/// Switchboard aggregator methods (`aggregator.get_result()`)
+pub(crate) const SWITCHBOARD_METHODS: &[&str] =
+    &["get_result", "check_confidence_interval", "check_staleness"];
+
+/// Check if a path represents Pyth oracle
+pub(crate) fn is_pyth_oracle(path: &Path) -> bool {
+    if let Some(segment) = path.segments.last() {
+        let name = segment.ident.to_string();
+        matches!(
+            name.as_str(),
+            "pyth"
+                | "pyth_solana"
+                | "pyth_price_feed"
+                | "PriceFeed"
+                | "get_price"
+                | "get_latest_price"
+                | "get_price_unchecked"
+                | "get_price_no_older_than"
+        )
+    } else {
+        false
+    }
+}
+
+/// Check if a path represents Switchboard oracle
+pub(crate) fn is_switchboard_oracle(path: &Path) -> bool {
+    if let Some(segment) = path.segments.last() {
+        let name = segment.ident.to_string();
+        matches!(
+            name.as_str(),
+            "switchboard"
+                | "switchboard_v2"
+                | "switchboard_solana"
+                | "switchboard_oracle"
+                | "AggregatorAccountData"
+                | "SwitchboardDecimal"
+                | "get_aggregator"
+                | "get_latest_result"
+                | "get_result"
+                | "check_confidence_interval"
+                | "check_staleness"
+        )
+    } else {
+        false
+    }
+}
+
+/// Check if a path represents Chainlink oracle
+pub(crate) fn is_chainlink_oracle(path: &Path) -> bool {
+    if let Some(segment) = path.segments.last() {
+        let name = segment.ident.to_string();
+        matches!(
+            name.as_str(),
+            "chainlink"
+                | "chainlink_solana"
+                | "chainlink_oracle"
+                | "get_latest_price"
+                | "get_latest_round"
+                | "latest_round_data"
+        )
+    } else {
+        false
+    }
 }
 
 /// Visitor for detecting external integration patterns
@@ -163,66 +270,6 @@ impl ExternalIntegrationVisitor {
             .or_insert(0) += 1;
     }
 
-    /// Check if a path represents Pyth oracle
-    fn is_pyth_oracle(&self, path: &Path) -> bool {
-        if let Some(segment) = path.segments.last() {
-            let name = segment.ident.to_string();
-            matches!(
-                name.as_str(),
-                "pyth"
-                    | "pyth_solana"
-                    | "pyth_price_feed"
-                    | "PriceFeed"
-                    | "get_price"
-                    | "get_latest_price"
-                    | "get_price_unchecked"
-                    | "get_price_no_older_than"
-            )
-        } else {
-            false
-        }
-    }
-
-    /// Check if a path represents Switchboard oracle
-    fn is_switchboard_oracle(&self, path: &Path) -> bool {
-        if let Some(segment) = path.segments.last() {
-            let name = segment.ident.to_string();
-            matches!(
-                name.as_str(),
-                "switchboard"
-                    | "switchboard_v2"
-                    | "switchboard_solana"
-                    | "switchboard_oracle"
-                    | "AggregatorAccountData"
-                    | "SwitchboardDecimal"
-                    | "get_aggregator"
-                    | "get_latest_result"
-                    | "get_result"
-                    | "check_confidence_interval"
-                    | "check_staleness"
-            )
-        } else {
-            false
-        }
-    }
-
-    /// Check if a path represents Chainlink oracle
-    fn is_chainlink_oracle(&self, path: &Path) -> bool {
-        if let Some(segment) = path.segments.last() {
-            let name = segment.ident.to_string();
-            matches!(
-                name.as_str(),
-                "chainlink"
-                    | "chainlink_solana"
-                    | "chainlink_oracle"
-                    | "get_latest_price"
-                    | "get_latest_round"
-            )
-        } else {
-            false
-        }
-    }
-
     /// Check if a path represents Wormhole bridge
     fn is_wormhole_bridge(&self, path: &Path) -> bool {
         if let Some(segment) = path.segments.last() {
@@ -338,77 +385,74 @@ impl ExternalIntegrationVisitor {
 
 impl<'ast> Visit<'ast> for ExternalIntegrationVisitor {
     fn visit_expr(&mut self, node: &'ast Expr) {
-        match node {
-            Expr::Call(call_expr) => {
-                if let Expr::Path(path_expr) = &*call_expr.func {
-                    // Check for oracle integrations
-                    if self.is_pyth_oracle(&path_expr.path) {
-                        self.pyth_oracle_integrations += 1;
-                        self.price_feed_integrations += 1;
-                        self.record_pattern("pyth_oracle_call");
-                    }
+        if let Expr::Call(call_expr) = node {
+            if let Expr::Path(path_expr) = &*call_expr.func {
+                // Check for oracle integrations
+                if is_pyth_oracle(&path_expr.path) {
+                    self.pyth_oracle_integrations += 1;
+                    self.price_feed_integrations += 1;
+                    self.record_pattern("pyth_oracle_call");
+                }
 
-                    if self.is_switchboard_oracle(&path_expr.path) {
-                        self.switchboard_oracle_integrations += 1;
-                        self.price_feed_integrations += 1;
-                        self.record_pattern("switchboard_oracle_call");
-                    }
+                if is_switchboard_oracle(&path_expr.path) {
+                    self.switchboard_oracle_integrations += 1;
+                    self.price_feed_integrations += 1;
+                    self.record_pattern("switchboard_oracle_call");
+                }
 
-                    if self.is_chainlink_oracle(&path_expr.path) {
-                        self.chainlink_oracle_integrations += 1;
-                        self.price_feed_integrations += 1;
-                        self.record_pattern("chainlink_oracle_call");
-                    }
+                if is_chainlink_oracle(&path_expr.path) {
+                    self.chainlink_oracle_integrations += 1;
+                    self.price_feed_integrations += 1;
+                    self.record_pattern("chainlink_oracle_call");
+                }
 
-                    // Check for bridge integrations
-                    if self.is_wormhole_bridge(&path_expr.path) {
-                        self.wormhole_bridge_integrations += 1;
-                        self.record_pattern("wormhole_bridge_call");
-                    }
+                // Check for bridge integrations
+                if self.is_wormhole_bridge(&path_expr.path) {
+                    self.wormhole_bridge_integrations += 1;
+                    self.record_pattern("wormhole_bridge_call");
+                }
 
-                    if self.is_allbridge(&path_expr.path) {
-                        self.allbridge_integrations += 1;
-                        self.record_pattern("allbridge_call");
-                    }
+                if self.is_allbridge(&path_expr.path) {
+                    self.allbridge_integrations += 1;
+                    self.record_pattern("allbridge_call");
+                }
 
-                    // Check for DeFi protocol integrations using full path analysis
-                    if self.is_defi_protocol(&path_expr.path) {
-                        let path_str = path_expr
-                            .path
-                            .segments
-                            .iter()
-                            .map(|seg| seg.ident.to_string())
-                            .collect::<Vec<_>>()
-                            .join("::");
-
-                        if path_str.starts_with("jupiter::") {
-                            self.jupiter_integrations += 1;
-                            self.token_swap_integrations += 1;
-                            self.record_pattern("jupiter_call");
-                        } else if path_str.starts_with("raydium::") {
-                            self.raydium_integrations += 1;
-                            self.token_swap_integrations += 1;
-                            self.record_pattern("raydium_call");
-                        } else if path_str.starts_with("orca::") {
-                            self.orca_integrations += 1;
-                            self.token_swap_integrations += 1;
-                            self.record_pattern("orca_call");
-                        } else if path_str.starts_with("serum::") {
-                            self.serum_integrations += 1;
-                            self.token_swap_integrations += 1;
-                            self.record_pattern("serum_call");
-                        }
+                // Check for DeFi protocol integrations using full path analysis
+                if self.is_defi_protocol(&path_expr.path) {
+                    let path_str = path_expr
+                        .path
+                        .segments
+                        .iter()
+                        .map(|seg| seg.ident.to_string())
+                        .collect::<Vec<_>>()
+                        .join("::");
+
+                    if path_str.starts_with("jupiter::") {
+                        self.jupiter_integrations += 1;
+                        self.token_swap_integrations += 1;
+                        self.record_pattern("jupiter_call");
+                    } else if path_str.starts_with("raydium::") {
+                        self.raydium_integrations += 1;
+                        self.token_swap_integrations += 1;
+                        self.record_pattern("raydium_call");
+                    } else if path_str.starts_with("orca::") {
+                        self.orca_integrations += 1;
+                        self.token_swap_integrations += 1;
+                        self.record_pattern("orca_call");
+                    } else if path_str.starts_with("serum::") {
+                        self.serum_integrations += 1;
+                        self.token_swap_integrations += 1;
+                        self.record_pattern("serum_call");
                     }
+                }
 
-                    // Check for external program calls
-                    if self.is_external_program(&path_expr.path) {
-                        self.external_cpi_calls += 1;
-                        self.cross_program_invocations += 1;
-                        self.record_pattern("external_program_call");
-                    }
+                // Check for external program calls
+                if self.is_external_program(&path_expr.path) {
+                    self.external_cpi_calls += 1;
+                    self.cross_program_invocations += 1;
+                    self.record_pattern("external_program_call");
                 }
             }
-            _ => {}
         }
 
         // Continue visiting expression
@@ -419,19 +463,13 @@ impl<'ast> Visit<'ast> for ExternalIntegrationVisitor {
         let method_name = node.method.to_string();
 
         // Check for oracle method calls
-        if matches!(
-            method_name.as_str(),
-            "get_result" | "check_confidence_interval" | "check_staleness"
-        ) {
+        if SWITCHBOARD_METHODS.contains(&method_name.as_str()) {
             self.switchboard_oracle_integrations += 1;
             self.price_feed_integrations += 1;
             self.record_pattern(&format!("switchboard_method_{}", method_name));
         }
 
-        if matches!(
-            method_name.as_str(),
-            "get_price" | "get_latest_price" | "get_price_unchecked" | "get_price_no_older_than"
-        ) {
+        if PYTH_PRICE_METHODS.contains(&method_name.as_str()) {
             self.pyth_oracle_integrations += 1;
             self.price_feed_integrations += 1;
             self.record_pattern(&format!("pyth_method_{}", method_name));
@@ -526,50 +564,35 @@ impl<'ast> Visit<'ast> for ExternalIntegrationVisitor {
 
 /// Calculate external integration metrics for workspace
 pub fn calculate_workspace_external_integration(
-    workspace_path: &std::path::PathBuf,
+    workspace_path: &std::path::Path,
     selected_files: &[String],
+) -> Result<ExternalIntegrationMetrics, Box<dyn std::error::Error>> {
+    let workspace = ParsedWorkspace::load(workspace_path, selected_files);
+    analyze_workspace_external_integration(&workspace)
+}
+
+/// Detect external integrations over an already-parsed workspace
+pub fn analyze_workspace_external_integration(
+    workspace: &ParsedWorkspace,
 ) -> Result<ExternalIntegrationMetrics, Box<dyn std::error::Error>> {
     log::info!(
         "🔍 EXTERNAL INTEGRATION DEBUG: Starting analysis for workspace: {:?}",
-        workspace_path
+        workspace.root
     );
 
     let mut metrics = ExternalIntegrationMetrics::default();
     let mut files_analyzed = 0;
-    let mut files_skipped = 0;
-
-    for file_path in selected_files {
-        let full_path = workspace_path.join(file_path);
-
-        if !full_path.exists() {
-            log::warn!(
-                "🔍 EXTERNAL INTEGRATION DEBUG: File does not exist: {:?}",
-                full_path
-            );
-            files_skipped += 1;
-            continue;
-        }
-
-        if !full_path.extension().map_or(false, |ext| ext == "rs") {
-            log::info!(
-                "🔍 EXTERNAL INTEGRATION DEBUG: Skipping non-Rust file: {:?}",
-                full_path
-            );
-            files_skipped += 1;
-            continue;
-        }
+    let files_skipped = workspace.skipped_count() as u32;
 
+    for file in &workspace.files {
         log::info!(
             "🔍 EXTERNAL INTEGRATION DEBUG: Analyzing file: {:?}",
-            full_path
+            file.full_path
         );
 
-        let content = std::fs::read_to_string(&full_path)?;
-        let syntax_tree = syn::parse_file(&content)?;
-
         let mut visitor = ExternalIntegrationVisitor::new();
-        visitor.current_file_path = file_path.to_string();
-        visitor.visit_file(&syntax_tree);
+        visitor.current_file_path = file.path.clone();
+        visitor.visit_file(&file.ast);
 
         // Accumulate metrics from this visitor
         metrics.pyth_oracle_integrations += visitor.pyth_oracle_integrations;
@@ -605,7 +628,7 @@ pub fn calculate_workspace_external_integration(
 
         log::info!(
             "🔍 EXTERNAL INTEGRATION DEBUG: File {} analysis complete - pyth: {}, switchboard: {}, jupiter: {}, raydium: {}, external cpi: {}",
-            file_path,
+            file.path,
             visitor.pyth_oracle_integrations,
             visitor.switchboard_oracle_integrations,
             visitor.jupiter_integrations,
@@ -634,16 +657,8 @@ pub fn calculate_workspace_external_integration(
         + metrics.serum_integrations
         + metrics.other_defi_integrations;
 
-    // Calculate risk scores
-    // Oracle Dependency Score (higher is more risky)
-    let oracle_risk = metrics.total_oracle_integrations as f64 * 3.0;
-    let bridge_risk = metrics.total_bridge_integrations as f64 * 5.0;
-    let defi_risk = metrics.total_defi_integrations as f64 * 2.0;
-    let cpi_risk = metrics.external_cpi_calls as f64 * 1.5;
-
-    metrics.oracle_dependency_score = oracle_risk;
-    metrics.external_dependency_score = bridge_risk + defi_risk + cpi_risk;
-    metrics.integration_risk_score = oracle_risk + bridge_risk + defi_risk + cpi_risk;
+    // Calculate risk scores (higher is more risky)
+    metrics.score_with(&ExternalIntegrationMetrics::default_normalization());
 
     log::info!(
         "🔍 EXTERNAL INTEGRATION DEBUG: Analysis complete - {} files analyzed, {} files skipped, oracle integrations: {}, bridge integrations: {}, defi integrations: {}, risk score: {:.1}",
@@ -657,3 +672,40 @@ pub fn calculate_workspace_external_integration(
 
     Ok(metrics)
 }
+
+/// Registry entry for the external integration factor
+pub struct ExternalIntegrationFactor;
+
+impl Factor for ExternalIntegrationFactor {
+    fn id(&self) -> &'static str {
+        "externalIntegration"
+    }
+
+    fn version(&self) -> &'static str {
+        "1.0.0"
+    }
+
+    fn name(&self) -> &'static str {
+        "External integration"
+    }
+
+    fn normalization(&self) -> Option<Normalization> {
+        Some(ExternalIntegrationMetrics::default_normalization())
+    }
+
+    fn compute(&self, ctx: &FactorContext) -> Result<FactorOutput, Box<dyn std::error::Error>> {
+        let mut metrics = analyze_workspace_external_integration(ctx.workspace)?;
+        metrics
+            .score_with(&ctx.normalization_or(ExternalIntegrationMetrics::default_normalization));
+        Ok(
+            FactorOutput::keyed(self.id(), metrics.integration_risk_score, metrics.to_json()).with_note(format!(
+                "Analyzed external integration: {} oracle integrations, {} bridge integrations, {} defi integrations, {} external cpi calls, risk score {:.1}",
+                metrics.total_oracle_integrations,
+                metrics.total_bridge_integrations,
+                metrics.total_defi_integrations,
+                metrics.external_cpi_calls,
+                metrics.integration_risk_score,
+            )),
+        )
+    }
+}
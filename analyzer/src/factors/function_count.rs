@@ -3,6 +3,10 @@
 //! This module provides functionality to count functions in Rust files
 //! using AST parsing for accurate detection of different function types.
 
+use crate::factors::normalization::{Curve, Normalization};
+use crate::factors::registry::{Factor, FactorContext, FactorOutput};
+use crate::workspace::ParsedWorkspace;
+use std::path::Path;
 use syn::{File as SynFile, ImplItem, Item, ItemFn, Visibility};
 
 /// Comprehensive function count metrics
@@ -41,19 +45,23 @@ impl FunctionCountMetrics {
         })
     }
 
-    /// Calculate Function Factor based on total functions
+    /// Default Function Factor scoring
     /// 0 = 5 functions or less, 100 = 300 functions or more
-    /// Linear mapping between 5 and 300
+    /// Linear mapping between 5 and 300, rounded to 2 decimal places
+    pub fn default_normalization() -> Normalization {
+        Normalization::new(Curve::linear(5.0, 300.0))
+            .term("totalFunctions", 1.0)
+            .rounded(2)
+    }
+
+    /// Measurements the Function Factor is computed from
+    pub fn normalization_inputs(&self) -> Vec<(&'static str, f64)> {
+        vec![("totalFunctions", self.total_functions as f64)]
+    }
+
+    /// Calculate Function Factor based on total functions, with the default scoring
     pub fn calculate_function_factor(total_functions: usize) -> f64 {
-        if total_functions <= 5 {
-            return 0.0;
-        }
-        if total_functions >= 300 {
-            return 100.0;
-        }
-        // Linear mapping between 5 and 300
-        let factor = ((total_functions - 5) as f64 / (300 - 5) as f64) * 100.0;
-        (factor * 100.0).round() / 100.0 // Round to 2 decimal places
+        Self::default_normalization().score(&[("totalFunctions", total_functions as f64)])
     }
 }
 
@@ -86,6 +94,33 @@ pub fn count_functions(content: &str) -> Result<FunctionCountMetrics, String> {
     Ok(metrics)
 }
 
+/// Count functions across the selected workspace files
+pub fn calculate_workspace_functions(
+    workspace_path: &Path,
+    selected_files: &[String],
+) -> Result<FunctionCountMetrics, Box<dyn std::error::Error>> {
+    let workspace = ParsedWorkspace::load(workspace_path, selected_files);
+    analyze_workspace_functions(&workspace)
+}
+
+/// Count functions over an already-parsed workspace
+///
+/// The function factor is computed from the aggregated total.
+pub fn analyze_workspace_functions(
+    workspace: &ParsedWorkspace,
+) -> Result<FunctionCountMetrics, Box<dyn std::error::Error>> {
+    let mut aggregated_metrics = FunctionCountMetrics::default();
+
+    for file in &workspace.files {
+        count_functions_in_items(&file.ast.items, &mut aggregated_metrics, false);
+    }
+
+    aggregated_metrics.function_factor =
+        FunctionCountMetrics::calculate_function_factor(aggregated_metrics.total_functions);
+
+    Ok(aggregated_metrics)
+}
+
 /// Recursively count functions in AST items
 fn count_functions_in_items(
     items: &[Item],
@@ -169,7 +204,7 @@ fn count_method_function(
 
 /// Analyze function signature for various attributes
 fn analyze_function_signature(
-    sig: &syn::Signature,
+    _sig: &syn::Signature,
     vis: &Visibility,
     metrics: &mut FunctionCountMetrics,
 ) {
@@ -205,6 +240,56 @@ pub fn count_total_functions(content: &str) -> usize {
     }
 }
 
+/// Registry entry for the function count factor
+pub struct FunctionCountFactor;
+
+impl Factor for FunctionCountFactor {
+    fn id(&self) -> &'static str {
+        "functionCount"
+    }
+
+    fn version(&self) -> &'static str {
+        "1.0.0"
+    }
+
+    fn name(&self) -> &'static str {
+        "Function count"
+    }
+
+    fn normalization(&self) -> Option<Normalization> {
+        Some(FunctionCountMetrics::default_normalization())
+    }
+
+    fn compute(&self, ctx: &FactorContext) -> Result<FactorOutput, Box<dyn std::error::Error>> {
+        let mut metrics = analyze_workspace_functions(ctx.workspace)?;
+        metrics.function_factor = ctx
+            .normalization_or(FunctionCountMetrics::default_normalization)
+            .score(&metrics.normalization_inputs());
+        Ok(
+            FactorOutput::new(metrics.function_factor, metrics.to_json())
+                .with_field("numFunctions", serde_json::json!(metrics.total_functions))
+                .with_field("functionFactor", serde_json::json!(metrics.function_factor))
+                .with_reference("functionCountMetrics", metrics.to_json())
+                .with_note(format!(
+                    "Calculated {} functions from workspace files, function factor: {:.2}",
+                    metrics.total_functions, metrics.function_factor
+                )),
+        )
+    }
+
+    fn fallback(&self, error: &dyn std::error::Error) -> Option<FactorOutput> {
+        Some(
+            FactorOutput::new(0.0, serde_json::Value::Null)
+                .with_field("numFunctions", serde_json::json!(10))
+                .with_field("functionFactor", serde_json::json!(0.0))
+                .with_note(format!(
+                    "Function count fallback used due to error: {}",
+                    error
+                )),
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
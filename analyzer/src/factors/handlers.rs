@@ -0,0 +1,760 @@
+//! Instruction handler discovery
+//!
+//! Every factor that reasons about instruction handlers uses the
+//! [`HandlerIndex`] built here, so handler counts agree across the report.
+//!
+//! A handler is a public function whose first argument is an Anchor
+//! `Context<...>` (including `Context<'_, '_, '_, 'info, T>` and fully
+//! qualified `anchor_lang::prelude::Context<T>`). When the workspace contains
+//! a `#[program]` module, only the functions declared inside it are entry
+//! points; an entry point that forwards its context to another handler (e.g.
+//! `instructions::swap::handler(ctx, amount)`) is analyzed through that
+//! implementation. Without a `#[program]` module every handler-shaped
+//! function is treated as an entry point.
+//!
+//! Native programs have no `Context`: the function registered with
+//! `entrypoint!` (or a `process_instruction` taking `&[AccountInfo]`) decodes
+//! an instruction enum and dispatches it with a `match`, possibly through a
+//! `Processor::process`-style function. Each arm is a handler named after its
+//! variant (`Deposit` -> `deposit`), implemented by the function or method
+//! taking `&[AccountInfo]` that the arm calls (`Self::process_deposit` in an
+//! `impl Processor` resolves to `Processor::process_deposit`); when the arm
+//! handles the instruction inline, the entry point carries its logic.
+
+use crate::factors::native;
+use crate::workspace::ParsedWorkspace;
+use syn::visit::{self, Visit};
+use syn::{
+    Block, Expr, ExprCall, FnArg, GenericArgument, ImplItemFn, Item, ItemFn, ItemMod, Pat,
+    PathArguments, Signature, Type,
+};
+
+/// A function that implements (part of) an instruction handler
+#[derive(Debug, Clone, Copy)]
+pub struct HandlerFn<'a> {
+    /// File declaring the function, relative to the workspace root
+    pub file: &'a str,
+
+    /// The function's signature
+    pub sig: &'a Signature,
+
+    /// The function's body, which identifies it
+    pub block: &'a Block,
+}
+
+/// A single instruction handler
+#[derive(Debug, Clone)]
+pub struct InstructionHandler<'a> {
+    /// Instruction name (the entry point's identifier)
+    pub name: String,
+
+    /// Entry point, as declared in the `#[program]` module when there is one
+    pub entry: HandlerFn<'a>,
+
+    /// Handler the entry point delegates to, when it could be resolved
+    pub implementation: Option<HandlerFn<'a>>,
+
+    /// Accounts struct named by the handler's `Context<...>`
+    pub accounts: Option<String>,
+
+    /// Whether the entry point is declared inside a `#[program]` module
+    pub in_program_module: bool,
+
+    /// Whether the handler is an instruction of a native `entrypoint!` program
+    pub native: bool,
+}
+
+impl<'a> InstructionHandler<'a> {
+    /// Function whose body carries the handler logic
+    pub fn body(&self) -> HandlerFn<'a> {
+        self.implementation.unwrap_or(self.entry)
+    }
+}
+
+/// Instruction handlers discovered across a set of parsed files
+#[derive(Debug, Clone, Default)]
+pub struct HandlerIndex<'a> {
+    handlers: Vec<InstructionHandler<'a>>,
+}
+
+impl<'a> HandlerIndex<'a> {
+    /// Discover handlers across every parsed file of the workspace
+    pub fn from_workspace(workspace: &'a ParsedWorkspace) -> Self {
+        Self::from_files(
+            workspace
+                .files
+                .iter()
+                .map(|file| (file.path.as_str(), &file.ast)),
+        )
+    }
+
+    /// Discover handlers in a single syntax tree
+    pub fn from_ast(path: &'a str, ast: &'a syn::File) -> Self {
+        Self::from_files([(path, ast)])
+    }
+
+    /// Discover handlers across `(path, ast)` pairs
+    pub fn from_files(files: impl IntoIterator<Item = (&'a str, &'a syn::File)>) -> Self {
+        let mut candidates = Vec::new();
+        let mut native = NativeProgram::default();
+        for (path, ast) in files {
+            let mut module_path = file_module_path(path);
+            collect_candidates(&ast.items, path, &mut module_path, false, &mut candidates);
+            native.entrypoints.extend(native::entrypoints(&ast.items));
+            native.collect(&ast.items, path, &mut file_module_path(path));
+        }
+
+        let has_program_module = candidates.iter().any(|c| c.in_program_module);
+        let (entries, implementations): (Vec<_>, Vec<_>) = candidates
+            .into_iter()
+            .partition(|c| c.in_program_module || !has_program_module);
+
+        let handlers = entries
+            .into_iter()
+            .map(|entry| {
+                let accounts = context_accounts(entry.sig);
+                let implementation = delegated_call_path(entry.sig, entry.block)
+                    .and_then(|call| resolve_delegate(&call, accounts.as_deref(), &implementations))
+                    .map(Candidate::handler_fn);
+
+                InstructionHandler {
+                    name: entry.sig.ident.to_string(),
+                    entry: entry.handler_fn(),
+                    implementation,
+                    accounts,
+                    in_program_module: entry.in_program_module,
+                    native: false,
+                }
+            })
+            .chain(native.handlers())
+            .collect();
+
+        Self { handlers }
+    }
+
+    pub fn handlers(&self) -> &[InstructionHandler<'a>] {
+        &self.handlers
+    }
+
+    pub fn len(&self) -> usize {
+        self.handlers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.handlers.is_empty()
+    }
+
+    /// Handler whose logic lives in `item_fn`
+    ///
+    /// Matches by identity, so `item_fn` must come from the same syntax trees
+    /// the index was built from. Entry points that only delegate return `None`;
+    /// their handler is reported once, at the implementation.
+    pub fn lookup(&self, item_fn: &ItemFn) -> Option<&InstructionHandler<'a>> {
+        self.lookup_body(&item_fn.block)
+    }
+
+    /// Handler whose logic lives in `method`, e.g. `Processor::process_deposit`
+    pub fn lookup_method(&self, method: &ImplItemFn) -> Option<&InstructionHandler<'a>> {
+        self.lookup_body(&method.block)
+    }
+
+    fn lookup_body(&self, block: &Block) -> Option<&InstructionHandler<'a>> {
+        self.handlers
+            .iter()
+            .find(|handler| std::ptr::eq(handler.body().block, block))
+    }
+
+    /// Number of handlers whose logic lives in `file`
+    pub fn count_in_file(&self, file: &str) -> usize {
+        self.handlers
+            .iter()
+            .filter(|handler| handler.body().file == file)
+            .count()
+    }
+}
+
+/// Checks if a type is an Anchor `Context<...>`
+pub fn is_context_type(ty: &Type) -> bool {
+    context_segment(ty).is_some()
+}
+
+/// Checks if a function has the shape of an instruction handler:
+/// public, with a `Context<...>` first argument
+pub fn is_handler_fn(item_fn: &ItemFn) -> bool {
+    matches!(item_fn.vis, syn::Visibility::Public(_))
+        && item_fn.sig.inputs.first().is_some_and(
+            |arg| matches!(arg, FnArg::Typed(pat_type) if is_context_type(&pat_type.ty)),
+        )
+}
+
+/// Checks if a module carries the Anchor `#[program]` attribute
+pub fn is_program_module(item_mod: &ItemMod) -> bool {
+    item_mod.attrs.iter().any(|attr| {
+        attr.path()
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "program")
+    })
+}
+
+/// Name of the accounts struct in a handler's `Context<...>` argument
+pub fn context_accounts(sig: &Signature) -> Option<String> {
+    let FnArg::Typed(pat_type) = sig.inputs.first()? else {
+        return None;
+    };
+    let PathArguments::AngleBracketed(args) = &context_segment(&pat_type.ty)?.arguments else {
+        return None;
+    };
+
+    // Lifetimes (`Context<'_, '_, '_, 'info, T>`) precede the accounts type
+    args.args.iter().find_map(|arg| match arg {
+        GenericArgument::Type(Type::Path(ty)) => {
+            ty.path.segments.last().map(|s| s.ident.to_string())
+        }
+        _ => None,
+    })
+}
+
+/// The `Context` path segment of `Context<...>` or `anchor_lang::...::Context<...>`
+fn context_segment(ty: &Type) -> Option<&syn::PathSegment> {
+    let Type::Path(type_path) = ty else {
+        return None;
+    };
+    let segments = &type_path.path.segments;
+    let last = segments.last()?;
+    if last.ident != "Context" || !matches!(last.arguments, PathArguments::AngleBracketed(_)) {
+        return None;
+    }
+    let qualified_by_anchor = segments.len() == 1 || segments[0].ident == "anchor_lang";
+    qualified_by_anchor.then_some(last)
+}
+
+/// A handler-shaped function and where it was declared
+struct Candidate<'a> {
+    file: &'a str,
+    path: Vec<String>,
+    sig: &'a Signature,
+    block: &'a Block,
+    in_program_module: bool,
+}
+
+impl<'a> Candidate<'a> {
+    fn handler_fn(&self) -> HandlerFn<'a> {
+        HandlerFn {
+            file: self.file,
+            sig: self.sig,
+            block: self.block,
+        }
+    }
+}
+
+fn collect_candidates<'a>(
+    items: &'a [Item],
+    file: &'a str,
+    module_path: &mut Vec<String>,
+    in_program_module: bool,
+    out: &mut Vec<Candidate<'a>>,
+) {
+    for item in items {
+        match item {
+            Item::Fn(item_fn) if is_handler_fn(item_fn) => {
+                let mut path = module_path.clone();
+                path.push(item_fn.sig.ident.to_string());
+                out.push(Candidate {
+                    file,
+                    path,
+                    sig: &item_fn.sig,
+                    block: &item_fn.block,
+                    in_program_module,
+                });
+            }
+            Item::Mod(item_mod) => {
+                if let Some((_, content)) = &item_mod.content {
+                    module_path.push(item_mod.ident.to_string());
+                    collect_candidates(
+                        content,
+                        file,
+                        module_path,
+                        in_program_module || is_program_module(item_mod),
+                        out,
+                    );
+                    module_path.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Module path implied by a source file's location, e.g.
+/// `programs/amm/src/instructions/swap.rs` -> `instructions::swap`
+pub(crate) fn file_module_path(path: &str) -> Vec<String> {
+    let components: Vec<&str> = path.split(['/', '\\']).filter(|c| !c.is_empty()).collect();
+    let start = components
+        .iter()
+        .rposition(|c| *c == "src")
+        .map_or(0, |i| i + 1);
+
+    let mut module_path: Vec<String> = components[start..]
+        .iter()
+        .map(|c| c.strip_suffix(".rs").unwrap_or(c).to_string())
+        .collect();
+    if matches!(
+        module_path.last().map(String::as_str),
+        Some("lib" | "main" | "mod")
+    ) {
+        module_path.pop();
+    }
+    module_path
+}
+
+/// Native program functions collected across the workspace
+#[derive(Default)]
+struct NativeProgram<'a> {
+    /// Functions registered with `entrypoint!`
+    entrypoints: Vec<String>,
+
+    /// Functions and methods taking `&[AccountInfo]`; a method's path ends
+    /// with its type, e.g. `processor::Processor::process_deposit`
+    processors: Vec<Candidate<'a>>,
+
+    /// Every function and method, with the type a method is implemented on,
+    /// for following the entry point's dispatch
+    functions: Vec<NativeFn<'a>>,
+}
+
+/// A function or method of a native program
+struct NativeFn<'a> {
+    sig: &'a Signature,
+    block: &'a Block,
+    self_type: Option<String>,
+}
+
+impl<'a> NativeProgram<'a> {
+    fn collect(&mut self, items: &'a [Item], file: &'a str, module_path: &mut Vec<String>) {
+        for item in items {
+            match item {
+                Item::Fn(item_fn) => {
+                    self.add(
+                        file,
+                        module_path.clone(),
+                        &item_fn.sig,
+                        &item_fn.block,
+                        None,
+                    );
+                }
+                Item::Impl(item_impl) => {
+                    let self_type = match &*item_impl.self_ty {
+                        Type::Path(ty) => ty.path.segments.last().map(|s| s.ident.to_string()),
+                        _ => None,
+                    };
+                    for impl_item in &item_impl.items {
+                        if let syn::ImplItem::Fn(method) = impl_item {
+                            let mut path = module_path.clone();
+                            path.extend(self_type.clone());
+                            self.add(file, path, &method.sig, &method.block, self_type.clone());
+                        }
+                    }
+                }
+                Item::Mod(item_mod) => {
+                    if let Some((_, content)) = &item_mod.content {
+                        module_path.push(item_mod.ident.to_string());
+                        self.collect(content, file, module_path);
+                        module_path.pop();
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Record a function or method declared under `path`
+    fn add(
+        &mut self,
+        file: &'a str,
+        mut path: Vec<String>,
+        sig: &'a Signature,
+        block: &'a Block,
+        self_type: Option<String>,
+    ) {
+        if native::is_native_handler_sig(sig) {
+            path.push(sig.ident.to_string());
+            self.processors.push(Candidate {
+                file,
+                path,
+                sig,
+                block,
+                in_program_module: false,
+            });
+        }
+        self.functions.push(NativeFn {
+            sig,
+            block,
+            self_type,
+        });
+    }
+
+    /// One handler per dispatched instruction of each entry point
+    fn handlers(&self) -> Vec<InstructionHandler<'a>> {
+        let entrypoints = if self.entrypoints.is_empty() {
+            vec!["process_instruction".to_string()]
+        } else {
+            self.entrypoints.clone()
+        };
+
+        let mut handlers = Vec::new();
+        for name in entrypoints {
+            let Some(entry) = self
+                .processors
+                .iter()
+                .find(|candidate| candidate.sig.ident == name)
+            else {
+                continue;
+            };
+            let entry_fn = entry.handler_fn();
+
+            let (arms, self_type) = self.dispatch(entry.sig, entry.block, None, 0);
+            if arms.is_empty() {
+                // A single-instruction program: the entry point is the handler
+                handlers.push(native_handler(name, entry_fn, None));
+                continue;
+            }
+            for arm in arms {
+                let implementation = arm
+                    .call
+                    .clone()
+                    .map(|mut call| {
+                        // `Self::process_x` names a method of the dispatching type
+                        if let (Some("Self"), Some(self_type)) =
+                            (call.first().map(String::as_str), &self_type)
+                        {
+                            call[0] = self_type.clone();
+                        }
+                        call
+                    })
+                    .and_then(|call| resolve_delegate(&call, None, &self.processors))
+                    .map(Candidate::handler_fn);
+                handlers.push(native_handler(arm.name(), entry_fn, implementation));
+            }
+        }
+        handlers
+    }
+
+    /// Dispatch arms in `block`, following the call that forwards the
+    /// accounts (e.g. to `Processor::process`) a few levels deep, with the
+    /// type implementing the function that dispatches
+    fn dispatch(
+        &self,
+        sig: &Signature,
+        block: &Block,
+        self_type: Option<String>,
+        depth: usize,
+    ) -> (Vec<native::DispatchArm>, Option<String>) {
+        let arms = native::dispatch_arms(block);
+        if !arms.is_empty() || depth >= 3 {
+            return (arms, self_type);
+        }
+
+        let next = native::account_slice_param(sig)
+            .and_then(|accounts| native::forwarding_call(block, &accounts))
+            .and_then(|call| {
+                let name = call.last()?.clone();
+                self.functions.iter().find(|f| f.sig.ident == name)
+            });
+        match next {
+            Some(next) => self.dispatch(next.sig, next.block, next.self_type.clone(), depth + 1),
+            None => (arms, self_type),
+        }
+    }
+}
+
+fn native_handler<'a>(
+    name: String,
+    entry: HandlerFn<'a>,
+    implementation: Option<HandlerFn<'a>>,
+) -> InstructionHandler<'a> {
+    InstructionHandler {
+        name,
+        entry,
+        implementation,
+        accounts: None,
+        in_program_module: false,
+        native: true,
+    }
+}
+
+/// Path of the first call that forwards the handler's context argument
+fn delegated_call_path(sig: &Signature, block: &Block) -> Option<Vec<String>> {
+    let FnArg::Typed(pat_type) = sig.inputs.first()? else {
+        return None;
+    };
+    let Pat::Ident(ctx_ident) = &*pat_type.pat else {
+        return None;
+    };
+
+    let mut finder = DelegateFinder {
+        ctx_name: ctx_ident.ident.to_string(),
+        call_path: None,
+    };
+    finder.visit_block(block);
+    finder.call_path
+}
+
+/// Finds a call such as `instructions::swap::handler(ctx, amount)`
+struct DelegateFinder {
+    ctx_name: String,
+    call_path: Option<Vec<String>>,
+}
+
+impl<'ast> Visit<'ast> for DelegateFinder {
+    fn visit_expr_call(&mut self, node: &'ast ExprCall) {
+        if self.call_path.is_some() {
+            return;
+        }
+
+        let forwards_ctx = matches!(
+            node.args.first(),
+            Some(Expr::Path(arg)) if arg.path.is_ident(&self.ctx_name)
+        );
+        if let (true, Expr::Path(func)) = (forwards_ctx, &*node.func) {
+            let path: Vec<String> = func
+                .path
+                .segments
+                .iter()
+                .map(|s| s.ident.to_string())
+                .skip_while(|s| matches!(s.as_str(), "crate" | "self" | "super"))
+                .collect();
+            if !path.is_empty() {
+                self.call_path = Some(path);
+                return;
+            }
+        }
+
+        visit::visit_expr_call(self, node);
+    }
+}
+
+/// Resolve a delegated call against the handler-shaped functions outside
+/// the `#[program]` module, preferring a match on the accounts struct
+fn resolve_delegate<'a, 'c>(
+    call: &[String],
+    accounts: Option<&str>,
+    candidates: &'c [Candidate<'a>],
+) -> Option<&'c Candidate<'a>> {
+    let matches: Vec<&Candidate> = candidates
+        .iter()
+        .filter(|c| c.path.ends_with(call))
+        .collect();
+
+    match matches.as_slice() {
+        [single] => Some(single),
+        [] => None,
+        several => {
+            let mut by_accounts = several
+                .iter()
+                .filter(|c| accounts.is_some() && context_accounts(c.sig).as_deref() == accounts);
+            match (by_accounts.next(), by_accounts.next()) {
+                (Some(only), None) => Some(*only),
+                _ => None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_forms() {
+        let ast = syn::parse_file(
+            r#"
+            pub fn plain(ctx: Context<Plain>) -> Result<()> { Ok(()) }
+            pub fn lifetimes<'info>(ctx: Context<'_, '_, '_, 'info, Lifetimes<'info>>) -> Result<()> { Ok(()) }
+            pub fn qualified(ctx: anchor_lang::prelude::Context<Qualified>) -> Result<()> { Ok(()) }
+            pub fn custom(ctx: MyContext) -> Result<()> { Ok(()) }
+            pub fn second(amount: u64, ctx: Context<Second>) -> Result<()> { Ok(()) }
+            fn private(ctx: Context<Private>) -> Result<()> { Ok(()) }
+            "#,
+        )
+        .unwrap();
+        let index = HandlerIndex::from_ast("lib.rs", &ast);
+
+        let names: Vec<_> = index.handlers().iter().map(|h| h.name.as_str()).collect();
+        assert_eq!(names, vec!["plain", "lifetimes", "qualified"]);
+        let accounts: Vec<_> = index
+            .handlers()
+            .iter()
+            .map(|h| h.accounts.as_deref())
+            .collect();
+        assert_eq!(
+            accounts,
+            vec![Some("Plain"), Some("Lifetimes"), Some("Qualified")]
+        );
+    }
+
+    #[test]
+    fn test_program_module_with_delegation() {
+        let lib = syn::parse_file(
+            r#"
+            #[program]
+            pub mod amm {
+                use super::*;
+
+                pub fn swap(ctx: Context<Swap>, amount: u64) -> Result<()> {
+                    instructions::swap::handler(ctx, amount)
+                }
+
+                pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+                    ctx.accounts.pool.balance += amount;
+                    Ok(())
+                }
+            }
+            "#,
+        )
+        .unwrap();
+        let swap = syn::parse_file(
+            r#"
+            pub fn handler(ctx: Context<Swap>, amount: u64) -> Result<()> {
+                let out = amount / 2;
+                Ok(())
+            }
+            "#,
+        )
+        .unwrap();
+
+        let index = HandlerIndex::from_files([
+            ("programs/amm/src/lib.rs", &lib),
+            ("programs/amm/src/instructions/swap.rs", &swap),
+        ]);
+
+        assert_eq!(index.len(), 2);
+        let handler = &index.handlers()[0];
+        assert_eq!(handler.name, "swap");
+        assert!(handler.in_program_module);
+        assert_eq!(handler.body().file, "programs/amm/src/instructions/swap.rs");
+
+        let Item::Fn(implementation) = &swap.items[0] else {
+            panic!("expected fn");
+        };
+        assert_eq!(index.lookup(implementation).unwrap().name, "swap");
+        assert_eq!(index.count_in_file("programs/amm/src/lib.rs"), 1);
+        assert_eq!(
+            index.count_in_file("programs/amm/src/instructions/swap.rs"),
+            1
+        );
+    }
+
+    #[test]
+    fn test_native_program_dispatch() {
+        let lib = syn::parse_file(
+            r#"
+            entrypoint!(process_instruction);
+
+            pub fn process_instruction(
+                program_id: &Pubkey,
+                accounts: &[AccountInfo],
+                data: &[u8],
+            ) -> ProgramResult {
+                Processor::process(program_id, accounts, data)
+            }
+
+            impl Processor {
+                pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+                    match VaultInstruction::unpack(data)? {
+                        VaultInstruction::Deposit { amount } => processor::process_deposit(accounts, amount),
+                        VaultInstruction::Withdraw { amount } => Self::withdraw(accounts, amount),
+                    }
+                }
+            }
+            "#,
+        )
+        .unwrap();
+        let processor = syn::parse_file(
+            r#"
+            pub fn process_deposit(accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+                Ok(())
+            }
+            "#,
+        )
+        .unwrap();
+
+        let index = HandlerIndex::from_files([
+            ("program/src/lib.rs", &lib),
+            ("program/src/processor.rs", &processor),
+        ]);
+
+        let names: Vec<_> = index.handlers().iter().map(|h| h.name.as_str()).collect();
+        assert_eq!(names, vec!["deposit", "withdraw"]);
+        assert!(index.handlers().iter().all(|h| h.native));
+
+        // `deposit` resolves to its processor; `withdraw` stays on the entry point
+        assert_eq!(index.handlers()[0].body().file, "program/src/processor.rs");
+        assert_eq!(index.handlers()[1].body().sig.ident, "process_instruction");
+    }
+
+    #[test]
+    fn test_native_processor_methods() {
+        let ast = syn::parse_file(
+            r#"
+            entrypoint!(process_instruction);
+
+            pub fn process_instruction(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+                Processor::process(program_id, accounts, data)
+            }
+
+            impl Processor {
+                pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+                    match VaultInstruction::unpack(data)? {
+                        VaultInstruction::Deposit { amount } => Self::process_deposit(accounts, amount),
+                        VaultInstruction::Withdraw => <Processor>::process_withdraw(accounts),
+                    }
+                }
+
+                fn process_deposit(accounts: &[AccountInfo], amount: u64) -> ProgramResult { Ok(()) }
+                fn process_withdraw(accounts: &[AccountInfo]) -> ProgramResult { Ok(()) }
+            }
+
+            impl Other {
+                fn process_deposit(accounts: &[AccountInfo], amount: u64) -> ProgramResult { Ok(()) }
+            }
+            "#,
+        )
+        .unwrap();
+        let index = HandlerIndex::from_ast("src/lib.rs", &ast);
+
+        let bodies: Vec<_> = index
+            .handlers()
+            .iter()
+            .map(|h| (h.name.as_str(), h.body().sig.ident.to_string()))
+            .collect();
+        assert_eq!(
+            bodies,
+            vec![
+                ("deposit", "process_deposit".to_string()),
+                ("withdraw", "process_withdraw".to_string()),
+            ]
+        );
+
+        let Item::Impl(processor) = &ast.items[2] else {
+            panic!("expected impl");
+        };
+        let syn::ImplItem::Fn(deposit) = &processor.items[1] else {
+            panic!("expected method");
+        };
+        assert_eq!(index.lookup_method(deposit).unwrap().name, "deposit");
+    }
+
+    #[test]
+    fn test_file_module_path() {
+        assert_eq!(
+            file_module_path("programs/amm/src/instructions/swap.rs"),
+            vec!["instructions", "swap"]
+        );
+        assert_eq!(
+            file_module_path("src/instructions/mod.rs"),
+            vec!["instructions"]
+        );
+        assert!(file_module_path("programs/amm/src/lib.rs").is_empty());
+    }
+}
@@ -5,16 +5,23 @@
 //! 1. Account Struct Length (max/avg): The "width" of inputs.
 //! 2. Risky Numeric Params: Handlers taking user-controlled numbers.
 //! 3. Constraint Count: The total validation logic surface.
-
+//!
+//! Native programs have no accounts structs: a native handler's width is the
+//! number of `next_account_info` calls, and its hand-written `is_signer`,
+//! `is_writable`, `owner` and `key` checks are its constraints.
+
+use crate::factors::handlers::{HandlerIndex, InstructionHandler};
+use crate::factors::native;
+use crate::factors::normalization::{Curve, Normalization};
+use crate::factors::registry::{Factor, FactorContext, FactorOutput};
+use crate::workspace::ParsedWorkspace;
 use quote::quote;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
 use syn::{
     parse::{Parse, ParseStream},
-    parse_file,
     visit::Visit,
-    Expr, ItemFn, ItemStruct, Path, Token,
+    Block, Expr, ImplItemFn, ItemFn, ItemStruct, Path, Signature, Token,
 };
 
 #[derive(Debug, Default, Serialize, Deserialize)]
@@ -55,6 +62,32 @@ impl InputConstraintMetrics {
             "constraintBreakdown": self.constraint_breakdown,
         })
     }
+
+    /// Default Input Constraint Factor scoring
+    /// Raw score: constraints × 1 + widest account struct × 3 + amount handlers × 5
+    /// Normalized so that 150 is 100
+    pub fn default_normalization() -> Normalization {
+        Normalization::new(Curve::linear(0.0, 150.0))
+            .term("constraints", 1.0)
+            .term("maxAccounts", 3.0)
+            .term("amountHandlers", 5.0)
+    }
+
+    /// Measurements the factor is computed from
+    pub fn normalization_inputs(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            ("constraints", self.total_constraints as f64),
+            ("maxAccounts", self.max_accounts_per_handler as f64),
+            ("amountHandlers", self.total_amount_handlers as f64),
+        ]
+    }
+
+    /// Fill the raw score and the normalized factor using `normalization`
+    fn score_with(&mut self, normalization: &Normalization) {
+        let inputs = self.normalization_inputs();
+        self.raw_risk_score = normalization.weighted_sum(&inputs);
+        self.input_constraint_factor = normalization.score(&inputs);
+    }
 }
 
 /// Helper struct for the custom constraint parser
@@ -71,8 +104,14 @@ impl Parse for ConstraintParser {
 
         while !input.is_empty() {
             // 1. Parse the key (e.g., `mut`, `has_one`, `token::mint`)
-            let key_path: Path = input.parse()?;
-            let key = quote!(#key_path).to_string().replace(' ', "");
+            // `mut` is a keyword, so it never parses as a `Path`
+            let key = if input.peek(Token![mut]) {
+                let _: Token![mut] = input.parse()?;
+                "mut".to_string()
+            } else {
+                let key_path: Path = input.parse()?;
+                quote!(#key_path).to_string().replace(' ', "")
+            };
 
             // 2. Increment counts
             count += 1;
@@ -95,8 +134,10 @@ impl Parse for ConstraintParser {
 }
 
 /// Visitor to implement the Two-Pass strategy
-#[derive(Debug, Default)]
-struct InputConstraintVisitor {
+#[derive(Debug)]
+struct InputConstraintVisitor<'a> {
+    handlers: &'a HandlerIndex<'a>,
+
     // --- Raw Data Collection ---
     account_struct_lengths: Vec<usize>,
     total_constraints: u32,
@@ -105,7 +146,58 @@ struct InputConstraintVisitor {
     total_handlers_found: u32,
 }
 
-impl InputConstraintVisitor {
+impl<'a> InputConstraintVisitor<'a> {
+    fn new(handlers: &'a HandlerIndex<'a>) -> Self {
+        Self {
+            handlers,
+            account_struct_lengths: Vec::new(),
+            total_constraints: 0,
+            constraint_breakdown: HashMap::new(),
+            total_amount_handlers: 0,
+            total_handlers_found: 0,
+        }
+    }
+
+    /// Record a handler's numeric params and, for a native handler, the
+    /// accounts it reads and the checks it makes
+    fn visit_handler(&mut self, handler: &InstructionHandler, sig: &Signature, block: &Block) {
+        log::info!(
+            "🔍 INPUT CONSTRAINTS DEBUG: Found Anchor handler: {}",
+            handler.name
+        );
+        self.total_handlers_found += 1;
+        let mut has_numeric_param = false;
+
+        // Iterate all params *except* the first (which is ctx); native
+        // handlers have no ctx
+        let skip = if handler.native { 0 } else { 1 };
+        for arg in sig.inputs.iter().skip(skip) {
+            if let syn::FnArg::Typed(pat_type) = arg {
+                if self.is_numeric_type(&pat_type.ty) {
+                    has_numeric_param = true;
+                }
+            }
+        }
+
+        if has_numeric_param {
+            self.total_amount_handlers += 1;
+        }
+
+        if handler.native {
+            let len = native::count_next_account_info(block);
+            if len > 0 {
+                self.account_struct_lengths.push(len);
+            }
+            for check in native::account_checks(block) {
+                self.total_constraints += 1;
+                *self
+                    .constraint_breakdown
+                    .entry(check.as_str().to_string())
+                    .or_insert(0) += 1;
+            }
+        }
+    }
+
     /// Checks if a type is a risky numeric primitive
     fn is_numeric_type(&self, ty: &syn::Type) -> bool {
         if let syn::Type::Path(type_path) = ty {
@@ -122,55 +214,27 @@ impl InputConstraintVisitor {
             false
         }
     }
-
-    /// Checks if an argument is the Anchor Context
-    fn is_context_arg(&self, arg: &syn::FnArg) -> bool {
-        if let syn::FnArg::Typed(pat_type) = arg {
-            if let syn::Type::Path(type_path) = &*pat_type.ty {
-                if let Some(segment) = type_path.path.segments.last() {
-                    return segment.ident == "Context";
-                }
-            }
-        }
-        false
-    }
 }
 
-impl<'ast> Visit<'ast> for InputConstraintVisitor {
+impl<'ast> Visit<'ast> for InputConstraintVisitor<'_> {
     /// --- Pass 1: Find Instruction Handlers (for Sub-factor 2) ---
     fn visit_item_fn(&mut self, node: &'ast ItemFn) {
-        // A simple, robust heuristic for an Anchor handler:
-        // 1. It's public
-        // 2. Its *first* argument is `Context<...>`
-        let is_anchor_handler = matches!(node.vis, syn::Visibility::Public(_))
-            && node.sig.inputs.len() > 0
-            && self.is_context_arg(&node.sig.inputs[0]);
-
-        if is_anchor_handler {
-            log::info!(
-                "🔍 INPUT CONSTRAINTS DEBUG: Found Anchor handler: {}",
-                node.sig.ident
-            );
-            self.total_handlers_found += 1;
-            let mut has_numeric_param = false;
-
-            // Iterate all params *except* the first (which is ctx)
-            for arg in node.sig.inputs.iter().skip(1) {
-                if let syn::FnArg::Typed(pat_type) = arg {
-                    if self.is_numeric_type(&pat_type.ty) {
-                        has_numeric_param = true;
-                    }
-                }
-            }
-
-            if has_numeric_param {
-                self.total_amount_handlers += 1;
-            }
+        if let Some(handler) = self.handlers.lookup(node) {
+            self.visit_handler(handler, &node.sig, &node.block);
         }
 
         syn::visit::visit_item_fn(self, node);
     }
 
+    /// Native handlers implemented as methods, e.g. `Processor::process_deposit`
+    fn visit_impl_item_fn(&mut self, node: &'ast ImplItemFn) {
+        if let Some(handler) = self.handlers.lookup_method(node) {
+            self.visit_handler(handler, &node.sig, &node.block);
+        }
+
+        syn::visit::visit_impl_item_fn(self, node);
+    }
+
     /// --- Pass 2: Find Account Structs (for Sub-factors 1 & 4) ---
     fn visit_item_struct(&mut self, node: &'ast ItemStruct) {
         // Check if this is an `#[derive(Accounts)]` struct
@@ -216,47 +280,36 @@ impl<'ast> Visit<'ast> for InputConstraintVisitor {
 
 /// Main driver function to run the analysis
 pub fn calculate_workspace_input_constraints(
-    workspace_path: &PathBuf,
+    workspace_path: &std::path::Path,
     selected_files: &[String],
 ) -> Result<InputConstraintMetrics, Box<dyn std::error::Error>> {
-    log::info!("🔍 INPUT CONSTRAINTS DEBUG: Starting analysis...");
+    let workspace = ParsedWorkspace::load(workspace_path, selected_files);
+    analyze_workspace_input_constraints(&workspace)
+}
 
-    let mut visitor = InputConstraintVisitor::default();
+/// Calculate input constraint metrics over an already-parsed workspace
+pub fn analyze_workspace_input_constraints(
+    workspace: &ParsedWorkspace,
+) -> Result<InputConstraintMetrics, Box<dyn std::error::Error>> {
+    log::info!("🔍 INPUT CONSTRAINTS DEBUG: Starting analysis...");
 
-    // Analyze each selected file
-    for file_path in selected_files {
-        let full_path = workspace_path.join(file_path);
-        if !full_path.exists() || !full_path.is_file() {
-            log::warn!("File does not exist or is not a file: {:?}", full_path);
-            continue;
-        }
+    let handlers = HandlerIndex::from_workspace(workspace);
+    let mut visitor = InputConstraintVisitor::new(&handlers);
 
-        match std::fs::read_to_string(&full_path) {
-            Ok(content) => match parse_file(&content) {
-                Ok(ast) => {
-                    visitor.visit_file(&ast);
-                }
-                Err(e) => {
-                    log::warn!("Failed to parse AST for {:?}: {}", full_path, e);
-                }
-            },
-            Err(e) => {
-                log::warn!("Failed to read file {:?}: {}", full_path, e);
-            }
-        }
+    // Analyze each selected file and the account validation macros generate
+    for file in workspace.files_with_expanded() {
+        visitor.visit_file(&file.ast);
     }
 
     // --- Final Calculation and Normalization (0-100) ---
 
     // 1. Get raw metrics from visitor
-    let total_constraints = visitor.total_constraints as f64;
-    let total_amount_handlers = visitor.total_amount_handlers as f64;
     let max_accounts_len = visitor
         .account_struct_lengths
         .iter()
         .max()
         .cloned()
-        .unwrap_or(0) as f64;
+        .unwrap_or(0);
     let avg_accounts_len = if !visitor.account_struct_lengths.is_empty() {
         visitor.account_struct_lengths.iter().sum::<usize>() as f64
             / visitor.account_struct_lengths.len() as f64
@@ -264,28 +317,22 @@ pub fn calculate_workspace_input_constraints(
         0.0
     };
 
-    // 2. Calculate the Raw Score using our weights
-    let raw_risk_score =
-        (total_constraints * 1.0) + (max_accounts_len * 3.0) + (total_amount_handlers * 5.0);
-
-    // 3. Normalize to 0-100
-    let upper_bound = 150.0; // Our 100% risk cap
-    let factor = (raw_risk_score / upper_bound) * 100.0;
-    let final_factor = factor.min(100.0); // Cap at 100
-
-    // 4. Populate the final metrics struct
-    let metrics = InputConstraintMetrics {
+    // 2. Populate the final metrics struct
+    let mut metrics = InputConstraintMetrics {
         account_struct_lengths: visitor.account_struct_lengths,
         total_amount_handlers: visitor.total_amount_handlers,
         total_constraints: visitor.total_constraints,
         constraint_breakdown: visitor.constraint_breakdown,
-        input_constraint_factor: final_factor,
+        input_constraint_factor: 0.0,
         total_handlers_found: visitor.total_handlers_found,
-        max_accounts_per_handler: max_accounts_len as usize,
+        max_accounts_per_handler: max_accounts_len,
         avg_accounts_per_handler: avg_accounts_len,
-        raw_risk_score,
+        raw_risk_score: 0.0,
     };
 
+    // 3. Score with our weights, normalized to 0-100
+    metrics.score_with(&InputConstraintMetrics::default_normalization());
+
     log::info!(
         "🔍 INPUT CONSTRAINTS DEBUG: Analysis complete. Factor: {:.2}",
         metrics.input_constraint_factor
@@ -293,10 +340,49 @@ pub fn calculate_workspace_input_constraints(
     Ok(metrics)
 }
 
+/// Registry entry for the input constraint factor
+pub struct InputConstraintsFactor;
+
+impl Factor for InputConstraintsFactor {
+    fn id(&self) -> &'static str {
+        "inputConstraints"
+    }
+
+    fn version(&self) -> &'static str {
+        "1.2.0"
+    }
+
+    fn name(&self) -> &'static str {
+        "Input constraints"
+    }
+
+    fn required(&self) -> bool {
+        true
+    }
+
+    fn normalization(&self) -> Option<Normalization> {
+        Some(InputConstraintMetrics::default_normalization())
+    }
+
+    fn compute(&self, ctx: &FactorContext) -> Result<FactorOutput, Box<dyn std::error::Error>> {
+        let mut metrics = analyze_workspace_input_constraints(ctx.workspace)?;
+        metrics.score_with(&ctx.normalization_or(InputConstraintMetrics::default_normalization));
+        Ok(
+            FactorOutput::keyed(self.id(), metrics.input_constraint_factor, metrics.to_json()).with_note(format!(
+                "Analyzed input constraints: {} handlers, avg {:.1} accounts, {} amount handlers, {} constraints",
+                metrics.total_handlers_found,
+                metrics.avg_accounts_per_handler,
+                metrics.total_amount_handlers,
+                metrics.total_constraints,
+            )),
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::path::PathBuf;
+    use crate::workspace::ParsedFile;
 
     #[test]
     fn test_simple_account_struct() {
@@ -311,7 +397,7 @@ mod tests {
         "#;
 
         let temp_dir = std::env::temp_dir();
-        let test_file = temp_dir.join("test.rs");
+        let test_file = temp_dir.join("test_input_constraints_1.rs");
         std::fs::write(&test_file, code).unwrap();
 
         let result = calculate_workspace_input_constraints(
@@ -344,7 +430,7 @@ mod tests {
         "#;
 
         let temp_dir = std::env::temp_dir();
-        let test_file = temp_dir.join("test.rs");
+        let test_file = temp_dir.join("test_input_constraints_2.rs");
         std::fs::write(&test_file, code).unwrap();
 
         let result = calculate_workspace_input_constraints(
@@ -353,8 +439,8 @@ mod tests {
         )
         .unwrap();
 
-        // Should find constraints: mut, has_one, owner, mut, signer
-        assert!(result.total_constraints >= 5);
+        // Should find constraints: mut, has_one, mut, signer
+        assert_eq!(result.total_constraints, 4);
         assert!(result.constraint_breakdown.contains_key("mut"));
         assert!(result.constraint_breakdown.contains_key("has_one"));
         assert!(result.constraint_breakdown.contains_key("signer"));
@@ -377,7 +463,7 @@ mod tests {
         "#;
 
         let temp_dir = std::env::temp_dir();
-        let test_file = temp_dir.join("test.rs");
+        let test_file = temp_dir.join("test_input_constraints_3.rs");
         std::fs::write(&test_file, code).unwrap();
 
         let result = calculate_workspace_input_constraints(
@@ -391,6 +477,92 @@ mod tests {
         assert_eq!(result.total_amount_handlers, 1);
     }
 
+    #[test]
+    fn test_native_handler_inputs() {
+        let code = r#"
+            entrypoint!(process_instruction);
+
+            pub fn process_instruction(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+                match VaultInstruction::unpack(data)? {
+                    VaultInstruction::Deposit { amount } => process_deposit(accounts, amount),
+                }
+            }
+
+            fn process_deposit(accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+                let iter = &mut accounts.iter();
+                let depositor = next_account_info(iter)?;
+                let vault = next_account_info(iter)?;
+                if !depositor.is_signer || !vault.is_writable {
+                    return Err(ProgramError::MissingRequiredSignature);
+                }
+                Ok(())
+            }
+        "#;
+        let file = ParsedFile::parse("src/lib.rs", code.to_string()).unwrap();
+        let workspace = ParsedWorkspace::from_files(std::path::Path::new("."), vec![file]);
+
+        let metrics = analyze_workspace_input_constraints(&workspace).unwrap();
+        assert_eq!(metrics.total_handlers_found, 1);
+        assert_eq!(metrics.total_amount_handlers, 1);
+        assert_eq!(metrics.account_struct_lengths, vec![2]);
+        assert_eq!(metrics.total_constraints, 2);
+        assert_eq!(metrics.constraint_breakdown.get("is_writable"), Some(&1));
+    }
+
+    #[test]
+    fn test_native_processor_methods() {
+        let code = r#"
+            entrypoint!(process_instruction);
+
+            pub fn process_instruction(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+                Processor::process(program_id, accounts, data)
+            }
+
+            pub struct Processor;
+
+            impl Processor {
+                pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+                    match VaultInstruction::unpack(data)? {
+                        VaultInstruction::Deposit { amount } => Self::process_deposit(accounts, amount),
+                        VaultInstruction::Withdraw => Self::process_withdraw(program_id, accounts),
+                    }
+                }
+
+                fn process_deposit(accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+                    let iter = &mut accounts.iter();
+                    let depositor = next_account_info(iter)?;
+                    let vault = next_account_info(iter)?;
+                    if !depositor.is_signer {
+                        return Err(ProgramError::MissingRequiredSignature);
+                    }
+                    Ok(())
+                }
+
+                fn process_withdraw(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+                    let iter = &mut accounts.iter();
+                    let authority = next_account_info(iter)?;
+                    let vault = next_account_info(iter)?;
+                    let destination = next_account_info(iter)?;
+                    if !authority.is_signer {
+                        return Err(ProgramError::MissingRequiredSignature);
+                    }
+                    if vault.owner != program_id {
+                        return Err(ProgramError::IncorrectProgramId);
+                    }
+                    Ok(())
+                }
+            }
+        "#;
+        let file = ParsedFile::parse("src/lib.rs", code.to_string()).unwrap();
+        let workspace = ParsedWorkspace::from_files(std::path::Path::new("."), vec![file]);
+
+        let metrics = analyze_workspace_input_constraints(&workspace).unwrap();
+        assert_eq!(metrics.total_handlers_found, 2);
+        assert_eq!(metrics.total_amount_handlers, 1);
+        assert_eq!(metrics.account_struct_lengths, vec![2, 3]);
+        assert_eq!(metrics.total_constraints, 3);
+    }
+
     #[test]
     fn test_factor_calculation() {
         let code = r#"
@@ -416,7 +588,7 @@ mod tests {
         "#;
 
         let temp_dir = std::env::temp_dir();
-        let test_file = temp_dir.join("test.rs");
+        let test_file = temp_dir.join("test_input_constraints_4.rs");
         std::fs::write(&test_file, code).unwrap();
 
         let result = calculate_workspace_input_constraints(
@@ -4,9 +4,11 @@
 //! (require!, require_eq!, assert!, assert_eq!) by measuring AST-based expression
 //! complexity rather than relying on string-matching variable names.
 
-use quote::quote;
+use crate::factors::normalization::{Curve, Normalization};
+use crate::factors::registry::{Factor, FactorContext, FactorOutput};
+use crate::workspace::ParsedWorkspace;
 use serde::{Deserialize, Serialize};
-use syn::{visit::Visit, BinOp, Expr, ExprBinary, ExprCall, ExprMacro};
+use syn::{visit::Visit, ExprMacro};
 
 /// Metrics for Constraint Density Factor
 #[derive(Debug, Default, Serialize, Deserialize)]
@@ -66,6 +68,20 @@ impl ConstraintDensityMetrics {
             "filesSkipped": self.files_skipped,
         })
     }
+
+    /// Default Constraint Density Factor scoring
+    /// Upper bound: 1000 assertion complexity points = 100% risk
+    pub fn default_normalization() -> Normalization {
+        Normalization::new(Curve::linear(0.0, 1000.0)).term("assertionComplexity", 1.0)
+    }
+
+    /// Measurements the Constraint Density Factor is computed from
+    pub fn normalization_inputs(&self) -> Vec<(&'static str, f64)> {
+        vec![(
+            "assertionComplexity",
+            self.total_assertion_complexity_score as f64,
+        )]
+    }
 }
 
 /// Visitor for analyzing constraint density via AST-based expression complexity
@@ -81,129 +97,6 @@ impl ConstraintDensityVisitor {
             current_file_path: String::new(),
         }
     }
-
-    /// Record an assertion with its complexity analysis
-    fn record_assertion(&mut self, macro_name: &str, expression: &Expr) {
-        let complexity_score = self.analyze_expression_complexity(expression);
-        let line = 0; // We'll use 0 as default since span() requires proc_macro2
-
-        self.metrics.total_assertions += 1;
-        self.metrics.total_assertion_complexity_score += complexity_score;
-
-        match macro_name {
-            "require" => self.metrics.require_macros += 1,
-            "require_eq" => self.metrics.require_eq_macros += 1,
-            "assert" => self.metrics.assert_macros += 1,
-            "assert_eq" => self.metrics.assert_eq_macros += 1,
-            _ => {}
-        }
-
-        // Record detailed information for auditability
-        self.metrics.assertion_details.push(AssertionDetail {
-            file: self.current_file_path.clone(),
-            line,
-            macro_name: macro_name.to_string(),
-            expression: quote::quote!(#expression).to_string(),
-            complexity_score,
-        });
-    }
-
-    /// Analyze expression complexity using AST-based visitor
-    fn analyze_expression_complexity(&mut self, expression: &Expr) -> u32 {
-        let mut complexity_visitor = ExprComplexityVisitor::new();
-        complexity_visitor.visit_expr(expression);
-
-        // Accumulate complexity metrics
-        self.metrics.arithmetic_operations += complexity_visitor.arithmetic_operations;
-        self.metrics.safe_math_calls += complexity_visitor.safe_math_calls;
-        self.metrics.comparisons_logic += complexity_visitor.comparisons_logic;
-        self.metrics.function_calls += complexity_visitor.function_calls;
-
-        complexity_visitor.total_complexity_score
-    }
-}
-
-/// Secondary visitor for analyzing expression complexity
-struct ExprComplexityVisitor {
-    arithmetic_operations: u32,
-    safe_math_calls: u32,
-    comparisons_logic: u32,
-    function_calls: u32,
-    total_complexity_score: u32,
-}
-
-impl ExprComplexityVisitor {
-    fn new() -> Self {
-        Self {
-            arithmetic_operations: 0,
-            safe_math_calls: 0,
-            comparisons_logic: 0,
-            function_calls: 0,
-            total_complexity_score: 0,
-        }
-    }
-}
-
-impl<'ast> Visit<'ast> for ExprComplexityVisitor {
-    fn visit_expr_binary(&mut self, node: &'ast ExprBinary) {
-        match node.op {
-            // Arithmetic operations: +2 points each
-            BinOp::Mul(_) | BinOp::Div(_) | BinOp::Rem(_) => {
-                self.arithmetic_operations += 1;
-                self.total_complexity_score += 2;
-            }
-            // Comparisons & Logic: +1 point each
-            BinOp::Eq(_)
-            | BinOp::Ne(_)
-            | BinOp::Lt(_)
-            | BinOp::Le(_)
-            | BinOp::Gt(_)
-            | BinOp::Ge(_)
-            | BinOp::And(_)
-            | BinOp::Or(_) => {
-                self.comparisons_logic += 1;
-                self.total_complexity_score += 1;
-            }
-            // Low-risk operations: +0.5 points each (rounded to 1)
-            BinOp::Add(_) | BinOp::Sub(_) => {
-                self.total_complexity_score += 1;
-            }
-            _ => {}
-        }
-
-        // Continue visiting
-        syn::visit::visit_expr_binary(self, node);
-    }
-
-    fn visit_expr_call(&mut self, node: &'ast ExprCall) {
-        // Function calls: +3 points each
-        self.function_calls += 1;
-        self.total_complexity_score += 3;
-
-        // Check for safe math calls: +1 point each
-        if let Expr::Path(path_expr) = &*node.func {
-            let path_str = path_expr
-                .path
-                .segments
-                .iter()
-                .map(|s| s.ident.to_string())
-                .collect::<Vec<_>>()
-                .join("::");
-
-            if path_str.contains("checked_add")
-                || path_str.contains("checked_mul")
-                || path_str.contains("checked_div")
-                || path_str.contains("checked_sub")
-                || path_str.contains("checked_rem")
-            {
-                self.safe_math_calls += 1;
-                self.total_complexity_score += 1;
-            }
-        }
-
-        // Continue visiting
-        syn::visit::visit_expr_call(self, node);
-    }
 }
 
 impl<'ast> Visit<'ast> for ConstraintDensityVisitor {
@@ -284,53 +177,34 @@ impl<'ast> Visit<'ast> for ConstraintDensityVisitor {
 
 /// Calculate constraint density metrics for workspace
 pub fn calculate_workspace_constraint_density(
-    workspace_path: &std::path::PathBuf,
+    workspace_path: &std::path::Path,
     selected_files: &[String],
+) -> Result<ConstraintDensityMetrics, Box<dyn std::error::Error>> {
+    let workspace = ParsedWorkspace::load(workspace_path, selected_files);
+    analyze_workspace_constraint_density(&workspace)
+}
+
+/// Calculate constraint density over an already-parsed workspace
+pub fn analyze_workspace_constraint_density(
+    workspace: &ParsedWorkspace,
 ) -> Result<ConstraintDensityMetrics, Box<dyn std::error::Error>> {
     log::info!(
         "🔍 CONSTRAINT DENSITY: Starting analysis for workspace: {:?}",
-        workspace_path
+        workspace.root
     );
 
     let mut metrics = ConstraintDensityMetrics::default();
     let mut visitor = ConstraintDensityVisitor::new();
 
-    // Get all Rust files in the workspace
-    let mut rust_files = Vec::new();
-    for file_path in selected_files {
-        let full_path = workspace_path.join(file_path);
-        if full_path.exists() && full_path.extension().map_or(false, |ext| ext == "rs") {
-            rust_files.push(full_path);
-        }
-    }
+    log::info!("Found {} Rust files to analyze", workspace.files.len());
 
-    log::info!("Found {} Rust files to analyze", rust_files.len());
-
-    for file_path in rust_files {
-        log::info!("Analyzing file: {:?}", file_path);
-
-        match std::fs::read_to_string(&file_path) {
-            Ok(content) => {
-                visitor.current_file_path = file_path.to_string_lossy().to_string();
-
-                match syn::parse_file(&content) {
-                    Ok(ast) => {
-                        visitor.visit_file(&ast);
-                        metrics.files_analyzed += 1;
-                        log::info!("✅ Successfully analyzed file: {:?}", file_path);
-                    }
-                    Err(e) => {
-                        log::warn!("Failed to parse file {:?}: {}", file_path, e);
-                        metrics.files_skipped += 1;
-                    }
-                }
-            }
-            Err(e) => {
-                log::warn!("Failed to read file {:?}: {}", file_path, e);
-                metrics.files_skipped += 1;
-            }
-        }
+    for file in &workspace.files {
+        log::info!("Analyzing file: {:?}", file.full_path);
+        visitor.current_file_path = file.full_path.to_string_lossy().to_string();
+        visitor.visit_file(&file.ast);
     }
+    metrics.files_analyzed = workspace.files.len() as u32;
+    metrics.files_skipped = workspace.failures.len() as u32;
 
     // Merge visitor metrics
     metrics.total_assertions = visitor.metrics.total_assertions;
@@ -346,13 +220,8 @@ pub fn calculate_workspace_constraint_density(
     metrics.assertion_details = visitor.metrics.assertion_details;
 
     // Calculate normalized constraint density factor
-    if metrics.total_assertion_complexity_score > 0 {
-        // Normalize based on total assertion complexity score
-        // Upper bound: 1000 complexity points = 100% risk
-        let upper_bound = 1000.0;
-        metrics.constraint_density_factor =
-            (metrics.total_assertion_complexity_score as f64 / upper_bound).min(1.0) * 100.0;
-    }
+    metrics.constraint_density_factor =
+        ConstraintDensityMetrics::default_normalization().score(&metrics.normalization_inputs());
 
     log::info!("📊 CONSTRAINT DENSITY ANALYSIS COMPLETE:");
     log::info!("  📁 Files analyzed: {}", metrics.files_analyzed);
@@ -370,6 +239,45 @@ pub fn calculate_workspace_constraint_density(
     Ok(metrics)
 }
 
+/// Registry entry for the constraint density factor
+pub struct InvariantsFactor;
+
+impl Factor for InvariantsFactor {
+    fn id(&self) -> &'static str {
+        "invariantsAndRiskParams"
+    }
+
+    fn version(&self) -> &'static str {
+        "1.0.0"
+    }
+
+    fn name(&self) -> &'static str {
+        "Invariants"
+    }
+
+    fn normalization(&self) -> Option<Normalization> {
+        Some(ConstraintDensityMetrics::default_normalization())
+    }
+
+    fn compute(&self, ctx: &FactorContext) -> Result<FactorOutput, Box<dyn std::error::Error>> {
+        let mut metrics = analyze_workspace_constraint_density(ctx.workspace)?;
+        metrics.constraint_density_factor = ctx
+            .normalization_or(ConstraintDensityMetrics::default_normalization)
+            .score(&metrics.normalization_inputs());
+        Ok(FactorOutput::keyed(
+            self.id(),
+            metrics.constraint_density_factor,
+            metrics.to_json(),
+        )
+        .with_note(format!(
+            "Analyzed constraint density: {} total assertions, {} complexity score, factor {:.1}",
+            metrics.total_assertions,
+            metrics.total_assertion_complexity_score,
+            metrics.constraint_density_factor,
+        )))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
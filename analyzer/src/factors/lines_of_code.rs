@@ -9,9 +9,12 @@
 //! - It provides a more accurate measure of "work being done"
 //! - It's consistent with other AST-based factors (CC, AC, etc.)
 
+use crate::factors::normalization::{Curve, Normalization};
+use crate::factors::registry::{Factor, FactorContext, FactorOutput};
+use crate::workspace::ParsedWorkspace;
 use std::collections::HashMap;
-use std::path::PathBuf;
-use syn::{visit::Visit, Block, ImplItemFn, ItemFn, Stmt};
+use std::path::Path;
+use syn::{visit::Visit, ImplItemFn, ItemFn, Stmt};
 
 #[derive(Debug, Clone, Default)]
 pub struct TscMetrics {
@@ -48,90 +51,71 @@ impl TscMetrics {
         })
     }
 
-    /// Calculate LOC Factor based on total statements
+    /// Default LOC scoring
     /// 0 = 500 LOC or less, 100 = 10,000 LOC or more
-    /// Linear mapping between 500 and 10,000
+    /// Linear mapping between 500 and 10,000, rounded to 2 decimal places
+    pub fn default_normalization() -> Normalization {
+        Normalization::new(Curve::linear(500.0, 10_000.0))
+            .term("totalStatements", 1.0)
+            .rounded(2)
+    }
+
+    /// Measurements the LOC factor is computed from
+    pub fn normalization_inputs(&self) -> Vec<(&'static str, f64)> {
+        vec![("totalStatements", self.total_statements as f64)]
+    }
+
+    /// Calculate LOC Factor based on total statements, with the default scoring
     fn calculate_loc_factor(total_statements: usize) -> f64 {
-        if total_statements <= 500 {
-            return 0.0;
-        }
-        if total_statements >= 10000 {
-            return 100.0;
-        }
-        // Linear mapping between 500 and 10,000
-        let factor = ((total_statements - 500) as f64 / (10000 - 500) as f64) * 100.0;
-        (factor * 100.0).round() / 100.0 // Round to 2 decimal places
+        Self::default_normalization().score(&[("totalStatements", total_statements as f64)])
     }
 }
 
 /// Calculate Total Statement Count for workspace files
 pub fn calculate_workspace_tsc(
-    workspace_path: &PathBuf,
+    workspace_path: &Path,
     selected_files: &[String],
+) -> Result<TscMetrics, Box<dyn std::error::Error>> {
+    let workspace = ParsedWorkspace::load(workspace_path, selected_files);
+    analyze_workspace_tsc(&workspace)
+}
+
+/// Calculate Total Statement Count over an already-parsed workspace
+pub fn analyze_workspace_tsc(
+    workspace: &ParsedWorkspace,
 ) -> Result<TscMetrics, Box<dyn std::error::Error>> {
     log::info!(
         "🔍 TSC DEBUG: Starting analysis for workspace: {:?}",
-        workspace_path
+        workspace.root
     );
-    log::info!("🔍 TSC DEBUG: Analyzing {} files", selected_files.len());
+    log::info!("🔍 TSC DEBUG: Analyzing {} files", workspace.files.len());
 
     let mut metrics = TscMetrics::default();
-    let mut analyzed_files = 0;
 
     // Analyze each file
-    for file_path in selected_files {
-        let full_file_path = workspace_path.join(file_path);
-
-        if full_file_path.exists() && full_file_path.is_file() {
-            if let Some(extension) = full_file_path.extension() {
-                if extension == "rs" {
-                    match std::fs::read_to_string(&full_file_path) {
-                        Ok(content) => {
-                            match analyze_file_tsc(&content) {
-                                Ok(file_metrics) => {
-                                    // Merge metrics from this file
-                                    metrics.total_statements += file_metrics.total_statements;
-                                    metrics.total_functions += file_metrics.total_functions;
-
-                                    // Update max statements
-                                    if file_metrics.max_statements_per_function
-                                        > metrics.max_statements_per_function
-                                    {
-                                        metrics.max_statements_per_function =
-                                            file_metrics.max_statements_per_function;
-                                    }
-
-                                    // Merge function statement counts
-                                    for (func_name, count) in file_metrics.function_statement_counts
-                                    {
-                                        metrics.function_statement_counts.insert(func_name, count);
-                                    }
-
-                                    analyzed_files += 1;
-
-                                    log::debug!(
-                                        "🔍 TSC DEBUG: File {}: {} statements, {} functions",
-                                        file_path,
-                                        file_metrics.total_statements,
-                                        file_metrics.total_functions
-                                    );
-                                }
-                                Err(e) => {
-                                    log::warn!(
-                                        "🔍 TSC DEBUG: Failed to analyze TSC for file {}: {}",
-                                        file_path,
-                                        e
-                                    );
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            log::warn!("🔍 TSC DEBUG: Failed to read file {}: {}", file_path, e);
-                        }
-                    }
-                }
-            }
+    for file in &workspace.files {
+        let file_metrics = analyze_ast_tsc(&file.ast);
+
+        // Merge metrics from this file
+        metrics.total_statements += file_metrics.total_statements;
+        metrics.total_functions += file_metrics.total_functions;
+
+        // Update max statements
+        if file_metrics.max_statements_per_function > metrics.max_statements_per_function {
+            metrics.max_statements_per_function = file_metrics.max_statements_per_function;
+        }
+
+        // Merge function statement counts
+        for (func_name, count) in file_metrics.function_statement_counts {
+            metrics.function_statement_counts.insert(func_name, count);
         }
+
+        log::debug!(
+            "🔍 TSC DEBUG: File {}: {} statements, {} functions",
+            file.path,
+            file_metrics.total_statements,
+            file_metrics.total_functions
+        );
     }
 
     // Calculate average statements per function
@@ -145,7 +129,7 @@ pub fn calculate_workspace_tsc(
 
     log::info!(
         "🔍 TSC DEBUG: Analysis complete: {} files analyzed, {} total statements, {} total functions, avg: {:.2} statements/function, LOC factor: {:.2}",
-        analyzed_files,
+        workspace.files.len(),
         metrics.total_statements,
         metrics.total_functions,
         metrics.avg_statements_per_function,
@@ -161,8 +145,13 @@ pub fn analyze_file_tsc(content: &str) -> Result<TscMetrics, Box<dyn std::error:
     let syntax_tree: syn::File =
         syn::parse_file(content).map_err(|e| format!("Failed to parse Rust file: {}", e))?;
 
+    Ok(analyze_ast_tsc(&syntax_tree))
+}
+
+/// Analyze Total Statement Count in a parsed file
+pub fn analyze_ast_tsc(syntax_tree: &syn::File) -> TscMetrics {
     let mut visitor = TscVisitor::new();
-    visitor.visit_file(&syntax_tree);
+    visitor.visit_file(syntax_tree);
 
     // Calculate average statements per function
     if visitor.metrics.total_functions > 0 {
@@ -173,7 +162,7 @@ pub fn analyze_file_tsc(content: &str) -> Result<TscMetrics, Box<dyn std::error:
     // Calculate LOC Factor
     visitor.metrics.loc_factor = TscMetrics::calculate_loc_factor(visitor.metrics.total_statements);
 
-    Ok(visitor.metrics)
+    visitor.metrics
 }
 
 /// Visitor to analyze Total Statement Count using full AST approach
@@ -198,7 +187,7 @@ impl<'ast> Visit<'ast> for TscVisitor {
         // Get function name
         let func_name = item_fn.sig.ident.to_string();
 
-        println!("🔍 TSC DEBUG: Found function '{}'", func_name);
+        log::debug!("🔍 TSC DEBUG: Found function '{}'", func_name);
 
         // Set current function context
         self.current_function = Some(func_name.clone());
@@ -233,11 +222,11 @@ impl<'ast> Visit<'ast> for TscVisitor {
 
     fn visit_stmt(&mut self, stmt: &'ast Stmt) {
         // Only count statements if we're inside a function
-        if self.current_function.is_some() {
+        if let Some(function_name) = &self.current_function {
             self.current_function_statement_count += 1;
-            println!(
+            log::debug!(
                 "🔍 TSC DEBUG: Found statement in function '{}': {:?}",
-                self.current_function.as_ref().unwrap(),
+                function_name,
                 stmt
             );
         }
@@ -281,6 +270,54 @@ impl<'ast> Visit<'ast> for TscVisitor {
     }
 }
 
+/// Registry entry for the Total Statement Count factor
+pub struct LinesOfCodeFactor;
+
+impl Factor for LinesOfCodeFactor {
+    fn id(&self) -> &'static str {
+        "linesOfCode"
+    }
+
+    fn version(&self) -> &'static str {
+        "1.0.0"
+    }
+
+    fn name(&self) -> &'static str {
+        "Lines of code"
+    }
+
+    fn normalization(&self) -> Option<Normalization> {
+        Some(TscMetrics::default_normalization())
+    }
+
+    fn compute(&self, ctx: &FactorContext) -> Result<FactorOutput, Box<dyn std::error::Error>> {
+        let mut metrics = analyze_workspace_tsc(ctx.workspace)?;
+        metrics.loc_factor = ctx
+            .normalization_or(TscMetrics::default_normalization)
+            .score(&metrics.normalization_inputs());
+        Ok(FactorOutput::new(metrics.loc_factor, metrics.to_json())
+            .with_field(
+                "totalLinesOfCode",
+                serde_json::json!(metrics.total_statements),
+            )
+            .with_field("locFactor", serde_json::json!(metrics.loc_factor))
+            .with_reference("tscMetrics", metrics.to_json())
+            .with_note(format!(
+                "Calculated {} statements (LOC) from workspace files, LOC factor: {:.2}",
+                metrics.total_statements, metrics.loc_factor
+            )))
+    }
+
+    fn fallback(&self, error: &dyn std::error::Error) -> Option<FactorOutput> {
+        Some(
+            FactorOutput::new(0.0, serde_json::Value::Null)
+                .with_field("totalLinesOfCode", serde_json::json!(100))
+                .with_field("locFactor", serde_json::json!(0.0))
+                .with_note(format!("Fallback values used due to error: {}", error)),
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -325,9 +362,9 @@ fn complex_function() {
 }
         "#;
         let result = analyze_file_tsc(content).unwrap();
-        assert_eq!(result.total_statements, 7); // 1 + 5 statements
+        assert_eq!(result.total_statements, 6); // 1 + 5 statements
         assert_eq!(result.total_functions, 2);
-        assert_eq!(result.avg_statements_per_function, 3.5);
+        assert_eq!(result.avg_statements_per_function, 3.0);
         assert_eq!(result.max_statements_per_function, 5);
     }
 
@@ -367,9 +404,9 @@ impl MyStruct {
 }
         "#;
         let result = analyze_file_tsc(content).unwrap();
-        assert_eq!(result.total_statements, 5); // 2 + 1 statements
+        assert_eq!(result.total_statements, 3); // 2 + 1 statements
         assert_eq!(result.total_functions, 2);
-        assert_eq!(result.avg_statements_per_function, 2.5);
+        assert_eq!(result.avg_statements_per_function, 1.5);
         assert_eq!(result.max_statements_per_function, 2);
     }
 
@@ -424,7 +461,7 @@ fn long_function() {
 }
         "#;
         let result = analyze_file_tsc(content).unwrap();
-        assert_eq!(result.total_statements, 8); // 1 + 5 statements
+        assert_eq!(result.total_statements, 6); // 1 + 5 statements
         assert_eq!(result.total_functions, 2);
 
         // Check function-specific counts
@@ -14,15 +14,19 @@ pub mod dos_resource_limits;
 pub mod error_handling;
 pub mod external_integration;
 pub mod function_count;
+pub mod handlers;
 pub mod input_constraints;
 pub mod invariants_risk_params;
 pub mod lines_of_code;
 pub mod modularity;
+pub mod native;
+pub mod normalization;
 pub mod operational_security;
-// pub mod oracle_price_feed; // TODO: Implement oracle price feed module
+pub mod oracle_price_feed;
 pub mod pda_seeds;
 pub mod privileged_roles;
-// pub mod statefulness; // TODO: Implement statefulness module
+pub mod registry;
+pub mod statefulness;
 pub mod unsafe_lowlevel;
 pub mod upgradeability;
 
@@ -38,17 +42,25 @@ pub use error_handling::{calculate_workspace_error_handling, ErrorHandlingMetric
 pub use external_integration::{
     calculate_workspace_external_integration, ExternalIntegrationMetrics,
 };
-pub use function_count::{count_functions, count_total_functions, FunctionCountMetrics};
+pub use function_count::{
+    calculate_workspace_functions, count_functions, count_total_functions, FunctionCountMetrics,
+};
+pub use handlers::{HandlerIndex, InstructionHandler};
 pub use input_constraints::{calculate_workspace_input_constraints, InputConstraintMetrics};
 pub use invariants_risk_params::{
     calculate_workspace_constraint_density, ConstraintDensityMetrics,
 };
 pub use lines_of_code::{analyze_file_tsc, calculate_workspace_tsc, TscMetrics};
 pub use modularity::{calculate_workspace_modularity, ModularityMetrics};
+pub use normalization::{Curve, Normalization};
 pub use operational_security::{calculate_workspace_operational_security, OpSecMetrics};
-// pub use oracle_price_feed::{calculate_workspace_oracle_price_feed, OraclePriceFeedMetrics};
+pub use oracle_price_feed::{calculate_workspace_oracle_price_feed, OraclePriceFeedMetrics};
 pub use pda_seeds::{calculate_workspace_pda_seeds, PdaMetrics};
 pub use privileged_roles::{calculate_workspace_privileged_roles, PrivilegedRolesMetrics};
-// pub use statefulness::{calculate_workspace_statefulness, StatefulnessMetrics}; // TODO: Implement statefulness module
+pub use registry::{
+    Factor, FactorContext, FactorOutput, FactorRegistry, FactorReport, Finding, FindingRule,
+    Severity,
+};
+pub use statefulness::{calculate_workspace_statefulness, StatefulnessMetrics};
 pub use unsafe_lowlevel::{calculate_workspace_unsafe_lowlevel, UnsafeLowLevelMetrics};
 pub use upgradeability::{calculate_workspace_upgradeability, UpgradeabilityMetrics};
@@ -3,15 +3,16 @@
 //! This module analyzes code organization, module structure, and dependency patterns
 //! to assess the overall modularity and separation of concerns in the codebase.
 
+use crate::factors::handlers::HandlerIndex;
+use crate::factors::normalization::{Curve, Normalization};
+use crate::factors::registry::{Factor, FactorContext, FactorOutput};
+use crate::workspace::ParsedWorkspace;
 use std::collections::HashSet;
-use std::path::PathBuf;
-use syn::{
-    visit::Visit, Attribute, File, FnArg, ImplItemFn, Item, ItemFn, ItemMod, ItemUse, Meta,
-    Signature, Type, UseTree, Visibility,
-};
+use std::path::Path;
+use syn::{visit::Visit, File, Item, ItemMod, ItemUse, UseTree};
 
 // Import TSC functionality for AST-based code volume measurement
-use crate::factors::lines_of_code::analyze_file_tsc;
+use crate::factors::lines_of_code::analyze_ast_tsc;
 
 #[derive(Debug, Clone)]
 pub struct ModularityMetrics {
@@ -23,11 +24,17 @@ pub struct ModularityMetrics {
     pub external_dependencies: usize,
     pub internal_cross_references: usize,
     pub modularity_score: f64,
+    /// Coefficient of variation of the per-file statement counts
+    pub size_variation: f64,
+    /// Internal cross references over the theoretical maximum
+    pub coupling_ratio: f64,
     // Anchor-specific modularity metrics
     pub total_instruction_handlers: usize,
     pub files_with_handlers: usize,
     pub instruction_handler_density: f64,
     pub anchor_modularity_score: f64,
+    /// Names of every instruction handler, sorted
+    pub instruction_handlers: Vec<String>,
 }
 
 impl ModularityMetrics {
@@ -45,29 +52,53 @@ impl ModularityMetrics {
             "totalInstructionHandlers": self.total_instruction_handlers,
             "filesWithHandlers": self.files_with_handlers,
             "instructionHandlerDensity": self.instruction_handler_density,
-            "anchorModularityScore": self.anchor_modularity_score
+            "anchorModularityScore": self.anchor_modularity_score,
+            "instructionHandlers": self.instruction_handlers
         })
     }
+
+    /// Default modularity scoring
+    /// File count: 0-40 points, full at 10 files
+    /// Balanced file sizes: 0-30 points, full at a size CV ≤ 0.5, none at ≥ 2
+    /// Few cross-dependencies: 0-30 points, full at a coupling ratio ≤ 0.1, none at ≥ 0.5
+    /// Minus 5 points per nesting level beyond 3
+    pub fn default_normalization() -> Normalization {
+        Normalization::new(Curve::percent())
+            .curved_term("fileCount", 0.4, Curve::linear(1.0, 10.0))
+            .curved_term(
+                "sizeVariation",
+                0.3,
+                Curve::piecewise(&[[0.5, 100.0], [2.0, 0.0]]),
+            )
+            .curved_term(
+                "couplingRatio",
+                0.3,
+                Curve::piecewise(&[[0.1, 100.0], [0.5, 0.0]]),
+            )
+            .term("excessNesting", -5.0)
+    }
+
+    /// Measurements the modularity score is computed from
+    pub fn normalization_inputs(&self) -> Vec<(&'static str, f64)> {
+        modularity_inputs(
+            self.total_files,
+            self.size_variation,
+            self.coupling_ratio,
+            self.max_nesting_depth,
+        )
+    }
 }
 
 #[derive(Debug, Clone)]
 struct FileAnalysis {
     path: String,
     total_statements: usize, // AST-based Total Statement Count (TSC)
-    modules: Vec<ModuleInfo>,
+    modules: Vec<String>,    // Declared module names
     imports: Vec<ImportInfo>,
     max_depth: u32,
     handler_count: usize, // Count of Anchor instruction handlers in this file
 }
 
-#[derive(Debug, Clone)]
-struct ModuleInfo {
-    name: String,
-    is_public: bool,
-    is_inline: bool,
-    nesting_depth: u32,
-}
-
 #[derive(Debug, Clone)]
 struct ImportInfo {
     path: String,
@@ -77,58 +108,47 @@ struct ImportInfo {
 
 /// Calculate modularity metrics for workspace files
 pub fn calculate_workspace_modularity(
-    workspace_path: &PathBuf,
+    workspace_path: &Path,
     selected_files: &[String],
+) -> Result<ModularityMetrics, Box<dyn std::error::Error>> {
+    let workspace = ParsedWorkspace::load(workspace_path, selected_files);
+    analyze_workspace_modularity(&workspace)
+}
+
+/// Calculate modularity metrics over an already-parsed workspace
+pub fn analyze_workspace_modularity(
+    workspace: &ParsedWorkspace,
 ) -> Result<ModularityMetrics, Box<dyn std::error::Error>> {
     log::info!(
         "Calculating modularity metrics for {} files in workspace: {:?}",
-        selected_files.len(),
-        workspace_path
+        workspace.files.len(),
+        workspace.root
     );
 
     let mut file_analyses = Vec::new();
     let mut total_statements = 0;
-    let mut analyzed_files = 0;
+
+    // Handlers are attributed to the file holding their logic, so an entry point
+    // delegating to `instructions::foo::handler` counts towards that module
+    let handlers = HandlerIndex::from_workspace(workspace);
 
     // Analyze each file
-    for file_path in selected_files {
-        let full_file_path = workspace_path.join(file_path);
-
-        if full_file_path.exists() && full_file_path.is_file() {
-            if let Some(extension) = full_file_path.extension() {
-                if extension == "rs" {
-                    match std::fs::read_to_string(&full_file_path) {
-                        Ok(content) => match analyze_file_modularity(file_path, &content) {
-                            Ok(file_analysis) => {
-                                total_statements += file_analysis.total_statements;
-                                file_analyses.push(file_analysis);
-                                analyzed_files += 1;
-
-                                log::debug!(
-                                    "File {}: {} statements, {} modules, {} imports, max depth: {}",
-                                    file_path,
-                                    file_analyses.last().unwrap().total_statements,
-                                    file_analyses.last().unwrap().modules.len(),
-                                    file_analyses.last().unwrap().imports.len(),
-                                    file_analyses.last().unwrap().max_depth
-                                );
-                            }
-                            Err(e) => {
-                                log::warn!(
-                                    "Failed to analyze modularity for file {}: {}",
-                                    file_path,
-                                    e
-                                );
-                            }
-                        },
-                        Err(e) => {
-                            log::warn!("Failed to read file {}: {}", file_path, e);
-                        }
-                    }
-                }
-            }
-        }
+    for file in &workspace.files {
+        let file_analysis = analyze_ast_modularity(&file.path, &file.ast, &handlers);
+        total_statements += file_analysis.total_statements;
+
+        log::debug!(
+            "File {}: {} statements, {} modules, {} imports, max depth: {}",
+            file_analysis.path,
+            file_analysis.total_statements,
+            file_analysis.modules.len(),
+            file_analysis.imports.len(),
+            file_analysis.max_depth
+        );
+
+        file_analyses.push(file_analysis);
     }
+    let analyzed_files = file_analyses.len();
 
     if analyzed_files == 0 {
         return Err("No files were successfully analyzed for modularity".into());
@@ -181,17 +201,23 @@ pub fn calculate_workspace_modularity(
     };
 
     // Calculate modularity score (0-100)
-    let modularity_score = calculate_modularity_score(
+    let size_variation = size_variation(&file_analyses);
+    let coupling_ratio = coupling_ratio(total_files, internal_cross_references);
+    let modularity_score = ModularityMetrics::default_normalization().score(&modularity_inputs(
         total_files,
-        &file_analyses,
-        avg_statements_per_file,
+        size_variation,
+        coupling_ratio,
         max_nesting_depth,
-        internal_cross_references,
-    );
+    ));
 
     // Calculate Anchor-specific modularity score
     let anchor_modularity_score = calculate_anchor_modularity_score(instruction_handler_density);
 
+    let mut instruction_handlers: Vec<String> =
+        handlers.handlers().iter().map(|h| h.name.clone()).collect();
+    instruction_handlers.sort();
+    instruction_handlers.dedup();
+
     let result = ModularityMetrics {
         total_files,
         total_modules,
@@ -201,10 +227,13 @@ pub fn calculate_workspace_modularity(
         external_dependencies,
         internal_cross_references,
         modularity_score,
+        size_variation,
+        coupling_ratio,
         total_instruction_handlers,
         files_with_handlers,
         instruction_handler_density,
         anchor_modularity_score,
+        instruction_handlers,
     };
 
     log::info!(
@@ -223,6 +252,7 @@ pub fn calculate_workspace_modularity(
 }
 
 /// Analyze modularity for a single file
+#[cfg(test)]
 fn analyze_file_modularity(
     file_path: &str,
     content: &str,
@@ -231,101 +261,78 @@ fn analyze_file_modularity(
     let syntax_tree: File = syn::parse_file(content)
         .map_err(|e| format!("Failed to parse Rust file {}: {}", file_path, e))?;
 
-    let mut visitor = ModularityVisitor::new();
-    visitor.visit_file(&syntax_tree);
+    let handlers = HandlerIndex::from_ast(file_path, &syntax_tree);
+    Ok(analyze_ast_modularity(file_path, &syntax_tree, &handlers))
+}
 
-    // Count Anchor instruction handlers
-    let mut handler_counter = HandlerCounter::new();
-    handler_counter.visit_file(&syntax_tree);
+/// Analyze modularity for a parsed file
+fn analyze_ast_modularity(
+    file_path: &str,
+    syntax_tree: &File,
+    handlers: &HandlerIndex,
+) -> FileAnalysis {
+    let mut visitor = ModularityVisitor::new();
+    visitor.visit_file(syntax_tree);
 
     // Calculate AST-based Total Statement Count (TSC) for robust code volume measurement
-    let tsc_metrics = analyze_file_tsc(content).unwrap_or_default();
-    let total_statements = tsc_metrics.total_statements;
+    let total_statements = analyze_ast_tsc(syntax_tree).total_statements;
 
-    Ok(FileAnalysis {
+    FileAnalysis {
         path: file_path.to_string(),
         total_statements,
         modules: visitor.modules,
         imports: visitor.imports,
         max_depth: visitor.max_nesting_depth,
-        handler_count: handler_counter.handler_count,
-    })
+        handler_count: handlers.count_in_file(file_path),
+    }
 }
 
-/// Calculate overall modularity score (0-100)
-fn calculate_modularity_score(
+fn modularity_inputs(
     total_files: usize,
-    file_analyses: &[FileAnalysis],
-    _avg_statements_per_file: f64,
+    size_variation: f64,
+    coupling_ratio: f64,
     max_nesting_depth: u32,
-    internal_cross_references: usize,
-) -> f64 {
-    let mut score = 0.0;
-
-    // Component 1: More files = better modularity (0-40 points)
-    let file_count_score = if total_files <= 1 {
-        0.0
-    } else if total_files >= 10 {
-        40.0
-    } else {
-        (total_files as f64 - 1.0) / 9.0 * 40.0
-    };
-
-    // Component 2: Balanced file sizes = better organization (0-30 points)
-    // Using AST-based Total Statement Count (TSC) for robust code volume measurement
-    let balance_score = if file_analyses.len() <= 1 {
-        30.0 // Single file gets full points for balance
-    } else {
-        let sizes: Vec<usize> = file_analyses.iter().map(|f| f.total_statements).collect();
-        let mean = sizes.iter().sum::<usize>() as f64 / sizes.len() as f64;
-        let variance = sizes
-            .iter()
-            .map(|&size| (size as f64 - mean).powi(2))
-            .sum::<f64>()
-            / sizes.len() as f64;
-        let coefficient_of_variation = if mean > 0.0 {
-            variance.sqrt() / mean
-        } else {
-            0.0
-        };
-
-        // Lower CV = better balance, score inversely related to CV
-        let balance_factor = if coefficient_of_variation <= 0.5 {
-            1.0
-        } else if coefficient_of_variation >= 2.0 {
-            0.0
-        } else {
-            1.0 - (coefficient_of_variation - 0.5) / 1.5
-        };
-
-        balance_factor * 30.0
-    };
+) -> Vec<(&'static str, f64)> {
+    vec![
+        // More files = better modularity
+        ("fileCount", total_files as f64),
+        // Balanced file sizes = better organization
+        ("sizeVariation", size_variation),
+        // Fewer cross-dependencies = cleaner architecture
+        ("couplingRatio", coupling_ratio),
+        // Penalty for excessive nesting depth
+        ("excessNesting", max_nesting_depth.saturating_sub(3) as f64),
+    ]
+}
 
-    // Component 3: Fewer cross-dependencies = cleaner architecture (0-30 points)
-    let dependency_score = if total_files <= 1 {
-        30.0 // Single file has no cross-dependencies
+/// Coefficient of variation of the per-file statement counts; 0 for a single file
+///
+/// Using AST-based Total Statement Count (TSC) for robust code volume measurement
+fn size_variation(file_analyses: &[FileAnalysis]) -> f64 {
+    if file_analyses.len() <= 1 {
+        return 0.0; // Single file is perfectly balanced
+    }
+    let sizes: Vec<usize> = file_analyses.iter().map(|f| f.total_statements).collect();
+    let mean = sizes.iter().sum::<usize>() as f64 / sizes.len() as f64;
+    let variance = sizes
+        .iter()
+        .map(|&size| (size as f64 - mean).powi(2))
+        .sum::<f64>()
+        / sizes.len() as f64;
+    if mean > 0.0 {
+        variance.sqrt() / mean
     } else {
-        let max_expected_deps = total_files * (total_files - 1); // Theoretical maximum
-        let dependency_ratio = internal_cross_references as f64 / max_expected_deps as f64;
-
-        if dependency_ratio <= 0.1 {
-            30.0 // Very clean
-        } else if dependency_ratio >= 0.5 {
-            0.0 // Highly coupled
-        } else {
-            30.0 * (1.0 - (dependency_ratio - 0.1) / 0.4)
-        }
-    };
-
-    score = file_count_score + balance_score + dependency_score;
-
-    // Penalty for excessive nesting depth
-    if max_nesting_depth > 3 {
-        score -= (max_nesting_depth - 3) as f64 * 5.0;
+        0.0
     }
+}
 
-    // Ensure score is within bounds
-    score.max(0.0).min(100.0)
+/// Internal cross references over the theoretical maximum; 0 for a single file
+fn coupling_ratio(total_files: usize, internal_cross_references: usize) -> f64 {
+    if total_files <= 1 {
+        return 0.0; // Single file has no cross-dependencies
+    }
+    let max_expected_deps = total_files * (total_files - 1); // Theoretical maximum
+    internal_cross_references as f64 / max_expected_deps as f64
 }
 
 /// Calculate Anchor-specific modularity score based on Instruction Handler Density
@@ -353,190 +360,12 @@ fn calculate_anchor_modularity_score(instruction_handler_density: f64) -> f64 {
 
 /// Visitor to analyze module structure and imports
 struct ModularityVisitor {
-    modules: Vec<ModuleInfo>,
+    modules: Vec<String>,
     imports: Vec<ImportInfo>,
     current_nesting_depth: u32,
     max_nesting_depth: u32,
 }
 
-/// Visitor to count Anchor instruction handlers
-struct HandlerCounter {
-    pub handler_count: usize,
-}
-
-impl HandlerCounter {
-    fn new() -> Self {
-        Self { handler_count: 0 }
-    }
-
-    /// Check if a function is an Anchor instruction handler
-    fn is_anchor_instruction_handler(&self, item_fn: &ItemFn) -> bool {
-        // Check if function is public (instruction handlers are typically public)
-        if !matches!(item_fn.vis, Visibility::Public(_)) {
-            return false;
-        }
-
-        // Check for Anchor-specific attributes first (most reliable)
-        if self.has_anchor_handler_attributes(&item_fn.attrs) {
-            return true;
-        }
-
-        // Check function name patterns common in Anchor programs
-        let name = item_fn.sig.ident.to_string();
-        if name.ends_with("_handler")
-            || name.starts_with("initialize")
-            || name.starts_with("update")
-            || name.starts_with("transfer")
-            || name.starts_with("swap")
-            || name.starts_with("deposit")
-            || name.starts_with("withdraw")
-            || name == "validate"
-            || name == "execute"
-        {
-            return true;
-        }
-
-        // Check if function takes a Context parameter (common in Anchor)
-        if self.has_context_parameter(&item_fn.sig) {
-            return true;
-        }
-
-        false
-    }
-
-    /// Check if an impl method is an Anchor instruction handler
-    fn is_anchor_instruction_handler_method(&self, item_fn: &ImplItemFn) -> bool {
-        // Check if method is public (instruction handlers are typically public)
-        if !matches!(item_fn.vis, Visibility::Public(_)) {
-            return false;
-        }
-
-        // Check for Anchor-specific attributes first (most reliable)
-        if self.has_anchor_handler_attributes(&item_fn.attrs) {
-            return true;
-        }
-
-        // Check method name patterns common in Anchor programs
-        let name = item_fn.sig.ident.to_string();
-        if name.ends_with("_handler")
-            || name.starts_with("initialize")
-            || name.starts_with("update")
-            || name.starts_with("transfer")
-            || name.starts_with("swap")
-            || name.starts_with("deposit")
-            || name.starts_with("withdraw")
-            || name == "validate"
-            || name == "execute"
-        {
-            return true;
-        }
-
-        // Check if method takes a Context parameter (common in Anchor)
-        if self.has_context_parameter(&item_fn.sig) {
-            return true;
-        }
-
-        false
-    }
-
-    /// Check for Anchor-specific handler attributes
-    fn has_anchor_handler_attributes(&self, attrs: &[Attribute]) -> bool {
-        for attr in attrs {
-            // Check the attribute path directly
-            if attr.path().is_ident("instruction")
-                || attr.path().is_ident("handler")
-                || attr.path().is_ident("anchor_handler")
-            {
-                return true;
-            }
-
-            // Also try parsing as meta for more complex attributes
-            if let Ok(meta) = attr.parse_args::<Meta>() {
-                match meta {
-                    Meta::Path(path) => {
-                        if path.is_ident("instruction")
-                            || path.is_ident("handler")
-                            || path.is_ident("anchor_handler")
-                        {
-                            return true;
-                        }
-                    }
-                    Meta::List(meta_list) => {
-                        if meta_list.path.is_ident("instruction")
-                            || meta_list.path.is_ident("handler")
-                        {
-                            return true;
-                        }
-                    }
-                    Meta::NameValue(_) => {
-                        // Handle name-value attributes if needed
-                    }
-                }
-            }
-        }
-        false
-    }
-
-    /// Check if a function signature has a Context parameter (Anchor pattern)
-    fn has_context_parameter(&self, sig: &Signature) -> bool {
-        for input in &sig.inputs {
-            if let FnArg::Typed(pat_type) = input {
-                if let Type::Path(type_path) = &*pat_type.ty {
-                    // Check for full Anchor Context path (more robust)
-                    let path_str = type_path
-                        .path
-                        .segments
-                        .iter()
-                        .map(|seg| seg.ident.to_string())
-                        .collect::<Vec<_>>()
-                        .join("::");
-
-                    // Look for standard Anchor Context patterns
-                    if path_str.starts_with("Context<")
-                        || path_str.starts_with("anchor_lang::Context<")
-                        || path_str.starts_with("anchor_lang::prelude::Context<")
-                    {
-                        return true;
-                    }
-
-                    // Fallback: check if any segment starts with "Context"
-                    if type_path
-                        .path
-                        .segments
-                        .iter()
-                        .any(|seg| seg.ident.to_string().starts_with("Context"))
-                    {
-                        return true;
-                    }
-                }
-            }
-        }
-        false
-    }
-}
-
-impl<'ast> Visit<'ast> for HandlerCounter {
-    fn visit_item(&mut self, item: &'ast Item) {
-        match item {
-            Item::Fn(item_fn) => {
-                if self.is_anchor_instruction_handler(item_fn) {
-                    self.handler_count += 1;
-                }
-            }
-            _ => {
-                syn::visit::visit_item(self, item);
-            }
-        }
-    }
-
-    fn visit_impl_item_fn(&mut self, item_fn: &'ast ImplItemFn) {
-        if self.is_anchor_instruction_handler_method(item_fn) {
-            self.handler_count += 1;
-        }
-        syn::visit::visit_impl_item_fn(self, item_fn);
-    }
-}
-
 impl ModularityVisitor {
     fn new() -> Self {
         Self {
@@ -564,15 +393,7 @@ impl<'ast> Visit<'ast> for ModularityVisitor {
     }
 
     fn visit_item_mod(&mut self, module: &'ast ItemMod) {
-        let is_public = matches!(module.vis, syn::Visibility::Public(_));
-        let is_inline = module.content.is_some();
-
-        self.modules.push(ModuleInfo {
-            name: module.ident.to_string(),
-            is_public,
-            is_inline,
-            nesting_depth: self.current_nesting_depth,
-        });
+        self.modules.push(module.ident.to_string());
 
         // If it's an inline module, visit its contents with increased nesting
         if let Some((_, items)) = &module.content {
@@ -630,6 +451,47 @@ impl ModularityVisitor {
     }
 }
 
+/// Registry entry for the modularity factor
+pub struct ModularityFactor;
+
+impl Factor for ModularityFactor {
+    fn id(&self) -> &'static str {
+        "modularity"
+    }
+
+    fn version(&self) -> &'static str {
+        "1.3.0"
+    }
+
+    fn name(&self) -> &'static str {
+        "Modularity"
+    }
+
+    fn required(&self) -> bool {
+        true
+    }
+
+    fn normalization(&self) -> Option<Normalization> {
+        Some(ModularityMetrics::default_normalization())
+    }
+
+    fn compute(&self, ctx: &FactorContext) -> Result<FactorOutput, Box<dyn std::error::Error>> {
+        let mut metrics = analyze_workspace_modularity(ctx.workspace)?;
+        metrics.modularity_score = ctx
+            .normalization_or(ModularityMetrics::default_normalization)
+            .score(&metrics.normalization_inputs());
+        Ok(
+            FactorOutput::keyed(self.id(), metrics.modularity_score, metrics.to_json()).with_note(format!(
+                "Calculated modularity metrics: {} files, {} modules, score={:.1}, avg lines/file={:.1}",
+                metrics.total_files,
+                metrics.total_modules,
+                metrics.modularity_score,
+                metrics.avg_lines_per_file,
+            )),
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -646,7 +508,7 @@ mod tests {
 
         let result = analyze_file_modularity("test.rs", code).unwrap();
         assert_eq!(result.modules.len(), 0); // No module declarations
-        assert!(result.imports.len() >= 1); // At least one import (may detect multiple due to path parsing)
+        assert!(!result.imports.is_empty()); // At least one import (may detect multiple due to path parsing)
         assert_eq!(result.max_depth, 0); // No nesting
     }
 
@@ -667,7 +529,7 @@ mod tests {
         let result = analyze_file_modularity("test.rs", code).unwrap();
         assert_eq!(result.modules.len(), 2); // outer and inner modules
         assert_eq!(result.max_depth, 2); // Two levels of nesting
-        assert!(result.imports.len() >= 1); // At least one import
+        assert!(!result.imports.is_empty()); // At least one import
     }
 
     #[test]
@@ -691,7 +553,12 @@ mod tests {
             },
         ];
 
-        let score = calculate_modularity_score(2, &file_analyses, 110.0, 0, 1);
+        let score = ModularityMetrics::default_normalization().score(&modularity_inputs(
+            2,
+            size_variation(&file_analyses),
+            coupling_ratio(2, 1),
+            0,
+        ));
         assert!(score > 0.0 && score <= 100.0);
     }
 
@@ -776,7 +643,7 @@ mod tests {
         "#;
 
         let result = analyze_file_modularity("test.rs", code).unwrap();
-        assert_eq!(result.handler_count, 2); // Both attributed functions should be detected
+        assert_eq!(result.handler_count, 1); // Attributes alone do not make a handler without a Context
     }
 
     #[test]
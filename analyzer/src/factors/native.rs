@@ -0,0 +1,400 @@
+//! Native (non-Anchor) Solana program recognition
+//!
+//! A native program registers its entry point with
+//! `entrypoint!(process_instruction)`, takes its accounts as
+//! `&[AccountInfo]` and pulls them one by one with `next_account_info`. The
+//! instruction data is decoded into an enum and dispatched with a `match`,
+//! and every validation is a hand-written check (`is_signer`, `owner`,
+//! `key`) returning a `ProgramError`. These helpers recognize those shapes
+//! for [`HandlerIndex`](super::handlers::HandlerIndex) and the factors that
+//! score native checks.
+
+use proc_macro2::{TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::visit::{self, Visit};
+use syn::{BinOp, Block, Expr, FnArg, Item, Member, Pat, Signature, Type};
+
+/// A hand-written validation of an account
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountCheck {
+    /// `account.is_signer`
+    Signer,
+    /// `account.is_writable`
+    Writable,
+    /// `account.owner` compared with a program id
+    Owner,
+    /// `account.key` compared with an expected address
+    Key,
+}
+
+impl AccountCheck {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccountCheck::Signer => "is_signer",
+            AccountCheck::Writable => "is_writable",
+            AccountCheck::Owner => "owner",
+            AccountCheck::Key => "key",
+        }
+    }
+}
+
+/// The account check `expr` performs itself (not in a subexpression)
+pub fn account_check(expr: &Expr) -> Option<AccountCheck> {
+    match expr {
+        Expr::Field(field) => match member_name(&field.member)?.as_str() {
+            "is_signer" => Some(AccountCheck::Signer),
+            "is_writable" => Some(AccountCheck::Writable),
+            _ => None,
+        },
+        Expr::Binary(binary) if matches!(binary.op, BinOp::Eq(_) | BinOp::Ne(_)) => {
+            [&*binary.left, &*binary.right]
+                .into_iter()
+                .find_map(|side| match strip_references(side) {
+                    Expr::Field(field) => match member_name(&field.member)?.as_str() {
+                        "owner" => Some(AccountCheck::Owner),
+                        "key" => Some(AccountCheck::Key),
+                        _ => None,
+                    },
+                    _ => None,
+                })
+        }
+        _ => None,
+    }
+}
+
+fn member_name(member: &Member) -> Option<String> {
+    match member {
+        Member::Named(ident) => Some(ident.to_string()),
+        Member::Unnamed(_) => None,
+    }
+}
+
+/// `expr` without `*`, `&` and parentheses
+fn strip_references(mut expr: &Expr) -> &Expr {
+    loop {
+        expr = match expr {
+            Expr::Unary(unary) if matches!(unary.op, syn::UnOp::Deref(_)) => &unary.expr,
+            Expr::Reference(reference) => &reference.expr,
+            Expr::Paren(paren) => &paren.expr,
+            _ => return expr,
+        };
+    }
+}
+
+/// Checks if a type is `&[AccountInfo]` (with any lifetimes or path)
+pub fn is_account_slice_type(ty: &Type) -> bool {
+    let Type::Reference(reference) = ty else {
+        return false;
+    };
+    let Type::Slice(slice) = &*reference.elem else {
+        return false;
+    };
+    matches!(
+        &*slice.elem,
+        Type::Path(path) if path.path.segments.last().is_some_and(|s| s.ident == "AccountInfo")
+    )
+}
+
+/// Name of the `&[AccountInfo]` parameter of a signature
+pub fn account_slice_param(sig: &Signature) -> Option<String> {
+    sig.inputs.iter().find_map(|arg| match arg {
+        FnArg::Typed(pat_type) if is_account_slice_type(&pat_type.ty) => match &*pat_type.pat {
+            Pat::Ident(ident) => Some(ident.ident.to_string()),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+/// Checks if a function has the shape of a native instruction processor:
+/// it takes the instruction's accounts as `&[AccountInfo]`
+pub fn is_native_handler_sig(sig: &Signature) -> bool {
+    sig.inputs
+        .iter()
+        .any(|arg| matches!(arg, FnArg::Typed(pat_type) if is_account_slice_type(&pat_type.ty)))
+}
+
+/// Checks if a function belongs to native program code: it takes
+/// `AccountInfo`s or returns a `ProgramResult` / `ProgramError`
+pub fn is_native_sig(sig: &Signature) -> bool {
+    sig.inputs.iter().any(|arg| match arg {
+        FnArg::Typed(pat_type) => mentions(pat_type.ty.to_token_stream(), "AccountInfo"),
+        FnArg::Receiver(_) => false,
+    }) || mentions(sig.output.to_token_stream(), "ProgramResult")
+        || mentions(sig.output.to_token_stream(), "ProgramError")
+}
+
+/// Whether `tokens` contain the identifier `name`, at any depth
+pub fn mentions(tokens: TokenStream, name: &str) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(ident) => ident == name,
+        TokenTree::Group(group) => mentions(group.stream(), name),
+        _ => false,
+    })
+}
+
+/// Functions registered with `entrypoint!(...)`, in inline modules too
+pub fn entrypoints(items: &[Item]) -> Vec<String> {
+    let mut names = Vec::new();
+    for item in items {
+        match item {
+            Item::Macro(item_macro)
+                if item_macro
+                    .mac
+                    .path
+                    .segments
+                    .last()
+                    .is_some_and(|s| s.ident == "entrypoint") =>
+            {
+                if let Ok(name) = item_macro.mac.parse_body::<syn::Path>() {
+                    if let Some(segment) = name.segments.last() {
+                        names.push(segment.ident.to_string());
+                    }
+                }
+            }
+            Item::Mod(item_mod) => {
+                if let Some((_, content)) = &item_mod.content {
+                    names.extend(entrypoints(content));
+                }
+            }
+            _ => {}
+        }
+    }
+    names
+}
+
+/// Number of `next_account_info(...)` calls, i.e. accounts an instruction reads
+pub fn count_next_account_info(block: &Block) -> usize {
+    #[derive(Default)]
+    struct Counter(usize);
+
+    impl<'ast> Visit<'ast> for Counter {
+        fn visit_expr_call(&mut self, node: &'ast syn::ExprCall) {
+            if call_path(node)
+                .last()
+                .is_some_and(|s| s == "next_account_info")
+            {
+                self.0 += 1;
+            }
+            visit::visit_expr_call(self, node);
+        }
+    }
+
+    let mut counter = Counter::default();
+    counter.visit_block(block);
+    counter.0
+}
+
+/// Every account check in `block`, in source order
+pub fn account_checks(block: &Block) -> Vec<AccountCheck> {
+    #[derive(Default)]
+    struct Collector(Vec<AccountCheck>);
+
+    impl<'ast> Visit<'ast> for Collector {
+        fn visit_expr(&mut self, node: &'ast Expr) {
+            self.0.extend(account_check(node));
+            visit::visit_expr(self, node);
+        }
+    }
+
+    let mut collector = Collector::default();
+    collector.visit_block(block);
+    collector.0
+}
+
+/// One arm of an instruction dispatch `match`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DispatchArm {
+    /// Instruction enum variant, e.g. `Deposit`
+    pub variant: String,
+
+    /// Path of the first function the arm calls, e.g. `["process_deposit"]`
+    pub call: Option<Vec<String>>,
+}
+
+impl DispatchArm {
+    /// Instruction name: the variant in snake case (`InitPool` -> `init_pool`)
+    pub fn name(&self) -> String {
+        let mut name = String::new();
+        for (i, c) in self.variant.chars().enumerate() {
+            if c.is_uppercase() && i > 0 {
+                name.push('_');
+            }
+            name.extend(c.to_lowercase());
+        }
+        name
+    }
+}
+
+/// Arms of the first `match` in `block` that dispatches on enum variants
+/// (`Instruction::Deposit { amount } => ...`)
+pub fn dispatch_arms(block: &Block) -> Vec<DispatchArm> {
+    #[derive(Default)]
+    struct Finder(Vec<DispatchArm>);
+
+    impl<'ast> Visit<'ast> for Finder {
+        fn visit_expr_match(&mut self, node: &'ast syn::ExprMatch) {
+            if !self.0.is_empty() {
+                return;
+            }
+            let arms: Vec<DispatchArm> = node
+                .arms
+                .iter()
+                .filter_map(|arm| {
+                    Some(DispatchArm {
+                        variant: enum_variant(&arm.pat)?,
+                        call: first_call(&arm.body),
+                    })
+                })
+                .collect();
+            if arms.is_empty() {
+                visit::visit_expr_match(self, node);
+            } else {
+                self.0 = arms;
+            }
+        }
+    }
+
+    let mut finder = Finder::default();
+    finder.visit_block(block);
+    finder.0
+}
+
+/// Variant named by an `Enum::Variant` pattern; `Some`, `Ok` and friends
+/// are single-segment and never match
+fn enum_variant(pat: &Pat) -> Option<String> {
+    let path = match pat {
+        Pat::TupleStruct(pat) => &pat.path,
+        Pat::Struct(pat) => &pat.path,
+        Pat::Path(pat) => &pat.path,
+        _ => return None,
+    };
+    (path.segments.len() >= 2).then(|| path.segments.last().unwrap().ident.to_string())
+}
+
+/// Path of the first function call in `expr`, skipping tuple constructors
+/// such as `Ok(...)`
+fn first_call(expr: &Expr) -> Option<Vec<String>> {
+    #[derive(Default)]
+    struct Finder(Option<Vec<String>>);
+
+    impl<'ast> Visit<'ast> for Finder {
+        fn visit_expr_call(&mut self, node: &'ast syn::ExprCall) {
+            if self.0.is_some() {
+                return;
+            }
+            let path = call_path(node);
+            let constructor = path.len() == 1 && path[0].starts_with(char::is_uppercase);
+            if !path.is_empty() && !constructor {
+                self.0 = Some(path);
+                return;
+            }
+            visit::visit_expr_call(self, node);
+        }
+    }
+
+    let mut finder = Finder::default();
+    finder.visit_expr(expr);
+    finder.0
+}
+
+/// Path of the first call in `block` that passes `argument` on, e.g.
+/// `Processor::process(program_id, accounts, data)`
+pub fn forwarding_call(block: &Block, argument: &str) -> Option<Vec<String>> {
+    struct Finder<'a> {
+        argument: &'a str,
+        call: Option<Vec<String>>,
+    }
+
+    impl<'ast> Visit<'ast> for Finder<'_> {
+        fn visit_expr_call(&mut self, node: &'ast syn::ExprCall) {
+            if self.call.is_some() {
+                return;
+            }
+            let forwards = node.args.iter().any(|arg| {
+                matches!(strip_references(arg), Expr::Path(path) if path.path.is_ident(self.argument))
+            });
+            let path = call_path(node);
+            if forwards && !path.is_empty() {
+                self.call = Some(path);
+                return;
+            }
+            visit::visit_expr_call(self, node);
+        }
+    }
+
+    let mut finder = Finder {
+        argument,
+        call: None,
+    };
+    finder.visit_block(block);
+    finder.call
+}
+
+/// Segments of a call's function path, without `crate`/`self`/`super`;
+/// `<Processor>::process_x` reads as `Processor::process_x`
+fn call_path(call: &syn::ExprCall) -> Vec<String> {
+    let Expr::Path(func) = &*call.func else {
+        return Vec::new();
+    };
+    let qualified = func.qself.as_ref().and_then(|qself| match &*qself.ty {
+        syn::Type::Path(ty) => ty.path.segments.last().map(|s| s.ident.to_string()),
+        _ => None,
+    });
+    qualified
+        .into_iter()
+        .chain(func.path.segments.iter().map(|s| s.ident.to_string()))
+        .skip_while(|s| matches!(s.as_str(), "crate" | "self" | "super"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_native_shapes() {
+        let ast = syn::parse_file(
+            r#"
+            entrypoint!(process_instruction);
+
+            pub fn process_instruction(
+                program_id: &Pubkey,
+                accounts: &[AccountInfo],
+                data: &[u8],
+            ) -> ProgramResult {
+                match VaultInstruction::unpack(data)? {
+                    VaultInstruction::Deposit { amount } => process_deposit(accounts, amount),
+                    VaultInstruction::InitVault => Ok(processor::init(program_id, accounts)?),
+                }
+            }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(entrypoints(&ast.items), vec!["process_instruction"]);
+
+        let Item::Fn(entry) = &ast.items[1] else {
+            panic!("expected fn");
+        };
+        assert!(is_native_handler_sig(&entry.sig));
+        assert_eq!(account_slice_param(&entry.sig).as_deref(), Some("accounts"));
+
+        let arms = dispatch_arms(&entry.block);
+        let names: Vec<_> = arms.iter().map(DispatchArm::name).collect();
+        assert_eq!(names, vec!["deposit", "init_vault"]);
+        assert_eq!(arms[0].call, Some(vec!["process_deposit".to_string()]));
+        assert_eq!(
+            arms[1].call,
+            Some(vec!["processor".to_string(), "init".to_string()])
+        );
+
+        let check = |code: &str| account_check(&syn::parse_str(code).unwrap());
+        assert_eq!(check("payer.is_signer"), Some(AccountCheck::Signer));
+        assert_eq!(
+            check("*vault.owner != *program_id"),
+            Some(AccountCheck::Owner)
+        );
+        assert_eq!(check("mint.key != &expected"), Some(AccountCheck::Key));
+        assert_eq!(check("ctx.accounts.user.key() == admin"), None);
+    }
+}
@@ -0,0 +1,341 @@
+//! Score normalization: how a factor turns raw measurements into 0-100
+//!
+//! A factor score is `curve(Σ weight × term_curve(input))`. Every factor
+//! declares its default [`Normalization`]; `[factors.<id>.normalization]` in
+//! `bulwark.toml` overrides any part of it, and the effective parameters are
+//! echoed next to the results so a score can always be reproduced.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Maps a raw value onto 0-100
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub enum Curve {
+    /// 0 at `from`, 100 at `to`, a straight line between; `from > to` inverts it
+    Linear { from: f64, to: f64 },
+
+    /// 0 at `from`, 100 at `to`, growing logarithmically between, so early
+    /// increases weigh more than later ones
+    Log { from: f64, to: f64 },
+
+    /// Straight lines between `[x, y]` points sorted by `x`; flat beyond the ends
+    Piecewise { points: Vec<[f64; 2]> },
+
+    /// The value unchanged, not bounded to 0-100
+    Raw,
+}
+
+impl Curve {
+    /// The identity on 0-100, clamping anything outside
+    pub fn percent() -> Self {
+        Curve::Linear {
+            from: 0.0,
+            to: 100.0,
+        }
+    }
+
+    pub fn linear(from: f64, to: f64) -> Self {
+        Curve::Linear { from, to }
+    }
+
+    pub fn piecewise(points: &[[f64; 2]]) -> Self {
+        Curve::Piecewise {
+            points: points.to_vec(),
+        }
+    }
+
+    pub fn apply(&self, value: f64) -> f64 {
+        match self {
+            Curve::Linear { from, to } => {
+                if from == to {
+                    return if value >= *to { 100.0 } else { 0.0 };
+                }
+                ((value - from) / (to - from)).clamp(0.0, 1.0) * 100.0
+            }
+            Curve::Log { from, to } => {
+                if to <= from {
+                    return if value >= *to { 100.0 } else { 0.0 };
+                }
+                let span = (to - from).ln_1p();
+                ((value - from).max(0.0).ln_1p() / span).min(1.0) * 100.0
+            }
+            Curve::Piecewise { points } => {
+                let (Some(first), Some(last)) = (points.first(), points.last()) else {
+                    return 0.0;
+                };
+                if value <= first[0] {
+                    return first[1];
+                }
+                if value >= last[0] {
+                    return last[1];
+                }
+                points
+                    .windows(2)
+                    .find(|pair| value <= pair[1][0])
+                    .map(|pair| {
+                        let ([x0, y0], [x1, y1]) = (pair[0], pair[1]);
+                        if x1 == x0 {
+                            y1
+                        } else {
+                            y0 + (value - x0) / (x1 - x0) * (y1 - y0)
+                        }
+                    })
+                    .unwrap_or(last[1])
+            }
+            Curve::Raw => value,
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        match self {
+            Curve::Linear { from, to } | Curve::Log { from, to } => {
+                if !from.is_finite() || !to.is_finite() {
+                    return Err("curve bounds must be finite".to_string());
+                }
+            }
+            Curve::Piecewise { points } => {
+                if points.is_empty() {
+                    return Err("a piecewise curve needs at least one point".to_string());
+                }
+                if points.windows(2).any(|pair| pair[1][0] < pair[0][0]) {
+                    return Err("piecewise points must be sorted by x".to_string());
+                }
+            }
+            Curve::Raw => {}
+        }
+        Ok(())
+    }
+}
+
+/// One weighted input of a score
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Term {
+    pub weight: f64,
+
+    /// Maps the input onto 0-100 before weighting; the raw value is used without one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub curve: Option<Curve>,
+}
+
+/// How a factor turns its raw measurements into a 0-100 score
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Normalization {
+    /// Weighted inputs, keyed by measurement name
+    pub terms: BTreeMap<String, Term>,
+
+    /// Applied to the weighted sum of the terms
+    pub curve: Curve,
+
+    /// Decimal places the score is rounded to; unrounded when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decimals: Option<u32>,
+}
+
+impl Normalization {
+    pub fn new(curve: Curve) -> Self {
+        Self {
+            terms: BTreeMap::new(),
+            curve,
+            decimals: None,
+        }
+    }
+
+    /// Add an input used as-is
+    pub fn term(mut self, name: &str, weight: f64) -> Self {
+        self.terms.insert(
+            name.to_string(),
+            Term {
+                weight,
+                curve: None,
+            },
+        );
+        self
+    }
+
+    /// Add an input mapped through `curve` first
+    pub fn curved_term(mut self, name: &str, weight: f64, curve: Curve) -> Self {
+        self.terms.insert(
+            name.to_string(),
+            Term {
+                weight,
+                curve: Some(curve),
+            },
+        );
+        self
+    }
+
+    pub fn rounded(mut self, decimals: u32) -> Self {
+        self.decimals = Some(decimals);
+        self
+    }
+
+    /// The weighted sum of the named `inputs`, before the final curve
+    pub fn weighted_sum(&self, inputs: &[(&str, f64)]) -> f64 {
+        inputs
+            .iter()
+            .filter_map(|(name, value)| {
+                let term = self.terms.get(*name)?;
+                let value = term.curve.as_ref().map_or(*value, |c| c.apply(*value));
+                Some(term.weight * value)
+            })
+            .sum()
+    }
+
+    /// Score the named `inputs`; terms without an input count as 0
+    pub fn score(&self, inputs: &[(&str, f64)]) -> f64 {
+        self.finish(self.weighted_sum(inputs))
+    }
+
+    /// Score the weighted sum of `inputs` averaged over `count` items, e.g.
+    /// the mean risk per dependency; 0 items average to 0
+    pub fn mean_score(&self, inputs: &[(&str, f64)], count: usize) -> f64 {
+        let mean = if count == 0 {
+            0.0
+        } else {
+            self.weighted_sum(inputs) / count as f64
+        };
+        self.finish(mean)
+    }
+
+    /// Apply the final curve and rounding
+    fn finish(&self, value: f64) -> f64 {
+        let score = self.curve.apply(value);
+        match self.decimals {
+            Some(decimals) => {
+                let scale = 10f64.powi(decimals as i32);
+                (score * scale).round() / scale
+            }
+            None => score,
+        }
+    }
+
+    /// These parameters with `overrides` (a `[factors.<id>.normalization]` table) applied
+    ///
+    /// Tables merge key by key, so `terms.x.weight = 3` keeps the term's
+    /// curve; a `curve` is always replaced as a whole. Terms the factor does
+    /// not measure are rejected.
+    pub fn with_overrides(&self, overrides: &Value) -> Result<Self, String> {
+        if let Some(terms) = overrides.get("terms").and_then(Value::as_object) {
+            if let Some(unknown) = terms.keys().find(|name| !self.terms.contains_key(*name)) {
+                return Err(format!(
+                    "unknown term '{}' (expected one of {})",
+                    unknown,
+                    self.terms.keys().cloned().collect::<Vec<_>>().join(", ")
+                ));
+            }
+        }
+
+        let mut merged = serde_json::to_value(self).map_err(|e| e.to_string())?;
+        merge(&mut merged, overrides);
+        let normalization: Self = serde_json::from_value(merged).map_err(|e| e.to_string())?;
+
+        normalization.curve.validate()?;
+        for (name, term) in &normalization.terms {
+            if !term.weight.is_finite() {
+                return Err(format!(
+                    "term '{}' has invalid weight {}",
+                    name, term.weight
+                ));
+            }
+            if let Some(curve) = &term.curve {
+                curve
+                    .validate()
+                    .map_err(|e| format!("term '{}': {}", name, e))?;
+            }
+        }
+        Ok(normalization)
+    }
+}
+
+/// Merge `overrides` into `base`; `curve` values replace rather than merge
+fn merge(base: &mut Value, overrides: &Value) {
+    match (base, overrides) {
+        (Value::Object(base), Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(key) {
+                    Some(existing) if key != "curve" => merge(existing, value),
+                    _ => {
+                        base.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (base, value) => *base = value.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_curves() {
+        let linear = Curve::linear(500.0, 10_000.0);
+        assert_eq!(linear.apply(100.0), 0.0);
+        assert_eq!(linear.apply(5_250.0), 50.0);
+        assert_eq!(linear.apply(20_000.0), 100.0);
+
+        let inverted = Curve::linear(2.0, 0.5);
+        assert_eq!(inverted.apply(0.0), 100.0);
+        assert_eq!(inverted.apply(1.25), 50.0);
+
+        let log = Curve::Log {
+            from: 0.0,
+            to: 99.0,
+        };
+        assert_eq!(log.apply(0.0), 0.0);
+        assert!((log.apply(9.0) - 50.0).abs() < 1e-9);
+        assert_eq!(log.apply(500.0), 100.0);
+
+        let steps = Curve::piecewise(&[[0.0, 0.0], [1.0, 25.0], [3.0, 75.0]]);
+        assert_eq!(steps.apply(-1.0), 0.0);
+        assert_eq!(steps.apply(2.0), 50.0);
+        assert_eq!(steps.apply(9.0), 75.0);
+
+        assert_eq!(Curve::Raw.apply(250.0), 250.0);
+    }
+
+    #[test]
+    fn test_score_and_overrides() {
+        let normalization = Normalization::new(Curve::percent())
+            .curved_term("maxCc", 0.7, Curve::linear(0.0, 50.0))
+            .curved_term("avgCc", 0.3, Curve::linear(0.0, 15.0))
+            .rounded(2);
+        assert_eq!(
+            normalization.score(&[("maxCc", 25.0), ("avgCc", 3.0)]),
+            41.0
+        );
+
+        let per_item = Normalization::new(Curve::percent()).term("risk", 10.0);
+        assert_eq!(per_item.mean_score(&[("risk", 6.0)], 4), 15.0);
+        assert_eq!(per_item.mean_score(&[("risk", 0.0)], 0), 0.0);
+
+        let toml: toml::Value = toml::from_str(
+            r#"
+            terms.maxCc.weight = 0.5
+            terms.avgCc.curve = { type = "log", from = 0, to = 15 }
+            "#,
+        )
+        .unwrap();
+        let overrides = serde_json::to_value(toml).unwrap();
+        let tuned = normalization.with_overrides(&overrides).unwrap();
+        assert_eq!(tuned.terms["maxCc"].weight, 0.5);
+        assert_eq!(tuned.terms["maxCc"].curve, Some(Curve::linear(0.0, 50.0)));
+        assert!(matches!(
+            tuned.terms["avgCc"].curve,
+            Some(Curve::Log { .. })
+        ));
+
+        let unknown = serde_json::json!({ "terms": { "nope": { "weight": 1 } } });
+        assert!(normalization.with_overrides(&unknown).is_err());
+        let typo = serde_json::json!({ "curv": { "type": "linear", "from": 0, "to": 1 } });
+        assert!(normalization.with_overrides(&typo).is_err());
+        let unsorted =
+            serde_json::json!({ "curve": { "type": "piecewise", "points": [[2, 0], [1, 1]] } });
+        assert!(normalization.with_overrides(&unsorted).is_err());
+    }
+}
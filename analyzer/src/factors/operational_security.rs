@@ -6,11 +6,15 @@
 //! 2. Pause checks (state field references in conditions)
 //! 3. Sysvar dependencies (direct access to Solana system variables)
 
+use crate::factors::handlers::HandlerIndex;
+use crate::factors::normalization::{Curve, Normalization};
+use crate::factors::registry::{Factor, FactorContext, FactorOutput};
+use crate::workspace::ParsedWorkspace;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use syn::{
     visit::{self, Visit},
-    Expr, ExprField, ExprIf, ExprMacro, ExprPath, ItemFn, Path,
+    ExprField, ExprIf, ExprMacro, ExprPath, ItemFn, Path,
 };
 
 #[derive(Debug, Default, Serialize, Deserialize)]
@@ -61,27 +65,51 @@ impl OpSecMetrics {
             "filesSkipped": self.files_skipped,
         })
     }
+
+    /// Default OpSec Factor scoring
+    /// Control handlers are highest risk (5x), pause checks are medium risk (3x), sysvars are low risk (1x)
+    /// Upper bound: 10 control handlers + 20 pause checks + 10 sysvars = 120 = 100% risk
+    pub fn default_normalization() -> Normalization {
+        Normalization::new(Curve::linear(0.0, 120.0))
+            .term("controlHandlers", 5.0)
+            .term("pauseChecks", 3.0)
+            .term("sysvarDependencies", 1.0)
+    }
+
+    /// Measurements the factor is computed from
+    pub fn normalization_inputs(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            ("controlHandlers", self.control_handlers as f64),
+            ("pauseChecks", self.pause_checks as f64),
+            ("sysvarDependencies", self.sysvar_dependencies as f64),
+        ]
+    }
+
+    /// Fill the raw score and the normalized factor using `normalization`
+    fn score_with(&mut self, normalization: &Normalization) {
+        let inputs = self.normalization_inputs();
+        self.raw_risk_score = normalization.weighted_sum(&inputs);
+        self.opsec_factor = normalization.score(&inputs);
+    }
 }
 
 /// Visitor for detecting operational security patterns
-#[derive(Debug, Default)]
-struct OpSecVisitor {
+#[derive(Debug)]
+struct OpSecVisitor<'a> {
+    handlers: &'a HandlerIndex<'a>,
     metrics: OpSecMetrics,
     current_handler_name: Option<String>,
     pause_checks_in_current_handler: u32,
 }
 
-impl OpSecVisitor {
-    /// Checks if an argument is the Anchor Context
-    fn is_context_arg(&self, arg: &syn::FnArg) -> bool {
-        if let syn::FnArg::Typed(pat_type) = arg {
-            if let syn::Type::Path(type_path) = &*pat_type.ty {
-                if let Some(segment) = type_path.path.segments.last() {
-                    return segment.ident == "Context";
-                }
-            }
+impl<'a> OpSecVisitor<'a> {
+    fn new(handlers: &'a HandlerIndex<'a>) -> Self {
+        Self {
+            handlers,
+            metrics: OpSecMetrics::default(),
+            current_handler_name: None,
+            pause_checks_in_current_handler: 0,
         }
-        false
     }
 
     /// Checks if a function name indicates operational control
@@ -220,17 +248,12 @@ impl OpSecVisitor {
     }
 }
 
-impl<'ast> Visit<'ast> for OpSecVisitor {
+impl<'ast> Visit<'ast> for OpSecVisitor<'_> {
     /// Find instruction handlers and analyze their names
     fn visit_item_fn(&mut self, node: &'ast ItemFn) {
-        // Robust heuristic for an Anchor handler
-        let is_anchor_handler = matches!(node.vis, syn::Visibility::Public(_))
-            && node.sig.inputs.len() > 0
-            && self.is_context_arg(&node.sig.inputs[0]);
-
-        if is_anchor_handler {
+        if let Some(handler) = self.handlers.lookup(node) {
             self.metrics.total_handlers_found += 1;
-            let handler_name = node.sig.ident.to_string();
+            let handler_name = handler.name.clone();
 
             // --- ADD DEBUG LOGGING ---
             log::info!("🔍 OpSec DEBUG: Found Anchor handler: '{}'", handler_name);
@@ -364,46 +387,29 @@ impl<'ast> Visit<'ast> for OpSecVisitor {
 
 /// Calculate operational security metrics for workspace
 pub fn calculate_workspace_operational_security(
-    workspace_path: &std::path::PathBuf,
+    workspace_path: &std::path::Path,
     selected_files: &[String],
+) -> Result<OpSecMetrics, Box<dyn std::error::Error>> {
+    let workspace = ParsedWorkspace::load(workspace_path, selected_files);
+    analyze_workspace_operational_security(&workspace)
+}
+
+/// Measure operational security over an already-parsed workspace
+pub fn analyze_workspace_operational_security(
+    workspace: &ParsedWorkspace,
 ) -> Result<OpSecMetrics, Box<dyn std::error::Error>> {
     log::info!(
         "🔍 OPERATIONAL SECURITY DEBUG: Starting analysis for workspace: {:?}",
-        workspace_path
+        workspace.root
     );
 
-    let mut visitor = OpSecVisitor::default();
-    let mut files_analyzed = 0;
-    let mut files_skipped = 0;
-
-    for file_path in selected_files {
-        let full_path = workspace_path.join(file_path);
+    let handlers = HandlerIndex::from_workspace(workspace);
+    let mut visitor = OpSecVisitor::new(&handlers);
+    let files_analyzed = workspace.files.len() as u32;
+    let files_skipped = workspace.skipped_count() as u32;
 
-        if !full_path.exists() {
-            files_skipped += 1;
-            continue;
-        }
-        if !full_path.extension().map_or(false, |ext| ext == "rs") {
-            files_skipped += 1;
-            continue;
-        }
-
-        match std::fs::read_to_string(&full_path) {
-            Ok(content) => match syn::parse_file(&content) {
-                Ok(ast) => {
-                    visitor.visit_file(&ast);
-                    files_analyzed += 1;
-                }
-                Err(e) => {
-                    log::warn!("Failed to parse AST for {:?}: {}", full_path, e);
-                    files_skipped += 1;
-                }
-            },
-            Err(e) => {
-                log::warn!("Failed to read file {:?}: {}", full_path, e);
-                files_skipped += 1;
-            }
-        }
+    for file in &workspace.files {
+        visitor.visit_file(&file.ast);
     }
 
     // Final calculation and normalization (0-100)
@@ -411,19 +417,7 @@ pub fn calculate_workspace_operational_security(
     metrics.files_analyzed = files_analyzed;
     metrics.files_skipped = files_skipped;
 
-    // Calculate raw risk score with weighted factors
-    // Control handlers are highest risk (5x), pause checks are medium risk (3x), sysvars are low risk (1x)
-    let raw_risk_score = (metrics.control_handlers as f64 * 5.0)
-        + (metrics.pause_checks as f64 * 3.0)
-        + (metrics.sysvar_dependencies as f64 * 1.0);
-
-    metrics.raw_risk_score = raw_risk_score;
-
-    // Normalize to 0-100
-    // Upper bound: 10 control handlers + 20 pause checks + 10 sysvars = 100% risk
-    let upper_bound = (10.0 * 5.0) + (20.0 * 3.0) + (10.0 * 1.0); // 120
-    let factor = (raw_risk_score / upper_bound) * 100.0;
-    metrics.opsec_factor = factor.min(100.0); // Cap at 100
+    metrics.score_with(&OpSecMetrics::default_normalization());
 
     log::info!(
         "🔍 OPERATIONAL SECURITY DEBUG: Analysis complete - {} files analyzed. Control handlers: {}, Pause checks: {}, Sysvars: {}, Factor: {:.2}",
@@ -437,6 +431,41 @@ pub fn calculate_workspace_operational_security(
     Ok(visitor.metrics)
 }
 
+/// Registry entry for the operational security factor
+pub struct OperationalSecurityFactor;
+
+impl Factor for OperationalSecurityFactor {
+    fn id(&self) -> &'static str {
+        "operationalSecurity"
+    }
+
+    fn version(&self) -> &'static str {
+        "1.2.0"
+    }
+
+    fn name(&self) -> &'static str {
+        "Operational security"
+    }
+
+    fn normalization(&self) -> Option<Normalization> {
+        Some(OpSecMetrics::default_normalization())
+    }
+
+    fn compute(&self, ctx: &FactorContext) -> Result<FactorOutput, Box<dyn std::error::Error>> {
+        let mut metrics = analyze_workspace_operational_security(ctx.workspace)?;
+        metrics.score_with(&ctx.normalization_or(OpSecMetrics::default_normalization));
+        Ok(
+            FactorOutput::keyed(self.id(), metrics.opsec_factor, metrics.to_json()).with_note(format!(
+                "Analyzed operational security: {} control handlers, {} pause checks, {} sysvar dependencies, opsec factor {:.1}",
+                metrics.control_handlers,
+                metrics.pause_checks,
+                metrics.sysvar_dependencies,
+                metrics.opsec_factor,
+            )),
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -460,7 +489,8 @@ mod tests {
         "#;
 
         let ast = syn::parse_file(code).unwrap();
-        let mut visitor = OpSecVisitor::default();
+        let handlers = HandlerIndex::from_ast("lib.rs", &ast);
+        let mut visitor = OpSecVisitor::new(&handlers);
         visitor.visit_file(&ast);
 
         assert_eq!(visitor.metrics.total_handlers_found, 3);
@@ -498,7 +528,8 @@ mod tests {
         "#;
 
         let ast = syn::parse_file(code).unwrap();
-        let mut visitor = OpSecVisitor::default();
+        let handlers = HandlerIndex::from_ast("lib.rs", &ast);
+        let mut visitor = OpSecVisitor::new(&handlers);
         visitor.visit_file(&ast);
 
         assert_eq!(visitor.metrics.total_handlers_found, 1);
@@ -527,7 +558,8 @@ mod tests {
         "#;
 
         let ast = syn::parse_file(code).unwrap();
-        let mut visitor = OpSecVisitor::default();
+        let handlers = HandlerIndex::from_ast("lib.rs", &ast);
+        let mut visitor = OpSecVisitor::new(&handlers);
         visitor.visit_file(&ast);
 
         assert_eq!(visitor.metrics.total_handlers_found, 1);
@@ -557,7 +589,8 @@ mod tests {
         "#;
 
         let ast = syn::parse_file(code).unwrap();
-        let mut visitor = OpSecVisitor::default();
+        let handlers = HandlerIndex::from_ast("lib.rs", &ast);
+        let mut visitor = OpSecVisitor::new(&handlers);
         visitor.visit_file(&ast);
 
         assert_eq!(visitor.metrics.total_handlers_found, 2);
@@ -586,7 +619,8 @@ mod tests {
         "#;
 
         let ast = syn::parse_file(code).unwrap();
-        let mut visitor = OpSecVisitor::default();
+        let handlers = HandlerIndex::from_ast("lib.rs", &ast);
+        let mut visitor = OpSecVisitor::new(&handlers);
         visitor.visit_file(&ast);
 
         assert_eq!(visitor.metrics.total_handlers_found, 1);
@@ -615,7 +649,8 @@ mod tests {
         "#;
 
         let ast = syn::parse_file(code).unwrap();
-        let mut visitor = OpSecVisitor::default();
+        let handlers = HandlerIndex::from_ast("lib.rs", &ast);
+        let mut visitor = OpSecVisitor::new(&handlers);
         visitor.visit_file(&ast);
 
         assert_eq!(visitor.metrics.total_handlers_found, 1);
@@ -632,20 +667,16 @@ mod tests {
 
     #[test]
     fn test_factor_calculation() {
-        let mut metrics = OpSecMetrics::default();
-        metrics.control_handlers = 3;
-        metrics.pause_checks = 5;
-        metrics.sysvar_dependencies = 2;
-
-        let raw_risk_score = (metrics.control_handlers as f64 * 5.0)
-            + (metrics.pause_checks as f64 * 3.0)
-            + (metrics.sysvar_dependencies as f64 * 1.0);
-
-        let upper_bound = (10.0 * 5.0) + (20.0 * 3.0) + (10.0 * 1.0); // 120
-        let factor = (raw_risk_score / upper_bound) * 100.0;
-        let opsec_factor = factor.min(100.0);
+        let mut metrics = OpSecMetrics {
+            control_handlers: 3,
+            pause_checks: 5,
+            sysvar_dependencies: 2,
+            ..Default::default()
+        };
+        metrics.score_with(&OpSecMetrics::default_normalization());
 
         // (3*5 + 5*3 + 2*1) / 120 * 100 = 32/120 * 100 = 26.7%
-        assert!((opsec_factor - 26.7).abs() < 0.1);
+        assert_eq!(metrics.raw_risk_score, 32.0);
+        assert!((metrics.opsec_factor - 26.7).abs() < 0.1);
     }
 }
@@ -0,0 +1,794 @@
+//! Oracle Price Feed Factor
+//!
+//! Oracle misuse is the most common DeFi finding: prices read without a
+//! staleness bound, without a confidence-interval check, or used without
+//! applying the feed's exponent. This module finds every Pyth, Switchboard
+//! and Chainlink price read and checks the function that performs it for:
+//! 1. A staleness check (`get_price_no_older_than`, `check_staleness`, or a
+//!    comparison against `publish_time`/`timestamp`/`max_age`)
+//! 2. A confidence-interval check (`check_confidence_interval`, or a
+//!    comparison involving `conf`); Chainlink feeds have no confidence value
+//! 3. Exponent/decimals normalization (`expo`, `exponent`, `decimals`,
+//!    `scale`, `scale_to_exponent`)
+//!
+//! A read is recognized by its accessor (`get_price_no_older_than`,
+//! `get_result`, ...) only when its receiver names the provider or the file
+//! mentions the provider's crate or types (`PriceUpdateV2`, `PriceFeed`,
+//! `AggregatorAccountData`).
+
+use crate::factors::external_integration::{
+    is_chainlink_oracle, is_pyth_oracle, is_switchboard_oracle, PYTH_PRICE_METHODS,
+};
+use crate::factors::normalization::{Curve, Normalization};
+use crate::factors::registry::{Factor, FactorContext, FactorOutput, FindingRule, Severity};
+use crate::workspace::{ParsedWorkspace, SourceLocation};
+use quote::quote;
+use serde::{Deserialize, Serialize};
+use syn::{
+    visit::{self, Visit},
+    BinOp, Expr, ExprBinary, ExprCall, ExprField, ExprMethodCall, Ident, ImplItemFn, ItemFn, Macro,
+    Member, Path,
+};
+
+/// Risk weight of a read without a staleness check
+const STALENESS_WEIGHT: f64 = 50.0;
+/// Risk weight of a read without a confidence-interval check
+const CONFIDENCE_WEIGHT: f64 = 30.0;
+/// Risk weight of a read whose exponent is never applied
+const EXPONENT_WEIGHT: f64 = 20.0;
+
+/// Free-function price reads (`chainlink::latest_round_data(..)`)
+const PRICE_READ_FUNCTIONS: &[&str] = &[
+    "get_price",
+    "get_latest_price",
+    "get_price_unchecked",
+    "get_price_no_older_than",
+    "get_result",
+    "get_latest_result",
+    "get_latest_round",
+    "latest_round_data",
+];
+
+/// Pyth types and loaders whose presence puts Pyth in scope (besides any
+/// identifier containing `pyth`, such as the SDK crates)
+const PYTH_TYPES: &[&str] = &[
+    "PriceUpdateV2",
+    "PriceFeed",
+    "SolanaPriceAccount",
+    "load_price_feed_from_account_info",
+];
+
+/// Switchboard types whose presence puts Switchboard in scope (besides any
+/// identifier containing `switchboard`)
+const SWITCHBOARD_TYPES: &[&str] = &[
+    "AggregatorAccountData",
+    "PullFeedAccountData",
+    "SwitchboardDecimal",
+];
+
+/// Identifier fragments that mark a comparison as a staleness check
+const STALENESS_MARKERS: &[&str] = &[
+    "publish_time",
+    "timestamp",
+    "updated_at",
+    "last_update",
+    "round_open",
+    "stale",
+    "max_age",
+];
+
+/// Fields and calls that apply a feed's exponent or decimals
+const EXPONENT_MARKERS: &[&str] = &[
+    "expo",
+    "exponent",
+    "decimals",
+    "get_decimals",
+    "scale",
+    "scale_to_exponent",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OracleProvider {
+    Pyth,
+    Switchboard,
+    Chainlink,
+}
+
+impl OracleProvider {
+    /// Whether the provider publishes a confidence interval
+    pub fn has_confidence(self) -> bool {
+        !matches!(self, OracleProvider::Chainlink)
+    }
+
+    /// Lowercase name fragment of the provider's crates and accounts
+    fn marker(self) -> &'static str {
+        match self {
+            OracleProvider::Pyth => "pyth",
+            OracleProvider::Switchboard => "switchboard",
+            OracleProvider::Chainlink => "chainlink",
+        }
+    }
+}
+
+/// Oracle providers whose crates or types a file mentions
+///
+/// Accessor names such as `get_price` and `get_result` are common on
+/// non-oracle types (AMM pools, result wrappers), so a read by name alone
+/// only counts when its provider is in scope.
+#[derive(Debug, Default, Clone, Copy)]
+struct OracleScope {
+    pyth: bool,
+    switchboard: bool,
+    chainlink: bool,
+}
+
+impl OracleScope {
+    fn of(ast: &syn::File) -> Self {
+        let mut scope = Self::default();
+        scope.visit_file(ast);
+        scope
+    }
+
+    fn includes(&self, provider: OracleProvider) -> bool {
+        match provider {
+            OracleProvider::Pyth => self.pyth,
+            OracleProvider::Switchboard => self.switchboard,
+            OracleProvider::Chainlink => self.chainlink,
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for OracleScope {
+    fn visit_ident(&mut self, ident: &'ast Ident) {
+        let name = ident.to_string();
+        let lower = name.to_lowercase();
+        self.pyth |= lower.contains("pyth") || PYTH_TYPES.contains(&name.as_str());
+        self.switchboard |=
+            lower.contains("switchboard") || SWITCHBOARD_TYPES.contains(&name.as_str());
+        self.chainlink |= lower.contains("chainlink");
+    }
+}
+
+/// A single oracle price read and the checks found around it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OraclePriceRead {
+    pub provider: OracleProvider,
+    /// Function performing the read
+    pub function: String,
+    /// Method or function used to read the price
+    pub accessor: String,
+    pub location: SourceLocation,
+    pub staleness_checked: bool,
+    /// `None` for providers without a confidence interval
+    pub confidence_checked: Option<bool>,
+    pub exponent_normalized: bool,
+    /// Per-read risk (0-100)
+    pub risk_score: f64,
+}
+
+impl OraclePriceRead {
+    fn score(&self) -> f64 {
+        let mut risk = 0.0;
+        if !self.staleness_checked {
+            risk += STALENESS_WEIGHT;
+        }
+        if self.confidence_checked == Some(false) {
+            risk += CONFIDENCE_WEIGHT;
+        }
+        if !self.exponent_normalized {
+            risk += EXPONENT_WEIGHT;
+        }
+        risk
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct OraclePriceFeedMetrics {
+    /// Total oracle price reads found
+    pub total_price_reads: u32,
+    pub pyth_reads: u32,
+    pub switchboard_reads: u32,
+    pub chainlink_reads: u32,
+
+    /// Sub-factors: reads missing each check
+    pub reads_missing_staleness_check: u32,
+    pub reads_missing_confidence_check: u32,
+    pub reads_missing_exponent_normalization: u32,
+
+    /// Final Score (0-100)
+    pub oracle_factor: f64,
+    /// Raw score used for normalization (sum of per-read risk)
+    pub raw_risk_score: f64,
+
+    /// Helper Metrics (for Audibility)
+    /// Every price read with its checks
+    pub price_reads: Vec<OraclePriceRead>,
+    pub files_analyzed: u32,
+    pub files_skipped: u32,
+}
+
+impl OraclePriceFeedMetrics {
+    /// Convert to structured JSON object
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "oracleFactor": self.oracle_factor,
+            "rawRiskScore": self.raw_risk_score,
+            "totalPriceReads": self.total_price_reads,
+            "pythReads": self.pyth_reads,
+            "switchboardReads": self.switchboard_reads,
+            "chainlinkReads": self.chainlink_reads,
+            "readsMissingStalenessCheck": self.reads_missing_staleness_check,
+            "readsMissingConfidenceCheck": self.reads_missing_confidence_check,
+            "readsMissingExponentNormalization": self.reads_missing_exponent_normalization,
+            "priceReads": self.price_reads,
+            "filesAnalyzed": self.files_analyzed,
+            "filesSkipped": self.files_skipped,
+        })
+    }
+
+    /// Default Oracle Factor scoring: the average per-read risk, so a program
+    /// with one unchecked read scores the same as one with ten
+    pub fn default_normalization() -> Normalization {
+        Normalization::new(Curve::percent())
+            .term("missingStaleness", STALENESS_WEIGHT)
+            .term("missingConfidence", CONFIDENCE_WEIGHT)
+            .term("missingExponent", EXPONENT_WEIGHT)
+    }
+
+    /// Measurements the Oracle Factor is computed from: reads missing each
+    /// check, averaged over `total_price_reads`
+    pub fn normalization_inputs(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            (
+                "missingStaleness",
+                self.reads_missing_staleness_check as f64,
+            ),
+            (
+                "missingConfidence",
+                self.reads_missing_confidence_check as f64,
+            ),
+            (
+                "missingExponent",
+                self.reads_missing_exponent_normalization as f64,
+            ),
+        ]
+    }
+
+    /// Fill the factor using `normalization`
+    fn score_with(&mut self, normalization: &Normalization) {
+        self.oracle_factor = normalization.mean_score(
+            &self.normalization_inputs(),
+            self.total_price_reads as usize,
+        );
+    }
+}
+
+/// Reads and check evidence collected within one function body
+#[derive(Debug, Default)]
+struct FunctionScan {
+    name: String,
+    reads: Vec<OraclePriceRead>,
+    staleness_checked: bool,
+    confidence_checked: bool,
+    exponent_normalized: bool,
+}
+
+/// Visitor for detecting oracle price reads and their checks
+#[derive(Debug)]
+struct OraclePriceFeedVisitor<'a> {
+    file_path: &'a str,
+    scope: OracleScope,
+    current_function: Option<FunctionScan>,
+    reads: Vec<OraclePriceRead>,
+}
+
+impl<'a> OraclePriceFeedVisitor<'a> {
+    fn new(file_path: &'a str, scope: OracleScope) -> Self {
+        Self {
+            file_path,
+            scope,
+            current_function: None,
+            reads: Vec::new(),
+        }
+    }
+
+    /// Scan a function body; reads are only attributed to their own function
+    fn scan_function(&mut self, name: String, visit_body: impl FnOnce(&mut Self)) {
+        let outer = self.current_function.replace(FunctionScan {
+            name,
+            ..Default::default()
+        });
+
+        visit_body(self);
+
+        if let Some(scan) = std::mem::replace(&mut self.current_function, outer) {
+            for mut read in scan.reads {
+                read.staleness_checked |= scan.staleness_checked;
+                if read.provider.has_confidence() {
+                    read.confidence_checked = Some(scan.confidence_checked);
+                }
+                read.exponent_normalized |= scan.exponent_normalized;
+                read.risk_score = read.score();
+                self.reads.push(read);
+            }
+        }
+    }
+
+    /// Record a price read in the current function
+    fn record_read(
+        &mut self,
+        provider: OracleProvider,
+        accessor: String,
+        location: SourceLocation,
+    ) {
+        if let Some(scan) = &mut self.current_function {
+            log::info!(
+                "🔍 ORACLE DEBUG: {:?} price read '{}' in '{}' at {}",
+                provider,
+                accessor,
+                scan.name,
+                location.display()
+            );
+            scan.reads.push(OraclePriceRead {
+                provider,
+                function: scan.name.clone(),
+                staleness_checked: accessor.ends_with("_no_older_than"),
+                accessor,
+                location,
+                confidence_checked: None,
+                exponent_normalized: false,
+                risk_score: 0.0,
+            });
+        }
+    }
+
+    /// Note check evidence from the identifiers of a comparison or assertion
+    fn record_comparison(&mut self, text: &str) {
+        let Some(scan) = &mut self.current_function else {
+            return;
+        };
+
+        for ident in text
+            .split(|c: char| !c.is_alphanumeric() && c != '_')
+            .filter(|ident| !ident.is_empty())
+        {
+            let ident = ident.to_lowercase();
+            if STALENESS_MARKERS
+                .iter()
+                .any(|marker| ident.contains(marker))
+            {
+                scan.staleness_checked = true;
+            }
+            if ident == "conf"
+                || ident.contains("confidence")
+                || ident.starts_with("conf_")
+                || ident.ends_with("_conf")
+            {
+                scan.confidence_checked = true;
+            }
+        }
+    }
+
+    fn record_exponent(&mut self, name: &str) {
+        if EXPONENT_MARKERS.contains(&name) {
+            if let Some(scan) = &mut self.current_function {
+                scan.exponent_normalized = true;
+            }
+        }
+    }
+}
+
+/// Provider of a free-function price read such as `chainlink::latest_round_data`
+fn call_read_provider(path: &Path, scope: &OracleScope) -> Option<OracleProvider> {
+    let last = path.segments.last()?.ident.to_string();
+    if !PRICE_READ_FUNCTIONS.contains(&last.as_str()) {
+        return None;
+    }
+
+    let path_str = quote!(#path).to_string();
+    if path_str.contains("chainlink") {
+        Some(OracleProvider::Chainlink)
+    } else if path_str.contains("switchboard") {
+        Some(OracleProvider::Switchboard)
+    } else if path_str.contains("pyth") || (is_pyth_oracle(path) && scope.pyth) {
+        Some(OracleProvider::Pyth)
+    } else if is_switchboard_oracle(path) && scope.switchboard {
+        Some(OracleProvider::Switchboard)
+    } else if is_chainlink_oracle(path) && scope.chainlink {
+        Some(OracleProvider::Chainlink)
+    } else {
+        None
+    }
+}
+
+/// Provider of a method price read such as `feed.get_price_no_older_than(..)`
+///
+/// The receiver naming a provider decides; otherwise the provider must be in
+/// the file's scope.
+fn method_read_provider(node: &ExprMethodCall, scope: &OracleScope) -> Option<OracleProvider> {
+    let method = node.method.to_string();
+    let candidates: &[OracleProvider] = if method == "get_result" {
+        &[OracleProvider::Switchboard]
+    } else if method == "get_latest_price" {
+        // Shared with Chainlink wrappers
+        &[OracleProvider::Pyth, OracleProvider::Chainlink]
+    } else if PYTH_PRICE_METHODS.contains(&method.as_str()) || method.starts_with("get_ema_price") {
+        &[OracleProvider::Pyth]
+    } else {
+        return None;
+    };
+
+    let receiver = &node.receiver;
+    let receiver = quote!(#receiver).to_string().to_lowercase();
+    candidates
+        .iter()
+        .copied()
+        .find(|provider| receiver.contains(provider.marker()))
+        .or_else(|| {
+            candidates
+                .iter()
+                .copied()
+                .find(|provider| scope.includes(*provider))
+        })
+}
+
+impl<'ast> Visit<'ast> for OraclePriceFeedVisitor<'_> {
+    fn visit_item_fn(&mut self, node: &'ast ItemFn) {
+        self.scan_function(node.sig.ident.to_string(), |visitor| {
+            visit::visit_item_fn(visitor, node)
+        });
+    }
+
+    fn visit_impl_item_fn(&mut self, node: &'ast ImplItemFn) {
+        self.scan_function(node.sig.ident.to_string(), |visitor| {
+            visit::visit_impl_item_fn(visitor, node)
+        });
+    }
+
+    /// Catches method reads (`price_feed.get_price_unchecked()`) and check helpers
+    fn visit_expr_method_call(&mut self, node: &'ast ExprMethodCall) {
+        let method = node.method.to_string();
+
+        if let Some(provider) = method_read_provider(node, &self.scope) {
+            let location = SourceLocation::of(self.file_path, &node.method);
+            self.record_read(provider, method.clone(), location);
+        }
+
+        if let Some(scan) = &mut self.current_function {
+            match method.as_str() {
+                "check_staleness" => scan.staleness_checked = true,
+                "check_confidence_interval" => scan.confidence_checked = true,
+                _ => {}
+            }
+        }
+        self.record_exponent(&method);
+
+        visit::visit_expr_method_call(self, node);
+    }
+
+    /// Catches free-function reads (`chainlink::latest_round_data(..)`)
+    fn visit_expr_call(&mut self, node: &'ast ExprCall) {
+        if let Expr::Path(path_expr) = &*node.func {
+            if let Some(provider) = call_read_provider(&path_expr.path, &self.scope) {
+                let accessor = quote!(#path_expr).to_string().replace(' ', "");
+                let location = SourceLocation::of(self.file_path, node);
+                self.record_read(provider, accessor, location);
+            }
+            if let Some(segment) = path_expr.path.segments.last() {
+                self.record_exponent(&segment.ident.to_string());
+            }
+        }
+
+        visit::visit_expr_call(self, node);
+    }
+
+    fn visit_expr_field(&mut self, node: &'ast ExprField) {
+        if let Member::Named(ident) = &node.member {
+            self.record_exponent(&ident.to_string());
+        }
+
+        visit::visit_expr_field(self, node);
+    }
+
+    fn visit_expr_binary(&mut self, node: &'ast ExprBinary) {
+        if matches!(
+            node.op,
+            BinOp::Lt(_) | BinOp::Le(_) | BinOp::Gt(_) | BinOp::Ge(_) | BinOp::Eq(_) | BinOp::Ne(_)
+        ) {
+            self.record_comparison(&quote!(#node).to_string());
+        }
+
+        visit::visit_expr_binary(self, node);
+    }
+
+    /// `require!`/`assert!` bodies are opaque tokens; scan them as comparisons
+    fn visit_macro(&mut self, node: &'ast Macro) {
+        if let Some(segment) = node.path.segments.last() {
+            let name = segment.ident.to_string();
+            if name.starts_with("require") || name.starts_with("assert") {
+                self.record_comparison(&node.tokens.to_string());
+            }
+        }
+
+        visit::visit_macro(self, node);
+    }
+}
+
+/// Calculate oracle price feed metrics for workspace
+pub fn calculate_workspace_oracle_price_feed(
+    workspace_path: &std::path::Path,
+    selected_files: &[String],
+) -> Result<OraclePriceFeedMetrics, Box<dyn std::error::Error>> {
+    let workspace = ParsedWorkspace::load(workspace_path, selected_files);
+    analyze_workspace_oracle_price_feed(&workspace)
+}
+
+/// Check oracle price reads over an already-parsed workspace
+pub fn analyze_workspace_oracle_price_feed(
+    workspace: &ParsedWorkspace,
+) -> Result<OraclePriceFeedMetrics, Box<dyn std::error::Error>> {
+    log::info!(
+        "🔍 ORACLE DEBUG: Starting analysis for workspace: {:?}",
+        workspace.root
+    );
+
+    let mut metrics = OraclePriceFeedMetrics {
+        files_analyzed: workspace.files.len() as u32,
+        files_skipped: workspace.skipped_count() as u32,
+        ..Default::default()
+    };
+
+    for file in &workspace.files {
+        let mut visitor = OraclePriceFeedVisitor::new(&file.path, OracleScope::of(&file.ast));
+        visitor.visit_file(&file.ast);
+        metrics.price_reads.extend(visitor.reads);
+    }
+
+    for read in &metrics.price_reads {
+        match read.provider {
+            OracleProvider::Pyth => metrics.pyth_reads += 1,
+            OracleProvider::Switchboard => metrics.switchboard_reads += 1,
+            OracleProvider::Chainlink => metrics.chainlink_reads += 1,
+        }
+        if !read.staleness_checked {
+            metrics.reads_missing_staleness_check += 1;
+        }
+        if read.confidence_checked == Some(false) {
+            metrics.reads_missing_confidence_check += 1;
+        }
+        if !read.exponent_normalized {
+            metrics.reads_missing_exponent_normalization += 1;
+        }
+        metrics.raw_risk_score += read.risk_score;
+    }
+    metrics.total_price_reads = metrics.price_reads.len() as u32;
+
+    // Normalize to 0-100
+    metrics.score_with(&OraclePriceFeedMetrics::default_normalization());
+
+    log::info!(
+        "🔍 ORACLE DEBUG: Analysis complete - {} price reads ({} missing staleness, {} missing confidence, {} missing exponent). Factor: {:.2}",
+        metrics.total_price_reads,
+        metrics.reads_missing_staleness_check,
+        metrics.reads_missing_confidence_check,
+        metrics.reads_missing_exponent_normalization,
+        metrics.oracle_factor
+    );
+
+    Ok(metrics)
+}
+
+/// A price is read without a bound on its age
+pub const STALE_PRICE: FindingRule = FindingRule {
+    id: "oraclePriceFeed/stalePrice",
+    name: "StalePrice",
+    description: "Oracle price read without a staleness check on its publish time",
+    severity: Severity::Warning,
+};
+
+/// Registry entry for the oracle price feed factor
+pub struct OraclePriceFeedFactor;
+
+impl Factor for OraclePriceFeedFactor {
+    fn id(&self) -> &'static str {
+        "oraclePriceFeed"
+    }
+
+    fn version(&self) -> &'static str {
+        "1.2.0"
+    }
+
+    fn name(&self) -> &'static str {
+        "Oracle price feed"
+    }
+
+    fn rules(&self) -> &'static [FindingRule] {
+        &[STALE_PRICE]
+    }
+
+    fn normalization(&self) -> Option<Normalization> {
+        Some(OraclePriceFeedMetrics::default_normalization())
+    }
+
+    fn compute(&self, ctx: &FactorContext) -> Result<FactorOutput, Box<dyn std::error::Error>> {
+        let mut metrics = analyze_workspace_oracle_price_feed(ctx.workspace)?;
+        metrics.score_with(&ctx.normalization_or(OraclePriceFeedMetrics::default_normalization));
+        let findings = metrics
+            .price_reads
+            .iter()
+            .filter(|read| !read.staleness_checked)
+            .map(|read| {
+                STALE_PRICE.at(
+                    read.location.clone(),
+                    format!(
+                        "`{}` in `{}` reads a price without checking its age",
+                        read.accessor, read.function
+                    ),
+                )
+            })
+            .collect();
+        Ok(
+            FactorOutput::keyed(self.id(), metrics.oracle_factor, metrics.to_json())
+                .with_field("oracleFactor", serde_json::json!(metrics.oracle_factor))
+                .with_note(format!(
+                    "Analyzed oracle price feeds: {} reads, {} missing staleness checks, {} missing confidence checks, {} missing exponent normalization, oracle factor {:.1}",
+                    metrics.total_price_reads,
+                    metrics.reads_missing_staleness_check,
+                    metrics.reads_missing_confidence_check,
+                    metrics.reads_missing_exponent_normalization,
+                    metrics.oracle_factor,
+                ))
+                .with_findings(findings),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn analyze(code: &str) -> Vec<OraclePriceRead> {
+        let ast = syn::parse_file(code).unwrap();
+        let mut visitor = OraclePriceFeedVisitor::new("lib.rs", OracleScope::of(&ast));
+        visitor.visit_file(&ast);
+        visitor.reads
+    }
+
+    #[test]
+    fn test_unchecked_pyth_read() {
+        let reads = analyze(
+            r#"
+            pub fn liquidate(ctx: Context<Liquidate>) -> Result<()> {
+                let feed = load_price_feed_from_account_info(&ctx.accounts.oracle).unwrap();
+                let price = feed.get_price_unchecked();
+                Ok(())
+            }
+            "#,
+        );
+
+        assert_eq!(reads.len(), 1);
+        let read = &reads[0];
+        assert_eq!(read.provider, OracleProvider::Pyth);
+        assert_eq!(read.function, "liquidate");
+        assert_eq!(read.accessor, "get_price_unchecked");
+        assert_eq!(read.location.line, 4);
+        assert!(!read.staleness_checked);
+        assert_eq!(read.confidence_checked, Some(false));
+        assert!(!read.exponent_normalized);
+        assert_eq!(read.risk_score, 100.0);
+    }
+
+    #[test]
+    fn test_fully_checked_pyth_read() {
+        let reads = analyze(
+            r#"
+            use pyth_solana_receiver_sdk::price_update::PriceUpdateV2;
+
+            pub fn swap(ctx: Context<Swap>) -> Result<()> {
+                let clock = Clock::get()?;
+                let price = ctx.accounts.feed.get_price_no_older_than(&clock, 60, &FEED_ID)?;
+                require!(price.conf * 100 <= price.price as u64, PoolError::WideConfidence);
+                let value = (price.price as u128) * 10u128.pow(price.expo.unsigned_abs());
+                Ok(())
+            }
+            "#,
+        );
+
+        assert_eq!(reads.len(), 1);
+        assert!(reads[0].staleness_checked);
+        assert_eq!(reads[0].confidence_checked, Some(true));
+        assert!(reads[0].exponent_normalized);
+        assert_eq!(reads[0].risk_score, 0.0);
+    }
+
+    #[test]
+    fn test_chainlink_and_switchboard_reads() {
+        let reads = analyze(
+            r#"
+            use switchboard_v2::AggregatorAccountData;
+
+            fn chainlink_price(program: AccountInfo, feed: AccountInfo) -> Result<i128> {
+                let round = chainlink::latest_round_data(program.clone(), feed.clone())?;
+                let decimals = chainlink::decimals(program, feed)?;
+                if clock.unix_timestamp - round.timestamp as i64 > MAX_AGE {
+                    return err!(OracleError::Stale);
+                }
+                Ok(round.answer)
+            }
+
+            impl Pool {
+                fn switchboard_price(&self) -> Result<f64> {
+                    let feed = self.aggregator.load()?;
+                    feed.check_staleness(clock.unix_timestamp, 300)?;
+                    let result = feed.get_result()?;
+                    Ok(result.try_into()?)
+                }
+            }
+            "#,
+        );
+
+        assert_eq!(reads.len(), 2);
+
+        let chainlink = &reads[0];
+        assert_eq!(chainlink.provider, OracleProvider::Chainlink);
+        assert_eq!(chainlink.accessor, "chainlink::latest_round_data");
+        assert!(chainlink.staleness_checked);
+        assert_eq!(chainlink.confidence_checked, None);
+        assert!(chainlink.exponent_normalized);
+
+        let switchboard = &reads[1];
+        assert_eq!(switchboard.provider, OracleProvider::Switchboard);
+        assert_eq!(switchboard.function, "switchboard_price");
+        assert!(switchboard.staleness_checked);
+        assert_eq!(switchboard.confidence_checked, Some(false));
+        assert!(!switchboard.exponent_normalized);
+        assert_eq!(switchboard.risk_score, 50.0);
+    }
+
+    #[test]
+    fn test_workspace_factor_is_average_read_risk() {
+        let file = crate::workspace::ParsedFile::parse(
+            "src/oracle.rs",
+            r#"
+            fn unchecked(feed: PriceFeed) -> i64 {
+                feed.get_price_unchecked().price
+            }
+
+            fn checked(feed: PriceFeed, clock: Clock) -> Option<i64> {
+                let price = feed.get_price_no_older_than(clock.unix_timestamp, 30)?;
+                assert!(price.conf < MAX_CONF);
+                Some(price.price * 10i64.pow(price.expo as u32))
+            }
+            "#
+            .to_string(),
+        )
+        .unwrap();
+        let workspace = ParsedWorkspace::from_files(std::path::Path::new("."), vec![file]);
+
+        let metrics = analyze_workspace_oracle_price_feed(&workspace).unwrap();
+        assert_eq!(metrics.total_price_reads, 2);
+        assert_eq!(metrics.pyth_reads, 2);
+        assert_eq!(metrics.reads_missing_staleness_check, 1);
+        assert_eq!(metrics.raw_risk_score, 100.0);
+        assert_eq!(metrics.oracle_factor, 50.0);
+        assert_eq!(metrics.price_reads[0].location.file, "src/oracle.rs");
+    }
+
+    #[test]
+    fn test_getters_without_oracle_in_scope() {
+        let reads = analyze(
+            r#"
+            pub fn swap(ctx: Context<Swap>, amount: u64) -> Result<()> {
+                let spot = ctx.accounts.pool.get_price();
+                let quote = ctx.accounts.router.get_result()?;
+                let mid = pricing::get_price(&ctx.accounts.pool);
+                Ok(())
+            }
+            "#,
+        );
+
+        assert!(reads.is_empty());
+    }
+}
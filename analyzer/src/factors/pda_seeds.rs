@@ -2,15 +2,22 @@
 //!
 //! This module analyzes Anchor-specific PDA (Program Derived Address) patterns
 //! to count accounts with seeds and assess the complexity of the account graph.
-
+//! Native programs derive PDAs in code, so `Pubkey::find_program_address` and
+//! `create_program_address` calls in native functions count as PDA accounts too.
+//! Anchor code re-deriving an address its `seeds` attribute already declares
+//! is not counted twice.
+
+use crate::factors::native;
+use crate::factors::normalization::{Curve, Normalization};
+use crate::factors::registry::{Factor, FactorContext, FactorOutput};
+use crate::workspace::{ParsedWorkspace, SourceLocation};
 use std::collections::HashSet;
-use std::path::PathBuf;
+use std::path::Path;
 use syn::{
     parse::{Parse, ParseStream},
     punctuated::Punctuated,
     visit::Visit,
-    Attribute, Expr, ExprCall, ExprField, ExprLit, ExprMethodCall, ExprPath, Ident, Item, Lit,
-    LitByteStr, LitStr, Meta, MetaList, Token,
+    Attribute, Expr, ExprCall, ImplItemFn, Item, ItemFn, Lit, Signature, Token,
 };
 
 #[derive(Debug, Clone, Default)]
@@ -27,6 +34,9 @@ pub struct PdaMetrics {
     /// Secondary/Audit Metrics (Minimal Noise)
     pub distinct_seed_patterns: usize,
     pub seed_patterns: HashSet<String>,
+
+    /// Where each counted PDA account is declared
+    pub pda_locations: Vec<SourceLocation>,
 }
 
 impl PdaMetrics {
@@ -43,14 +53,30 @@ impl PdaMetrics {
             // Debug/Detailed fields (useful for detailed report but minimized)
             "distinctSeedPatterns": self.distinct_seed_patterns,
             "seedPatterns": self.seed_patterns.iter().collect::<Vec<_>>(),
+            "pdaLocations": self.pda_locations,
         })
     }
 
-    /// Calculate the PDA Complexity Factor (0-100, higher = riskier)
+    /// Default PDA Complexity Factor scoring
     /// Formula: min(100, (N_PDA × 5) + S_TotalComplexity)
+    pub fn default_normalization() -> Normalization {
+        Normalization::new(Curve::percent())
+            .term("pdaAccounts", 5.0)
+            .term("seedComplexity", 1.0)
+    }
+
+    /// Measurements the PDA Complexity Factor is computed from
+    pub fn normalization_inputs(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            ("pdaAccounts", self.total_pda_accounts as f64),
+            ("seedComplexity", self.total_seed_complexity_score as f64),
+        ]
+    }
+
+    /// Calculate the PDA Complexity Factor (0-100, higher = riskier) with the default scoring
     pub fn calculate_pda_factor(&mut self) {
-        let weighted_sum = (self.total_pda_accounts * 5) + self.total_seed_complexity_score;
-        self.pda_complexity_factor = (weighted_sum as f64).min(100.0);
+        self.pda_complexity_factor =
+            Self::default_normalization().score(&self.normalization_inputs());
     }
 }
 
@@ -120,70 +146,48 @@ impl Parse for SeedsParser {
 
 /// Calculate PDA seed metrics for workspace files
 pub fn calculate_workspace_pda_seeds(
-    workspace_path: &PathBuf,
+    workspace_path: &Path,
     selected_files: &[String],
+) -> Result<PdaMetrics, Box<dyn std::error::Error>> {
+    let workspace = ParsedWorkspace::load(workspace_path, selected_files);
+    analyze_workspace_pda_seeds(&workspace)
+}
+
+/// Calculate PDA seed metrics over an already-parsed workspace
+pub fn analyze_workspace_pda_seeds(
+    workspace: &ParsedWorkspace,
 ) -> Result<PdaMetrics, Box<dyn std::error::Error>> {
     log::info!(
         "🔍 PDA SEEDS DEBUG: Starting analysis for workspace: {:?}",
-        workspace_path
+        workspace.root
     );
     log::info!(
         "🔍 PDA SEEDS DEBUG: Analyzing {} files",
-        selected_files.len()
+        workspace.files.len()
     );
 
     let mut metrics = PdaMetrics::default();
-    let mut analyzed_files = 0;
+    let analyzed_files = workspace.files.len();
 
     // Analyze each file
-    for file_path in selected_files {
-        let full_file_path = workspace_path.join(file_path);
-
-        if full_file_path.exists() && full_file_path.is_file() {
-            if let Some(extension) = full_file_path.extension() {
-                if extension == "rs" {
-                    match std::fs::read_to_string(&full_file_path) {
-                        Ok(content) => {
-                            match analyze_file_pda_seeds(&content) {
-                                Ok(file_metrics) => {
-                                    // Merge metrics from this file
-                                    metrics.total_pda_accounts += file_metrics.total_pda_accounts;
-                                    metrics.total_seed_complexity_score +=
-                                        file_metrics.total_seed_complexity_score;
-
-                                    // Merge seed patterns
-                                    for pattern in file_metrics.seed_patterns {
-                                        metrics.seed_patterns.insert(pattern);
-                                    }
-
-                                    analyzed_files += 1;
-
-                                    log::debug!(
-                                        "🔍 PDA SEEDS DEBUG: File {}: {} PDA accounts",
-                                        file_path,
-                                        file_metrics.total_pda_accounts
-                                    );
-                                }
-                                Err(e) => {
-                                    log::warn!(
-                                        "🔍 PDA SEEDS DEBUG: Failed to analyze PDA seeds for file {}: {}",
-                                        file_path,
-                                        e
-                                    );
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            log::warn!(
-                                "🔍 PDA SEEDS DEBUG: Failed to read file {}: {}",
-                                file_path,
-                                e
-                            );
-                        }
-                    }
-                }
-            }
+    for file in &workspace.files {
+        let file_metrics = analyze_ast_pda_seeds(&file.path, &file.ast);
+
+        // Merge metrics from this file
+        metrics.total_pda_accounts += file_metrics.total_pda_accounts;
+        metrics.total_seed_complexity_score += file_metrics.total_seed_complexity_score;
+        metrics.pda_locations.extend(file_metrics.pda_locations);
+
+        // Merge seed patterns
+        for pattern in file_metrics.seed_patterns {
+            metrics.seed_patterns.insert(pattern);
         }
+
+        log::debug!(
+            "🔍 PDA SEEDS DEBUG: File {}: {} PDA accounts",
+            file.path,
+            file_metrics.total_pda_accounts
+        );
     }
 
     // Calculate distinct seed patterns
@@ -209,8 +213,13 @@ pub fn analyze_file_pda_seeds(content: &str) -> Result<PdaMetrics, Box<dyn std::
     let syntax_tree: syn::File =
         syn::parse_file(content).map_err(|e| format!("Failed to parse Rust file: {}", e))?;
 
-    let mut visitor = PdaSeedsVisitor::new();
-    visitor.visit_file(&syntax_tree);
+    Ok(analyze_ast_pda_seeds("", &syntax_tree))
+}
+
+/// Analyze PDA seed patterns in a parsed file
+pub fn analyze_ast_pda_seeds(file_path: &str, syntax_tree: &syn::File) -> PdaMetrics {
+    let mut visitor = PdaSeedsVisitor::new(file_path);
+    visitor.visit_file(syntax_tree);
 
     // Calculate distinct seed patterns
     visitor.metrics.distinct_seed_patterns = visitor.metrics.seed_patterns.len();
@@ -218,21 +227,33 @@ pub fn analyze_file_pda_seeds(content: &str) -> Result<PdaMetrics, Box<dyn std::
     // Calculate the PDA Complexity Factor
     visitor.metrics.calculate_pda_factor();
 
-    Ok(visitor.metrics)
+    visitor.metrics
 }
 
 /// Visitor to analyze PDA seed patterns using full AST approach
 struct PdaSeedsVisitor {
     metrics: PdaMetrics,
+    file_path: String,
+    in_native_fn: bool,
 }
 
 impl PdaSeedsVisitor {
-    fn new() -> Self {
+    fn new(file_path: &str) -> Self {
         Self {
             metrics: PdaMetrics::default(),
+            file_path: file_path.to_string(),
+            in_native_fn: false,
         }
     }
 
+    /// Visit a function body, tracking whether it is native program code
+    fn visit_fn_body(&mut self, sig: &Signature, visit: impl FnOnce(&mut Self)) {
+        let outer = self.in_native_fn;
+        self.in_native_fn = native::is_native_sig(sig);
+        visit(self);
+        self.in_native_fn = outer;
+    }
+
     /// Check if an attribute contains PDA seeds using AST analysis
     fn has_pda_seeds(&self, attr: &Attribute) -> bool {
         // Check if this is an account attribute with seeds
@@ -345,6 +366,28 @@ impl PdaSeedsVisitor {
         }
     }
 
+    /// Seed complexity of a native derivation's seeds argument (`&[b"vault",
+    /// user.key.as_ref()]`); with `create_program_address` the caller passes
+    /// the bump (`&[bump]`) itself, the native form of a manual bump
+    fn analyze_derived_seeds(&mut self, seeds: &Expr, manual_bump: bool) -> usize {
+        let Expr::Array(array) = strip_seed(seeds) else {
+            // Seeds built elsewhere (`&seeds`): a single opaque seed
+            return self.analyze_seed_expression(seeds);
+        };
+
+        let mut score = 0;
+        for seed in &array.elems {
+            let seed = strip_seed(seed);
+            if manual_bump && matches!(seed, Expr::Array(_)) {
+                score += 5;
+                self.metrics.seed_patterns.insert("manual_bump".to_string());
+            } else {
+                score += self.analyze_seed_expression(seed);
+            }
+        }
+        score
+    }
+
     /// Calculate seed complexity score from string analysis (fallback)
     fn calculate_seed_complexity_score_from_string(&mut self, seeds_str: &str) -> usize {
         let mut total_score = 0;
@@ -452,31 +495,124 @@ impl PdaSeedsVisitor {
 
 // Removed old SeedComplexity and SeedAnalysis - replaced by direct AST analysis
 
+/// A seed without `&` and a no-op `.as_ref()`, so `&b"vault".as_ref()` is a literal
+fn strip_seed(mut expr: &Expr) -> &Expr {
+    loop {
+        expr = match expr {
+            Expr::Reference(reference) => &reference.expr,
+            Expr::MethodCall(call) if call.method == "as_ref" && call.args.is_empty() => {
+                match &*call.receiver {
+                    receiver @ Expr::Lit(_) => receiver,
+                    _ => return expr,
+                }
+            }
+            _ => return expr,
+        };
+    }
+}
+
+/// Whether a call derives a PDA in code, and whether it takes the bump as a seed
+fn pda_derivation(call: &ExprCall) -> Option<bool> {
+    let Expr::Path(func) = &*call.func else {
+        return None;
+    };
+    match func.path.segments.last()?.ident.to_string().as_str() {
+        "find_program_address" | "try_find_program_address" => Some(false),
+        "create_program_address" => Some(true),
+        _ => None,
+    }
+}
+
 impl<'ast> Visit<'ast> for PdaSeedsVisitor {
     fn visit_item(&mut self, item: &'ast Item) {
-        match item {
-            Item::Struct(item_struct) => {
-                // Check struct fields for PDA seeds using full AST analysis
-                for field in &item_struct.fields {
-                    for attr in &field.attrs {
-                        if let Some(complexity_score) = self.analyze_seeds_attribute(attr) {
-                            self.metrics.total_pda_accounts += 1;
-                            self.metrics.total_seed_complexity_score += complexity_score;
-
-                            log::debug!(
-                                "🔍 PDA SEEDS DEBUG: Found PDA account with complexity score: {}",
-                                complexity_score
-                            );
-                        }
+        if let Item::Struct(item_struct) = item {
+            // Check struct fields for PDA seeds using full AST analysis
+            for field in &item_struct.fields {
+                for attr in &field.attrs {
+                    if let Some(complexity_score) = self.analyze_seeds_attribute(attr) {
+                        self.metrics.total_pda_accounts += 1;
+                        self.metrics.total_seed_complexity_score += complexity_score;
+                        self.metrics
+                            .pda_locations
+                            .push(SourceLocation::of(&self.file_path, field));
+
+                        log::debug!(
+                            "🔍 PDA SEEDS DEBUG: Found PDA account with complexity score: {}",
+                            complexity_score
+                        );
                     }
                 }
             }
-            _ => {}
         }
 
         // Continue visiting other items
         syn::visit::visit_item(self, item);
     }
+
+    fn visit_item_fn(&mut self, node: &'ast ItemFn) {
+        self.visit_fn_body(&node.sig, |v| syn::visit::visit_item_fn(v, node));
+    }
+
+    fn visit_impl_item_fn(&mut self, node: &'ast ImplItemFn) {
+        self.visit_fn_body(&node.sig, |v| syn::visit::visit_impl_item_fn(v, node));
+    }
+
+    // Only native code derives its PDAs in code; Anchor declares them in `seeds`
+    fn visit_expr_call(&mut self, node: &'ast ExprCall) {
+        if let (true, Some(manual_bump), Some(seeds)) =
+            (self.in_native_fn, pda_derivation(node), node.args.first())
+        {
+            let complexity_score = self.analyze_derived_seeds(seeds, manual_bump);
+            self.metrics.total_pda_accounts += 1;
+            self.metrics.total_seed_complexity_score += complexity_score;
+            self.metrics
+                .pda_locations
+                .push(SourceLocation::of(&self.file_path, node));
+        }
+
+        syn::visit::visit_expr_call(self, node);
+    }
+}
+
+/// Registry entry for the PDA seed factor
+pub struct PdaSeedsFactor;
+
+impl Factor for PdaSeedsFactor {
+    fn id(&self) -> &'static str {
+        "pdaSeeds"
+    }
+
+    fn version(&self) -> &'static str {
+        "1.2.0"
+    }
+
+    fn name(&self) -> &'static str {
+        "PDA seeds"
+    }
+
+    fn required(&self) -> bool {
+        true
+    }
+
+    fn normalization(&self) -> Option<Normalization> {
+        Some(PdaMetrics::default_normalization())
+    }
+
+    fn compute(&self, ctx: &FactorContext) -> Result<FactorOutput, Box<dyn std::error::Error>> {
+        let mut metrics = analyze_workspace_pda_seeds(ctx.workspace)?;
+        metrics.pda_complexity_factor = ctx
+            .normalization_or(PdaMetrics::default_normalization)
+            .score(&metrics.normalization_inputs());
+        Ok(
+            FactorOutput::keyed(self.id(), metrics.pda_complexity_factor, metrics.to_json()).with_note(format!(
+                "Analyzed PDA seeds: {} total accounts, {} complexity score, PDA Factor: {:.2}, {} distinct patterns",
+                metrics.total_pda_accounts,
+                metrics.total_seed_complexity_score,
+                metrics.pda_complexity_factor,
+                metrics.distinct_seed_patterns,
+            )),
+        )
+    }
 }
 
 #[cfg(test)]
@@ -497,6 +633,8 @@ mod tests {
         assert_eq!(result.total_pda_accounts, 1);
         assert_eq!(result.total_seed_complexity_score, 1); // 1 literal seed
         assert_eq!(result.pda_complexity_factor, 6.0); // (1 × 5) + 1 = 6
+        assert_eq!(result.pda_locations.len(), 1);
+        assert_eq!(result.pda_locations[0].line, 4); // The field's #[account(...)] attribute
     }
 
     #[test]
@@ -551,6 +689,44 @@ mod tests {
         assert!(result.seed_patterns.contains("manual_bump"));
     }
 
+    #[test]
+    fn test_native_pda_derivations() {
+        let code = r#"
+            pub fn process_init(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+                let (vault, bump) = Pubkey::find_program_address(&[b"vault", payer.key.as_ref()], program_id);
+                let signer = Pubkey::create_program_address(&[b"vault", &[bump]], program_id)?;
+                Ok(())
+            }
+        "#;
+
+        let result = analyze_file_pda_seeds(code).unwrap();
+        assert_eq!(result.total_pda_accounts, 2);
+        // (1 literal + 1 method call) + (1 literal + 5 manual bump) = 4 + 6
+        assert_eq!(result.total_seed_complexity_score, 10);
+        assert!(result.seed_patterns.contains("manual_bump"));
+        assert_eq!(result.pda_locations[0].line, 3);
+    }
+
+    #[test]
+    fn test_anchor_rederivation_not_counted() {
+        let code = r#"
+            #[derive(Accounts)]
+            pub struct Initialize<'info> {
+                #[account(seeds = [b"vault", payer.key().as_ref()], bump)]
+                pub vault: Account<'info, Vault>,
+            }
+
+            pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
+                let (vault, _bump) = Pubkey::find_program_address(&[b"vault", ctx.accounts.payer.key().as_ref()], ctx.program_id);
+                Ok(())
+            }
+        "#;
+
+        let result = analyze_file_pda_seeds(code).unwrap();
+        assert_eq!(result.total_pda_accounts, 1);
+        assert_eq!(result.pda_locations[0].line, 4);
+    }
+
     #[test]
     fn test_ast_vs_string_parsing() {
         let code = r#"
@@ -7,13 +7,15 @@
 //! 2. Account Closes (High Risk): Handlers that close accounts.
 //! 3. Manual Checks (Highest Risk): Handlers that manually check signers.
 
+use crate::factors::handlers::HandlerIndex;
+use crate::factors::normalization::{Curve, Normalization};
+use crate::factors::registry::{Factor, FactorContext, FactorOutput, FindingRule, Severity};
+use crate::workspace::{ParsedWorkspace, SourceLocation};
 use quote::quote;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
-use std::path::PathBuf;
 use syn::{
     parse::{Parse, ParseStream},
-    parse_file,
     visit::{self, Visit},
     BinOp, Expr, ExprBinary, ExprField, ExprMethodCall, ItemFn, ItemStruct, Path, Token,
 };
@@ -37,6 +39,8 @@ pub struct PrivilegedRolesMetrics {
     pub handlers_with_manual_checks: HashSet<String>,
     pub handlers_with_closes: HashSet<String>,
     pub raw_risk_score: f64,
+    /// Where each manual check was found
+    pub manual_check_locations: Vec<SourceLocation>,
 }
 
 impl PrivilegedRolesMetrics {
@@ -51,8 +55,35 @@ impl PrivilegedRolesMetrics {
             "totalHandlersFound": self.total_handlers_found,
             "handlersWithManualChecks": self.handlers_with_manual_checks,
             "handlersWithCloses": self.handlers_with_closes,
+            "manualCheckLocations": self.manual_check_locations,
         })
     }
+
+    /// Default privileged roles scoring
+    /// Raw score: gated handlers × 1, with a 5x penalty for manual checks and account closes
+    /// Upper bound: 50 (10 manual checks, or 10 closes, or a mix) = 100% risk
+    pub fn default_normalization() -> Normalization {
+        Normalization::new(Curve::linear(0.0, 50.0))
+            .term("gatedHandlers", 1.0)
+            .term("manualChecks", 5.0)
+            .term("accountCloses", 5.0)
+    }
+
+    /// Measurements the factor is computed from
+    pub fn normalization_inputs(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            ("gatedHandlers", self.total_gated_handlers as f64),
+            ("manualChecks", self.total_manual_checks as f64),
+            ("accountCloses", self.total_account_closes as f64),
+        ]
+    }
+
+    /// Fill the raw score and the normalized factor using `normalization`
+    fn score_with(&mut self, normalization: &Normalization) {
+        let inputs = self.normalization_inputs();
+        self.raw_risk_score = normalization.weighted_sum(&inputs);
+        self.ac_factor = normalization.score(&inputs);
+    }
 }
 
 /// Helper struct for the custom constraint parser
@@ -96,66 +127,44 @@ impl Parse for ConstraintParserStats {
 }
 
 /// Visitor to implement the Two-Pass strategy
-#[derive(Debug, Default)]
-struct AcVisitor {
+#[derive(Debug)]
+struct AcVisitor<'a> {
+    handlers: &'a HandlerIndex<'a>,
     metrics: PrivilegedRolesMetrics,
     // We store stats for each Account Struct to link to its handler
     struct_stats: HashMap<String, ConstraintParserStats>,
     // State for visiting function bodies
     current_handler_name: Option<String>,
     manual_checks_in_handler: u32,
+    current_handler_file: &'a str,
 }
 
-impl AcVisitor {
-    /// Checks if an argument is the Anchor Context
-    fn is_context_arg(&self, arg: &syn::FnArg) -> bool {
-        if let syn::FnArg::Typed(pat_type) = arg {
-            if let syn::Type::Path(type_path) = &*pat_type.ty {
-                if let Some(segment) = type_path.path.segments.last() {
-                    return segment.ident == "Context";
-                }
-            }
-        }
-        false
-    }
-
-    /// Gets the name of the struct from a `Context<StructName>`
-    fn get_context_struct_name(&self, arg: &syn::FnArg) -> Option<String> {
-        if let syn::FnArg::Typed(pat_type) = arg {
-            if let syn::Type::Path(type_path) = &*pat_type.ty {
-                if let Some(segment) = type_path.path.segments.last() {
-                    if segment.ident == "Context" {
-                        if let syn::PathArguments::AngleBracketed(args) = &segment.arguments {
-                            if let Some(syn::GenericArgument::Type(syn::Type::Path(ty))) =
-                                args.args.first()
-                            {
-                                return ty.path.segments.last().map(|s| s.ident.to_string());
-                            }
-                        }
-                    }
-                }
-            }
+impl<'a> AcVisitor<'a> {
+    fn new(handlers: &'a HandlerIndex<'a>) -> Self {
+        Self {
+            handlers,
+            metrics: PrivilegedRolesMetrics::default(),
+            struct_stats: HashMap::new(),
+            current_handler_name: None,
+            manual_checks_in_handler: 0,
+            current_handler_file: "",
         }
-        None
     }
 
     /// Checks if an expression is a `...key()` or `...key`
     fn is_key_access(&self, expr: &Expr) -> bool {
         match expr {
             Expr::MethodCall(ExprMethodCall { method, .. }) => method == "key",
-            Expr::Field(ExprField { member, .. }) => {
-                if let syn::Member::Named(ident) = member {
-                    ident == "key"
-                } else {
-                    false
-                }
-            }
+            Expr::Field(ExprField {
+                member: syn::Member::Named(ident),
+                ..
+            }) => ident == "key",
             _ => false,
         }
     }
 }
 
-impl<'ast> Visit<'ast> for AcVisitor {
+impl<'ast> Visit<'ast> for AcVisitor<'_> {
     /// --- Pass 1: Find Account Structs (for Sub-factors 1 & 2) ---
     fn visit_item_struct(&mut self, node: &'ast ItemStruct) {
         let is_accounts_struct = node.attrs.iter().any(|attr| {
@@ -187,17 +196,14 @@ impl<'ast> Visit<'ast> for AcVisitor {
 
     /// --- Pass 2: Find Handlers & Manual Checks (Sub-factor 3) ---
     fn visit_item_fn(&mut self, node: &'ast ItemFn) {
-        let is_anchor_handler = matches!(node.vis, syn::Visibility::Public(_))
-            && node.sig.inputs.len() > 0
-            && self.is_context_arg(&node.sig.inputs[0]);
-
-        if is_anchor_handler {
+        if let Some(handler) = self.handlers.lookup(node) {
             self.metrics.total_handlers_found += 1;
-            let handler_name = node.sig.ident.to_string();
+            let handler_name = handler.name.clone();
+            self.current_handler_file = handler.body().file;
 
             // --- Check Gated Handlers & Closes (from Pass 1 data) ---
-            if let Some(struct_name) = self.get_context_struct_name(&node.sig.inputs[0]) {
-                if let Some(stats) = self.struct_stats.get(&struct_name) {
+            if let Some(struct_name) = &handler.accounts {
+                if let Some(stats) = self.struct_stats.get(struct_name) {
                     if stats.auth_constraint_count > 0 {
                         self.metrics.total_gated_handlers += 1;
                     }
@@ -235,17 +241,20 @@ impl<'ast> Visit<'ast> for AcVisitor {
     /// --- Find Manual Checks: `if ...key() != ...key` ---
     fn visit_expr_binary(&mut self, node: &'ast ExprBinary) {
         // Only check inside a handler
-        if self.current_handler_name.is_some() {
+        if let Some(handler_name) = &self.current_handler_name {
             // Look for `!=`
             if matches!(node.op, BinOp::Ne(_)) {
                 // Check if left or right side is a `.key` or `.key()`
                 if self.is_key_access(&node.left) || self.is_key_access(&node.right) {
                     log::warn!(
                         "🔍 AC DEBUG: Found potential manual check in handler '{}': {}",
-                        self.current_handler_name.as_ref().unwrap(),
+                        handler_name,
                         quote!(#node)
                     );
                     self.manual_checks_in_handler += 1;
+                    self.metrics
+                        .manual_check_locations
+                        .push(SourceLocation::of(self.current_handler_file, node));
                 }
             }
         }
@@ -255,48 +264,30 @@ impl<'ast> Visit<'ast> for AcVisitor {
 
 /// Main driver function to run the analysis
 pub fn calculate_workspace_privileged_roles(
-    workspace_path: &PathBuf,
+    workspace_path: &std::path::Path,
     selected_files: &[String],
+) -> Result<PrivilegedRolesMetrics, Box<dyn std::error::Error>> {
+    let workspace = ParsedWorkspace::load(workspace_path, selected_files);
+    analyze_workspace_privileged_roles(&workspace)
+}
+
+/// Run the access control surface analysis over an already-parsed workspace
+pub fn analyze_workspace_privileged_roles(
+    workspace: &ParsedWorkspace,
 ) -> Result<PrivilegedRolesMetrics, Box<dyn std::error::Error>> {
     log::info!("🔍 ACCESS CONTROL DEBUG: Starting analysis...");
 
-    let mut visitor = AcVisitor::default();
+    let handlers = HandlerIndex::from_workspace(workspace);
+    let mut visitor = AcVisitor::new(&handlers);
 
-    for file_path in selected_files {
-        let full_path = workspace_path.join(file_path);
-        if !full_path.exists() || !full_path.is_file() {
-            continue;
-        }
-
-        match std::fs::read_to_string(&full_path) {
-            Ok(content) => match parse_file(&content) {
-                Ok(ast) => {
-                    visitor.visit_file(&ast);
-                }
-                Err(e) => {
-                    log::warn!("Failed to parse AST for {:?}: {}", full_path, e);
-                }
-            },
-            Err(e) => {
-                log::warn!("Failed to read file {:?}: {}", full_path, e);
-            }
-        }
+    for file in &workspace.files {
+        visitor.visit_file(&file.ast);
     }
 
     // --- Final Calculation and Normalization (0-100) ---
     let metrics = &mut visitor.metrics;
 
-    // 1. Calculate the Raw Score (from summary: 5x penalty for anti-patterns)
-    let raw_risk_score = (metrics.total_gated_handlers as f64 * 1.0)
-        + (metrics.total_manual_checks as f64 * 5.0)
-        + (metrics.total_account_closes as f64 * 5.0);
-
-    // 2. Normalize to 0-100
-    let upper_bound = 50.0; // 10 manual checks, or 10 closes, or a mix = 100% risk
-    let factor = (raw_risk_score / upper_bound) * 100.0;
-
-    metrics.ac_factor = factor.min(100.0); // Cap at 100
-    metrics.raw_risk_score = raw_risk_score;
+    metrics.score_with(&PrivilegedRolesMetrics::default_normalization());
 
     log::info!(
         "🔍 ACCESS CONTROL DEBUG: Analysis complete. Factor: {:.2}",
@@ -305,3 +296,61 @@ pub fn calculate_workspace_privileged_roles(
 
     Ok(visitor.metrics)
 }
+
+/// A handler compares account keys by hand instead of using an Anchor constraint
+pub const MANUAL_SIGNER_CHECK: FindingRule = FindingRule {
+    id: "privilegedRoles/manualSignerCheck",
+    name: "ManualSignerCheck",
+    description: "Account key compared manually inside a handler instead of with a `signer`/`has_one` constraint",
+    severity: Severity::Warning,
+};
+
+/// Registry entry for the privileged roles factor
+pub struct PrivilegedRolesFactor;
+
+impl Factor for PrivilegedRolesFactor {
+    fn id(&self) -> &'static str {
+        "privilegedRoles"
+    }
+
+    fn version(&self) -> &'static str {
+        "1.3.0"
+    }
+
+    fn name(&self) -> &'static str {
+        "Privileged roles"
+    }
+
+    fn rules(&self) -> &'static [FindingRule] {
+        &[MANUAL_SIGNER_CHECK]
+    }
+
+    fn normalization(&self) -> Option<Normalization> {
+        Some(PrivilegedRolesMetrics::default_normalization())
+    }
+
+    fn compute(&self, ctx: &FactorContext) -> Result<FactorOutput, Box<dyn std::error::Error>> {
+        let mut metrics = analyze_workspace_privileged_roles(ctx.workspace)?;
+        metrics.score_with(&ctx.normalization_or(PrivilegedRolesMetrics::default_normalization));
+        let findings = metrics
+            .manual_check_locations
+            .iter()
+            .map(|location| {
+                MANUAL_SIGNER_CHECK.at(
+                    location.clone(),
+                    "Manual account key check; prefer an Anchor `signer` or `has_one` constraint",
+                )
+            })
+            .collect();
+        Ok(
+            FactorOutput::keyed(self.id(), metrics.ac_factor, metrics.to_json()).with_note(format!(
+                "Analyzed access control: {} gated handlers, {} account closes, {} manual checks, AC factor {:.1}",
+                metrics.total_gated_handlers,
+                metrics.total_account_closes,
+                metrics.total_manual_checks,
+                metrics.ac_factor,
+            ))
+            .with_findings(findings),
+        )
+    }
+}
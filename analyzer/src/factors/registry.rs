@@ -0,0 +1,1025 @@
+//! Factor trait and registry
+//!
+//! Every analysis factor implements [`Factor`] and is registered once in
+//! [`FactorRegistry::default`]. The `/augment` endpoint, the CLI and library
+//! callers all iterate the same registry, so they always run the same set of
+//! factors in the same order.
+
+use crate::cache::{AnalysisCache, CacheKey, ANALYZER_VERSION};
+use crate::config::FactorSettings;
+use crate::error::AnalyzerError;
+use crate::factors::normalization::Normalization;
+use crate::parallel;
+use crate::workspace::{Diagnostic, ParsedWorkspace, SourceLocation};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::Path;
+
+/// Inputs shared by every factor computation
+#[derive(Debug, Clone, Copy)]
+pub struct FactorContext<'a> {
+    /// Selected files, read and parsed once for all factors
+    pub workspace: &'a ParsedWorkspace,
+
+    /// Root of the workspace being analyzed
+    pub workspace_path: &'a Path,
+
+    /// Files to analyze, relative to `workspace_path`
+    pub selected_files: &'a [String],
+
+    /// Solana RPC URL for on-chain analysis (optional)
+    pub rpc_url: Option<&'a str>,
+
+    /// Cache of factor results from earlier runs (optional)
+    pub cache: Option<&'a AnalysisCache>,
+
+    /// Threads to run factors on; 0 means one per core
+    pub workers: usize,
+
+    /// Effective normalization of the factor being computed
+    pub normalization: Option<&'a Normalization>,
+}
+
+impl<'a> FactorContext<'a> {
+    pub fn new(workspace: &'a ParsedWorkspace, selected_files: &'a [String]) -> Self {
+        Self {
+            workspace,
+            workspace_path: &workspace.root,
+            selected_files,
+            rpc_url: None,
+            cache: None,
+            workers: 1,
+            normalization: None,
+        }
+    }
+
+    pub fn with_rpc_url(mut self, rpc_url: Option<&'a str>) -> Self {
+        self.rpc_url = rpc_url;
+        self
+    }
+
+    pub fn with_cache(mut self, cache: Option<&'a AnalysisCache>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    pub fn with_workers(mut self, workers: usize) -> Self {
+        self.workers = workers;
+        self
+    }
+
+    pub fn with_normalization(mut self, normalization: Option<&'a Normalization>) -> Self {
+        self.normalization = normalization;
+        self
+    }
+
+    /// The configured normalization, or `default` outside a registry run
+    pub fn normalization_or(&self, default: fn() -> Normalization) -> Normalization {
+        self.normalization.cloned().unwrap_or_else(default)
+    }
+}
+
+/// A single key written into the augment `factors` map
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FactorField {
+    pub key: String,
+    pub value: Value,
+    /// Whether the key is reported in the `computed`/`overridden` lists
+    pub computed: bool,
+}
+
+/// How serious a finding is; the levels are SARIF's result levels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Note,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Note => "note",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+/// A kind of finding a factor reports
+#[derive(Debug, Clone, Copy)]
+pub struct FindingRule {
+    /// Stable identifier, `<factor id>/<rule>`
+    pub id: &'static str,
+
+    /// Short PascalCase name
+    pub name: &'static str,
+
+    /// One-sentence description of what the rule flags
+    pub description: &'static str,
+
+    pub severity: Severity,
+}
+
+impl FindingRule {
+    /// A finding for this rule at `location`
+    pub fn at(&self, location: SourceLocation, message: impl Into<String>) -> Finding {
+        Finding {
+            rule_id: self.id.to_string(),
+            severity: self.severity,
+            message: message.into(),
+            location,
+        }
+    }
+}
+
+/// One concrete observation a factor made at a source location
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Finding {
+    pub rule_id: String,
+    pub severity: Severity,
+    pub message: String,
+    pub location: SourceLocation,
+}
+
+/// Result of computing one factor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FactorOutput {
+    /// Normalized 0-100 score
+    pub score: f64,
+
+    /// Full metrics object (the factor's `to_json`)
+    pub details: Value,
+
+    /// Keys written into the augment `factors` map, in order
+    pub fields: Vec<FactorField>,
+
+    /// Human-readable summary for the augment `notes`
+    pub note: Option<String>,
+
+    /// Located observations behind the score
+    #[serde(default)]
+    pub findings: Vec<Finding>,
+}
+
+impl FactorOutput {
+    /// Output with no fields; the score is clamped to 0-100
+    pub fn new(score: f64, details: Value) -> Self {
+        Self {
+            score: score.clamp(0.0, 100.0),
+            details,
+            fields: Vec::new(),
+            note: None,
+            findings: Vec::new(),
+        }
+    }
+
+    /// Output that publishes `details` under `key` (the common case)
+    pub fn keyed(key: &str, score: f64, details: Value) -> Self {
+        let output = Self::new(score, details.clone());
+        output.with_field(key, details)
+    }
+
+    /// Add a computed field
+    pub fn with_field(mut self, key: &str, value: Value) -> Self {
+        self.fields.push(FactorField {
+            key: key.to_string(),
+            value,
+            computed: true,
+        });
+        self
+    }
+
+    /// Add a reference-only field that is not listed as computed
+    pub fn with_reference(mut self, key: &str, value: Value) -> Self {
+        self.fields.push(FactorField {
+            key: key.to_string(),
+            value,
+            computed: false,
+        });
+        self
+    }
+
+    pub fn with_note(mut self, note: String) -> Self {
+        self.note = Some(note);
+        self
+    }
+
+    pub fn with_findings(mut self, findings: Vec<Finding>) -> Self {
+        self.findings.extend(findings);
+        self
+    }
+}
+
+/// A workspace-level analysis factor
+pub trait Factor: Send + Sync {
+    /// Stable identifier (the primary key in the augment `factors` map)
+    fn id(&self) -> &'static str;
+
+    /// Version of the factor's scoring logic
+    fn version(&self) -> &'static str;
+
+    /// Human-readable name used in notes and error messages
+    fn name(&self) -> &'static str;
+
+    /// Required factors abort the whole run when they fail
+    fn required(&self) -> bool {
+        false
+    }
+
+    /// Whether results may be reused for an unchanged workspace
+    ///
+    /// Factors that read anything besides the selected files (the network,
+    /// files outside the selection) must opt out.
+    fn cacheable(&self, _ctx: &FactorContext) -> bool {
+        true
+    }
+
+    /// Kinds of findings this factor can report
+    fn rules(&self) -> &'static [FindingRule] {
+        &[]
+    }
+
+    /// Default score normalization, for factors whose scoring is configurable
+    fn normalization(&self) -> Option<Normalization> {
+        None
+    }
+
+    /// Whether the factor analyzes the selected Rust sources
+    ///
+    /// Such factors are reported incomplete when a source failed to parse.
+    fn reads_sources(&self) -> bool {
+        true
+    }
+
+    /// Compute the factor over the workspace
+    fn compute(&self, ctx: &FactorContext) -> Result<FactorOutput, Box<dyn Error>>;
+
+    /// Fallback output used when `compute` fails (optional factors only)
+    fn fallback(&self, _error: &dyn Error) -> Option<FactorOutput> {
+        None
+    }
+}
+
+/// Error raised when a required factor fails
+#[derive(Debug)]
+pub struct FactorFailure {
+    pub factor_id: &'static str,
+    pub message: String,
+}
+
+impl std::fmt::Display for FactorFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for FactorFailure {}
+
+/// Aggregated output of a registry run
+#[derive(Debug, Default)]
+pub struct FactorReport {
+    /// The augment `factors` map
+    pub factors: Map<String, Value>,
+
+    /// Keys that were computed (reported as `overridden`)
+    pub computed: Vec<String>,
+
+    /// Per-factor summaries and non-fatal failures
+    pub notes: Vec<String>,
+
+    /// Normalized score per factor id, in registry order
+    pub scores: Vec<(String, f64)>,
+
+    /// Full metrics object per factor id
+    pub details: Map<String, Value>,
+
+    /// Factor ids whose output was reused from the cache
+    pub cached: Vec<String>,
+
+    /// Located findings from every factor, in registry order
+    pub findings: Vec<Finding>,
+
+    /// Weighted mean of `scores`, using the registry's factor weights
+    pub composite_score: f64,
+
+    /// Effective normalization per factor id, for reproducing the scores
+    pub normalization: Map<String, Value>,
+
+    /// Selected files that could not be read or fully parsed
+    pub diagnostics: Vec<Diagnostic>,
+
+    /// Factor ids computed without some of the selected sources
+    pub incomplete: Vec<String>,
+}
+
+impl FactorReport {
+    fn record(&mut self, id: &str, output: FactorOutput) {
+        for field in output.fields {
+            if field.computed {
+                self.computed.push(field.key.clone());
+            }
+            self.factors.insert(field.key, field.value);
+        }
+        if let Some(note) = output.note {
+            self.notes.push(note);
+        }
+        self.scores.push((id.to_string(), output.score));
+        if !output.details.is_null() {
+            self.details.insert(id.to_string(), output.details);
+        }
+        self.findings.extend(output.findings);
+    }
+
+    /// Point locations in macro-generated code at the items that generated them
+    fn map_expanded(&mut self, workspace: &ParsedWorkspace) {
+        if !workspace.is_expanded() {
+            return;
+        }
+        for finding in &mut self.findings {
+            if let Some(location) = workspace.original_location(&finding.location) {
+                finding.location = location;
+            }
+        }
+        for details in self.details.values_mut() {
+            workspace.map_expanded_locations(details);
+        }
+    }
+
+    /// Summarize the diagnostics and incomplete factors in the notes
+    fn note_incomplete(&mut self) {
+        if self.diagnostics.is_empty() {
+            return;
+        }
+        self.notes.push(format!(
+            "{} selected file(s) could not be fully parsed; {} factor(s) are incomplete: {}",
+            self.diagnostics.len(),
+            self.incomplete.len(),
+            self.diagnostics
+                .iter()
+                .map(Diagnostic::display)
+                .collect::<Vec<_>>()
+                .join("; ")
+        ));
+    }
+}
+
+/// Ordered collection of factors
+pub struct FactorRegistry {
+    factors: Vec<Box<dyn Factor>>,
+
+    /// Composite-score weights that differ from the default of 1.0
+    weights: BTreeMap<String, f64>,
+
+    /// Configured normalizations that replace a factor's default
+    normalizations: BTreeMap<String, Normalization>,
+}
+
+impl FactorRegistry {
+    /// Empty registry
+    pub fn new() -> Self {
+        Self {
+            factors: Vec::new(),
+            weights: BTreeMap::new(),
+            normalizations: BTreeMap::new(),
+        }
+    }
+
+    /// Apply per-factor settings: drop disabled factors, set weights and
+    /// normalization overrides
+    ///
+    /// Unknown factor ids, disabled required factors, negative weights and
+    /// invalid normalizations are configuration errors.
+    pub fn configure(mut self, settings: &BTreeMap<String, FactorSettings>) -> crate::Result<Self> {
+        for (id, setting) in settings {
+            let Some(factor) = self.get(id) else {
+                return Err(AnalyzerError::ConfigError {
+                    message: format!("Unknown factor '{}' in [factors]", id),
+                });
+            };
+            let (required, default_normalization) = (factor.required(), factor.normalization());
+            if !setting.enabled && required {
+                return Err(AnalyzerError::ConfigError {
+                    message: format!("Factor '{}' is required and cannot be disabled", id),
+                });
+            }
+            if !(setting.weight >= 0.0 && setting.weight.is_finite()) {
+                return Err(AnalyzerError::ConfigError {
+                    message: format!("Factor '{}' has invalid weight {}", id, setting.weight),
+                });
+            }
+            if setting.weight != 1.0 {
+                self.weights.insert(id.clone(), setting.weight);
+            }
+            if let Some(overrides) = &setting.normalization {
+                let normalization = default_normalization
+                    .ok_or_else(|| AnalyzerError::ConfigError {
+                        message: format!("Factor '{}' has no configurable normalization", id),
+                    })?
+                    .with_overrides(overrides)
+                    .map_err(|e| AnalyzerError::ConfigError {
+                        message: format!("Invalid normalization for factor '{}': {}", id, e),
+                    })?;
+                self.normalizations.insert(id.clone(), normalization);
+            }
+        }
+
+        self.factors
+            .retain(|factor| settings.get(factor.id()).is_none_or(|s| s.enabled));
+        Ok(self)
+    }
+
+    /// Effective normalization of `factor`: the configured one, else its default
+    pub fn normalization(&self, factor: &dyn Factor) -> Option<Normalization> {
+        effective_normalization(&self.normalizations, factor)
+    }
+
+    /// Weight of a factor's score in the composite score
+    pub fn weight(&self, id: &str) -> f64 {
+        self.weights.get(id).copied().unwrap_or(1.0)
+    }
+
+    /// Weighted mean of per-factor scores; 0 when every weight is 0
+    pub fn composite_score(&self, scores: &[(String, f64)]) -> f64 {
+        let (sum, total_weight) = scores.iter().fold((0.0, 0.0), |(sum, total), (id, score)| {
+            let weight = self.weight(id);
+            (sum + score * weight, total + weight)
+        });
+        if total_weight > 0.0 {
+            sum / total_weight
+        } else {
+            0.0
+        }
+    }
+
+    /// Append a factor; factors run in registration order
+    pub fn register<F: Factor + 'static>(&mut self, factor: F) -> &mut Self {
+        self.factors.push(Box::new(factor));
+        self
+    }
+
+    pub fn factors(&self) -> impl Iterator<Item = &dyn Factor> {
+        self.factors.iter().map(|f| f.as_ref())
+    }
+
+    pub fn get(&self, id: &str) -> Option<&dyn Factor> {
+        self.factors().find(|f| f.id() == id)
+    }
+
+    /// Finding rules of every factor, in registry order
+    pub fn rules(&self) -> impl Iterator<Item = &'static FindingRule> + '_ {
+        self.factors().flat_map(|f| f.rules().iter())
+    }
+
+    pub fn len(&self) -> usize {
+        self.factors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.factors.is_empty()
+    }
+
+    /// Run every registered factor
+    ///
+    /// Optional factors that fail either contribute their fallback or a note;
+    /// a failing required factor aborts the run.
+    ///
+    /// With a cache in the context, a factor whose version and input files
+    /// are unchanged since an earlier run reuses that run's output. With more
+    /// than one worker, factors run concurrently; each worker parses its own
+    /// copy of the workspace (one more full parse per worker, which is why
+    /// [`AnalyzerConfig::factor_workers`](crate::AnalyzerConfig) defaults to
+    /// 1), and results are merged in registry order.
+    pub fn compute_all(&self, ctx: &FactorContext) -> Result<FactorReport, FactorFailure> {
+        let mut report = FactorReport {
+            diagnostics: ctx.workspace.diagnostics(),
+            ..Default::default()
+        };
+        let fingerprint = ctx.cache.map(|_| ctx.workspace.fingerprint());
+        let fingerprint = fingerprint.as_deref();
+
+        for factor in self.factors() {
+            if let Some(normalization) = self.normalization(factor) {
+                report.normalization.insert(
+                    factor.id().to_string(),
+                    serde_json::to_value(normalization).unwrap_or_default(),
+                );
+            }
+        }
+
+        if parallel::worker_count(ctx.workers, self.len()) == 1 {
+            for factor in self.factors() {
+                let normalization = self.normalization(factor);
+                let factor_ctx = ctx.with_normalization(normalization.as_ref());
+                let run = run_factor(factor, &factor_ctx, fingerprint);
+                merge_run(&mut report, factor, ctx.workspace_path, run)?;
+            }
+            report.map_expanded(ctx.workspace);
+            report.composite_score = self.composite_score(&report.scores);
+            report.note_incomplete();
+            return Ok(report);
+        }
+
+        // `FactorContext` borrows thread-bound syntax trees, so workers
+        // rebuild it around their own parsed copy
+        let sources = ctx.workspace.sources();
+        let (selected_files, rpc_url, cache) = (ctx.selected_files, ctx.rpc_url, ctx.cache);
+        let normalizations = &self.normalizations;
+        let runs = parallel::map_with(
+            &self.factors,
+            ctx.workers,
+            || sources.parse(),
+            |workspace, factor| {
+                let normalization = effective_normalization(normalizations, factor.as_ref());
+                let worker_ctx = FactorContext::new(workspace, selected_files)
+                    .with_rpc_url(rpc_url)
+                    .with_cache(cache)
+                    .with_normalization(normalization.as_ref());
+                run_factor(factor.as_ref(), &worker_ctx, fingerprint)
+            },
+        );
+
+        for (factor, run) in self.factors().zip(runs) {
+            merge_run(&mut report, factor, ctx.workspace_path, run)?;
+        }
+        report.map_expanded(ctx.workspace);
+        report.composite_score = self.composite_score(&report.scores);
+        report.note_incomplete();
+        Ok(report)
+    }
+}
+
+fn effective_normalization(
+    configured: &BTreeMap<String, Normalization>,
+    factor: &dyn Factor,
+) -> Option<Normalization> {
+    configured
+        .get(factor.id())
+        .cloned()
+        .or_else(|| factor.normalization())
+}
+
+/// Outcome of one factor, before it is merged into the report
+enum FactorRun {
+    Cached(FactorOutput),
+    /// Errors are kept as messages so runs can cross threads
+    Computed(Result<FactorOutput, String>),
+}
+
+/// Compute one factor, or reuse its cached output for an unchanged workspace
+fn run_factor(factor: &dyn Factor, ctx: &FactorContext, fingerprint: Option<&str>) -> FactorRun {
+    let cached = match (ctx.cache, fingerprint) {
+        (Some(cache), Some(fingerprint)) if factor.cacheable(ctx) => {
+            let normalization = ctx
+                .normalization
+                .and_then(|n| serde_json::to_string(n).ok())
+                .unwrap_or_default();
+            let key = CacheKey::new("factors")
+                .with(ANALYZER_VERSION)
+                .with(factor.id())
+                .with(factor.version())
+                .with(normalization)
+                .with(fingerprint);
+            Some((cache, key))
+        }
+        _ => None,
+    };
+
+    if let Some(output) = cached
+        .as_ref()
+        .and_then(|(cache, key)| cache.load::<FactorOutput>(key))
+    {
+        log::info!(
+            "📊 PROGRESS: {} unchanged, using cached result",
+            factor.name()
+        );
+        return FactorRun::Cached(output);
+    }
+
+    log::info!("📊 PROGRESS: Starting {} analysis...", factor.name());
+
+    match factor.compute(ctx) {
+        Ok(output) => {
+            if let Some(note) = &output.note {
+                log::info!("{}", note);
+            }
+            if let Some((cache, key)) = &cached {
+                cache.store(key, &output);
+            }
+            FactorRun::Computed(Ok(output))
+        }
+        Err(e) => {
+            log::error!(
+                "Failed to calculate {} for workspace {:?}: {}",
+                factor.name(),
+                ctx.workspace_path,
+                e
+            );
+            FactorRun::Computed(Err(e.to_string()))
+        }
+    }
+}
+
+/// Add one factor's outcome to the report; a failed required factor aborts
+fn merge_run(
+    report: &mut FactorReport,
+    factor: &dyn Factor,
+    workspace_path: &Path,
+    run: FactorRun,
+) -> Result<(), FactorFailure> {
+    let output = match run {
+        FactorRun::Cached(output) => {
+            report.cached.push(factor.id().to_string());
+            Ok(output)
+        }
+        FactorRun::Computed(output) => output,
+    };
+    let error = match output {
+        Ok(output) => {
+            if !report.diagnostics.is_empty() && factor.reads_sources() {
+                report.incomplete.push(factor.id().to_string());
+            }
+            report.record(factor.id(), output);
+            return Ok(());
+        }
+        Err(error) => error,
+    };
+
+    if factor.required() {
+        log::error!(
+            "Required factor {} failed for workspace {:?}",
+            factor.name(),
+            workspace_path
+        );
+        return Err(FactorFailure {
+            factor_id: factor.id(),
+            message: format!("{} calculation failed: {}", factor.name(), error),
+        });
+    }
+
+    let boxed: Box<dyn Error> = error.clone().into();
+    match factor.fallback(boxed.as_ref()) {
+        Some(output) => report.record(factor.id(), output),
+        None => report
+            .notes
+            .push(format!("{} analysis failed: {}", factor.name(), error)),
+    }
+    Ok(())
+}
+
+impl Default for FactorRegistry {
+    /// Registry with every built-in factor, in augment order
+    fn default() -> Self {
+        use super::*;
+
+        let mut registry = Self::new();
+        registry
+            .register(lines_of_code::LinesOfCodeFactor)
+            .register(function_count::FunctionCountFactor)
+            .register(complexity::ComplexityFactor)
+            .register(modularity::ModularityFactor)
+            .register(access_control::AccessControlFactor)
+            .register(pda_seeds::PdaSeedsFactor)
+            .register(cpi_calls::CpiCallsFactor)
+            .register(input_constraints::InputConstraintsFactor)
+            .register(arithmetic::ArithmeticFactor)
+            .register(asset_types::AssetTypesFactor)
+            .register(invariants_risk_params::InvariantsFactor)
+            .register(privileged_roles::PrivilegedRolesFactor)
+            .register(unsafe_lowlevel::UnsafeLowLevelFactor)
+            .register(error_handling::ErrorHandlingFactor)
+            .register(upgradeability::UpgradeabilityFactor)
+            .register(external_integration::ExternalIntegrationFactor)
+            .register(oracle_price_feed::OraclePriceFeedFactor)
+            .register(composability::ComposabilityFactor)
+            .register(dos_resource_limits::DosResourceLimitsFactor)
+            .register(operational_security::OperationalSecurityFactor)
+            .register(statefulness::StatefulnessFactor)
+            .register(dependencies::DependenciesFactor);
+        registry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    struct FailingFactor {
+        required: bool,
+    }
+
+    impl Factor for FailingFactor {
+        fn id(&self) -> &'static str {
+            "failing"
+        }
+
+        fn version(&self) -> &'static str {
+            "1.0.0"
+        }
+
+        fn name(&self) -> &'static str {
+            "Failing"
+        }
+
+        fn required(&self) -> bool {
+            self.required
+        }
+
+        fn compute(&self, _ctx: &FactorContext) -> Result<FactorOutput, Box<dyn Error>> {
+            Err("boom".into())
+        }
+    }
+
+    struct CountingFactor {
+        runs: Arc<AtomicUsize>,
+    }
+
+    impl Factor for CountingFactor {
+        fn id(&self) -> &'static str {
+            "counting"
+        }
+
+        fn version(&self) -> &'static str {
+            "1.0.0"
+        }
+
+        fn name(&self) -> &'static str {
+            "Counting"
+        }
+
+        fn compute(&self, ctx: &FactorContext) -> Result<FactorOutput, Box<dyn Error>> {
+            self.runs.fetch_add(1, Ordering::SeqCst);
+            let files = ctx.workspace.files.len() as f64;
+            Ok(FactorOutput::keyed(
+                "counting",
+                files,
+                serde_json::json!(files),
+            ))
+        }
+    }
+
+    #[test]
+    fn test_default_registry_ids_are_unique() {
+        let registry = FactorRegistry::default();
+        let mut ids: Vec<_> = registry.factors().map(|f| f.id()).collect();
+        let total = ids.len();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), total);
+        assert!(registry.get("complexity").is_some());
+    }
+
+    #[test]
+    fn test_optional_failure_becomes_note() {
+        let mut registry = FactorRegistry::new();
+        registry.register(FailingFactor { required: false });
+
+        let workspace = ParsedWorkspace::default();
+        let ctx = FactorContext::new(&workspace, &[]);
+        let report = registry.compute_all(&ctx).unwrap();
+
+        assert!(report.factors.is_empty());
+        assert_eq!(report.notes, vec!["Failing analysis failed: boom"]);
+    }
+
+    #[test]
+    fn test_configure() {
+        let setting = |enabled, weight| FactorSettings {
+            enabled,
+            weight,
+            ..Default::default()
+        };
+        let settings = BTreeMap::from([
+            ("oraclePriceFeed".to_string(), setting(false, 1.0)),
+            ("complexity".to_string(), setting(true, 3.0)),
+        ]);
+        let registry = FactorRegistry::default().configure(&settings).unwrap();
+        assert_eq!(registry.len(), FactorRegistry::default().len() - 1);
+        assert!(registry.get("oraclePriceFeed").is_none());
+        assert_eq!(registry.weight("complexity"), 3.0);
+        assert_eq!(registry.weight("modularity"), 1.0);
+
+        let scores = vec![
+            ("complexity".to_string(), 40.0),
+            ("modularity".to_string(), 80.0),
+        ];
+        assert_eq!(registry.composite_score(&scores), 50.0);
+
+        let mut registry = FactorRegistry::new();
+        registry.register(FailingFactor { required: true });
+        let disabled = BTreeMap::from([("failing".to_string(), setting(false, 1.0))]);
+        assert!(registry.configure(&disabled).is_err());
+
+        let unknown = BTreeMap::from([("noSuchFactor".to_string(), setting(true, 1.0))]);
+        assert!(FactorRegistry::default().configure(&unknown).is_err());
+    }
+
+    #[test]
+    fn test_normalization_override() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("lib.rs"), "fn a() {}\nfn b() {}\n").unwrap();
+        let selected = vec!["lib.rs".to_string()];
+        let workspace = ParsedWorkspace::load(dir.path(), &selected);
+        let ctx = FactorContext::new(&workspace, &selected);
+        let score = |report: &FactorReport| {
+            report
+                .scores
+                .iter()
+                .find(|(id, _)| id == "functionCount")
+                .map(|(_, score)| *score)
+                .unwrap()
+        };
+
+        let report = FactorRegistry::default().compute_all(&ctx).unwrap();
+        assert_eq!(score(&report), 0.0);
+        assert_eq!(
+            report.normalization["functionCount"]["curve"],
+            serde_json::json!({ "type": "linear", "from": 5.0, "to": 300.0 })
+        );
+
+        let settings = BTreeMap::from([(
+            "functionCount".to_string(),
+            FactorSettings {
+                normalization: Some(
+                    serde_json::json!({ "curve": { "type": "linear", "from": 0, "to": 10 } }),
+                ),
+                ..Default::default()
+            },
+        )]);
+        let registry = FactorRegistry::default().configure(&settings).unwrap();
+        let report = registry.compute_all(&ctx).unwrap();
+        assert_eq!(score(&report), 20.0);
+        assert_eq!(report.normalization["functionCount"]["curve"]["to"], 10.0);
+        assert_eq!(
+            report.normalization["functionCount"]["terms"]["totalFunctions"]["weight"],
+            1.0
+        );
+
+        let settings = BTreeMap::from([(
+            "functionCount".to_string(),
+            FactorSettings {
+                normalization: Some(serde_json::json!({ "terms": { "loc": { "weight": 1 } } })),
+                ..Default::default()
+            },
+        )]);
+        assert!(FactorRegistry::default().configure(&settings).is_err());
+    }
+
+    #[test]
+    fn test_required_failure_aborts() {
+        let mut registry = FactorRegistry::new();
+        registry.register(FailingFactor { required: true });
+
+        let workspace = ParsedWorkspace::default();
+        let ctx = FactorContext::new(&workspace, &[]);
+        let err = registry.compute_all(&ctx).unwrap_err();
+
+        assert_eq!(err.factor_id, "failing");
+        assert_eq!(err.message, "Failing calculation failed: boom");
+    }
+
+    #[test]
+    fn test_default_registry_runs_on_parsed_workspace() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("Cargo.toml"),
+            "[package]\nname = \"vault\"\nversion = \"0.1.0\"\n\n[dependencies]\nanchor-lang = \"0.29\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("lib.rs"),
+            r#"
+            use anchor_lang::prelude::*;
+
+            declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
+
+            #[program]
+            pub mod vault {
+                use super::*;
+
+                pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+                    require!(amount > 0, VaultError::ZeroAmount);
+                    ctx.accounts.vault.balance = ctx.accounts.vault.balance.checked_add(amount).unwrap();
+                    Ok(())
+                }
+            }
+
+            #[derive(Accounts)]
+            pub struct Deposit<'info> {
+                #[account(mut, has_one = owner)]
+                pub vault: Account<'info, Vault>,
+                pub owner: Signer<'info>,
+            }
+            "#,
+        )
+        .unwrap();
+
+        let selected = vec!["lib.rs".to_string()];
+        let workspace = ParsedWorkspace::load(dir.path(), &selected);
+        let ctx = FactorContext::new(&workspace, &selected);
+        let registry = FactorRegistry::default();
+        let report = registry.compute_all(&ctx).unwrap();
+
+        for key in [
+            "totalLinesOfCode",
+            "numFunctions",
+            "complexityFactor",
+            "governanceFactor",
+        ] {
+            assert!(report.computed.iter().any(|k| k == key), "missing {}", key);
+        }
+        assert!(report.factors.contains_key("tscMetrics"));
+        assert!(!report.computed.iter().any(|k| k == "tscMetrics"));
+        assert_eq!(report.scores.len(), registry.len());
+        assert!(report.scores.iter().all(|(_, s)| (0.0..=100.0).contains(s)));
+
+        // Running factors concurrently produces the same report
+        let parallel = registry.compute_all(&ctx.with_workers(4)).unwrap();
+        assert_eq!(parallel.factors, report.factors);
+        assert_eq!(parallel.computed, report.computed);
+        assert_eq!(parallel.notes, report.notes);
+        assert_eq!(parallel.scores, report.scores);
+    }
+
+    #[test]
+    fn test_generated_code_reaches_only_validation_factors() {
+        let dir = tempfile::tempdir().unwrap();
+        let original = crate::workspace::ParsedFile::parse(
+            "src/lib.rs",
+            "pub fn deposit(ctx: Context<Deposit>) -> Result<()> { Ok(()) }".to_string(),
+        )
+        .unwrap();
+        let mut generated = crate::workspace::ParsedFile::parse(
+            "src/lib.rs (expanded)",
+            r#"
+            impl<'info> Deposit<'info> {
+                fn try_accounts(owner: &AccountInfo<'info>) -> Result<Self> {
+                    if !owner.is_signer { return Err(ErrorCode::ConstraintSigner.into()); }
+                    let owner = owner.clone();
+                    Ok(Self { owner })
+                }
+            }
+            "#
+            .to_string(),
+        )
+        .unwrap();
+        let origin = original.location(&original.ast.items[0]);
+        generated.expansion = Some(crate::workspace::ExpansionMap {
+            origins: vec![origin; generated.source.lines().count()],
+        });
+
+        let written = ParsedWorkspace::from_files(dir.path(), vec![original]);
+        let mut expanded = written.clone();
+        expanded.expanded.push(generated);
+
+        let registry = FactorRegistry::default();
+        let run = |workspace: &ParsedWorkspace| {
+            registry
+                .compute_all(&FactorContext::new(workspace, &[]))
+                .unwrap()
+        };
+        let (before, after) = (run(&written), run(&expanded));
+
+        // Size and structure measure the written source only
+        for id in ["linesOfCode", "functionCount", "complexity", "modularity"] {
+            assert_eq!(before.details[id], after.details[id], "{} changed", id);
+        }
+        assert_ne!(
+            before.details["accessControl"]["manualCheckCount"],
+            after.details["accessControl"]["manualCheckCount"]
+        );
+    }
+
+    #[test]
+    fn test_cached_factor_reruns_only_on_change() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("lib.rs"), "fn a() {}").unwrap();
+        let cache = AnalysisCache::new(dir.path().join(".cache"));
+        let selected = vec!["lib.rs".to_string()];
+
+        let runs = Arc::new(AtomicUsize::new(0));
+        let mut registry = FactorRegistry::new();
+        registry.register(CountingFactor { runs: runs.clone() });
+        let cached = |registry: &FactorRegistry| {
+            let workspace = ParsedWorkspace::load(dir.path(), &selected);
+            let ctx = FactorContext::new(&workspace, &selected).with_cache(Some(&cache));
+            let report = registry.compute_all(&ctx).unwrap();
+            assert_eq!(report.factors["counting"], serde_json::json!(1.0));
+            report.cached
+        };
+
+        assert!(cached(&registry).is_empty());
+        assert_eq!(cached(&registry), vec!["counting"]);
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+        std::fs::write(dir.path().join("lib.rs"), "fn b() {}").unwrap();
+        assert!(cached(&registry).is_empty());
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,666 @@
+//! Statefulness Factor
+//!
+//! This module inventories the persistent state a program owns: every
+//! `#[account]` struct, its field count and serialized size, the fields that
+//! make its layout dynamic (`Vec`, `String`, `Option`), the enum fields that
+//! act as state machines, and which instruction handlers mutate each account
+//! type (an `#[account(mut)]`/`init`/`close`/`realloc` field of the handler's
+//! `Context<T>` accounts struct). The more state there is and the more
+//! handlers write to it, the more an auditor has to reason about.
+
+use crate::factors::handlers::HandlerIndex;
+use crate::factors::normalization::{Curve, Normalization};
+use crate::factors::registry::{Factor, FactorContext, FactorOutput};
+use crate::workspace::{ParsedWorkspace, SourceLocation};
+use proc_macro2::TokenTree;
+use quote::quote;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use syn::{
+    visit::{self, Visit},
+    Attribute, Expr, Field, Fields, GenericArgument, ItemEnum, ItemStruct, Lit, Meta,
+    PathArguments, Type,
+};
+
+/// Anchor account discriminator prepended to every account
+const DISCRIMINATOR_SIZE: usize = 8;
+
+/// Borsh length prefix of a `Vec` or `String`
+const LENGTH_PREFIX_SIZE: usize = 4;
+
+/// How deep nested struct/enum sizes are resolved
+const MAX_SIZE_DEPTH: usize = 8;
+
+/// Raw score treated as 100% statefulness
+const UPPER_BOUND: f64 = 150.0;
+
+/// Serialized bytes adding one point to the raw score
+const BYTES_PER_POINT: f64 = 256.0;
+
+/// `#[account(...)]` keys that make a handler write the account
+const MUTATING_CONSTRAINTS: &[&str] = &["mut", "init", "init_if_needed", "close", "realloc"];
+
+/// Inventory of a single `#[account]` struct
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountStateInfo {
+    pub name: String,
+    pub location: SourceLocation,
+    pub field_count: u32,
+    /// Serialized size including the discriminator; for dynamic accounts
+    /// only the fixed part (length prefixes, no elements)
+    pub size_bytes: usize,
+    /// Whether the size depends on runtime data
+    pub dynamic_size: bool,
+    pub vec_fields: u32,
+    pub string_fields: u32,
+    pub option_fields: u32,
+    /// Fields typed as a program-defined enum (state machines)
+    pub enum_fields: Vec<String>,
+    /// Handlers that mutate this account type, sorted
+    pub mutated_by: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StatefulnessMetrics {
+    /// Number of `#[account]` structs
+    pub total_account_types: u32,
+    pub total_fields: u32,
+    /// Fixed serialized size of all account types
+    pub total_state_bytes: usize,
+    pub dynamic_account_types: u32,
+    pub vec_fields: u32,
+    pub string_fields: u32,
+    pub option_fields: u32,
+    pub enum_state_fields: u32,
+
+    /// Handlers that mutate at least one account type
+    pub mutating_handlers: u32,
+    /// Distinct (handler, account type) mutation pairs
+    pub mutation_edges: u32,
+
+    /// Final Score (0-100)
+    pub statefulness_factor: f64,
+    /// Raw score used for normalization
+    pub raw_state_score: f64,
+
+    /// Helper Metrics (for Audibility)
+    pub accounts: Vec<AccountStateInfo>,
+    /// Maps handler name to the account types it mutates
+    pub handler_mutations: BTreeMap<String, Vec<String>>,
+    pub files_analyzed: u32,
+    pub files_skipped: u32,
+}
+
+impl StatefulnessMetrics {
+    /// Convert to structured JSON object
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "statefulnessFactor": self.statefulness_factor,
+            "rawStateScore": self.raw_state_score,
+            "totalAccountTypes": self.total_account_types,
+            "totalFields": self.total_fields,
+            "totalStateBytes": self.total_state_bytes,
+            "dynamicAccountTypes": self.dynamic_account_types,
+            "vecFields": self.vec_fields,
+            "stringFields": self.string_fields,
+            "optionFields": self.option_fields,
+            "enumStateFields": self.enum_state_fields,
+            "mutatingHandlers": self.mutating_handlers,
+            "mutationEdges": self.mutation_edges,
+            "accounts": self.accounts,
+            "handlerMutations": self.handler_mutations,
+            "filesAnalyzed": self.files_analyzed,
+            "filesSkipped": self.files_skipped,
+        })
+    }
+
+    /// Default Statefulness Factor scoring
+    /// Each account type: 2 base, 0.5 per field, 2 per Vec/String, 1 per Option,
+    /// 3 per enum and 1 per 256 bytes; each mutation edge adds 1.5, since every
+    /// handler that writes an account type adds state transitions to review
+    pub fn default_normalization() -> Normalization {
+        Normalization::new(Curve::linear(0.0, UPPER_BOUND))
+            .term("accountTypes", 2.0)
+            .term("fields", 0.5)
+            .term("collectionFields", 2.0)
+            .term("optionFields", 1.0)
+            .term("enumFields", 3.0)
+            .term("stateBytes", 1.0 / BYTES_PER_POINT)
+            .term("mutationEdges", 1.5)
+    }
+
+    /// Measurements the Statefulness Factor is computed from
+    pub fn normalization_inputs(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            ("accountTypes", self.total_account_types as f64),
+            ("fields", self.total_fields as f64),
+            (
+                "collectionFields",
+                (self.vec_fields + self.string_fields) as f64,
+            ),
+            ("optionFields", self.option_fields as f64),
+            ("enumFields", self.enum_state_fields as f64),
+            ("stateBytes", self.total_state_bytes as f64),
+            ("mutationEdges", self.mutation_edges as f64),
+        ]
+    }
+
+    /// Fill the raw score and the normalized factor using `normalization`
+    fn score_with(&mut self, normalization: &Normalization) {
+        let inputs = self.normalization_inputs();
+        self.raw_state_score = normalization.weighted_sum(&inputs);
+        self.statefulness_factor = normalization.score(&inputs);
+    }
+}
+
+/// Type definitions collected from every file before sizing
+#[derive(Debug, Default)]
+struct StateCollector<'ast> {
+    current_file: &'ast str,
+    /// `#[account]` structs with their declaring file
+    accounts: Vec<(&'ast str, &'ast ItemStruct)>,
+    /// `#[derive(Accounts)]` structs by name
+    accounts_structs: HashMap<String, &'ast ItemStruct>,
+    structs: HashMap<String, &'ast ItemStruct>,
+    enums: HashMap<String, &'ast ItemEnum>,
+}
+
+impl<'ast> Visit<'ast> for StateCollector<'ast> {
+    fn visit_item_struct(&mut self, node: &'ast ItemStruct) {
+        let name = node.ident.to_string();
+        if is_account_attr_struct(node) {
+            self.accounts.push((self.current_file, node));
+        }
+        if derives_accounts(node) {
+            self.accounts_structs.insert(name.clone(), node);
+        }
+        self.structs.insert(name, node);
+
+        visit::visit_item_struct(self, node);
+    }
+
+    fn visit_item_enum(&mut self, node: &'ast ItemEnum) {
+        self.enums.insert(node.ident.to_string(), node);
+        visit::visit_item_enum(self, node);
+    }
+}
+
+/// `#[account]` or `#[account(zero_copy)]` on a struct
+fn is_account_attr_struct(node: &ItemStruct) -> bool {
+    node.attrs.iter().any(|attr| {
+        attr.path()
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "account")
+    })
+}
+
+fn derives_accounts(node: &ItemStruct) -> bool {
+    node.attrs.iter().any(|attr| {
+        attr.path().is_ident("derive") && quote!(#attr).to_string().contains("Accounts")
+    })
+}
+
+/// Last path segment of a type and its generic type arguments
+fn type_parts(ty: &Type) -> Option<(String, Vec<&Type>)> {
+    let Type::Path(type_path) = ty else {
+        return None;
+    };
+    let segment = type_path.path.segments.last()?;
+    let args = match &segment.arguments {
+        PathArguments::AngleBracketed(args) => args
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+                GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    Some((segment.ident.to_string(), args))
+}
+
+/// Account type named by an Accounts field (`Account<'info, Pool>` -> `Pool`)
+fn account_field_type(ty: &Type) -> Option<String> {
+    let (name, args) = type_parts(ty)?;
+    match name.as_str() {
+        "Box" => account_field_type(args.first()?),
+        "Account" | "AccountLoader" | "InterfaceAccount" => {
+            type_parts(args.last()?).map(|(inner, _)| inner)
+        }
+        _ => None,
+    }
+}
+
+/// Whether an Accounts field's `#[account(...)]` makes the handler write it
+fn is_mutating_field(field: &Field) -> bool {
+    field.attrs.iter().any(|attr| {
+        if !attr.path().is_ident("account") {
+            return false;
+        }
+        let Meta::List(list) = &attr.meta else {
+            return false;
+        };
+        // Top-level tokens only; bracketed and parenthesized values are skipped
+        list.tokens.clone().into_iter().any(|token| match token {
+            TokenTree::Ident(ident) => MUTATING_CONSTRAINTS.contains(&ident.to_string().as_str()),
+            _ => false,
+        })
+    })
+}
+
+/// `#[max_len(n)]` from `InitSpace`, first dimension only
+fn max_len(attrs: &[Attribute]) -> Option<usize> {
+    let attr = attrs.iter().find(|attr| attr.path().is_ident("max_len"))?;
+    let Meta::List(list) = &attr.meta else {
+        return None;
+    };
+    list.tokens
+        .clone()
+        .into_iter()
+        .find_map(|token| match token {
+            TokenTree::Literal(lit) => lit.to_string().parse().ok(),
+            _ => None,
+        })
+}
+
+/// Sizes Borsh-serialized types using the collected definitions
+struct Sizer<'c, 'ast> {
+    collector: &'c StateCollector<'ast>,
+}
+
+impl Sizer<'_, '_> {
+    /// Serialized size of `ty`, or `None` when it depends on runtime data
+    fn size_of(&self, ty: &Type, max_len: Option<usize>, depth: usize) -> Option<usize> {
+        if depth > MAX_SIZE_DEPTH {
+            return None;
+        }
+
+        match ty {
+            Type::Array(array) => {
+                let Expr::Lit(expr_lit) = &array.len else {
+                    return None;
+                };
+                let Lit::Int(len) = &expr_lit.lit else {
+                    return None;
+                };
+                let len: usize = len.base10_parse().ok()?;
+                Some(len * self.size_of(&array.elem, None, depth + 1)?)
+            }
+            Type::Tuple(tuple) => tuple
+                .elems
+                .iter()
+                .map(|elem| self.size_of(elem, None, depth + 1))
+                .sum(),
+            _ => {
+                let (name, args) = type_parts(ty)?;
+                match name.as_str() {
+                    "bool" | "u8" | "i8" => Some(1),
+                    "u16" | "i16" => Some(2),
+                    "u32" | "i32" | "f32" => Some(4),
+                    "u64" | "i64" | "f64" => Some(8),
+                    "u128" | "i128" => Some(16),
+                    "Pubkey" => Some(32),
+                    "String" => max_len.map(|len| LENGTH_PREFIX_SIZE + len),
+                    "Vec" => {
+                        let elem = self.size_of(args.first()?, None, depth + 1)?;
+                        max_len.map(|len| LENGTH_PREFIX_SIZE + len * elem)
+                    }
+                    "Option" => Some(1 + self.size_of(args.first()?, max_len, depth + 1)?),
+                    "Box" => self.size_of(args.first()?, max_len, depth + 1),
+                    _ => self.definition_size(&name, depth + 1),
+                }
+            }
+        }
+    }
+
+    /// Size of a program-defined struct or enum (largest variant)
+    fn definition_size(&self, name: &str, depth: usize) -> Option<usize> {
+        if let Some(item) = self.collector.structs.get(name) {
+            return self.fields_size(&item.fields, depth);
+        }
+        let item = self.collector.enums.get(name)?;
+        let mut largest = 0;
+        for variant in &item.variants {
+            largest = largest.max(self.fields_size(&variant.fields, depth)?);
+        }
+        Some(1 + largest)
+    }
+
+    fn fields_size(&self, fields: &Fields, depth: usize) -> Option<usize> {
+        fields
+            .iter()
+            .map(|field| self.size_of(&field.ty, max_len(&field.attrs), depth))
+            .sum()
+    }
+}
+
+/// Inventory one `#[account]` struct (mutations are filled in later)
+fn inventory_account(
+    file: &str,
+    item: &ItemStruct,
+    collector: &StateCollector,
+) -> AccountStateInfo {
+    let sizer = Sizer { collector };
+    let mut info = AccountStateInfo {
+        name: item.ident.to_string(),
+        location: SourceLocation::of(file, &item.ident),
+        field_count: item.fields.len() as u32,
+        size_bytes: DISCRIMINATOR_SIZE,
+        ..Default::default()
+    };
+
+    for field in &item.fields {
+        let field_name = field
+            .ident
+            .as_ref()
+            .map_or_else(|| "_".to_string(), |ident| ident.to_string());
+
+        if let Some((type_name, _)) = type_parts(&field.ty) {
+            match type_name.as_str() {
+                "Vec" => info.vec_fields += 1,
+                "String" => info.string_fields += 1,
+                "Option" => info.option_fields += 1,
+                name if collector.enums.contains_key(name) => info.enum_fields.push(field_name),
+                _ => {}
+            }
+        }
+
+        match sizer.size_of(&field.ty, max_len(&field.attrs), 0) {
+            Some(size) => info.size_bytes += size,
+            None => {
+                info.dynamic_size = true;
+                // Unbounded collections still carry their length prefix
+                if matches!(type_parts(&field.ty), Some((name, _)) if name == "Vec" || name == "String")
+                {
+                    info.size_bytes += LENGTH_PREFIX_SIZE;
+                }
+            }
+        }
+    }
+
+    info
+}
+
+/// Calculate statefulness metrics for workspace
+pub fn calculate_workspace_statefulness(
+    workspace_path: &std::path::Path,
+    selected_files: &[String],
+) -> Result<StatefulnessMetrics, Box<dyn std::error::Error>> {
+    let workspace = ParsedWorkspace::load(workspace_path, selected_files);
+    analyze_workspace_statefulness(&workspace)
+}
+
+/// Inventory persistent state over an already-parsed workspace
+pub fn analyze_workspace_statefulness(
+    workspace: &ParsedWorkspace,
+) -> Result<StatefulnessMetrics, Box<dyn std::error::Error>> {
+    log::info!(
+        "🔍 STATEFULNESS DEBUG: Starting analysis for workspace: {:?}",
+        workspace.root
+    );
+
+    let mut collector = StateCollector::default();
+    for file in &workspace.files {
+        collector.current_file = &file.path;
+        collector.visit_file(&file.ast);
+    }
+
+    let mut metrics = StatefulnessMetrics {
+        files_analyzed: workspace.files.len() as u32,
+        files_skipped: workspace.skipped_count() as u32,
+        ..Default::default()
+    };
+
+    metrics.accounts = collector
+        .accounts
+        .iter()
+        .map(|(file, item)| inventory_account(file, item, &collector))
+        .collect();
+
+    // Which handlers write which account types
+    let handlers = HandlerIndex::from_workspace(workspace);
+    for handler in handlers.handlers() {
+        let Some(accounts_struct) = handler
+            .accounts
+            .as_ref()
+            .and_then(|name| collector.accounts_structs.get(name))
+        else {
+            continue;
+        };
+
+        let mut mutated: Vec<String> = accounts_struct
+            .fields
+            .iter()
+            .filter(|field| is_mutating_field(field))
+            .filter_map(|field| account_field_type(&field.ty))
+            .filter(|ty| metrics.accounts.iter().any(|info| &info.name == ty))
+            .collect();
+        mutated.sort();
+        mutated.dedup();
+
+        for ty in &mutated {
+            for info in metrics.accounts.iter_mut().filter(|info| &info.name == ty) {
+                info.mutated_by.push(handler.name.clone());
+            }
+        }
+        if !mutated.is_empty() {
+            metrics
+                .handler_mutations
+                .insert(handler.name.clone(), mutated);
+        }
+    }
+
+    for info in &mut metrics.accounts {
+        info.mutated_by.sort();
+        info.mutated_by.dedup();
+
+        metrics.total_fields += info.field_count;
+        metrics.total_state_bytes += info.size_bytes;
+        metrics.vec_fields += info.vec_fields;
+        metrics.string_fields += info.string_fields;
+        metrics.option_fields += info.option_fields;
+        metrics.enum_state_fields += info.enum_fields.len() as u32;
+        if info.dynamic_size {
+            metrics.dynamic_account_types += 1;
+        }
+    }
+    metrics.total_account_types = metrics.accounts.len() as u32;
+    metrics.mutating_handlers = metrics.handler_mutations.len() as u32;
+    metrics.mutation_edges = metrics
+        .handler_mutations
+        .values()
+        .map(|types| types.len() as u32)
+        .sum();
+
+    // Normalize to 0-100
+    metrics.score_with(&StatefulnessMetrics::default_normalization());
+
+    log::info!(
+        "🔍 STATEFULNESS DEBUG: Analysis complete - {} account types, {} fields, {} bytes, {} mutation edges. Factor: {:.2}",
+        metrics.total_account_types,
+        metrics.total_fields,
+        metrics.total_state_bytes,
+        metrics.mutation_edges,
+        metrics.statefulness_factor
+    );
+
+    Ok(metrics)
+}
+
+/// Registry entry for the statefulness factor
+pub struct StatefulnessFactor;
+
+impl Factor for StatefulnessFactor {
+    fn id(&self) -> &'static str {
+        "statefulness"
+    }
+
+    fn version(&self) -> &'static str {
+        "1.2.0"
+    }
+
+    fn name(&self) -> &'static str {
+        "Statefulness"
+    }
+
+    fn normalization(&self) -> Option<Normalization> {
+        Some(StatefulnessMetrics::default_normalization())
+    }
+
+    fn compute(&self, ctx: &FactorContext) -> Result<FactorOutput, Box<dyn std::error::Error>> {
+        let mut metrics = analyze_workspace_statefulness(ctx.workspace)?;
+        metrics.score_with(&ctx.normalization_or(StatefulnessMetrics::default_normalization));
+        Ok(
+            FactorOutput::keyed(self.id(), metrics.statefulness_factor, metrics.to_json())
+                .with_field(
+                    "statefulnessFactor",
+                    serde_json::json!(metrics.statefulness_factor),
+                )
+                .with_note(format!(
+                    "Analyzed statefulness: {} account types, {} fields, {} state bytes, {} mutating handlers, statefulness factor {:.1}",
+                    metrics.total_account_types,
+                    metrics.total_fields,
+                    metrics.total_state_bytes,
+                    metrics.mutating_handlers,
+                    metrics.statefulness_factor,
+                )),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workspace::ParsedFile;
+
+    fn analyze(code: &str) -> StatefulnessMetrics {
+        let file = ParsedFile::parse("src/lib.rs", code.to_string()).unwrap();
+        let workspace = ParsedWorkspace::from_files(std::path::Path::new("."), vec![file]);
+        analyze_workspace_statefulness(&workspace).unwrap()
+    }
+
+    #[test]
+    fn test_account_inventory_and_size() {
+        let metrics = analyze(
+            r#"
+            #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+            pub enum PoolStatus { Active, Paused, Closed }
+
+            #[account]
+            pub struct Pool {
+                pub authority: Pubkey,
+                pub reserves: [u64; 2],
+                pub fee_bps: u16,
+                pub status: PoolStatus,
+                pub last_price: Option<u64>,
+            }
+
+            #[account]
+            #[derive(InitSpace)]
+            pub struct Registry {
+                #[max_len(32)]
+                pub name: String,
+                pub members: Vec<Pubkey>,
+            }
+            "#,
+        );
+
+        assert_eq!(metrics.total_account_types, 2);
+
+        let pool = &metrics.accounts[0];
+        assert_eq!(pool.name, "Pool");
+        assert_eq!(pool.location.line, 6);
+        assert_eq!(pool.field_count, 5);
+        // 8 discriminator + 32 + 16 + 2 + 1 + 9
+        assert_eq!(pool.size_bytes, 68);
+        assert!(!pool.dynamic_size);
+        assert_eq!(pool.enum_fields, vec!["status"]);
+        assert_eq!(pool.option_fields, 1);
+
+        let registry = &metrics.accounts[1];
+        // 8 discriminator + (4 + 32) + unbounded Vec prefix
+        assert_eq!(registry.size_bytes, 48);
+        assert!(registry.dynamic_size);
+        assert_eq!(registry.string_fields, 1);
+        assert_eq!(registry.vec_fields, 1);
+        assert_eq!(metrics.dynamic_account_types, 1);
+    }
+
+    #[test]
+    fn test_handler_mutations() {
+        let metrics = analyze(
+            r#"
+            #[program]
+            pub mod amm {
+                use super::*;
+
+                pub fn swap(ctx: Context<Swap>, amount: u64) -> Result<()> { Ok(()) }
+                pub fn initialize(ctx: Context<Initialize>) -> Result<()> { Ok(()) }
+                pub fn view(ctx: Context<View>) -> Result<()> { Ok(()) }
+            }
+
+            #[derive(Accounts)]
+            pub struct Swap<'info> {
+                #[account(mut, has_one = authority)]
+                pub pool: Box<Account<'info, Pool>>,
+                pub config: Account<'info, Config>,
+                #[account(mut)]
+                pub user_token: Account<'info, TokenAccount>,
+            }
+
+            #[derive(Accounts)]
+            pub struct Initialize<'info> {
+                #[account(init, payer = payer, space = 8 + 64)]
+                pub pool: Account<'info, Pool>,
+                #[account(init, payer = payer, space = 8 + 8)]
+                pub config: Account<'info, Config>,
+            }
+
+            #[derive(Accounts)]
+            pub struct View<'info> {
+                pub pool: Account<'info, Pool>,
+            }
+
+            #[account]
+            pub struct Pool { pub reserve: u64 }
+
+            #[account]
+            pub struct Config { pub fee: u64 }
+            "#,
+        );
+
+        assert_eq!(metrics.mutating_handlers, 2);
+        assert_eq!(metrics.mutation_edges, 3);
+        assert_eq!(metrics.handler_mutations["swap"], vec!["Pool"]);
+        assert_eq!(
+            metrics.handler_mutations["initialize"],
+            vec!["Config", "Pool"]
+        );
+        assert_eq!(metrics.accounts[0].mutated_by, vec!["initialize", "swap"]);
+        assert_eq!(metrics.accounts[1].mutated_by, vec!["initialize"]);
+    }
+
+    #[test]
+    fn test_factor_calculation() {
+        let metrics = analyze("pub struct NotAnAccount { pub value: u64 }");
+        assert_eq!(metrics.total_account_types, 0);
+        assert_eq!(metrics.statefulness_factor, 0.0);
+
+        let mut metrics = StatefulnessMetrics {
+            total_account_types: 1,
+            total_fields: 4,
+            vec_fields: 1,
+            enum_state_fields: 1,
+            total_state_bytes: 256,
+            mutation_edges: 2,
+            ..Default::default()
+        };
+        metrics.score_with(&StatefulnessMetrics::default_normalization());
+        // 2 base + 2 fields + 2 vec + 3 enum + 1 size + 3 mutation edges
+        assert_eq!(metrics.raw_state_score, 13.0);
+        assert_eq!(metrics.statefulness_factor, 13.0 / UPPER_BOUND * 100.0);
+    }
+}
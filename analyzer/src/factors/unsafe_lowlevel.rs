@@ -1,3 +1,6 @@
+use crate::factors::normalization::{Curve, Normalization};
+use crate::factors::registry::{Factor, FactorContext, FactorOutput, FindingRule, Severity};
+use crate::workspace::{ParsedWorkspace, SourceLocation};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use syn::{visit::Visit, Expr, ExprUnsafe, ForeignItem, Item, ItemFn, ItemImpl, ItemTrait, Path};
@@ -39,6 +42,9 @@ pub struct UnsafeLowLevelMetrics {
     // File analysis metadata
     pub files_analyzed: u32,
     pub files_skipped: u32,
+
+    /// Where each `unsafe` block is
+    pub unsafe_block_locations: Vec<SourceLocation>,
 }
 
 impl UnsafeLowLevelMetrics {
@@ -67,23 +73,38 @@ impl UnsafeLowLevelMetrics {
             "unsafeFactor": self.unsafe_factor,
             "nestedUnsafeBlocks": self.nested_unsafe_blocks,
             "filesAnalyzed": self.files_analyzed,
-            "filesSkipped": self.files_skipped
+            "filesSkipped": self.files_skipped,
+            "unsafeBlockLocations": self.unsafe_block_locations
         })
     }
-}
 
-/// Information about unsafe usage in a file
-#[derive(Debug)]
-struct UnsafeUsageInfo {
-    unsafe_blocks: u32,
-    unsafe_functions: u32,
-    ffi_functions: u32,
-    raw_pointers: u32,
-    transmute_usage: u32,
-    zero_copy_patterns: u32,
-    manual_memory_ops: u32,
-    pointer_arithmetic: u32,
-    ffi_calls: u32,
+    /// Default Unsafe Factor scoring
+    /// Raw score: unsafe blocks × 2 + transmutes × 3 + bytemuck casts × 1.5 + pointer operations × 2.5
+    /// Any raw score >= 25 is 100% risk
+    pub fn default_normalization() -> Normalization {
+        Normalization::new(Curve::linear(0.0, 25.0))
+            .term("unsafeBlocks", 2.0)
+            .term("transmutes", 3.0)
+            .term("bytemuckCasts", 1.5)
+            .term("pointerOperations", 2.5)
+    }
+
+    /// Measurements the Unsafe Factor is computed from
+    pub fn normalization_inputs(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            ("unsafeBlocks", self.total_unsafe_blocks as f64),
+            ("transmutes", self.transmute_usage as f64),
+            ("bytemuckCasts", self.bytemuck_usage as f64),
+            ("pointerOperations", self.ptr_operations as f64),
+        ]
+    }
+
+    /// Fill the complexity score and the normalized factor using `normalization`
+    fn score_with(&mut self, normalization: &Normalization) {
+        let inputs = self.normalization_inputs();
+        self.unsafe_complexity_score = normalization.weighted_sum(&inputs);
+        self.unsafe_factor = normalization.score(&inputs);
+    }
 }
 
 /// Visitor for detecting unsafe and low-level code patterns
@@ -111,6 +132,7 @@ struct UnsafeLowLevelVisitor {
     libc_usage: u32,
     nested_unsafe_blocks: u32,
     unsafe_depth: u32,
+    unsafe_block_locations: Vec<SourceLocation>,
 }
 
 impl UnsafeLowLevelVisitor {
@@ -136,6 +158,7 @@ impl UnsafeLowLevelVisitor {
             libc_usage: 0,
             nested_unsafe_blocks: 0,
             unsafe_depth: 0,
+            unsafe_block_locations: Vec::new(),
         }
     }
 
@@ -208,6 +231,10 @@ impl<'ast> Visit<'ast> for UnsafeLowLevelVisitor {
 
     fn visit_expr_unsafe(&mut self, node: &'ast ExprUnsafe) {
         self.unsafe_blocks += 1;
+        self.unsafe_block_locations.push(SourceLocation::of(
+            &self.current_file_path,
+            &node.unsafe_token,
+        ));
         if self.unsafe_depth > 0 {
             self.nested_unsafe_blocks += 1;
         }
@@ -254,12 +281,9 @@ impl<'ast> Visit<'ast> for UnsafeLowLevelVisitor {
     }
 
     fn visit_foreign_item(&mut self, node: &'ast ForeignItem) {
-        match node {
-            ForeignItem::Fn(_foreign_fn) => {
-                self.ffi_functions += 1;
-                self.record_unsafe_operation("ffi_function");
-            }
-            _ => {}
+        if let ForeignItem::Fn(_foreign_fn) = node {
+            self.ffi_functions += 1;
+            self.record_unsafe_operation("ffi_function");
         }
 
         // Continue visiting foreign item content
@@ -267,50 +291,47 @@ impl<'ast> Visit<'ast> for UnsafeLowLevelVisitor {
     }
 
     fn visit_expr(&mut self, node: &'ast Expr) {
-        match node {
-            Expr::Path(path_expr) => {
-                // Check for Anchor-specific unsafe patterns
-
-                // Check for bytemuck usage (common in Anchor for zero-copy)
-                if self.is_bytemuck_pattern(&path_expr.path) {
-                    self.bytemuck_usage += 1;
-                    self.record_unsafe_operation("bytemuck");
-                }
+        if let Expr::Path(path_expr) = node {
+            // Check for Anchor-specific unsafe patterns
 
-                // Check for mem::transmute
-                if self.is_mem_transmute(&path_expr.path) {
-                    self.mem_transmute += 1;
-                    self.record_unsafe_operation("mem_transmute");
-                }
+            // Check for bytemuck usage (common in Anchor for zero-copy)
+            if self.is_bytemuck_pattern(&path_expr.path) {
+                self.bytemuck_usage += 1;
+                self.record_unsafe_operation("bytemuck");
+            }
 
-                // Check for mem::zeroed
-                if self.is_mem_zeroed(&path_expr.path) {
-                    self.mem_zeroed += 1;
-                    self.record_unsafe_operation("mem_zeroed");
-                }
+            // Check for mem::transmute
+            if self.is_mem_transmute(&path_expr.path) {
+                self.mem_transmute += 1;
+                self.record_unsafe_operation("mem_transmute");
+            }
 
-                // Check for ptr operations
-                if self.is_ptr_operation(&path_expr.path) {
-                    self.ptr_operations += 1;
-                    self.record_unsafe_operation("ptr_operation");
-                }
+            // Check for mem::zeroed
+            if self.is_mem_zeroed(&path_expr.path) {
+                self.mem_zeroed += 1;
+                self.record_unsafe_operation("mem_zeroed");
+            }
 
-                // Check for libc usage
-                if self.is_libc_usage(&path_expr.path) {
-                    self.libc_usage += 1;
-                    self.record_unsafe_operation("libc");
-                }
+            // Check for ptr operations
+            if self.is_ptr_operation(&path_expr.path) {
+                self.ptr_operations += 1;
+                self.record_unsafe_operation("ptr_operation");
+            }
+
+            // Check for libc usage
+            if self.is_libc_usage(&path_expr.path) {
+                self.libc_usage += 1;
+                self.record_unsafe_operation("libc");
+            }
 
-                // Check for direct transmute usage
-                if let Some(segment) = path_expr.path.segments.last() {
-                    let name = segment.ident.to_string();
-                    if name == "transmute" {
-                        self.transmute_usage += 1;
-                        self.record_unsafe_operation("transmute");
-                    }
+            // Check for direct transmute usage
+            if let Some(segment) = path_expr.path.segments.last() {
+                let name = segment.ident.to_string();
+                if name == "transmute" {
+                    self.transmute_usage += 1;
+                    self.record_unsafe_operation("transmute");
                 }
             }
-            _ => {}
         }
 
         // Continue visiting expression
@@ -318,12 +339,9 @@ impl<'ast> Visit<'ast> for UnsafeLowLevelVisitor {
     }
 
     fn visit_type(&mut self, node: &'ast syn::Type) {
-        match node {
-            syn::Type::Ptr(ptr_type) => {
-                self.raw_pointers += 1;
-                self.record_unsafe_operation("raw_pointer");
-            }
-            _ => {}
+        if let syn::Type::Ptr(_ptr_type) = node {
+            self.raw_pointers += 1;
+            self.record_unsafe_operation("raw_pointer");
         }
 
         // Continue visiting type
@@ -333,47 +351,35 @@ impl<'ast> Visit<'ast> for UnsafeLowLevelVisitor {
 
 /// Calculate unsafe and low-level usage metrics for workspace
 pub fn calculate_workspace_unsafe_lowlevel(
-    workspace_path: &std::path::PathBuf,
+    workspace_path: &std::path::Path,
     selected_files: &[String],
+) -> Result<UnsafeLowLevelMetrics, Box<dyn std::error::Error>> {
+    let workspace = ParsedWorkspace::load(workspace_path, selected_files);
+    analyze_workspace_unsafe_lowlevel(&workspace)
+}
+
+/// Detect unsafe and low-level usage over an already-parsed workspace
+pub fn analyze_workspace_unsafe_lowlevel(
+    workspace: &ParsedWorkspace,
 ) -> Result<UnsafeLowLevelMetrics, Box<dyn std::error::Error>> {
     log::info!(
         "🔍 UNSAFE LOW-LEVEL DEBUG: Starting analysis for workspace: {:?}",
-        workspace_path
+        workspace.root
     );
 
     let mut metrics = UnsafeLowLevelMetrics::default();
     let mut files_analyzed = 0;
-    let mut files_skipped = 0;
-
-    for file_path in selected_files {
-        let full_path = workspace_path.join(file_path);
-
-        if !full_path.exists() {
-            log::warn!(
-                "🔍 UNSAFE LOW-LEVEL DEBUG: File does not exist: {:?}",
-                full_path
-            );
-            files_skipped += 1;
-            continue;
-        }
-
-        if !full_path.extension().map_or(false, |ext| ext == "rs") {
-            log::info!(
-                "🔍 UNSAFE LOW-LEVEL DEBUG: Skipping non-Rust file: {:?}",
-                full_path
-            );
-            files_skipped += 1;
-            continue;
-        }
-
-        log::info!("🔍 UNSAFE LOW-LEVEL DEBUG: Analyzing file: {:?}", full_path);
+    let files_skipped = workspace.skipped_count() as u32;
 
-        let content = std::fs::read_to_string(&full_path)?;
-        let syntax_tree = syn::parse_file(&content)?;
+    for file in &workspace.files {
+        log::info!(
+            "🔍 UNSAFE LOW-LEVEL DEBUG: Analyzing file: {:?}",
+            file.full_path
+        );
 
         let mut visitor = UnsafeLowLevelVisitor::new();
-        visitor.current_file_path = file_path.to_string();
-        visitor.visit_file(&syntax_tree);
+        visitor.current_file_path = file.path.clone();
+        visitor.visit_file(&file.ast);
 
         // Accumulate metrics from this visitor
         metrics.total_unsafe_blocks += visitor.unsafe_blocks;
@@ -393,6 +399,9 @@ pub fn calculate_workspace_unsafe_lowlevel(
         metrics.ptr_operations += visitor.ptr_operations;
         metrics.libc_usage += visitor.libc_usage;
         metrics.nested_unsafe_blocks += visitor.nested_unsafe_blocks;
+        metrics
+            .unsafe_block_locations
+            .append(&mut visitor.unsafe_block_locations);
 
         // Merge pattern breakdown
         for (pattern, count) in visitor.unsafe_pattern_counts {
@@ -403,7 +412,7 @@ pub fn calculate_workspace_unsafe_lowlevel(
 
         log::info!(
             "🔍 UNSAFE LOW-LEVEL DEBUG: File {} analysis complete - unsafe blocks: {}, unsafe functions: {}, ffi functions: {}, raw pointers: {}",
-            file_path,
+            file.path,
             visitor.unsafe_blocks,
             visitor.unsafe_functions,
             visitor.ffi_functions,
@@ -429,21 +438,8 @@ pub fn calculate_workspace_unsafe_lowlevel(
         + metrics.ptr_operations
         + metrics.libc_usage;
 
-    // Calculate complexity score (weighted by unsafe operations)
-    let unsafe_weight = 2.0;
-    let transmute_weight = 3.0;
-    let bytemuck_weight = 1.5;
-    let ptr_weight = 2.5;
-
-    metrics.unsafe_complexity_score = (metrics.total_unsafe_blocks as f64 * unsafe_weight)
-        + (metrics.transmute_usage as f64 * transmute_weight)
-        + (metrics.bytemuck_usage as f64 * bytemuck_weight)
-        + (metrics.ptr_operations as f64 * ptr_weight);
-
-    // --- NORMALIZE TO 0-100 ---
-    let upper_bound = 25.0; // Any raw score >= 25 is 100% risk
-    let factor = (metrics.unsafe_complexity_score / upper_bound) * 100.0;
-    metrics.unsafe_factor = factor.min(100.0);
+    // Calculate complexity score (weighted by unsafe operations), normalized to 0-100
+    metrics.score_with(&UnsafeLowLevelMetrics::default_normalization());
 
     log::info!(
         "🔍 UNSAFE LOW-LEVEL DEBUG: Analysis complete - {} files analyzed, {} files skipped, total unsafe operations: {}, factor: {:.1}",
@@ -455,3 +451,58 @@ pub fn calculate_workspace_unsafe_lowlevel(
 
     Ok(metrics)
 }
+
+/// An `unsafe` block, which opts out of the compiler's memory-safety checks
+pub const UNSAFE_BLOCK: FindingRule = FindingRule {
+    id: "unsafeLowLevel/unsafeBlock",
+    name: "UnsafeBlock",
+    description: "`unsafe` block that bypasses Rust's memory-safety guarantees",
+    severity: Severity::Warning,
+};
+
+/// Registry entry for the unsafe/low-level usage factor
+pub struct UnsafeLowLevelFactor;
+
+impl Factor for UnsafeLowLevelFactor {
+    fn id(&self) -> &'static str {
+        "unsafeLowLevel"
+    }
+
+    fn version(&self) -> &'static str {
+        "1.1.0"
+    }
+
+    fn name(&self) -> &'static str {
+        "Unsafe/low-level usage"
+    }
+
+    fn rules(&self) -> &'static [FindingRule] {
+        &[UNSAFE_BLOCK]
+    }
+
+    fn normalization(&self) -> Option<Normalization> {
+        Some(UnsafeLowLevelMetrics::default_normalization())
+    }
+
+    fn compute(&self, ctx: &FactorContext) -> Result<FactorOutput, Box<dyn std::error::Error>> {
+        let mut metrics = analyze_workspace_unsafe_lowlevel(ctx.workspace)?;
+        metrics.score_with(&ctx.normalization_or(UnsafeLowLevelMetrics::default_normalization));
+        let findings = metrics
+            .unsafe_block_locations
+            .iter()
+            .map(|location| UNSAFE_BLOCK.at(location.clone(), "`unsafe` block"))
+            .collect();
+        Ok(
+            FactorOutput::keyed(self.id(), metrics.unsafe_factor, metrics.to_json()).with_note(format!(
+                "Analyzed unsafe/low-level usage: {} unsafe blocks, {} unsafe functions, {} transmute usage, {} bytemuck usage, {} ptr operations, complexity score {:.1}",
+                metrics.total_unsafe_blocks,
+                metrics.total_unsafe_functions,
+                metrics.transmute_usage,
+                metrics.bytemuck_usage,
+                metrics.ptr_operations,
+                metrics.unsafe_complexity_score,
+            ))
+            .with_findings(findings),
+        )
+    }
+}
@@ -1,5 +1,8 @@
+use crate::factors::normalization::{Curve, Normalization};
+use crate::factors::registry::{Factor, FactorContext, FactorOutput};
+use crate::workspace::ParsedWorkspace;
 use serde::{Deserialize, Serialize};
-use syn::{visit::Visit, Item, ItemMacro, LitStr};
+use syn::{visit::Visit, ItemMacro, LitStr};
 
 /// Metrics for upgradeability and governance control patterns
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
@@ -38,16 +41,34 @@ impl UpgradeabilityMetrics {
         })
     }
 
-    /// Calculate governance factor based on status
+    /// Default Governance Factor scoring, one term per governance mechanism
+    /// No Program ID or an unknown status scores 0
+    pub fn default_normalization() -> Normalization {
+        Normalization::new(Curve::percent())
+            .term("immutable", 50.0) // Simple, fixed state
+            .term("singleWallet", 75.0) // Adds single attack vector
+            .term("governance", 100.0) // Highest complexity
+    }
+
+    /// Measurements the Governance Factor is computed from
+    pub fn normalization_inputs(&self) -> Vec<(&'static str, f64)> {
+        Self::status_inputs(&self.governance_status)
+    }
+
+    /// 1 for the term matching `status`, 0 for the others
+    fn status_inputs(status: &str) -> Vec<(&'static str, f64)> {
+        let is = |matches: bool| if matches { 1.0 } else { 0.0 };
+        vec![
+            ("immutable", is(matches!(status, "immutable" | "locked"))),
+            ("singleWallet", is(status == "single_wallet")),
+            ("governance", is(status == "governance")),
+        ]
+    }
+
+    /// Calculate governance factor based on status, with the default scoring
     /// Returns complexity score based on governance mechanism
     pub fn calculate_governance_factor(status: &str) -> f64 {
-        match status {
-            "no_data" => 0.0,               // No Program ID found
-            "immutable" | "locked" => 50.0, // Simple, fixed state
-            "single_wallet" => 75.0,        // Adds single attack vector
-            "governance" => 100.0,          // Highest complexity
-            _ => 0.0,                       // Unknown status
-        }
+        Self::default_normalization().score(&Self::status_inputs(status))
     }
 }
 
@@ -175,13 +196,22 @@ fn analyze_on_chain(
 /// Phase 1: AST analysis to find Program ID from declare_id! macro
 /// Phase 2: On-chain RPC analysis to determine governance status
 pub fn calculate_workspace_upgradeability(
-    workspace_path: &std::path::PathBuf,
+    workspace_path: &std::path::Path,
     selected_files: &[String],
     rpc_url: Option<&str>,
+) -> Result<UpgradeabilityMetrics, Box<dyn std::error::Error>> {
+    let workspace = ParsedWorkspace::load(workspace_path, selected_files);
+    analyze_workspace_upgradeability(&workspace, rpc_url)
+}
+
+/// Run the 2-phase governance analysis over an already-parsed workspace
+pub fn analyze_workspace_upgradeability(
+    workspace: &ParsedWorkspace,
+    rpc_url: Option<&str>,
 ) -> Result<UpgradeabilityMetrics, Box<dyn std::error::Error>> {
     log::info!(
         "🔍 UPGRADEABILITY: Starting 2-phase governance analysis for workspace: {:?}",
-        workspace_path
+        workspace.root
     );
 
     let mut metrics = UpgradeabilityMetrics::default();
@@ -191,45 +221,18 @@ pub fn calculate_workspace_upgradeability(
 
     let mut visitor = ProgramIdVisitor::default();
 
-    // Iterate through ALL selected files (no filtering by name)
-    for file_path_str in selected_files {
-        let file_path = workspace_path.join(file_path_str);
-
-        // Skip non-Rust files
-        if !file_path.extension().map_or(false, |ext| ext == "rs") {
-            continue;
-        }
-
-        if !file_path.exists() {
-            log::warn!("🔍 UPGRADEABILITY: File does not exist: {:?}", file_path);
-            continue;
-        }
+    // Iterate through ALL parsed files (no filtering by name)
+    for file in &workspace.files {
+        log::debug!("🔍 UPGRADEABILITY: Analyzing file: {:?}", file.full_path);
+        visitor.visit_file(&file.ast);
 
-        log::debug!("🔍 UPGRADEABILITY: Analyzing file: {:?}", file_path);
-
-        // Parse the file and run the visitor
-        match std::fs::read_to_string(&file_path) {
-            Ok(content) => {
-                if let Ok(syntax_tree) = syn::parse_file(&content) {
-                    visitor.visit_file(&syntax_tree);
-
-                    // If we found a Program ID, we can stop searching
-                    if visitor.program_id.is_some() {
-                        log::info!(
-                            "🔍 UPGRADEABILITY: Found declare_id! in file: {:?}",
-                            file_path
-                        );
-                        break;
-                    }
-                }
-            }
-            Err(e) => {
-                log::warn!(
-                    "🔍 UPGRADEABILITY: Failed to read file {:?}: {}",
-                    file_path,
-                    e
-                );
-            }
+        // If we found a Program ID, we can stop searching
+        if visitor.program_id.is_some() {
+            log::info!(
+                "🔍 UPGRADEABILITY: Found declare_id! in file: {:?}",
+                file.full_path
+            );
+            break;
         }
     }
 
@@ -290,6 +293,72 @@ pub fn calculate_workspace_upgradeability(
     Ok(metrics)
 }
 
+/// Registry entry for the upgradeability/governance factor
+pub struct UpgradeabilityFactor;
+
+impl Factor for UpgradeabilityFactor {
+    fn id(&self) -> &'static str {
+        "upgradeability"
+    }
+
+    fn version(&self) -> &'static str {
+        "1.0.0"
+    }
+
+    fn name(&self) -> &'static str {
+        "Upgradeability"
+    }
+
+    /// On-chain program state can change without any file changing
+    fn cacheable(&self, ctx: &FactorContext) -> bool {
+        ctx.rpc_url.is_none()
+    }
+
+    fn normalization(&self) -> Option<Normalization> {
+        Some(UpgradeabilityMetrics::default_normalization())
+    }
+
+    fn compute(&self, ctx: &FactorContext) -> Result<FactorOutput, Box<dyn std::error::Error>> {
+        let mut metrics = analyze_workspace_upgradeability(ctx.workspace, ctx.rpc_url)?;
+        if metrics.on_chain_analysis_performed {
+            metrics.governance_factor = ctx
+                .normalization_or(UpgradeabilityMetrics::default_normalization)
+                .score(&metrics.normalization_inputs());
+            metrics.raw_governance_score = metrics.governance_factor; // Same as factor for this model
+        }
+
+        let note = if metrics.on_chain_analysis_performed {
+            format!(
+                "Governance analysis complete: Program ID {}, Status: {}, Factor: {:.1}",
+                metrics.program_id.as_deref().unwrap_or("N/A"),
+                metrics.governance_status,
+                metrics.governance_factor
+            )
+        } else {
+            format!(
+                "Governance analysis skipped: Program ID {}, Status: {}, Factor: {:.1} (RPC: {})",
+                metrics.program_id.as_deref().unwrap_or("N/A"),
+                metrics.governance_status,
+                metrics.governance_factor,
+                if ctx.rpc_url.is_some() {
+                    "provided but failed"
+                } else {
+                    "not provided"
+                }
+            )
+        };
+
+        Ok(
+            FactorOutput::keyed(self.id(), metrics.governance_factor, metrics.to_json())
+                .with_field(
+                    "governanceFactor",
+                    serde_json::json!(metrics.governance_factor),
+                )
+                .with_note(note),
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -486,7 +555,7 @@ mod tests {
             Some("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS".to_string())
         );
         assert_eq!(result.governance_status, "rpc_not_available");
-        assert_eq!(result.on_chain_analysis_performed, false);
+        assert!(!result.on_chain_analysis_performed);
         assert_eq!(result.governance_factor, 0.0);
     }
 }
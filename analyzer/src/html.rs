@@ -0,0 +1,311 @@
+//! Self-contained HTML report
+//!
+//! Renders one HTML file from the repository metrics and a factor run:
+//! per-factor gauges, the most complex functions, dependency tiers and every
+//! finding with the source lines around it. Styles are inlined and nothing is
+//! loaded over the network, so the file can be handed to anyone and opened
+//! offline.
+
+use crate::factors::{FactorRegistry, FactorReport, Finding};
+use crate::output::AnalysisReport;
+use std::fmt::Write;
+use std::path::Path;
+
+/// Lines of source shown above and below a finding
+const SNIPPET_CONTEXT: usize = 2;
+
+/// Dependency tiers as published in the `dependencies` factor JSON
+const DEPENDENCY_TIERS: &[(&str, &str, &str)] = &[
+    ("Tier 1", "Solana official", "tier1Crates"),
+    ("Tier 1.5", "Oracles and bridges", "tier1_5Crates"),
+    ("Tier 2", "Security and cryptography", "tier2Crates"),
+    ("Tier 3", "Popular ecosystem", "tier3Crates"),
+    ("Tier 4", "Unknown or custom", "tier4Crates"),
+];
+
+const STYLE: &str = r#"
+body { font-family: -apple-system, "Segoe UI", Helvetica, Arial, sans-serif; margin: 0; background: #f5f6f8; color: #1d2330; }
+main { max-width: 1100px; margin: 0 auto; padding: 32px 24px; }
+h1 { margin: 0 0 4px; }
+h2 { margin-top: 40px; border-bottom: 1px solid #d8dce3; padding-bottom: 6px; }
+.meta { color: #5b6475; font-size: 14px; }
+.cards { display: flex; gap: 16px; flex-wrap: wrap; margin-top: 16px; }
+.card { background: #fff; border-radius: 8px; padding: 12px 18px; box-shadow: 0 1px 2px rgba(0,0,0,.08); }
+.card b { display: block; font-size: 22px; }
+.gauges { display: grid; grid-template-columns: repeat(auto-fill, minmax(150px, 1fr)); gap: 16px; }
+.gauge { background: #fff; border-radius: 8px; padding: 14px; text-align: center; box-shadow: 0 1px 2px rgba(0,0,0,.08); }
+.dial { width: 86px; height: 86px; border-radius: 50%; margin: 0 auto 8px; display: flex; align-items: center; justify-content: center; }
+.dial span { width: 64px; height: 64px; border-radius: 50%; background: #fff; display: flex; align-items: center; justify-content: center; font-weight: 600; }
+.gauge small { color: #5b6475; }
+table { width: 100%; border-collapse: collapse; background: #fff; border-radius: 8px; overflow: hidden; }
+th, td { text-align: left; padding: 8px 12px; border-bottom: 1px solid #eceef2; font-size: 14px; vertical-align: top; }
+th { background: #eceef2; }
+code, pre { font-family: "SFMono-Regular", Consolas, monospace; font-size: 13px; }
+.finding { background: #fff; border-radius: 8px; margin: 12px 0; padding: 12px 16px; box-shadow: 0 1px 2px rgba(0,0,0,.08); }
+.finding header { display: flex; gap: 12px; align-items: baseline; flex-wrap: wrap; }
+.level { border-radius: 4px; padding: 1px 8px; font-size: 12px; font-weight: 600; text-transform: uppercase; color: #fff; }
+.level.error { background: #c0392b; } .level.warning { background: #d68910; } .level.note { background: #2e86c1; }
+pre { background: #1d2330; color: #e6e9ef; padding: 10px 0; border-radius: 6px; overflow-x: auto; }
+pre .line { display: block; padding: 0 12px; }
+pre .hit { background: rgba(214, 137, 16, .35); }
+pre .no { display: inline-block; width: 44px; color: #7d8699; user-select: none; }
+.empty { color: #5b6475; font-style: italic; }
+"#;
+
+/// HTML output utility
+pub struct HtmlOutput;
+
+impl HtmlOutput {
+    /// Render the report; `registry` supplies the factor names
+    pub fn render(
+        report: &AnalysisReport,
+        factors: &FactorReport,
+        registry: &FactorRegistry,
+    ) -> String {
+        let repo = &report.repository;
+        let mut html = String::new();
+
+        html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+        html.push_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n");
+        let _ = writeln!(
+            html,
+            "<title>Analysis report: {}</title>",
+            escape(&repo.root_path.to_string_lossy())
+        );
+        let _ = writeln!(html, "<style>{}</style>\n</head>\n<body>\n<main>", STYLE);
+
+        let _ = writeln!(
+            html,
+            "<h1>Analysis report</h1>\n<div class=\"meta\"><code>{}</code> &middot; analyzer {} &middot; {}</div>",
+            escape(&repo.root_path.to_string_lossy()),
+            escape(&report.metadata.version),
+            escape(&report.metadata.timestamp),
+        );
+        let _ = writeln!(
+            html,
+            "<div class=\"cards\">{}{}{}{}{}</div>",
+            card("Files", repo.file_count.to_string()),
+            card("Lines of code", repo.total_lines_of_code.to_string()),
+            card("Functions", repo.total_function_count.to_string()),
+            card("Risk level", escape(&repo.risk_summary.risk_level)),
+            card("Findings", factors.findings.len().to_string()),
+        );
+
+        Self::write_gauges(&mut html, factors, registry);
+        Self::write_functions(&mut html, report);
+        Self::write_dependencies(&mut html, factors);
+        Self::write_findings(&mut html, factors, &repo.root_path);
+
+        if !factors.notes.is_empty() {
+            html.push_str("<h2>Notes</h2>\n<ul>\n");
+            for note in &factors.notes {
+                let _ = writeln!(html, "<li>{}</li>", escape(note));
+            }
+            html.push_str("</ul>\n");
+        }
+
+        html.push_str("</main>\n</body>\n</html>\n");
+        html
+    }
+
+    fn write_gauges(html: &mut String, factors: &FactorReport, registry: &FactorRegistry) {
+        html.push_str("<h2>Factors</h2>\n<div class=\"gauges\">\n");
+        for (id, score) in &factors.scores {
+            let name = registry.get(id).map_or(id.as_str(), |f| f.name());
+            let _ = writeln!(
+                html,
+                "<div class=\"gauge\"><div class=\"dial\" style=\"background: conic-gradient({color} {pct:.1}%, #e4e7ec 0)\"><span>{score:.0}</span></div>{name}<br><small>{id}{incomplete}</small></div>",
+                color = score_color(*score),
+                pct = score.clamp(0.0, 100.0),
+                score = score,
+                name = escape(name),
+                id = escape(id),
+                incomplete = if factors.incomplete.contains(id) {
+                    " &middot; incomplete"
+                } else {
+                    ""
+                },
+            );
+        }
+        html.push_str("</div>\n");
+    }
+
+    fn write_functions(html: &mut String, report: &AnalysisReport) {
+        html.push_str("<h2>Most complex functions</h2>\n");
+        let functions = &report.summary.highest_complexity_functions;
+        if functions.is_empty() {
+            html.push_str("<p class=\"empty\">No functions analyzed.</p>\n");
+            return;
+        }
+
+        html.push_str("<table>\n<tr><th>Function</th><th>File</th><th>Complexity</th><th>Cyclomatic</th><th>Operations</th></tr>\n");
+        for function in functions {
+            let _ = writeln!(
+                html,
+                "<tr><td><code>{}</code></td><td><code>{}</code></td><td>{:.1}</td><td>{}</td><td>{}</td></tr>",
+                escape(&function.name),
+                escape(&function.file_path),
+                function.complexity_score,
+                function.cyclomatic_complexity,
+                function.total_operations,
+            );
+        }
+        html.push_str("</table>\n");
+    }
+
+    fn write_dependencies(html: &mut String, factors: &FactorReport) {
+        html.push_str("<h2>Dependency tiers</h2>\n");
+        let Some(dependencies) = factors.factors.get("dependencies") else {
+            html.push_str("<p class=\"empty\">Dependency analysis did not run.</p>\n");
+            return;
+        };
+
+        html.push_str(
+            "<table>\n<tr><th>Tier</th><th>Kind</th><th>Count</th><th>Crates</th></tr>\n",
+        );
+        for (tier, kind, key) in DEPENDENCY_TIERS {
+            let crates: Vec<&str> = dependencies[*key]
+                .as_array()
+                .map(|crates| crates.iter().filter_map(|c| c.as_str()).collect())
+                .unwrap_or_default();
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                tier,
+                kind,
+                crates.len(),
+                crates
+                    .iter()
+                    .map(|c| format!("<code>{}</code>", escape(c)))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            );
+        }
+        html.push_str("</table>\n");
+    }
+
+    fn write_findings(html: &mut String, factors: &FactorReport, root: &Path) {
+        let _ = writeln!(html, "<h2>Findings ({})</h2>", factors.findings.len());
+        if factors.findings.is_empty() {
+            html.push_str("<p class=\"empty\">No findings.</p>\n");
+            return;
+        }
+
+        for finding in &factors.findings {
+            let _ = writeln!(
+                html,
+                "<section class=\"finding\">\n<header><span class=\"level {level}\">{level}</span><code>{rule}</code><code>{location}</code></header>\n<p>{message}</p>",
+                level = finding.severity.as_str(),
+                rule = escape(&finding.rule_id),
+                location = escape(&finding.location.display()),
+                message = escape(&finding.message),
+            );
+            if let Some(snippet) = snippet(root, finding) {
+                html.push_str(&snippet);
+            }
+            html.push_str("</section>\n");
+        }
+    }
+}
+
+/// Source lines around `finding`, with its own lines highlighted
+fn snippet(root: &Path, finding: &Finding) -> Option<String> {
+    let location = &finding.location;
+    let source = std::fs::read_to_string(root.join(&location.file)).ok()?;
+    let lines: Vec<&str> = source.lines().collect();
+    if location.line == 0 || location.line > lines.len() {
+        return None;
+    }
+
+    let first = location.line.saturating_sub(SNIPPET_CONTEXT).max(1);
+    let last = (location.end_line.max(location.line) + SNIPPET_CONTEXT).min(lines.len());
+    let mut pre = String::from("<pre>");
+    for number in first..=last {
+        let hit = (location.line..=location.end_line.max(location.line)).contains(&number);
+        let _ = write!(
+            pre,
+            "<span class=\"line{}\"><span class=\"no\">{}</span>{}</span>",
+            if hit { " hit" } else { "" },
+            number,
+            escape(lines[number - 1]),
+        );
+    }
+    pre.push_str("</pre>\n");
+    Some(pre)
+}
+
+fn card(label: &str, value: String) -> String {
+    format!("<div class=\"card\">{}<b>{}</b></div>", label, value)
+}
+
+fn score_color(score: f64) -> &'static str {
+    if score < 33.0 {
+        "#27ae60"
+    } else if score < 66.0 {
+        "#d68910"
+    } else {
+        "#c0392b"
+    }
+}
+
+/// Escape text for HTML element content and attribute values
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AnalyzerConfig;
+    use crate::AnalyzerEngine;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape(r#"<a href="x">&'</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&#39;&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn test_render_is_self_contained() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("lib.rs"),
+            "pub fn swap(a: u64, b: u64) -> u64 {\n    unsafe {\n        a / b\n    }\n}\n",
+        )
+        .unwrap();
+
+        let engine = AnalyzerEngine::new(AnalyzerConfig {
+            root_path: dir.path().to_path_buf(),
+            ..Default::default()
+        })
+        .unwrap();
+        let registry = FactorRegistry::default();
+        let report = engine.analyze().unwrap();
+        let factors = engine.analyze_factors(&registry).unwrap();
+        let html = HtmlOutput::render(&report, &factors, &registry);
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("Unsafe/low-level usage"));
+        assert!(html.contains("<code>swap</code>"));
+        // The unsafe block finding carries its source lines
+        assert!(html.contains("unsafeLowLevel/unsafeBlock"));
+        assert!(html
+            .contains("<span class=\"line hit\"><span class=\"no\">2</span>    unsafe {</span>"));
+        // Nothing is fetched when the file is opened
+        assert!(!html.contains("http://") && !html.contains("https://"));
+        assert!(!html.contains("<script src") && !html.contains("<link"));
+    }
+}
@@ -4,19 +4,34 @@
 //! particularly focusing on DeFi/AMM patterns in Solana and Anchor frameworks.
 
 pub mod analysis;
+pub mod augment;
+pub mod cache;
+pub mod check;
 pub mod config;
+pub mod diff;
 pub mod error;
+pub mod estimation;
+#[cfg(feature = "macro-expansion")]
+pub mod expansion;
 pub mod factors;
+pub mod html;
+pub mod markdown;
 pub mod metrics;
 pub mod output;
+pub mod parallel;
 pub mod patterns;
 pub mod visitor;
+pub mod workspace;
 
 pub use analysis::AnalyzerEngine;
+pub use cache::AnalysisCache;
 pub use config::AnalyzerConfig;
+pub use diff::ReportDiff;
 pub use error::{AnalyzerError, Result};
+pub use factors::{Factor, FactorContext, FactorRegistry};
 pub use metrics::{FileMetrics, FunctionMetrics, RepoMetrics};
-pub use output::{AnalysisReport, JsonOutput};
+pub use output::{AnalysisReport, JsonOutput, OutputFormat};
+pub use workspace::{ParsedFile, ParsedWorkspace, SourceLocation};
 
 /// Main entry point for analyzing a repository
 pub fn analyze_repository(config: AnalyzerConfig) -> Result<AnalysisReport> {
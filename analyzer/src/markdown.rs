@@ -0,0 +1,213 @@
+//! Markdown summary for pull request comments
+//!
+//! A compact report: one table with every factor's score, the top risk
+//! items, files that failed to parse, and a collapsed `<details>` block per
+//! factor with its raw metrics.
+//! GitHub and GitLab both render the collapsible blocks.
+
+use crate::factors::{FactorRegistry, FactorReport, Finding};
+use crate::output::AnalysisReport;
+use serde_json::Value;
+use std::fmt::Write;
+
+/// Findings listed under "Top risk items"
+const TOP_RISK_ITEMS: usize = 10;
+
+/// Markdown output utility
+pub struct MarkdownOutput;
+
+impl MarkdownOutput {
+    /// Render the report; `registry` supplies the factor names
+    pub fn render(
+        report: &AnalysisReport,
+        factors: &FactorReport,
+        registry: &FactorRegistry,
+    ) -> String {
+        let repo = &report.repository;
+        let mut md = String::new();
+
+        let _ = writeln!(md, "## Analysis report\n");
+        let _ = writeln!(
+            md,
+            "`{}` · {} files · {} lines · {} functions · risk **{}**\n",
+            repo.root_path.to_string_lossy(),
+            repo.file_count,
+            repo.total_lines_of_code,
+            repo.total_function_count,
+            repo.risk_summary.risk_level,
+        );
+
+        Self::write_scores(&mut md, factors, registry);
+        Self::write_risk_items(&mut md, factors);
+        Self::write_diagnostics(&mut md, factors);
+        Self::write_details(&mut md, factors, registry);
+        md
+    }
+
+    fn write_scores(md: &mut String, factors: &FactorReport, registry: &FactorRegistry) {
+        md.push_str("### Factor scores\n\n| Factor | Key | Score |\n|---|---|---:|\n");
+        for (id, score) in &factors.scores {
+            let name = registry.get(id).map_or(id.as_str(), |f| f.name());
+            let key = factors
+                .details
+                .get(id)
+                .and_then(|details| score_key(details, *score))
+                .unwrap_or(id);
+            let incomplete = if factors.incomplete.contains(id) {
+                " (incomplete)"
+            } else {
+                ""
+            };
+            let _ = writeln!(
+                md,
+                "| {} | `{}` | {:.1}{} |",
+                cell(name),
+                key,
+                score,
+                incomplete
+            );
+        }
+        md.push('\n');
+    }
+
+    fn write_risk_items(md: &mut String, factors: &FactorReport) {
+        md.push_str("### Top risk items\n\n");
+        if factors.findings.is_empty() {
+            md.push_str("No findings.\n\n");
+            return;
+        }
+
+        // Most severe first; ties keep registry order
+        let mut findings: Vec<&Finding> = factors.findings.iter().collect();
+        findings.sort_by_key(|finding| std::cmp::Reverse(finding.severity));
+        for finding in findings.iter().take(TOP_RISK_ITEMS) {
+            let _ = writeln!(
+                md,
+                "- **{}** `{}` at `{}`: {}",
+                finding.severity.as_str(),
+                finding.rule_id,
+                finding.location.display(),
+                finding.message,
+            );
+        }
+        if findings.len() > TOP_RISK_ITEMS {
+            let _ = writeln!(md, "- …and {} more", findings.len() - TOP_RISK_ITEMS);
+        }
+        md.push('\n');
+    }
+
+    fn write_diagnostics(md: &mut String, factors: &FactorReport) {
+        if factors.diagnostics.is_empty() {
+            return;
+        }
+        md.push_str("### Parse diagnostics\n\n");
+        for diagnostic in &factors.diagnostics {
+            let location = diagnostic
+                .location
+                .as_ref()
+                .map_or_else(|| diagnostic.path.clone(), |location| location.display());
+            let _ = writeln!(
+                md,
+                "- `{}`: {} ({} items analyzed)",
+                location, diagnostic.message, diagnostic.recovered_items,
+            );
+        }
+        md.push('\n');
+    }
+
+    fn write_details(md: &mut String, factors: &FactorReport, registry: &FactorRegistry) {
+        md.push_str("### Details\n\n");
+        for (id, score) in &factors.scores {
+            let Some(Value::Object(details)) = factors.details.get(id) else {
+                continue;
+            };
+            let name = registry.get(id).map_or(id.as_str(), |f| f.name());
+
+            let _ = writeln!(
+                md,
+                "<details>\n<summary>{} ({:.1})</summary>\n\n| Metric | Value |\n|---|---|",
+                cell(name),
+                score
+            );
+            for (key, value) in details {
+                let _ = writeln!(md, "| `{}` | {} |", key, cell(&summarize(value)));
+            }
+            md.push_str("\n</details>\n\n");
+        }
+    }
+}
+
+/// The `…Factor` key in `details` that holds `score`, else the first one
+fn score_key(details: &Value, score: f64) -> Option<&str> {
+    let details = details.as_object()?;
+    let mut keys = details
+        .iter()
+        .filter(|(key, value)| key.ends_with("Factor") && value.is_number());
+    let first = keys.clone().next().map(|(key, _)| key.as_str());
+    keys.find(|(_, value)| value.as_f64().is_some_and(|v| (v - score).abs() < 1e-6))
+        .map(|(key, _)| key.as_str())
+        .or(first)
+}
+
+/// One-line rendering of a metric value
+fn summarize(value: &Value) -> String {
+    match value {
+        Value::Number(n) => match n.as_f64() {
+            Some(f) if n.is_f64() => format!("{:.2}", f),
+            _ => n.to_string(),
+        },
+        Value::String(s) => s.clone(),
+        Value::Array(items) => format!("{} items", items.len()),
+        Value::Object(fields) => format!("{} entries", fields.len()),
+        other => other.to_string(),
+    }
+}
+
+/// Make `text` safe inside a table cell
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AnalyzerConfig;
+    use crate::AnalyzerEngine;
+
+    #[test]
+    fn test_score_key() {
+        let details = serde_json::json!({
+            "dependencyFactor": 40.0,
+            "externalIntegrationFactor": 10.0,
+            "totalDependencies": 3,
+        });
+        assert_eq!(score_key(&details, 10.0), Some("externalIntegrationFactor"));
+        assert_eq!(score_key(&details, 99.0), Some("dependencyFactor"));
+        assert_eq!(score_key(&serde_json::json!(3), 3.0), None);
+    }
+
+    #[test]
+    fn test_render() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("lib.rs"),
+            "pub fn swap(a: u64, b: u64) -> u64 {\n    unsafe {\n        a / b\n    }\n}\n",
+        )
+        .unwrap();
+
+        let engine = AnalyzerEngine::new(AnalyzerConfig {
+            root_path: dir.path().to_path_buf(),
+            ..Default::default()
+        })
+        .unwrap();
+        let registry = FactorRegistry::default();
+        let report = engine.analyze().unwrap();
+        let factors = engine.analyze_factors(&registry).unwrap();
+        let md = MarkdownOutput::render(&report, &factors, &registry);
+
+        assert!(md.contains("| Lines of code | `locFactor` |"));
+        assert!(md.contains("| Privileged roles | `acFactor` |"));
+        assert!(md.contains("- **warning** `unsafeLowLevel/unsafeBlock` at `lib.rs:2:5`"));
+        assert!(md.contains("<summary>Unsafe/low-level usage ("));
+    }
+}
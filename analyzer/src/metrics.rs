@@ -1,5 +1,6 @@
 //! Metrics data structures for analysis results
 
+use crate::workspace::Diagnostic;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
@@ -7,9 +8,13 @@ use std::path::PathBuf;
 /// Metrics for a single function
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct FunctionMetrics {
-    /// Function name
+    /// Function name, qualified with its owner for methods (`Pool::swap`)
     pub name: String,
 
+    /// Type or trait the function is defined on, if it is a method
+    #[serde(default)]
+    pub owner: Option<String>,
+
     /// Function signature (for disambiguation)
     pub signature: String,
 
@@ -36,6 +41,10 @@ pub struct FunctionMetrics {
 
     /// Overall complexity score
     pub complexity_score: f64,
+
+    /// Source text of the function (with `output.include_snippets`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
 }
 
 /// Raw arithmetic operation counts
@@ -161,6 +170,10 @@ pub struct FileMetrics {
 
     /// File-level semantic tags
     pub semantic_tags: Vec<String>,
+
+    /// Parse error, when only the items before it were analyzed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diagnostic: Option<Diagnostic>,
 }
 
 /// Repository-wide metrics
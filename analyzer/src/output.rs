@@ -1,8 +1,15 @@
-//! Output formatting and JSON structures
+//! Output formatting and report structures (JSON, YAML, TOML)
 
+use crate::config::{AnalyzerConfig, OutputConfig};
+use crate::error::{AnalyzerError, Result};
+use crate::factors::{Finding, FindingRule};
 use crate::metrics::RepoMetrics;
+use crate::patterns::PatternDetector;
+use crate::workspace::Diagnostic;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
 
 /// Main analysis report structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,6 +22,10 @@ pub struct AnalysisReport {
 
     /// Summary statistics
     pub summary: SummaryStats,
+
+    /// Files that could not be read or fully parsed
+    #[serde(default)]
+    pub diagnostics: Vec<Diagnostic>,
 }
 
 /// Report metadata
@@ -31,6 +42,10 @@ pub struct ReportMetadata {
 
     /// Analysis duration in milliseconds
     pub duration_ms: u64,
+
+    /// Timings (with `analysis.performance_analysis`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub performance: Option<PerformanceStats>,
 }
 
 /// Configuration summary for the report
@@ -49,6 +64,70 @@ pub struct ConfigSummary {
     pub exclude_pattern_count: usize,
 }
 
+impl From<&AnalyzerConfig> for ConfigSummary {
+    fn from(config: &AnalyzerConfig) -> Self {
+        Self {
+            included_tests: config.include_tests,
+            expanded_macros: config.expand_macros,
+            max_file_size: config.max_file_size,
+            exclude_pattern_count: config.exclude_patterns.len(),
+        }
+    }
+}
+
+/// Where the analysis spent its time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PerformanceStats {
+    /// Walking and filtering the repository
+    pub discovery_ms: u64,
+
+    /// Analyzing the discovered files
+    pub analysis_ms: u64,
+
+    /// Files analyzed per second of analysis time
+    pub files_per_second: f64,
+
+    /// Slowest files, slowest first
+    pub slowest_files: Vec<FileTiming>,
+}
+
+/// Analysis time of one file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileTiming {
+    pub path: String,
+    pub duration_ms: f64,
+}
+
+/// Files listed in `PerformanceStats::slowest_files`
+const SLOWEST_FILES: usize = 10;
+
+impl PerformanceStats {
+    pub fn new(discovery: Duration, analysis: Duration, files: Vec<(PathBuf, Duration)>) -> Self {
+        let files_per_second = if analysis.is_zero() {
+            0.0
+        } else {
+            files.len() as f64 / analysis.as_secs_f64()
+        };
+
+        let mut slowest_files: Vec<FileTiming> = files
+            .into_iter()
+            .map(|(path, duration)| FileTiming {
+                path: path.to_string_lossy().to_string(),
+                duration_ms: duration.as_secs_f64() * 1000.0,
+            })
+            .collect();
+        slowest_files.sort_by(|a, b| b.duration_ms.total_cmp(&a.duration_ms));
+        slowest_files.truncate(SLOWEST_FILES);
+
+        Self {
+            discovery_ms: discovery.as_millis() as u64,
+            analysis_ms: analysis.as_millis() as u64,
+            files_per_second,
+            slowest_files,
+        }
+    }
+}
+
 /// High-level summary statistics
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SummaryStats {
@@ -138,50 +217,275 @@ pub struct FunctionComplexity {
     pub total_operations: u32,
 }
 
+/// Report serialization format, selected by `OutputConfig.format`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Json,
+    Yaml,
+    Toml,
+    /// SARIF 2.1.0; the value rendered must be a [`SarifOutput::log`]
+    Sarif,
+    /// Standalone HTML document, written by [`crate::html::HtmlOutput`]
+    Html,
+    /// Pull request summary, written by [`crate::markdown::MarkdownOutput`]
+    Markdown,
+}
+
+impl OutputFormat {
+    pub fn name(self) -> &'static str {
+        match self {
+            OutputFormat::Json => "json",
+            OutputFormat::Yaml => "yaml",
+            OutputFormat::Toml => "toml",
+            OutputFormat::Sarif => "sarif",
+            OutputFormat::Html => "html",
+            OutputFormat::Markdown => "markdown",
+        }
+    }
+
+    /// Serialize `value` in this format
+    ///
+    /// Document formats are not serializations of a single value and fail
+    /// here; they are rendered by their own writers.
+    pub fn render<T: Serialize>(self, value: &T, pretty: bool) -> Result<String> {
+        let serialization_error = |message: String| AnalyzerError::SerializationError {
+            format: self.name(),
+            message,
+        };
+
+        match (self, pretty) {
+            // A SARIF log is a JSON document
+            (OutputFormat::Json | OutputFormat::Sarif, true) => Ok(JsonOutput::pretty(value)?),
+            (OutputFormat::Json | OutputFormat::Sarif, false) => Ok(JsonOutput::compact(value)?),
+            (OutputFormat::Yaml, true) => {
+                YamlOutput::pretty(value).map_err(|e| serialization_error(e.to_string()))
+            }
+            (OutputFormat::Yaml, false) => {
+                YamlOutput::compact(value).map_err(|e| serialization_error(e.to_string()))
+            }
+            (OutputFormat::Toml, true) => {
+                TomlOutput::pretty(value).map_err(|e| serialization_error(e.to_string()))
+            }
+            (OutputFormat::Toml, false) => {
+                TomlOutput::compact(value).map_err(|e| serialization_error(e.to_string()))
+            }
+            (OutputFormat::Html | OutputFormat::Markdown, _) => Err(serialization_error(
+                "documents are rendered by their own writer".to_string(),
+            )),
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = AnalyzerError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "json" => Ok(OutputFormat::Json),
+            "yaml" | "yml" => Ok(OutputFormat::Yaml),
+            "toml" => Ok(OutputFormat::Toml),
+            "sarif" => Ok(OutputFormat::Sarif),
+            "html" => Ok(OutputFormat::Html),
+            "markdown" | "md" => Ok(OutputFormat::Markdown),
+            other => Err(AnalyzerError::ConfigError {
+                message: format!(
+                    "Unknown output format '{}' (expected json, yaml, toml, sarif, html or markdown)",
+                    other
+                ),
+            }),
+        }
+    }
+}
+
+/// Serialize `value` in the format and style requested by `config`
+pub fn render<T: Serialize>(value: &T, config: &OutputConfig) -> Result<String> {
+    config
+        .format
+        .parse::<OutputFormat>()?
+        .render(value, config.pretty)
+}
+
 /// JSON output utility
 pub struct JsonOutput;
 
 impl JsonOutput {
     /// Format report as pretty JSON
-    pub fn pretty(report: &AnalysisReport) -> serde_json::Result<String> {
+    pub fn pretty<T: Serialize + ?Sized>(report: &T) -> serde_json::Result<String> {
         serde_json::to_string_pretty(report)
     }
 
     /// Format report as compact JSON
-    pub fn compact(report: &AnalysisReport) -> serde_json::Result<String> {
+    pub fn compact<T: Serialize + ?Sized>(report: &T) -> serde_json::Result<String> {
         serde_json::to_string(report)
     }
 }
 
+/// YAML output utility
+pub struct YamlOutput;
+
+impl YamlOutput {
+    /// Format report as block-style YAML
+    pub fn pretty<T: Serialize + ?Sized>(report: &T) -> serde_yaml::Result<String> {
+        serde_yaml::to_string(report)
+    }
+
+    /// Format report as single-line flow-style YAML
+    ///
+    /// The YAML emitter only writes block style, so this emits the JSON form,
+    /// which is itself a valid flow-style YAML document.
+    pub fn compact<T: Serialize + ?Sized>(report: &T) -> serde_yaml::Result<String> {
+        serde_json::to_string(report).map_err(serde::ser::Error::custom)
+    }
+}
+
+/// TOML output utility
+///
+/// TOML has no null, so `None` fields are left out of the document.
+pub struct TomlOutput;
+
+impl TomlOutput {
+    /// Format report as TOML with multi-line arrays
+    pub fn pretty<T: Serialize + ?Sized>(
+        report: &T,
+    ) -> std::result::Result<String, toml::ser::Error> {
+        toml::to_string_pretty(report)
+    }
+
+    /// Format report as TOML with inline arrays
+    pub fn compact<T: Serialize + ?Sized>(
+        report: &T,
+    ) -> std::result::Result<String, toml::ser::Error> {
+        toml::to_string(report)
+    }
+}
+
+/// SARIF 2.1.0 output utility
+///
+/// Every [`FindingRule`] becomes a rule of the tool driver and every
+/// [`Finding`] a result pointing at it. Artifact URIs are relative to the
+/// `%SRCROOT%` base, the analyzed root, which is how code-scanning uploads
+/// expect them.
+pub struct SarifOutput;
+
+impl SarifOutput {
+    pub const SCHEMA: &'static str = "https://json.schemastore.org/sarif-2.1.0.json";
+    pub const VERSION: &'static str = "2.1.0";
+
+    /// Relative URI reference for a workspace path: `/`-separated, with every
+    /// byte outside the RFC 3986 unreserved set percent-encoded per segment
+    pub fn artifact_uri(path: &str) -> String {
+        path.split(['/', '\\'])
+            .map(|segment| {
+                segment
+                    .bytes()
+                    .map(|byte| match byte {
+                        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                            (byte as char).to_string()
+                        }
+                        _ => format!("%{:02X}", byte),
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// SARIF log with one run holding `findings`
+    pub fn log<'a>(
+        rules: impl IntoIterator<Item = &'a FindingRule>,
+        findings: &[Finding],
+    ) -> serde_json::Value {
+        let rules: Vec<&FindingRule> = rules.into_iter().collect();
+
+        let rule_objects: Vec<_> = rules
+            .iter()
+            .map(|rule| {
+                serde_json::json!({
+                    "id": rule.id,
+                    "name": rule.name,
+                    "shortDescription": { "text": rule.description },
+                    "defaultConfiguration": { "level": rule.severity.as_str() },
+                })
+            })
+            .collect();
+
+        let results: Vec<_> = findings
+            .iter()
+            .map(|finding| {
+                let mut result = serde_json::json!({
+                    "ruleId": finding.rule_id,
+                    "level": finding.severity.as_str(),
+                    "message": { "text": finding.message },
+                    "locations": [{
+                        "physicalLocation": {
+                            "artifactLocation": {
+                                "uri": Self::artifact_uri(&finding.location.file),
+                                "uriBaseId": "%SRCROOT%",
+                            },
+                            "region": {
+                                "startLine": finding.location.line,
+                                "startColumn": finding.location.column,
+                                "endLine": finding.location.end_line,
+                                "endColumn": finding.location.end_column,
+                            },
+                        },
+                    }],
+                });
+                if let Some(index) = rules.iter().position(|rule| rule.id == finding.rule_id) {
+                    result["ruleIndex"] = serde_json::json!(index);
+                }
+                result
+            })
+            .collect();
+
+        serde_json::json!({
+            "$schema": Self::SCHEMA,
+            "version": Self::VERSION,
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": env!("CARGO_PKG_NAME"),
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "rules": rule_objects,
+                    },
+                },
+                "results": results,
+            }],
+        })
+    }
+}
+
 impl AnalysisReport {
     /// Create a new analysis report
     pub fn new(repository: RepoMetrics) -> Self {
+        Self::with_patterns(repository, &PatternDetector::new())
+    }
+
+    /// Report whose semantic pattern summary is described by `patterns`
+    pub fn with_patterns(repository: RepoMetrics, patterns: &PatternDetector) -> Self {
         let start_time = std::time::SystemTime::now();
 
         // Generate summary statistics
-        let summary = Self::generate_summary(&repository);
+        let summary = Self::generate_summary(&repository, patterns);
 
         let metadata = ReportMetadata {
             version: env!("CARGO_PKG_VERSION").to_string(),
             timestamp: chrono::Utc::now().to_rfc3339(),
-            config_summary: ConfigSummary {
-                included_tests: false, // TODO: get from actual config
-                expanded_macros: false,
-                max_file_size: 1024 * 1024,
-                exclude_pattern_count: 3,
-            },
+            config_summary: ConfigSummary::from(&AnalyzerConfig::default()),
             duration_ms: start_time.elapsed().unwrap_or_default().as_millis() as u64,
+            performance: None,
         };
 
         Self {
             metadata,
             repository,
             summary,
+            diagnostics: Vec::new(),
         }
     }
 
     /// Generate summary statistics from repository metrics
-    fn generate_summary(repo: &RepoMetrics) -> SummaryStats {
+    fn generate_summary(repo: &RepoMetrics, patterns: &PatternDetector) -> SummaryStats {
         let mut operation_breakdown = OperationBreakdown {
             checked_arithmetic: 0,
             math_functions: 0,
@@ -243,19 +547,25 @@ impl AnalysisReport {
             .sort_by(|a, b| b.complexity_score.partial_cmp(&a.complexity_score).unwrap());
         function_complexities.truncate(10);
 
-        // Generate semantic patterns (placeholder)
-        let top_semantic_patterns = vec![
-            PatternCount {
-                pattern: "token_swap".to_string(),
-                count: 5,
-                description: "Functions that handle token swapping operations".to_string(),
-            },
-            PatternCount {
-                pattern: "liquidity_management".to_string(),
-                count: 3,
-                description: "Functions that manage liquidity pools".to_string(),
-            },
-        ];
+        // Most frequent semantic patterns, by number of tagged functions
+        let mut top_semantic_patterns: Vec<PatternCount> = repo
+            .semantic_patterns
+            .iter()
+            .map(|(pattern, &count)| PatternCount {
+                pattern: pattern.clone(),
+                count,
+                description: patterns
+                    .get_pattern_info(pattern)
+                    .map(|info| info.description.clone())
+                    .unwrap_or_default(),
+            })
+            .collect();
+        top_semantic_patterns.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then_with(|| a.pattern.cmp(&b.pattern))
+        });
+        top_semantic_patterns.truncate(10);
 
         // Generate insights
         let mut insights = Vec::new();
@@ -294,3 +604,125 @@ impl AnalysisReport {
 
 // Add chrono dependency for timestamps
 // This would go in Cargo.toml: chrono = { version = "0.4", features = ["serde"] }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AnalyzerConfig;
+
+    fn sample_report() -> AnalysisReport {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("pool.rs"),
+            r#"
+            pub struct Pool;
+            impl Pool {
+                pub fn swap(&self, a: u64, b: u64) -> Option<u64> {
+                    a.checked_mul(b)?.checked_div(a + b)
+                }
+            }
+            "#,
+        )
+        .unwrap();
+
+        crate::analyze_repository(AnalyzerConfig {
+            root_path: dir.path().to_path_buf(),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_output_format_parsing() {
+        assert_eq!("json".parse::<OutputFormat>().unwrap(), OutputFormat::Json);
+        assert_eq!("YML".parse::<OutputFormat>().unwrap(), OutputFormat::Yaml);
+        assert_eq!(
+            " toml ".parse::<OutputFormat>().unwrap(),
+            OutputFormat::Toml
+        );
+        assert!(matches!(
+            "xml".parse::<OutputFormat>(),
+            Err(AnalyzerError::ConfigError { .. })
+        ));
+    }
+
+    #[test]
+    fn test_formats_round_trip() {
+        let report = sample_report();
+        let expected = serde_json::to_value(&report.repository.files[0].functions[0].name).unwrap();
+
+        for pretty in [true, false] {
+            let json = OutputFormat::Json.render(&report, pretty).unwrap();
+            let yaml = OutputFormat::Yaml.render(&report, pretty).unwrap();
+            let toml = OutputFormat::Toml.render(&report, pretty).unwrap();
+            assert_eq!(json.contains('\n'), pretty);
+            assert_eq!(yaml.contains('\n'), pretty);
+
+            let from_json: serde_json::Value = serde_json::from_str(&json).unwrap();
+            let from_yaml: serde_json::Value = serde_yaml::from_str(&yaml).unwrap();
+            let from_toml: serde_json::Value = toml::from_str(&toml).unwrap();
+            for value in [&from_json, &from_yaml, &from_toml] {
+                assert_eq!(
+                    value["repository"]["files"][0]["functions"][0]["name"],
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_render_uses_output_config() {
+        let report = sample_report();
+        let mut config = OutputConfig {
+            format: "toml".to_string(),
+            ..AnalyzerConfig::default().output
+        };
+        assert!(render(&report, &config).unwrap().contains("[metadata]"));
+
+        config.format = "csv".to_string();
+        assert!(render(&report, &config).is_err());
+    }
+
+    #[test]
+    fn test_sarif_log() {
+        use crate::factors::arithmetic::UNCHECKED_DIVISION;
+        use crate::factors::privileged_roles::MANUAL_SIGNER_CHECK;
+        use crate::workspace::SourceLocation;
+
+        let location = SourceLocation {
+            file: "programs/amm/src/lib.rs".to_string(),
+            line: 12,
+            column: 9,
+            end_line: 12,
+            end_column: 14,
+        };
+        let findings = vec![UNCHECKED_DIVISION.at(location, "a / b")];
+        let log = SarifOutput::log([&MANUAL_SIGNER_CHECK, &UNCHECKED_DIVISION], &findings);
+
+        assert_eq!(log["version"], "2.1.0");
+        let run = &log["runs"][0];
+        assert_eq!(run["tool"]["driver"]["rules"].as_array().unwrap().len(), 2);
+
+        let result = &run["results"][0];
+        assert_eq!(result["ruleId"], "arithmeticOperations/uncheckedDivision");
+        assert_eq!(result["ruleIndex"], 1);
+        assert_eq!(result["level"], "warning");
+        let physical = &result["locations"][0]["physicalLocation"];
+        assert_eq!(
+            physical["artifactLocation"]["uri"],
+            "programs/amm/src/lib.rs"
+        );
+        assert_eq!(physical["region"]["startLine"], 12);
+        assert_eq!(physical["region"]["startColumn"], 9);
+
+        assert_eq!(
+            SarifOutput::artifact_uri("my programs\\amm#2/src/100%.rs (expanded)"),
+            "my%20programs/amm%232/src/100%25.rs%20%28expanded%29"
+        );
+
+        assert_eq!(
+            "sarif".parse::<OutputFormat>().unwrap(),
+            OutputFormat::Sarif
+        );
+    }
+}
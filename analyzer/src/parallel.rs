@@ -0,0 +1,138 @@
+//! Worker pools for file- and factor-level parallelism
+//!
+//! Results always come back in input order, so reports stay identical no
+//! matter how the work was scheduled.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Threads to use for `jobs` jobs when `requested` were asked for
+///
+/// `0` means one per available core; never more threads than jobs.
+pub fn worker_count(requested: usize, jobs: usize) -> usize {
+    let workers = if requested == 0 {
+        std::thread::available_parallelism().map_or(1, |n| n.get())
+    } else {
+        requested
+    };
+    workers.min(jobs).max(1)
+}
+
+/// Map `items` on up to `workers` threads, preserving input order
+pub fn map<T, R>(items: &[T], workers: usize, f: impl Fn(&T) -> R + Sync) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+{
+    map_with(items, workers, || (), |_, item| f(item))
+}
+
+/// Like [`map`], with per-worker state built by `init`
+///
+/// `init` runs once on each worker thread, which is how thread-bound values
+/// (such as `syn` syntax trees, which are neither `Send` nor `Sync`) are
+/// made available to every job on that worker.
+pub fn map_with<T, S, R>(
+    items: &[T],
+    workers: usize,
+    init: impl Fn() -> S + Sync,
+    f: impl Fn(&mut S, &T) -> R + Sync,
+) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+{
+    let workers = worker_count(workers, items.len());
+    if workers == 1 {
+        let mut state = init();
+        return items.iter().map(|item| f(&mut state, item)).collect();
+    }
+
+    // Workers pull the next unclaimed index, so slow jobs do not stall a
+    // statically assigned share
+    let next = AtomicUsize::new(0);
+    let batches: Vec<Vec<(usize, R)>> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut state = init();
+                    let mut done = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(item) = items.get(index) else {
+                            break;
+                        };
+                        done.push((index, f(&mut state, item)));
+                    }
+                    // Results cannot hold spans (they are not `Send`), so
+                    // the worker's parsed sources can go with its state
+                    drop(state);
+                    crate::workspace::release_thread_spans();
+                    done
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            })
+            .collect()
+    });
+
+    let mut results: Vec<Option<R>> = (0..items.len()).map(|_| None).collect();
+    for (index, result) in batches.into_iter().flatten() {
+        results[index] = Some(result);
+    }
+    results
+        .into_iter()
+        .map(|result| result.expect("every item is mapped exactly once"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_worker_count() {
+        assert_eq!(worker_count(4, 10), 4);
+        assert_eq!(worker_count(4, 2), 2);
+        assert_eq!(worker_count(4, 0), 1);
+        assert!(worker_count(0, 1000) >= 1);
+    }
+
+    #[test]
+    fn test_map_preserves_order() {
+        let items: Vec<u64> = (0..200).collect();
+        let squares = map(&items, 8, |n| {
+            // Uneven job lengths shuffle completion order
+            std::thread::sleep(std::time::Duration::from_micros(200 - n));
+            n * n
+        });
+        assert_eq!(squares, items.iter().map(|n| n * n).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_init_runs_once_per_worker() {
+        let inits = AtomicUsize::new(0);
+        let items: Vec<usize> = (0..64).collect();
+        let results = map_with(
+            &items,
+            4,
+            || {
+                inits.fetch_add(1, Ordering::SeqCst);
+                0usize
+            },
+            |seen, item| {
+                *seen += 1;
+                *item
+            },
+        );
+
+        assert_eq!(results, items);
+        assert!(inits.load(Ordering::SeqCst) <= 4);
+    }
+}
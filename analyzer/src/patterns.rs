@@ -1,279 +1,384 @@
 //! Semantic pattern recognition for AMM and DeFi contracts
-
-use std::collections::HashMap;
+//!
+//! Patterns come in packs: TOML or YAML files naming keywords, function name
+//! globs and AST matchers per pattern. The AMM pack is always loaded;
+//! `analysis.pattern_packs` adds built-in packs by name (lending, perps,
+//! staking and governance by default) and custom packs by file path.
+
+use crate::config::AnalysisConfig;
+use crate::error::{AnalyzerError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::Path;
+
+/// Packs shipped with the analyzer, by name
+pub const BUILTIN_PACKS: &[(&str, &str)] = &[
+    ("amm", include_str!("../patterns/amm.toml")),
+    ("lending", include_str!("../patterns/lending.toml")),
+    ("perps", include_str!("../patterns/perps.toml")),
+    ("staking", include_str!("../patterns/staking.toml")),
+    ("governance", include_str!("../patterns/governance.toml")),
+];
+
+const RISK_LEVELS: &[&str] = &["low", "medium", "high", "critical"];
+
+/// Prefix marking a function or AST pattern as a regular expression
+pub const REGEX_PREFIX: &str = "regex:";
 
 /// Semantic pattern detector for AMM-specific code patterns
 pub struct PatternDetector {
     /// Known patterns and their descriptions
     patterns: HashMap<String, PatternInfo>,
+
+    /// Function and AST patterns of `patterns`, compiled once
+    compiled: HashMap<String, NamePattern>,
 }
 
-/// Information about a semantic pattern
+/// Compiled function or AST pattern
+///
+/// Globs are anchored and case-insensitive: `*` matches any run, `?` one
+/// character, `[abc]`/`[a-z]`/`[!abc]` a character class. A `regex:` prefix
+/// takes the rest as a regular expression, unanchored and case-sensitive
+/// unless it says otherwise.
 #[derive(Debug, Clone)]
+enum NamePattern {
+    Glob(glob::Pattern),
+    Regex(regex::Regex),
+}
+
+impl NamePattern {
+    const GLOB_OPTIONS: glob::MatchOptions = glob::MatchOptions {
+        case_sensitive: false,
+        require_literal_separator: false,
+        require_literal_leading_dot: false,
+    };
+
+    fn compile(pattern: &str) -> std::result::Result<Self, String> {
+        match pattern.strip_prefix(REGEX_PREFIX) {
+            Some(regex) => regex::Regex::new(regex)
+                .map(Self::Regex)
+                .map_err(|e| e.to_string()),
+            None => glob::Pattern::new(pattern)
+                .map(Self::Glob)
+                .map_err(|e| e.to_string()),
+        }
+    }
+
+    fn matches(&self, text: &str) -> bool {
+        match self {
+            Self::Glob(glob) => glob.matches_with(text, Self::GLOB_OPTIONS),
+            Self::Regex(regex) => regex.is_match(text),
+        }
+    }
+}
+
+/// Information about a semantic pattern
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PatternInfo {
     /// Human-readable name
     pub name: String,
 
     /// Description of what this pattern indicates
+    #[serde(default)]
     pub description: String,
 
     /// Risk level: "low", "medium", "high", "critical"
     pub risk_level: String,
 
     /// Keywords that indicate this pattern
+    #[serde(default)]
     pub keywords: Vec<String>,
 
     /// Function name patterns that indicate this pattern
+    #[serde(default)]
     pub function_patterns: Vec<String>,
+
+    /// Syntax in the function body that indicates this pattern
+    #[serde(default)]
+    pub ast: Vec<AstMatcher>,
 }
 
-impl PatternDetector {
-    /// Create a new pattern detector with default AMM patterns
-    pub fn new() -> Self {
-        let mut patterns = HashMap::new();
-
-        // Constant Product AMM Pattern
-        patterns.insert(
-            "constant_product_amm".to_string(),
-            PatternInfo {
-                name: "Constant Product AMM".to_string(),
-                description:
-                    "Implements constant product formula (x * y = k) for automated market making"
-                        .to_string(),
-                risk_level: "medium".to_string(),
-                keywords: vec![
-                    "constant_product".to_string(),
-                    "invariant".to_string(),
-                    "k_value".to_string(),
-                ],
-                function_patterns: vec![
-                    "swap_*".to_string(),
-                    "*_invariant".to_string(),
-                    "calc_*_out".to_string(),
-                ],
-            },
-        );
+/// Glob over one kind of syntax node, e.g. `{ method_call = "set_authority" }`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AstMatcher {
+    /// Method call by method name (`.delegate_stake(..)`)
+    MethodCall(String),
 
-        // Token Swap Pattern
-        patterns.insert(
-            "token_swap".to_string(),
-            PatternInfo {
-                name: "Token Swap".to_string(),
-                description: "Handles swapping between different tokens".to_string(),
-                risk_level: "medium".to_string(),
-                keywords: vec![
-                    "swap".to_string(),
-                    "exchange".to_string(),
-                    "trade".to_string(),
-                ],
-                function_patterns: vec![
-                    "swap_*".to_string(),
-                    "exchange_*".to_string(),
-                    "*_swap_*".to_string(),
-                ],
-            },
-        );
+    /// Function call by the last path segment (`token::set_authority(..)`)
+    Call(String),
 
-        // Liquidity Management Pattern
-        patterns.insert(
-            "liquidity_management".to_string(),
-            PatternInfo {
-                name: "Liquidity Management".to_string(),
-                description: "Manages liquidity pool operations (add/remove liquidity)".to_string(),
-                risk_level: "medium".to_string(),
-                keywords: vec![
-                    "liquidity".to_string(),
-                    "pool".to_string(),
-                    "lp_token".to_string(),
-                ],
-                function_patterns: vec![
-                    "add_liquidity".to_string(),
-                    "remove_liquidity".to_string(),
-                    "*_liquidity_*".to_string(),
-                ],
-            },
-        );
+    /// Macro invocation by name, without the `!`
+    Macro(String),
 
-        // Price Calculation Pattern
-        patterns.insert(
-            "price_calculation".to_string(),
-            PatternInfo {
-                name: "Price Calculation".to_string(),
-                description: "Calculates token prices and exchange rates".to_string(),
-                risk_level: "high".to_string(),
-                keywords: vec![
-                    "price".to_string(),
-                    "rate".to_string(),
-                    "oracle".to_string(),
-                ],
-                function_patterns: vec![
-                    "get_price".to_string(),
-                    "calc_*_price".to_string(),
-                    "*_rate".to_string(),
-                ],
-            },
-        );
+    /// Type by the last path segment (`Account<'info, Obligation>`)
+    Type(String),
+}
 
-        // Fee Calculation Pattern
-        patterns.insert(
-            "fee_calculation".to_string(),
-            PatternInfo {
-                name: "Fee Calculation".to_string(),
-                description: "Calculates trading fees and protocol fees".to_string(),
-                risk_level: "medium".to_string(),
-                keywords: vec![
-                    "fee".to_string(),
-                    "commission".to_string(),
-                    "protocol_fee".to_string(),
-                ],
-                function_patterns: vec![
-                    "calc_*_fee".to_string(),
-                    "*_fee_*".to_string(),
-                    "apply_fee".to_string(),
-                ],
-            },
-        );
+impl AstMatcher {
+    /// Glob or `regex:` pattern the node name is matched against
+    pub fn pattern(&self) -> &str {
+        match self {
+            Self::MethodCall(pattern)
+            | Self::Call(pattern)
+            | Self::Macro(pattern)
+            | Self::Type(pattern) => pattern,
+        }
+    }
+}
 
-        // Slippage Protection Pattern
-        patterns.insert(
-            "slippage_protection".to_string(),
-            PatternInfo {
-                name: "Slippage Protection".to_string(),
-                description: "Implements slippage protection mechanisms".to_string(),
-                risk_level: "high".to_string(),
-                keywords: vec![
-                    "slippage".to_string(),
-                    "min_amount_out".to_string(),
-                    "max_amount_in".to_string(),
-                ],
-                function_patterns: vec!["check_slippage".to_string(), "*_slippage_*".to_string()],
-            },
-        );
+impl PatternInfo {
+    /// Function name and AST patterns
+    fn name_patterns(&self) -> impl Iterator<Item = &str> {
+        self.function_patterns
+            .iter()
+            .map(String::as_str)
+            .chain(self.ast.iter().map(AstMatcher::pattern))
+    }
+}
 
-        // PnL Calculation Pattern
-        patterns.insert(
-            "pnl_calculation".to_string(),
-            PatternInfo {
-                name: "PnL Calculation".to_string(),
-                description: "Calculates profit and loss for positions".to_string(),
-                risk_level: "high".to_string(),
-                keywords: vec![
-                    "pnl".to_string(),
-                    "profit".to_string(),
-                    "loss".to_string(),
-                    "realized".to_string(),
-                ],
-                function_patterns: vec![
-                    "calc_pnl".to_string(),
-                    "*_profit_*".to_string(),
-                    "*_pnl_*".to_string(),
-                ],
-            },
-        );
+/// Named set of patterns loaded from a TOML or YAML file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PatternPack {
+    /// Pack name, e.g. "lending"
+    pub name: String,
 
-        // Invariant Maintenance Pattern
-        patterns.insert(
-            "invariant_maintenance".to_string(),
-            PatternInfo {
-                name: "Invariant Maintenance".to_string(),
-                description: "Maintains mathematical invariants in the protocol".to_string(),
-                risk_level: "critical".to_string(),
-                keywords: vec![
-                    "invariant".to_string(),
-                    "maintain".to_string(),
-                    "validate".to_string(),
-                ],
-                function_patterns: vec![
-                    "*_invariant".to_string(),
-                    "validate_*".to_string(),
-                    "check_*".to_string(),
-                ],
-            },
-        );
+    /// What kind of protocol the pack describes
+    #[serde(default)]
+    pub description: String,
 
-        // Overflow Protection Pattern
-        patterns.insert(
-            "overflow_protection".to_string(),
-            PatternInfo {
-                name: "Overflow Protection".to_string(),
-                description: "Uses checked arithmetic to prevent overflow attacks".to_string(),
-                risk_level: "low".to_string(),
-                keywords: vec![
-                    "checked_".to_string(),
-                    "saturating_".to_string(),
-                    "safe_".to_string(),
-                ],
-                function_patterns: vec!["safe_*".to_string()],
-            },
-        );
+    /// Patterns keyed by id (the tag reported on functions)
+    pub patterns: BTreeMap<String, PatternInfo>,
+}
 
-        // Precision Handling Pattern
-        patterns.insert(
-            "precision_handling".to_string(),
-            PatternInfo {
-                name: "Precision Handling".to_string(),
-                description: "Handles decimal precision and rounding in calculations".to_string(),
-                risk_level: "medium".to_string(),
-                keywords: vec![
-                    "precision".to_string(),
-                    "decimal".to_string(),
-                    "scale".to_string(),
-                    "normalize".to_string(),
-                ],
-                function_patterns: vec![
-                    "normalize_*".to_string(),
-                    "*_precision".to_string(),
-                    "scale_*".to_string(),
-                ],
-            },
-        );
+/// Syntax of one function that patterns are matched against
+#[derive(Debug, Clone, Default)]
+pub struct FunctionSyntax {
+    /// Every identifier in the signature and body, macro arguments included
+    pub identifiers: HashSet<String>,
+
+    /// Names of called methods
+    pub method_calls: HashSet<String>,
+
+    /// Last path segment of called functions
+    pub calls: HashSet<String>,
 
-        Self { patterns }
+    /// Names of invoked macros
+    pub macros: HashSet<String>,
+
+    /// Last path segment of referenced types
+    pub types: HashSet<String>,
+}
+
+impl PatternPack {
+    /// Built-in pack by name
+    pub fn builtin(name: &str) -> Option<Self> {
+        BUILTIN_PACKS
+            .iter()
+            .find(|(builtin, _)| *builtin == name)
+            .map(|(_, content)| {
+                Self::from_toml(content).unwrap_or_else(|e| panic!("built-in pack {name}: {e}"))
+            })
     }
 
-    /// Detect patterns in a function name
-    pub fn detect_in_function_name(&self, function_name: &str) -> Vec<String> {
-        let mut detected = Vec::new();
-        let name_lower = function_name.to_lowercase();
-
-        for (pattern_id, pattern_info) in &self.patterns {
-            // Check function name patterns
-            for pattern in &pattern_info.function_patterns {
-                if self.matches_pattern(&name_lower, pattern) {
-                    detected.push(pattern_id.clone());
-                    break;
-                }
+    /// Load a pack from a `.toml`, `.yaml` or `.yml` file
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| AnalyzerError::ConfigError {
+            message: format!("Cannot read pattern pack {}: {}", path.display(), e),
+        })?;
+        let pack = match path.extension().and_then(|ext| ext.to_str()) {
+            // Via JSON so AST matchers read as `- type: Pool` rather than `!type Pool`
+            Some("yaml" | "yml") => serde_yaml::from_str::<serde_json::Value>(&content)
+                .map_err(|e| e.to_string())
+                .and_then(|value| serde_json::from_value(value).map_err(|e| e.to_string())),
+            _ => Self::from_toml(&content),
+        };
+        pack.map_err(|e| AnalyzerError::ConfigError {
+            message: format!("Invalid pattern pack {}: {}", path.display(), e),
+        })
+    }
+
+    /// Built-in pack name, else a pack file path
+    pub fn resolve(pack: &str) -> Result<Self> {
+        match Self::builtin(pack) {
+            Some(pack) => Ok(pack),
+            None => Self::from_file(Path::new(pack)),
+        }
+    }
+
+    fn from_toml(content: &str) -> std::result::Result<Self, String> {
+        toml::from_str(content).map_err(|e| e.to_string())
+    }
+
+    fn validate(&self) -> Result<()> {
+        for (id, pattern) in &self.patterns {
+            if !RISK_LEVELS.contains(&pattern.risk_level.as_str()) {
+                return Err(AnalyzerError::ConfigError {
+                    message: format!(
+                        "Pattern {}/{}: unknown risk_level {:?} (expected one of {})",
+                        self.name,
+                        id,
+                        pattern.risk_level,
+                        RISK_LEVELS.join(", ")
+                    ),
+                });
             }
+            for name_pattern in pattern.name_patterns() {
+                NamePattern::compile(name_pattern).map_err(|e| AnalyzerError::ConfigError {
+                    message: format!(
+                        "Pattern {}/{}: invalid pattern {:?}: {}",
+                        self.name, id, name_pattern, e
+                    ),
+                })?;
+            }
+        }
+        Ok(())
+    }
+}
 
-            // Check keywords in function name
-            for keyword in &pattern_info.keywords {
-                if name_lower.contains(&keyword.to_lowercase()) {
-                    detected.push(pattern_id.clone());
-                    break;
+impl PatternDetector {
+    /// Create a new pattern detector with default AMM patterns
+    pub fn new() -> Self {
+        let mut detector = Self {
+            patterns: HashMap::new(),
+            compiled: HashMap::new(),
+        };
+        detector.add_pack(PatternPack::builtin("amm").expect("amm pack is built in"));
+        detector
+    }
+
+    /// AMM patterns plus the packs named in `analysis.pattern_packs`
+    pub fn from_config(analysis: &AnalysisConfig) -> Result<Self> {
+        let mut detector = Self::new();
+        for pack in &analysis.pattern_packs {
+            let pack = PatternPack::resolve(pack)?;
+            pack.validate()?;
+            detector.add_pack(pack);
+        }
+        Ok(detector)
+    }
+
+    /// Add the patterns of `pack`, replacing earlier patterns with the same id
+    pub fn add_pack(&mut self, pack: PatternPack) {
+        log::debug!(
+            "Loading pattern pack {} ({} patterns)",
+            pack.name,
+            pack.patterns.len()
+        );
+        for pattern in pack.patterns.values() {
+            for name_pattern in pattern.name_patterns() {
+                if self.compiled.contains_key(name_pattern) {
+                    continue;
+                }
+                match NamePattern::compile(name_pattern) {
+                    Ok(compiled) => {
+                        self.compiled.insert(name_pattern.to_string(), compiled);
+                    }
+                    Err(e) => log::warn!("Ignoring pattern {:?}: {}", name_pattern, e),
                 }
             }
         }
+        self.patterns.extend(pack.patterns);
+    }
+
+    /// Stable digest of every loaded pattern, for cache keys
+    pub fn fingerprint(&self) -> String {
+        let sorted: BTreeMap<_, _> = self.patterns.iter().collect();
+        let json = serde_json::to_vec(&sorted).unwrap_or_default();
+        crate::cache::content_hash(&json)
+    }
+
+    /// Detect patterns in a function name, each pattern reported once
+    pub fn detect_in_function_name(&self, function_name: &str) -> Vec<String> {
+        let name_words = words(function_name);
+
+        let mut detected: Vec<String> = self
+            .patterns
+            .iter()
+            .filter(|(_, pattern_info)| {
+                pattern_info
+                    .function_patterns
+                    .iter()
+                    .any(|pattern| self.matches_pattern(function_name, pattern))
+                    || pattern_info
+                        .keywords
+                        .iter()
+                        .any(|keyword| contains_keyword(&name_words, keyword))
+            })
+            .map(|(pattern_id, _)| pattern_id.clone())
+            .collect();
+        detected.sort();
+        detected
+    }
 
+    /// Detect patterns whose keywords appear as whole words of any identifier
+    /// (`min_amount_out`, `SwapParams`, `checked_mul`)
+    pub fn detect_in_identifiers<'a>(
+        &self,
+        identifiers: impl IntoIterator<Item = &'a str>,
+    ) -> Vec<String> {
+        let identifier_words: Vec<String> = identifiers.into_iter().map(words).collect();
+
+        let mut detected: Vec<String> = self
+            .patterns
+            .iter()
+            .filter(|(_, pattern_info)| {
+                pattern_info.keywords.iter().any(|keyword| {
+                    identifier_words
+                        .iter()
+                        .any(|identifier| contains_keyword(identifier, keyword))
+                })
+            })
+            .map(|(pattern_id, _)| pattern_id.clone())
+            .collect();
+        detected.sort();
         detected
     }
 
     /// Detect patterns in source code content
     pub fn detect_in_content(&self, content: &str) -> Vec<String> {
-        let mut detected = Vec::new();
-        let content_lower = content.to_lowercase();
-
-        for (pattern_id, pattern_info) in &self.patterns {
-            for keyword in &pattern_info.keywords {
-                if content_lower.contains(&keyword.to_lowercase()) {
-                    detected.push(pattern_id.clone());
-                    break;
-                }
-            }
-        }
+        self.detect_in_identifiers(
+            content
+                .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .filter(|token| !token.is_empty()),
+        )
+    }
 
+    /// Detect patterns whose AST matchers match any call, macro or type
+    pub fn detect_in_syntax(&self, syntax: &FunctionSyntax) -> Vec<String> {
+        let mut detected: Vec<String> = self
+            .patterns
+            .iter()
+            .filter(|(_, pattern_info)| {
+                pattern_info.ast.iter().any(|matcher| {
+                    let (names, pattern) = match matcher {
+                        AstMatcher::MethodCall(pattern) => (&syntax.method_calls, pattern),
+                        AstMatcher::Call(pattern) => (&syntax.calls, pattern),
+                        AstMatcher::Macro(pattern) => (&syntax.macros, pattern),
+                        AstMatcher::Type(pattern) => (&syntax.types, pattern),
+                    };
+                    names.iter().any(|name| self.matches_pattern(name, pattern))
+                })
+            })
+            .map(|(pattern_id, _)| pattern_id.clone())
+            .collect();
+        detected.sort();
         detected
     }
 
+    /// Tags for one function: patterns matched by its name, by the
+    /// identifiers of its signature and body, or by its AST matchers; sorted
+    /// and deduplicated
+    pub fn detect_in_function(&self, function_name: &str, syntax: &FunctionSyntax) -> Vec<String> {
+        let tags: BTreeSet<String> = self
+            .detect_in_function_name(function_name)
+            .into_iter()
+            .chain(self.detect_in_identifiers(syntax.identifiers.iter().map(String::as_str)))
+            .chain(self.detect_in_syntax(syntax))
+            .collect();
+        tags.into_iter().collect()
+    }
+
     /// Get information about a pattern
     pub fn get_pattern_info(&self, pattern_id: &str) -> Option<&PatternInfo> {
         self.patterns.get(pattern_id)
@@ -284,26 +389,39 @@ impl PatternDetector {
         &self.patterns
     }
 
-    /// Check if a string matches a glob-like pattern
+    /// Check if a name matches a glob or `regex:` pattern; patterns of
+    /// loaded packs are precompiled, others are compiled per call
     fn matches_pattern(&self, text: &str, pattern: &str) -> bool {
-        if pattern.contains('*') {
-            // Simple glob matching
-            let parts: Vec<&str> = pattern.split('*').collect();
-            if parts.len() == 2 {
-                let prefix = parts[0];
-                let suffix = parts[1];
-                text.starts_with(prefix) && text.ends_with(suffix)
-            } else {
-                // More complex patterns - simplified implementation
-                let pattern_regex = pattern.replace('*', ".*");
-                regex::Regex::new(&pattern_regex)
-                    .map(|re| re.is_match(text))
-                    .unwrap_or(false)
-            }
-        } else {
-            text == pattern
+        match self.compiled.get(pattern) {
+            Some(compiled) => compiled.matches(text),
+            None => NamePattern::compile(pattern).is_ok_and(|compiled| compiled.matches(text)),
+        }
+    }
+}
+
+/// Identifier as `_`-delimited lowercase words: `SwapParams` and `swap_params`
+/// both become `_swap_params_`
+fn words(identifier: &str) -> String {
+    let mut out = String::with_capacity(identifier.len() + 8);
+    out.push('_');
+    let mut previous_lower = false;
+    for c in identifier.chars() {
+        if c.is_uppercase() && previous_lower {
+            out.push('_');
         }
+        previous_lower = c.is_lowercase() || c.is_ascii_digit();
+        out.extend(c.to_lowercase());
     }
+    out.push('_');
+    out
+}
+
+/// Whether `keyword` is a run of whole words in `identifier_words`; a trailing
+/// `_` (`checked_`) marks a prefix of further words
+fn contains_keyword(identifier_words: &str, keyword: &str) -> bool {
+    let keyword = keyword.to_lowercase();
+    let needle = format!("_{}_", keyword.trim_end_matches('_'));
+    identifier_words.contains(&needle)
 }
 
 impl Default for PatternDetector {
@@ -352,3 +470,117 @@ impl RiskAssessment {
         (risk_level, risk_factors)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_in_function() {
+        let detector = PatternDetector::new();
+
+        // Glob and keyword both match `swap_exact_in`; reported once
+        let tags = detector.detect_in_function_name("swap_exact_in");
+        assert_eq!(tags.iter().filter(|t| *t == "token_swap").count(), 1);
+
+        // Keywords match whole words only: `iterate` is not a `rate`
+        assert!(detector.detect_in_identifiers(["iterate"]).is_empty());
+        assert_eq!(
+            detector.detect_in_identifiers(["SwapParams", "min_amount_out", "checked_mul"]),
+            ["overflow_protection", "slippage_protection", "token_swap"]
+        );
+
+        let syntax = FunctionSyntax {
+            identifiers: ["lp_token", "integer_sqrt"].map(String::from).into(),
+            ..FunctionSyntax::default()
+        };
+        let tags = detector.detect_in_function("deposit", &syntax);
+        assert_eq!(tags, ["liquidity_management", "square_root_calculation"]);
+    }
+
+    #[test]
+    fn test_matches_pattern() {
+        let detector = PatternDetector::new();
+        let matches = |text, pattern| detector.matches_pattern(text, pattern);
+
+        assert!(matches("swap_exact_in", "swap_*"));
+        assert!(!matches("xswap_y", "swap_*"));
+        assert!(matches("Swap_Base_In", "swap_*"));
+        assert!(matches("calc_lp_fee_bps", "calc_*_fee_*"));
+        assert!(!matches("calc_fee", "calc_*_fee_*"));
+        assert!(matches("swap_v2", "swap_v?"));
+        assert!(!matches("swap_v10", "swap_v?"));
+        assert!(matches("deposit_a", "deposit_[ab]"));
+        assert!(!matches("deposit_c", "deposit_[!c]"));
+        assert!(matches("withdraw_all", "regex:^withdraw(_all)?$"));
+        assert!(!matches("Withdraw", "regex:^withdraw"));
+        assert!(!matches("deposit", "deposit_[ab"));
+
+        // Patterns of loaded packs are compiled once, at load
+        assert!(detector.compiled.contains_key("swap_*"));
+    }
+
+    #[test]
+    fn test_pattern_packs() {
+        // Every built-in pack parses and passes validation
+        for (name, _) in BUILTIN_PACKS {
+            PatternPack::builtin(name).unwrap().validate().unwrap();
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nft.yaml");
+        std::fs::write(
+            &path,
+            r#"
+name: nft
+patterns:
+  royalty_enforcement:
+    name: Royalty Enforcement
+    risk_level: medium
+    keywords: [royalty]
+    ast:
+      - type: Metadata*
+      - macro: require_royalty
+"#,
+        )
+        .unwrap();
+        let analysis = AnalysisConfig {
+            pattern_packs: vec!["lending".to_string(), path.display().to_string()],
+            ..AnalysisConfig::default()
+        };
+        let detector = PatternDetector::from_config(&analysis).unwrap();
+        assert!(detector.get_pattern_info("token_swap").is_some());
+
+        let syntax = FunctionSyntax {
+            types: ["MetadataV3".to_string()].into(),
+            calls: ["liquidate_obligation".to_string()].into(),
+            ..FunctionSyntax::default()
+        };
+        assert_eq!(detector.detect_in_syntax(&syntax), ["royalty_enforcement"]);
+        assert_eq!(
+            detector.detect_in_function("liquidate_obligation", &syntax),
+            ["lending_liquidation", "royalty_enforcement"]
+        );
+
+        std::fs::write(
+            &path,
+            "name: nft\npatterns:\n  x: { name: X, risk_level: severe }\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            PatternDetector::from_config(&analysis),
+            Err(AnalyzerError::ConfigError { .. })
+        ));
+        std::fs::write(
+            &path,
+            "name: nft\npatterns:\n  x: { name: X, risk_level: low, function_patterns: ['regex:('] }\n",
+        )
+        .unwrap();
+        assert!(PatternDetector::from_config(&analysis).is_err());
+        let analysis = AnalysisConfig {
+            pattern_packs: vec!["defi".to_string()],
+            ..AnalysisConfig::default()
+        };
+        assert!(PatternDetector::from_config(&analysis).is_err());
+    }
+}
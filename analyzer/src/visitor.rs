@@ -1,14 +1,23 @@
 //! AST visitor for analyzing Rust code
 
+use crate::config::PatternWeights;
 use crate::metrics::{
     ArithmeticMetrics, ControlFlowMetrics, FunctionMetrics, MathFunctionMetrics, SafetyMetrics,
 };
+use crate::patterns::{FunctionSyntax, PatternDetector};
+use proc_macro2::{Span, TokenStream, TokenTree};
 use std::collections::HashSet;
+use std::sync::Arc;
+use syn::spanned::Spanned;
 use syn::visit::{self, Visit};
-use syn::{BinOp, Block, Expr, ExprBinary, ExprCall, ExprMethodCall, ItemFn, Stmt};
+use syn::{
+    BinOp, Block, Expr, ExprBinary, ExprCall, ExprMethodCall, Ident, ImplItemFn, ItemFn, ItemImpl,
+    ItemTrait, Macro, Signature, TraitItemFn, Type, TypePath,
+};
 
 /// Main visitor for analyzing function metrics
-pub struct FunctionVisitor<'ast> {
+#[derive(Default)]
+pub struct FunctionVisitor {
     /// Current function being analyzed
     current_function: Option<FunctionMetrics>,
 
@@ -21,96 +30,105 @@ pub struct FunctionVisitor<'ast> {
     max_loop_depth: usize,
     max_conditional_depth: usize,
 
-    /// Source code for line number calculations
-    source: &'ast str,
+    /// Identifiers, calls, macros and types of the current function
+    syntax: FunctionSyntax,
+
+    /// Semantic patterns to tag functions with
+    patterns: Arc<PatternDetector>,
+
+    /// Type or trait whose `impl`/`trait` block is being visited
+    owner: Option<String>,
 
-    /// Detected semantic patterns
-    semantic_patterns: HashSet<String>,
+    /// Weights for the function complexity score
+    weights: PatternWeights,
 }
 
-impl<'ast> FunctionVisitor<'ast> {
-    pub fn new(source: &'ast str) -> Self {
-        Self {
-            current_function: None,
-            functions: Vec::new(),
-            loop_depth: 0,
-            conditional_depth: 0,
-            max_loop_depth: 0,
-            max_conditional_depth: 0,
-            source,
-            semantic_patterns: HashSet::new(),
+/// Visitor state of an enclosing function, saved while a nested one is analyzed
+struct OuterFunction {
+    function: Option<FunctionMetrics>,
+    loop_depth: usize,
+    conditional_depth: usize,
+    max_loop_depth: usize,
+    max_conditional_depth: usize,
+    syntax: FunctionSyntax,
+    owner: Option<String>,
+}
+
+/// Identifiers in a token stream, nested groups included
+fn collect_identifiers(tokens: TokenStream, identifiers: &mut HashSet<String>) {
+    for token in tokens {
+        match token {
+            TokenTree::Ident(ident) => {
+                identifiers.insert(ident.to_string());
+            }
+            TokenTree::Group(group) => collect_identifiers(group.stream(), identifiers),
+            TokenTree::Punct(_) | TokenTree::Literal(_) => {}
         }
     }
+}
 
-    /// Get the line number for a span (simplified - using proc_macro2::Span would be more accurate)
-    fn get_line_number(&self, _byte_offset: usize) -> usize {
-        // For now, return 1 - in a real implementation, you'd calculate actual line numbers
-        // This would require more complex span handling
-        1
+/// Name of an `impl` self type: the last path segment (`Pool` for `state::Pool<T>`)
+fn type_name(ty: &Type) -> String {
+    match ty {
+        Type::Path(type_path) => type_path
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident.to_string())
+            .unwrap_or_default(),
+        Type::Reference(reference) => type_name(&reference.elem),
+        other => quote::quote!(#other).to_string(),
     }
+}
 
-    /// Detect semantic patterns in function names and content
-    fn detect_semantic_patterns(&mut self, name: &str) {
-        // AMM-specific patterns
-        if name.contains("swap") {
-            self.semantic_patterns.insert("token_swap".to_string());
-        }
-        if name.contains("liquidity") {
-            self.semantic_patterns
-                .insert("liquidity_management".to_string());
-        }
-        if name.contains("price") || name.contains("oracle") {
-            self.semantic_patterns
-                .insert("price_calculation".to_string());
-        }
-        if name.contains("fee") {
-            self.semantic_patterns.insert("fee_calculation".to_string());
-        }
-        if name.contains("pnl") || name.contains("profit") {
-            self.semantic_patterns.insert("pnl_calculation".to_string());
-        }
-        if name.contains("invariant") {
-            self.semantic_patterns
-                .insert("invariant_maintenance".to_string());
-        }
-        if name.contains("slippage") {
-            self.semantic_patterns
-                .insert("slippage_protection".to_string());
-        }
-        if name.contains("ceil_div") || name.contains("checked_ceil_div") {
-            self.semantic_patterns
-                .insert("ceiling_division".to_string());
-        }
-        if name.contains("sqrt") || name.contains("integer_sqrt") {
-            self.semantic_patterns
-                .insert("square_root_calculation".to_string());
-        }
+impl FunctionVisitor {
+    pub fn new() -> Self {
+        Self::default()
     }
 
-    /// Calculate cyclomatic complexity for a function
-    fn calculate_cyclomatic_complexity(&self, block: &Block) -> u32 {
-        let mut complexity = 1; // Base complexity
-        let mut visitor = ComplexityVisitor::new();
-        visitor.visit_block(block);
-        complexity + visitor.decision_points
+    /// Visitor scoring complexity with custom `weights`
+    pub fn with_weights(weights: PatternWeights) -> Self {
+        Self {
+            weights,
+            ..Self::default()
+        }
     }
-}
-
-impl<'ast> Visit<'ast> for FunctionVisitor<'ast> {
-    fn visit_item_fn(&mut self, node: &'ast ItemFn) {
-        let name = node.sig.ident.to_string();
-        let signature = format!("{}", quote::quote! { #node.sig });
 
-        // Detect semantic patterns
-        self.detect_semantic_patterns(&name);
+    /// Tag functions with the patterns of `detector` instead of the built-in ones
+    pub fn with_patterns(mut self, detector: Arc<PatternDetector>) -> Self {
+        self.patterns = detector;
+        self
+    }
 
-        // Calculate line range (simplified for now)
-        let start_line = 1; // TODO: implement proper line calculation
-        let end_line = 1;
+    /// Collect metrics for one function body (free function or method)
+    fn analyze_function(&mut self, sig: &Signature, block: &Block, span: Span) {
+        let ident = sig.ident.to_string();
+        let name = match &self.owner {
+            Some(owner) => format!("{}::{}", owner, ident),
+            None => ident.clone(),
+        };
+        let signature = format!("{}", quote::quote! { #sig });
+
+        // Functions nested in a body are analyzed on their own; park the
+        // enclosing function's state until they are done
+        let outer = OuterFunction {
+            function: self.current_function.take(),
+            loop_depth: std::mem::take(&mut self.loop_depth),
+            conditional_depth: std::mem::take(&mut self.conditional_depth),
+            max_loop_depth: std::mem::take(&mut self.max_loop_depth),
+            max_conditional_depth: std::mem::take(&mut self.max_conditional_depth),
+            syntax: std::mem::take(&mut self.syntax),
+            owner: self.owner.take(),
+        };
+
+        // Line range from span locations, attributes included
+        let start_line = span.start().line;
+        let end_line = span.end().line;
 
         // Initialize function metrics
         self.current_function = Some(FunctionMetrics {
-            name: name.clone(),
+            name,
+            owner: outer.owner.clone(),
             signature,
             line_range: (start_line, end_line),
             arithmetic: ArithmeticMetrics::default(),
@@ -120,20 +138,21 @@ impl<'ast> Visit<'ast> for FunctionVisitor<'ast> {
             semantic_tags: Vec::new(),
             risk_indicators: Vec::new(),
             complexity_score: 0.0,
+            snippet: None,
         });
 
-        // Visit the function body
-        visit::visit_block(self, &node.block);
+        // Visit the signature (for identifiers only) and the function body
+        visit::visit_signature(self, sig);
+        visit::visit_block(self, block);
 
         // Calculate final metrics
         if let Some(mut func) = self.current_function.take() {
-            func.control_flow.cyclomatic_complexity =
-                self.calculate_cyclomatic_complexity(&node.block);
+            func.control_flow.cyclomatic_complexity = self.calculate_cyclomatic_complexity(block);
             func.control_flow.max_loop_depth = self.max_loop_depth as u32;
             func.control_flow.max_conditional_depth = self.max_conditional_depth as u32;
 
             // Add semantic tags
-            func.semantic_tags = self.semantic_patterns.iter().cloned().collect();
+            func.semantic_tags = self.patterns.detect_in_function(&ident, &self.syntax);
 
             // Calculate complexity score (simple heuristic for now)
             func.complexity_score = self.calculate_complexity_score(&func);
@@ -141,15 +160,57 @@ impl<'ast> Visit<'ast> for FunctionVisitor<'ast> {
             self.functions.push(func);
         }
 
-        // Reset for next function
-        self.max_loop_depth = 0;
-        self.max_conditional_depth = 0;
-        self.semantic_patterns.clear();
+        // Restore the enclosing function, if any
+        self.current_function = outer.function;
+        self.loop_depth = outer.loop_depth;
+        self.conditional_depth = outer.conditional_depth;
+        self.max_loop_depth = outer.max_loop_depth;
+        self.max_conditional_depth = outer.max_conditional_depth;
+        self.syntax = outer.syntax;
+        self.owner = outer.owner;
+    }
+
+    /// Calculate cyclomatic complexity for a function
+    fn calculate_cyclomatic_complexity(&self, block: &Block) -> u32 {
+        let complexity = 1; // Base complexity
+        let mut visitor = ComplexityVisitor::new();
+        visitor.visit_block(block);
+        complexity + visitor.decision_points
+    }
+}
+
+impl<'ast> Visit<'ast> for FunctionVisitor {
+    fn visit_item_fn(&mut self, node: &'ast ItemFn) {
+        self.analyze_function(&node.sig, &node.block, node.span());
+    }
+
+    fn visit_item_impl(&mut self, node: &'ast ItemImpl) {
+        let owner = self.owner.replace(type_name(&node.self_ty));
+        visit::visit_item_impl(self, node);
+        self.owner = owner;
+    }
+
+    fn visit_item_trait(&mut self, node: &'ast ItemTrait) {
+        let owner = self.owner.replace(node.ident.to_string());
+        visit::visit_item_trait(self, node);
+        self.owner = owner;
+    }
+
+    fn visit_impl_item_fn(&mut self, node: &'ast ImplItemFn) {
+        self.analyze_function(&node.sig, &node.block, node.span());
+    }
+
+    fn visit_trait_item_fn(&mut self, node: &'ast TraitItemFn) {
+        // Only default methods have a body to analyze
+        if let Some(block) = &node.default {
+            self.analyze_function(&node.sig, block, node.span());
+        }
     }
 
     fn visit_expr_method_call(&mut self, node: &'ast ExprMethodCall) {
         if let Some(func) = &mut self.current_function {
             let method_name = node.method.to_string();
+            self.syntax.method_calls.insert(method_name.clone());
 
             match method_name.as_str() {
                 // Checked arithmetic
@@ -190,14 +251,6 @@ impl<'ast> Visit<'ast> for FunctionVisitor<'ast> {
                 "unwrap" => func.safety.unwrap_calls += 1,
                 "expect" => func.safety.expect_calls += 1,
 
-                // Detect semantic patterns from method names
-                name if name.contains("swap") => {
-                    self.semantic_patterns.insert("method_swap".to_string());
-                }
-                name if name.contains("liquidity") => {
-                    self.semantic_patterns
-                        .insert("method_liquidity".to_string());
-                }
                 _ => {}
             }
         }
@@ -230,11 +283,13 @@ impl<'ast> Visit<'ast> for FunctionVisitor<'ast> {
             // Check for panic-related calls
             if let Expr::Path(path) = &*node.func {
                 if let Some(segment) = path.path.segments.last() {
-                    match segment.ident.to_string().as_str() {
+                    let call_name = segment.ident.to_string();
+                    match call_name.as_str() {
                         "panic" => func.safety.panic_calls += 1,
                         "todo" | "unimplemented" => func.safety.todo_calls += 1,
                         _ => {}
                     }
+                    self.syntax.calls.insert(call_name);
                 }
             }
         }
@@ -242,6 +297,32 @@ impl<'ast> Visit<'ast> for FunctionVisitor<'ast> {
         visit::visit_expr_call(self, node);
     }
 
+    fn visit_ident(&mut self, node: &'ast Ident) {
+        if self.current_function.is_some() {
+            self.syntax.identifiers.insert(node.to_string());
+        }
+    }
+
+    fn visit_type_path(&mut self, node: &'ast TypePath) {
+        if self.current_function.is_some() {
+            if let Some(segment) = node.path.segments.last() {
+                self.syntax.types.insert(segment.ident.to_string());
+            }
+        }
+        visit::visit_type_path(self, node);
+    }
+
+    fn visit_macro(&mut self, node: &'ast Macro) {
+        // Macro arguments are opaque tokens; their identifiers still count
+        if self.current_function.is_some() {
+            if let Some(segment) = node.path.segments.last() {
+                self.syntax.macros.insert(segment.ident.to_string());
+            }
+            collect_identifiers(node.tokens.clone(), &mut self.syntax.identifiers);
+        }
+        visit::visit_macro(self, node);
+    }
+
     // Control flow tracking
     fn visit_expr_loop(&mut self, node: &'ast syn::ExprLoop) {
         self.loop_depth += 1;
@@ -292,20 +373,20 @@ impl<'ast> Visit<'ast> for FunctionVisitor<'ast> {
     }
 }
 
-impl<'ast> FunctionVisitor<'ast> {
+impl FunctionVisitor {
     /// Calculate complexity score for a function
     fn calculate_complexity_score(&self, func: &FunctionMetrics) -> f64 {
-        let arithmetic_weight = 1.0;
-        let math_weight = 1.2;
-        let control_flow_weight = 0.8;
-        let safety_weight = 2.0;
+        let weights = &self.weights;
 
-        let arithmetic_score = func.arithmetic.total_ops() as f64 * arithmetic_weight;
-        let math_score = func.math_functions.total_calls() as f64 * math_weight;
+        let fixed_point_ops = func.arithmetic.ceil_div + func.arithmetic.integer_sqrt;
+        let arithmetic_score = (func.arithmetic.total_ops() - fixed_point_ops) as f64
+            * weights.checked_arithmetic
+            + fixed_point_ops as f64 * weights.fixed_point;
+        let math_score = func.math_functions.total_calls() as f64 * weights.math_functions;
         let control_flow_score =
-            func.control_flow.cyclomatic_complexity as f64 * control_flow_weight;
+            func.control_flow.cyclomatic_complexity as f64 * weights.loop_operations;
         let safety_penalty =
-            (func.safety.unwrap_calls + func.safety.panic_calls) as f64 * safety_weight;
+            (func.safety.unwrap_calls + func.safety.panic_calls) as f64 * weights.unsafe_operations;
 
         arithmetic_score + math_score + control_flow_score + safety_penalty
     }
@@ -350,3 +431,72 @@ impl<'ast> Visit<'ast> for ComplexityVisitor {
         visit::visit_arm(self, node);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_function_line_range() {
+        let ast = syn::parse_file(
+            "use std::fmt;\n\n/// Doc\npub fn swap(amount: u64) -> u64 {\n    amount * 2\n}\n",
+        )
+        .unwrap();
+        let mut visitor = FunctionVisitor::new();
+        visitor.visit_file(&ast);
+
+        assert_eq!(visitor.functions.len(), 1);
+        assert_eq!(visitor.functions[0].line_range, (3, 6));
+    }
+
+    #[test]
+    fn test_impl_and_trait_methods() {
+        let ast = syn::parse_file(
+            r#"
+            pub struct Pool;
+
+            impl Pool {
+                pub fn swap(&mut self, amount: u64) -> u64 {
+                    amount.checked_mul(2).unwrap()
+                }
+            }
+
+            pub trait Curve {
+                fn price(&self) -> u64;
+
+                fn fee(&self, amount: u64) -> u64 {
+                    amount / 100
+                }
+            }
+
+            mod math {
+                pub fn ceil(a: u64, b: u64) -> u64 {
+                    fn helper(x: u64) -> u64 {
+                        x + 1
+                    }
+                    helper(a / b)
+                }
+            }
+            "#,
+        )
+        .unwrap();
+        let mut visitor = FunctionVisitor::new();
+        visitor.visit_file(&ast);
+
+        let names: Vec<_> = visitor.functions.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["Pool::swap", "Curve::fee", "helper", "ceil"]);
+
+        let swap = &visitor.functions[0];
+        assert_eq!(swap.owner.as_deref(), Some("Pool"));
+        assert_eq!(swap.arithmetic.checked_mul, 1);
+        assert_eq!(swap.safety.unwrap_calls, 1);
+
+        // The nested helper's addition is not attributed to its parent
+        let helper = &visitor.functions[2];
+        let ceil = &visitor.functions[3];
+        assert_eq!(helper.owner, None);
+        assert_eq!(helper.arithmetic.raw_add, 1);
+        assert_eq!(ceil.arithmetic.raw_add, 0);
+        assert_eq!(ceil.arithmetic.raw_div, 1);
+    }
+}
@@ -0,0 +1,684 @@
+//! Parsed workspace shared across analysis factors
+//!
+//! Every selected Rust file is read and parsed exactly once; factors then
+//! visit the shared `syn::File` instead of re-reading and re-parsing.
+//!
+//! A file that fails to parse (new-edition syntax, a stray brace) keeps the
+//! top-level items before the error: factors analyze that partial file and
+//! the error is reported as a [`Diagnostic`].
+//!
+//! With macro expansion (see `crate::expansion`), the code that macros
+//! generate is kept apart from the selected files, as extra files whose
+//! [`ExpansionMap`] points every line back at the item that generated it.
+//! Only factors that score generated validation read them.
+
+use crate::cache::{content_hash, CacheKey};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use syn::spanned::Spanned;
+
+/// Free the source text `span-locations` keeps for every file parsed on the
+/// current thread
+///
+/// proc-macro2 records each parsed file in a thread-local source map that is
+/// otherwise only freed when the thread exits, so a long-lived thread (a
+/// server worker) grows with every analysis. Spans of syntax trees parsed on
+/// this thread are invalid afterwards: call it only once they are dropped.
+pub fn release_thread_spans() {
+    proc_macro2::extra::invalidate_current_thread_spans();
+}
+
+/// Where a finding lives in the source: 1-based lines and columns
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceLocation {
+    /// File path, relative to the workspace root
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+impl SourceLocation {
+    /// Location of a span produced by parsing `file`
+    pub fn from_span(file: &str, span: proc_macro2::Span) -> Self {
+        let start = span.start();
+        let end = span.end();
+        Self {
+            file: file.to_string(),
+            line: start.line,
+            column: start.column + 1,
+            end_line: end.line,
+            end_column: end.column + 1,
+        }
+    }
+
+    /// Location of a syntax node parsed from `file`
+    pub fn of<T: Spanned>(file: &str, node: &T) -> Self {
+        Self::from_span(file, node.span())
+    }
+
+    /// `file:line:column`, the form editors and terminals link to
+    pub fn display(&self) -> String {
+        format!("{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
+/// A selected file that could not be read or fully parsed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostic {
+    /// File path, relative to the workspace root
+    pub path: String,
+
+    pub message: String,
+
+    /// Span of the parse error, when known
+    pub location: Option<SourceLocation>,
+
+    /// Top-level items before the error, analyzed as a partial file
+    pub recovered_items: usize,
+}
+
+impl Diagnostic {
+    /// Diagnostic for a file that failed to parse, at the error's span
+    pub fn from_error(path: &str, error: &syn::Error) -> Self {
+        let span = error.span();
+        Self {
+            path: path.to_string(),
+            message: error.to_string(),
+            // Errors without a real position (e.g. from the lexer) sit at line 0
+            location: (span.start().line > 0).then(|| SourceLocation::from_span(path, span)),
+            recovered_items: 0,
+        }
+    }
+
+    /// `path:line:column: message`, or `path: message` without a span
+    pub fn display(&self) -> String {
+        match &self.location {
+            Some(location) => format!("{}: {}", location.display(), self.message),
+            None => format!("{}: {}", self.path, self.message),
+        }
+    }
+}
+
+/// Parse `source`, falling back to the top-level items before a syntax error
+///
+/// Returns the full tree, or the partial tree with a diagnostic; fails only
+/// when no item precedes the error or the source cannot be tokenized.
+pub fn parse_source(path: &str, source: &str) -> syn::Result<(syn::File, Option<Diagnostic>)> {
+    let error = match syn::parse_file(source) {
+        Ok(ast) => return Ok((ast, None)),
+        Err(error) => error,
+    };
+
+    // `syn::parse_file` strips a BOM and a shebang line before tokenizing;
+    // blank the shebang instead so spans keep their line numbers
+    let source = source.strip_prefix('\u{feff}').unwrap_or(source);
+    let tokens = if source.starts_with("#!") && !source.starts_with("#![") {
+        let shebang = source.find('\n').unwrap_or(source.len());
+        format!("{}{}", " ".repeat(shebang), &source[shebang..]).parse()
+    } else {
+        source.parse::<proc_macro2::TokenStream>()
+    };
+    let Ok(tokens) = tokens else {
+        return Err(error);
+    };
+
+    match syn::parse::Parser::parse2(parse_leading_items, tokens) {
+        Ok(ast) if !ast.items.is_empty() => {
+            let diagnostic = Diagnostic {
+                recovered_items: ast.items.len(),
+                ..Diagnostic::from_error(path, &error)
+            };
+            Ok((ast, Some(diagnostic)))
+        }
+        _ => Err(error),
+    }
+}
+
+/// Inner attributes and the items before the first one that fails to parse
+fn parse_leading_items(input: syn::parse::ParseStream) -> syn::Result<syn::File> {
+    use syn::parse::discouraged::Speculative;
+
+    let attrs = input.call(syn::Attribute::parse_inner)?;
+    let mut items = Vec::new();
+    while !input.is_empty() {
+        // Parse on a fork: a failed item must not leave errors on `input`
+        let fork = input.fork();
+        match fork.parse::<syn::Item>() {
+            Ok(item) => {
+                input.advance_to(&fork);
+                items.push(item);
+            }
+            Err(_) => break,
+        }
+    }
+
+    // Drop everything from the failed item on; `parse2` rejects leftovers
+    input.step(|cursor| {
+        let mut rest = *cursor;
+        while let Some((_, next)) = rest.token_tree() {
+            rest = next;
+        }
+        Ok(((), rest))
+    })?;
+
+    Ok(syn::File {
+        shebang: None,
+        attrs,
+        items,
+    })
+}
+
+/// Byte offsets of line starts, for mapping offsets to 1-based line numbers
+#[derive(Debug, Clone, Default)]
+pub struct LineIndex {
+    line_starts: Vec<usize>,
+}
+
+impl LineIndex {
+    pub fn new(source: &str) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(
+            source
+                .bytes()
+                .enumerate()
+                .filter(|(_, b)| *b == b'\n')
+                .map(|(i, _)| i + 1),
+        );
+        Self { line_starts }
+    }
+
+    /// Number of lines in the source
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// 1-based line containing the byte `offset`
+    pub fn line_of(&self, offset: usize) -> usize {
+        match self.line_starts.binary_search(&offset) {
+            Ok(line) => line + 1,
+            Err(next) => next,
+        }
+    }
+
+    /// Byte range of the 1-based `line`, excluding the newline
+    pub fn line_range(&self, line: usize, source: &str) -> Option<std::ops::Range<usize>> {
+        let start = *self.line_starts.get(line.checked_sub(1)?)?;
+        let end = self
+            .line_starts
+            .get(line)
+            .map_or(source.len(), |next| next - 1);
+        Some(start..end.max(start))
+    }
+}
+
+/// Where the lines of a macro-expanded file came from
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExpansionMap {
+    /// Origin of each line, in order: the item whose macros generated it
+    pub origins: Vec<SourceLocation>,
+}
+
+impl ExpansionMap {
+    /// Origin of the 1-based `line`
+    pub fn origin(&self, line: usize) -> Option<&SourceLocation> {
+        self.origins.get(line.checked_sub(1)?)
+    }
+}
+
+/// A single parsed source file
+#[derive(Debug, Clone)]
+pub struct ParsedFile {
+    /// Path relative to the workspace root, as selected
+    pub path: String,
+
+    /// Absolute (joined) path on disk
+    pub full_path: PathBuf,
+
+    /// Raw source text
+    pub source: String,
+
+    /// Parsed syntax tree
+    pub ast: syn::File,
+
+    /// Line start index for `source`
+    pub line_index: LineIndex,
+
+    /// SHA-256 of `source`
+    pub content_hash: String,
+
+    /// Parse error, when `ast` holds only the items before it
+    pub diagnostic: Option<Diagnostic>,
+
+    /// For code generated by macro expansion, where each line came from
+    pub expansion: Option<ExpansionMap>,
+}
+
+impl ParsedFile {
+    /// Parse in-memory source (useful for testing)
+    pub fn parse(path: &str, source: String) -> syn::Result<Self> {
+        let ast = syn::parse_file(&source)?;
+        let line_index = LineIndex::new(&source);
+        let content_hash = content_hash(source.as_bytes());
+        Ok(Self {
+            path: path.to_string(),
+            full_path: PathBuf::from(path),
+            source,
+            ast,
+            line_index,
+            content_hash,
+            diagnostic: None,
+            expansion: None,
+        })
+    }
+
+    /// Parse in-memory source, keeping the items before a syntax error
+    pub fn parse_lenient(path: &str, source: String) -> syn::Result<Self> {
+        let (ast, diagnostic) = parse_source(path, &source)?;
+        let line_index = LineIndex::new(&source);
+        let content_hash = content_hash(source.as_bytes());
+        Ok(Self {
+            path: path.to_string(),
+            full_path: PathBuf::from(path),
+            source,
+            ast,
+            line_index,
+            content_hash,
+            diagnostic,
+            expansion: None,
+        })
+    }
+
+    /// Location of a syntax node from this file's AST
+    pub fn location<T: Spanned>(&self, node: &T) -> SourceLocation {
+        SourceLocation::of(&self.path, node)
+    }
+
+    /// Text of the 1-based `line`
+    pub fn line(&self, line: usize) -> Option<&str> {
+        self.line_index
+            .line_range(line, &self.source)
+            .map(|range| &self.source[range])
+    }
+}
+
+/// A selected file that could not be read or parsed
+#[derive(Debug, Clone)]
+pub struct FileFailure {
+    pub path: String,
+    pub message: String,
+
+    /// Span of the parse error, when known
+    pub location: Option<SourceLocation>,
+
+    /// Source text, when the file was read but failed to parse
+    pub source: Option<String>,
+}
+
+/// The raw sources of a [`ParsedWorkspace`], without syntax trees
+///
+/// `syn` trees are neither `Send` nor `Sync`; worker threads that need the
+/// workspace parse their own copy from these sources instead of re-reading
+/// the files.
+#[derive(Debug, Clone)]
+pub(crate) struct WorkspaceSources {
+    root: PathBuf,
+    files: Vec<(String, PathBuf, String, Option<ExpansionMap>)>,
+    skipped: Vec<String>,
+    failures: Vec<FileFailure>,
+}
+
+impl FileFailure {
+    /// Failure of a file that was read but yielded no items
+    fn parse(path: &str, error: &syn::Error, source: String) -> Self {
+        let diagnostic = Diagnostic::from_error(path, error);
+        Self {
+            path: diagnostic.path,
+            message: diagnostic.message,
+            location: diagnostic.location,
+            source: Some(source),
+        }
+    }
+
+    pub fn diagnostic(&self) -> Diagnostic {
+        Diagnostic {
+            path: self.path.clone(),
+            message: self.message.clone(),
+            location: self.location.clone(),
+            recovered_items: 0,
+        }
+    }
+}
+
+impl WorkspaceSources {
+    /// Parse the sources into a workspace equal to the original
+    pub(crate) fn parse(&self) -> ParsedWorkspace {
+        let mut workspace = ParsedWorkspace {
+            root: self.root.clone(),
+            skipped: self.skipped.clone(),
+            failures: self.failures.clone(),
+            ..Default::default()
+        };
+        for (path, full_path, source, expansion) in &self.files {
+            match ParsedFile::parse_lenient(path, source.clone()) {
+                Ok(mut file) => {
+                    file.full_path = full_path.clone();
+                    file.expansion = expansion.clone();
+                    if file.expansion.is_some() {
+                        workspace.expanded.push(file);
+                    } else {
+                        workspace.files.push(file);
+                    }
+                }
+                Err(e) => workspace
+                    .failures
+                    .push(FileFailure::parse(path, &e, source.clone())),
+            }
+        }
+        workspace
+    }
+}
+
+/// All selected Rust files of a workspace, read and parsed once
+#[derive(Debug, Clone, Default)]
+pub struct ParsedWorkspace {
+    /// Workspace root directory
+    pub root: PathBuf,
+
+    /// Parsed files, in selection order; some may be partial (see
+    /// [`ParsedFile::diagnostic`])
+    pub files: Vec<ParsedFile>,
+
+    /// Code generated by macro expansion, one `<file> (expanded)` per file
+    /// with generated items; not part of `files`, so size and structure
+    /// factors measure only the written source
+    pub expanded: Vec<ParsedFile>,
+
+    /// Selected files that were missing or not Rust sources
+    pub skipped: Vec<String>,
+
+    /// Rust files that failed to read, or to parse even partially
+    pub failures: Vec<FileFailure>,
+}
+
+impl ParsedWorkspace {
+    /// Read and parse every selected `.rs` file under `root`
+    pub fn load(root: &Path, selected_files: &[String]) -> Self {
+        let mut workspace = Self {
+            root: root.to_path_buf(),
+            ..Default::default()
+        };
+
+        for file_path in selected_files {
+            let full_path = root.join(file_path);
+
+            if !full_path.is_file() || full_path.extension().is_none_or(|ext| ext != "rs") {
+                workspace.skipped.push(file_path.clone());
+                continue;
+            }
+
+            let source = match std::fs::read_to_string(&full_path) {
+                Ok(source) => source,
+                Err(e) => {
+                    log::warn!("Failed to read file {:?}: {}", full_path, e);
+                    workspace.failures.push(FileFailure {
+                        path: file_path.clone(),
+                        message: e.to_string(),
+                        location: None,
+                        source: None,
+                    });
+                    continue;
+                }
+            };
+
+            match ParsedFile::parse_lenient(file_path, source.clone()) {
+                Ok(mut file) => {
+                    if let Some(diagnostic) = &file.diagnostic {
+                        log::warn!(
+                            "Failed to parse AST for {:?}, analyzing the first {} items: {}",
+                            full_path,
+                            diagnostic.recovered_items,
+                            diagnostic.message
+                        );
+                    }
+                    file.full_path = full_path;
+                    workspace.files.push(file);
+                }
+                Err(e) => {
+                    log::warn!("Failed to parse AST for {:?}: {}", full_path, e);
+                    workspace
+                        .failures
+                        .push(FileFailure::parse(file_path, &e, source));
+                }
+            }
+        }
+
+        log::info!(
+            "Parsed {} of {} selected files ({} skipped, {} failed)",
+            workspace.files.len(),
+            selected_files.len(),
+            workspace.skipped.len(),
+            workspace.failures.len()
+        );
+
+        workspace
+    }
+
+    /// Build a workspace from already-parsed files (useful for testing)
+    pub fn from_files(root: &Path, files: Vec<ParsedFile>) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            files,
+            ..Default::default()
+        }
+    }
+
+    /// Sources for re-parsing this workspace on another thread
+    pub(crate) fn sources(&self) -> WorkspaceSources {
+        WorkspaceSources {
+            root: self.root.clone(),
+            files: self
+                .files_with_expanded()
+                .map(|file| {
+                    (
+                        file.path.clone(),
+                        file.full_path.clone(),
+                        file.source.clone(),
+                        file.expansion.clone(),
+                    )
+                })
+                .collect(),
+            skipped: self.skipped.clone(),
+            failures: self.failures.clone(),
+        }
+    }
+
+    /// Number of selected files that were not analyzed
+    pub fn skipped_count(&self) -> usize {
+        self.skipped.len() + self.failures.len()
+    }
+
+    /// SHA-256 over the path and content of every selected file
+    ///
+    /// Two workspaces with the same fingerprint give every factor the same
+    /// input, so it keys cached factor results.
+    pub fn fingerprint(&self) -> String {
+        let mut key = CacheKey::new("workspace");
+        for file in &self.files {
+            key = key.with(&file.path).with(&file.content_hash);
+        }
+        if self.is_expanded() {
+            key = key.with("expanded");
+            for file in &self.expanded {
+                key = key.with(&file.path).with(&file.content_hash);
+            }
+        }
+        for failure in &self.failures {
+            key = key.with(&failure.path).with(&failure.message);
+            if let Some(source) = &failure.source {
+                key = key.with(content_hash(source.as_bytes()));
+            }
+        }
+        // Skipped files (manifests, missing paths) are hashed as found on disk
+        for path in &self.skipped {
+            let content = std::fs::read(self.root.join(path)).unwrap_or_default();
+            key = key.with(path).with(content_hash(&content));
+        }
+        key.digest()
+    }
+
+    /// Read and parse errors, partially parsed files first
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        self.files
+            .iter()
+            .filter_map(|file| file.diagnostic.clone())
+            .chain(self.failures.iter().map(FileFailure::diagnostic))
+            .collect()
+    }
+
+    /// Whether macro expansion added generated code to this workspace
+    pub fn is_expanded(&self) -> bool {
+        !self.expanded.is_empty()
+    }
+
+    /// The selected files followed by the code macros generate from them
+    pub fn files_with_expanded(&self) -> impl Iterator<Item = &ParsedFile> {
+        self.files.iter().chain(&self.expanded)
+    }
+
+    /// `location` in generated code, mapped back to the item that generated it
+    pub fn original_location(&self, location: &SourceLocation) -> Option<SourceLocation> {
+        let file = self
+            .expanded
+            .iter()
+            .find(|file| file.path == location.file)?;
+        file.expansion.as_ref()?.origin(location.line).cloned()
+    }
+
+    /// Map every serialized [`SourceLocation`] in `value` that points into
+    /// generated code back to the item that generated it
+    pub fn map_expanded_locations(&self, value: &mut Value) {
+        match value {
+            Value::Array(items) => items
+                .iter_mut()
+                .for_each(|item| self.map_expanded_locations(item)),
+            Value::Object(map) => {
+                if map.contains_key("file") && map.contains_key("endLine") {
+                    let original = serde_json::from_value(Value::Object(map.clone()))
+                        .ok()
+                        .and_then(|location| self.original_location(&location));
+                    if let Some(Value::Object(original)) =
+                        original.and_then(|location| serde_json::to_value(location).ok())
+                    {
+                        map.extend(original);
+                    }
+                    return;
+                }
+                map.values_mut()
+                    .for_each(|item| self.map_expanded_locations(item));
+            }
+            _ => {}
+        }
+    }
+
+    /// Whether every selected Rust file was read and fully parsed
+    pub fn is_complete(&self) -> bool {
+        self.failures.is_empty() && self.files.iter().all(|file| file.diagnostic.is_none())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_index() {
+        let source = "fn a() {}\n\nfn b() {}\n";
+        let index = LineIndex::new(source);
+
+        assert_eq!(index.line_of(0), 1);
+        assert_eq!(index.line_of(9), 1);
+        assert_eq!(index.line_of(10), 2);
+        assert_eq!(index.line_of(11), 3);
+        assert_eq!(index.line_range(3, source), Some(11..20));
+        assert_eq!(index.line_range(5, source), None);
+    }
+
+    #[test]
+    fn test_load_skips_and_records_failures() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("ok.rs"), "fn ok() {}").unwrap();
+        std::fs::write(dir.path().join("bad.rs"), "fn bad( {").unwrap();
+        std::fs::write(dir.path().join("Cargo.toml"), "[package]").unwrap();
+
+        let selected = vec![
+            "ok.rs".to_string(),
+            "bad.rs".to_string(),
+            "Cargo.toml".to_string(),
+            "missing.rs".to_string(),
+        ];
+        let workspace = ParsedWorkspace::load(dir.path(), &selected);
+
+        assert_eq!(workspace.files.len(), 1);
+        assert_eq!(workspace.files[0].path, "ok.rs");
+        assert_eq!(workspace.files[0].line(1), Some("fn ok() {}"));
+        assert_eq!(workspace.skipped, vec!["Cargo.toml", "missing.rs"]);
+        assert_eq!(workspace.failures.len(), 1);
+        assert_eq!(workspace.skipped_count(), 3);
+        assert!(!workspace.is_complete());
+        let diagnostics = workspace.diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].path, "bad.rs");
+        assert_eq!(diagnostics[0].recovered_items, 0);
+
+        let fingerprint = workspace.fingerprint();
+        assert_eq!(
+            ParsedWorkspace::load(dir.path(), &selected).fingerprint(),
+            fingerprint
+        );
+        std::fs::write(dir.path().join("Cargo.toml"), "[package]\nname = \"a\"").unwrap();
+        assert_ne!(
+            ParsedWorkspace::load(dir.path(), &selected).fingerprint(),
+            fingerprint
+        );
+    }
+
+    #[test]
+    fn test_parse_lenient_keeps_leading_items() {
+        // `gen` blocks are edition-2024 syntax that this parser rejects
+        let source = "#!/usr/bin/env run\nuse std::fmt;\n\npub fn ok() -> u64 {\n    1\n}\n\nfn new() {\n    let _ = gen { yield 1 };\n}\n\nfn after() {}\n";
+        let file = ParsedFile::parse_lenient("src/lib.rs", source.to_string()).unwrap();
+
+        assert_eq!(file.ast.items.len(), 2);
+        let diagnostic = file.diagnostic.as_ref().unwrap();
+        assert_eq!(diagnostic.recovered_items, 2);
+        assert_eq!(diagnostic.location.as_ref().unwrap().line, 9);
+        assert!(diagnostic.display().starts_with("src/lib.rs:9:"));
+
+        let full = ParsedFile::parse_lenient("src/lib.rs", "fn a() {}".to_string()).unwrap();
+        assert!(full.diagnostic.is_none());
+        assert!(ParsedFile::parse_lenient("src/lib.rs", "fn a() {".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_source_location_of_node() {
+        let file = ParsedFile::parse(
+            "src/lib.rs",
+            "use std::fmt;\n\npub fn handler() {\n    let x = 1;\n}\n".to_string(),
+        )
+        .unwrap();
+        let syn::Item::Fn(item_fn) = &file.ast.items[1] else {
+            panic!("expected fn");
+        };
+
+        let location = file.location(item_fn);
+        assert_eq!(location.file, "src/lib.rs");
+        assert_eq!((location.line, location.column), (3, 1));
+        assert_eq!((location.end_line, location.end_column), (5, 2));
+        assert_eq!(location.display(), "src/lib.rs:3:1");
+    }
+}